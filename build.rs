@@ -0,0 +1,215 @@
+// Checks the resources folder before the game is built, so a missing or
+// broken file stops the build instead of the game on a player's machine:
+// every default sprite has to be there and decode, sprite sheets have to
+// split into the frames `animations.cfg` gives them, and the config files
+// have to read as `key = value` lines.
+//
+// Then it packs the default sprites into one atlas and lists every file in
+// the folder with its size and SHA-256, and both go into the binary (see
+// `src/assets.rs`): the atlas stands in for sprites that go missing, and
+// the list is what the game checks its resources folder against at start.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{GenericImage, RgbaImage};
+
+#[allow(dead_code)]
+#[path = "src/signing.rs"]
+mod signing;
+
+const RESOURCES_DIR: &str = "resources";
+// The sprites themes fall back to; `panel.png` is the one in
+// `theme::SPRITE_FILES` with no default
+const DEFAULT_SPRITES: [&str; 12] = [
+    "bird.png",
+    "pipe.png",
+    "stone.png",
+    "tree.png",
+    "background.png",
+    "cursor.png",
+    "cursor_pressed.png",
+    "coin.png",
+    "medal.png",
+    "hats.png",
+    "checkpoint.png",
+    "finish.png",
+];
+const CONFIG_FILES: [&str; 3] = [
+    "quests.cfg",
+    "sprites/animations.cfg",
+    "sprites/anchors.cfg",
+];
+// Anything bigger is a mistake at this game's resolution
+const MAX_SPRITE_SIZE: u32 = 2048;
+// Sprites are packed in rows this wide, spaced so they don't bleed
+const ATLAS_WIDTH: u32 = 512;
+const ATLAS_PADDING: u32 = 1;
+
+fn main() {
+    println!("cargo:rerun-if-changed={RESOURCES_DIR}");
+    println!("cargo:rerun-if-changed=src/signing.rs");
+
+    let root = Path::new(RESOURCES_DIR);
+    let mut problems = Vec::new();
+    let mut sprites = Vec::new();
+    for file in DEFAULT_SPRITES {
+        let path = root.join("sprites").join(file);
+        match image::open(&path) {
+            Ok(image) => {
+                let image = image.to_rgba8();
+                let (width, height) = image.dimensions();
+                if width == 0 || height == 0 {
+                    problems.push(format!("{} is empty", path.display()));
+                } else if width.max(height) > MAX_SPRITE_SIZE {
+                    problems.push(format!(
+                        "{} is {width}x{height}, over {MAX_SPRITE_SIZE} pixels",
+                        path.display()
+                    ));
+                } else {
+                    sprites.push((file, image));
+                }
+            }
+            Err(err) => problems.push(format!("{}: {err}", path.display())),
+        }
+    }
+    for file in CONFIG_FILES {
+        let path = root.join(file);
+        match fs::read_to_string(&path) {
+            Ok(text) => problems.extend(check_config(&path, &text)),
+            Err(err) => problems.push(format!("{}: {err}", path.display())),
+        }
+    }
+    if let Ok(text) = fs::read_to_string(root.join("sprites/animations.cfg")) {
+        problems.extend(check_frames(&text, &sprites));
+    }
+    if !problems.is_empty() {
+        panic!(
+            "the resources folder isn't fit to ship:\n  {}",
+            problems.join("\n  ")
+        );
+    }
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let (atlas, places) = pack(&sprites);
+    atlas
+        .save(out_dir.join("atlas.png"))
+        .expect("couldn't write the sprite atlas");
+
+    let mut code = String::from("// Made by build.rs\n\n");
+    code.push_str("pub const ATLAS_PNG: &[u8] = include_bytes!(concat!(env!(\"OUT_DIR\"), \"/atlas.png\"));\n\n");
+    code.push_str("pub const ATLAS: &[(&str, [u16; 4])] = &[\n");
+    for ((file, image), (x, y)) in sprites.iter().zip(places) {
+        let (width, height) = image.dimensions();
+        writeln!(code, "    ({file:?}, [{x}, {y}, {width}, {height}]),").unwrap();
+    }
+    code.push_str("];\n\n");
+    // Unused where `assets::check` can't look at files
+    code.push_str(
+        "#[cfg_attr(any(target_os = \"android\", target_arch = \"wasm32\"), allow(dead_code))]\n",
+    );
+    code.push_str("pub const MANIFEST: &[(&str, u64, &str)] = &[\n");
+    let mut files = Vec::new();
+    list_files(root, &mut files);
+    files.sort();
+    for path in files {
+        let bytes = fs::read(&path).unwrap();
+        let name = path
+            .strip_prefix(root)
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/");
+        let sum = signing::hex(&signing::sha256(&[&bytes]));
+        writeln!(code, "    ({name:?}, {}, {sum:?}),", bytes.len()).unwrap();
+    }
+    code.push_str("];\n");
+    fs::write(out_dir.join("assets.rs"), code).expect("couldn't write the asset list");
+}
+
+// Lines that aren't blank, a comment or `key = value`
+fn check_config(path: &Path, text: &str) -> Vec<String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#') && !line.contains('=')
+        })
+        .map(|(i, line)| {
+            format!(
+                "{} line {}: {line:?} isn't key = value",
+                path.display(),
+                i + 1
+            )
+        })
+        .collect()
+}
+
+// Sprite sheets whose width doesn't split into their `<sheet>_frames`
+fn check_frames(animations: &str, sprites: &[(&str, RgbaImage)]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for line in animations.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(sheet) = key.trim().strip_suffix("_frames") else {
+            continue;
+        };
+        let file = format!("{sheet}.png");
+        let Some((_, image)) = sprites.iter().find(|(name, _)| *name == file) else {
+            problems.push(format!(
+                "animations.cfg has frames for {file}, which isn't a default sprite"
+            ));
+            continue;
+        };
+        match value.trim().parse::<u32>() {
+            Ok(frames) if frames > 0 && image.width() % frames == 0 => {}
+            _ => problems.push(format!(
+                "{file} is {} wide, which doesn't split into {} frames",
+                image.width(),
+                value.trim()
+            )),
+        }
+    }
+    problems
+}
+
+// Shelves the sprites tallest first, returning the atlas and where each
+// one went, in the order given
+fn pack(sprites: &[(&str, RgbaImage)]) -> (RgbaImage, Vec<(u32, u32)>) {
+    let width = sprites
+        .iter()
+        .map(|(_, image)| image.width())
+        .max()
+        .unwrap_or(1)
+        .max(ATLAS_WIDTH);
+    let mut order: Vec<usize> = (0..sprites.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sprites[i].1.height()));
+    let mut places = vec![(0, 0); sprites.len()];
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = sprites[i].1.dimensions();
+        if x + w > width {
+            (x, y, row_height) = (0, y + row_height + ATLAS_PADDING, 0);
+        }
+        places[i] = (x, y);
+        x += w + ATLAS_PADDING;
+        row_height = row_height.max(h);
+    }
+    let mut atlas = RgbaImage::new(width, (y + row_height).max(1));
+    for ((_, image), &(x, y)) in sprites.iter().zip(&places) {
+        atlas.copy_from(image, x, y).unwrap();
+    }
+    (atlas, places)
+}
+
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Flappy Bird as a mini-game inside another macroquad program: an arcade
+// cabinet in a dim room, with the game on its screen. Space or a click on
+// the screen flaps, P pauses and Escape leaves. Run it from the repository,
+// where the sprites are:
+//
+//     cargo run --example embedded
+
+use flappy_bird_rust::{FlappyGame, Input};
+use macroquad::prelude::*;
+
+const WALL: Color = Color::new(0.13, 0.11, 0.18, 1.0);
+const FLOOR: Color = Color::new(0.2, 0.16, 0.14, 1.0);
+const CABINET: Color = Color::new(0.55, 0.1, 0.12, 1.0);
+const BEZEL: Color = Color::new(0.05, 0.05, 0.06, 1.0);
+
+#[macroquad::main("Arcade")]
+async fn main() {
+    let mut game = FlappyGame::new(None).await;
+    let (width, height) = FlappyGame::SIZE;
+    let screen = render_target(width, height);
+    screen.texture.set_filter(FilterMode::Nearest);
+    let mut best = 0;
+
+    while !is_key_pressed(KeyCode::Escape) {
+        // The cabinet's screen, keeping the game's shape as the window
+        // changes size
+        let tall = (screen_height() * 0.55).round();
+        let wide = (tall * width as f32 / height as f32).round();
+        let area = Rect::new(
+            ((screen_width() - wide) / 2.0).round(),
+            (screen_height() * 0.18).round(),
+            wide,
+            tall,
+        );
+
+        let clicked =
+            is_mouse_button_pressed(MouseButton::Left) && area.contains(mouse_position().into());
+        if is_key_pressed(KeyCode::Space) || clicked {
+            game.input(Input::Flap);
+        }
+        if is_key_pressed(KeyCode::P) {
+            game.input(Input::Pause);
+        }
+        game.step(get_frame_time());
+        best = best.max(game.score());
+        game.render(&screen);
+
+        clear_background(WALL);
+        let floor = screen_height() * 0.85;
+        draw_rectangle(0.0, floor, screen_width(), floor, FLOOR);
+        let body = Rect::new(
+            area.x - 40.0,
+            area.y - 80.0,
+            area.w + 80.0,
+            floor - area.y + 80.0,
+        );
+        draw_rectangle(body.x, body.y, body.w, body.h, CABINET);
+        draw_rectangle(body.x, body.y, body.w, 60.0, BEZEL);
+        let marquee = format!("FLAPPY BIRD  BEST {best}");
+        let size = measure_text(&marquee, None, 28, 1.0);
+        let x = body.x + (body.w - size.width) / 2.0;
+        draw_text(&marquee, x, body.y + 40.0, 28.0, GOLD);
+        draw_rectangle(
+            area.x - 12.0,
+            area.y - 12.0,
+            area.w + 24.0,
+            area.h + 24.0,
+            BEZEL,
+        );
+        draw_texture_ex(
+            &screen.texture,
+            area.x,
+            area.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(area.size()),
+                ..Default::default()
+            },
+        );
+        if game.is_over() {
+            draw_text(
+                "Insert coin (or press Space)",
+                area.x,
+                area.bottom() + 36.0,
+                20.0,
+                WHITE,
+            );
+        }
+
+        next_frame().await;
+    }
+}
@@ -0,0 +1,65 @@
+// Achievements unlocked by reaching milestones and by daily play streaks,
+// and Odometer for taking the score past 999 in one run
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Achievement {
+    KeepGoing,
+    HalfCentury,
+    Centurion,
+    WeekStreak,
+    MonthStreak,
+    Odometer,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 6] = [
+        Achievement::KeepGoing,
+        Achievement::HalfCentury,
+        Achievement::Centurion,
+        Achievement::WeekStreak,
+        Achievement::MonthStreak,
+        Achievement::Odometer,
+    ];
+
+    /// Stable name used in the profile file.
+    pub fn id(self) -> &'static str {
+        match self {
+            Achievement::KeepGoing => "keep_going",
+            Achievement::HalfCentury => "half_century",
+            Achievement::Centurion => "centurion",
+            Achievement::WeekStreak => "week_streak",
+            Achievement::MonthStreak => "month_streak",
+            Achievement::Odometer => "odometer",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Achievement::KeepGoing => "Keep Going",
+            Achievement::HalfCentury => "Half Century",
+            Achievement::Centurion => "Centurion",
+            Achievement::WeekStreak => "Week Streak",
+            Achievement::MonthStreak => "Month Streak",
+            Achievement::Odometer => "Odometer",
+        }
+    }
+
+    /// The achievement earned by passing `pipes` pipes in one run.
+    pub fn for_milestone(pipes: u32) -> Option<Achievement> {
+        match pipes {
+            25 => Some(Achievement::KeepGoing),
+            50 => Some(Achievement::HalfCentury),
+            100 => Some(Achievement::Centurion),
+            _ => None,
+        }
+    }
+
+    /// The achievement earned by playing `days` days in a row.
+    pub fn for_streak(days: u32) -> Option<Achievement> {
+        match days {
+            30.. => Some(Achievement::MonthStreak),
+            7.. => Some(Achievement::WeekStreak),
+            _ => None,
+        }
+    }
+}
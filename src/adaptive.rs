@@ -0,0 +1,59 @@
+// Adaptive difficulty, an assist in the settings menu: it eases the gaps and
+// speed off for players who keep crashing early, and tightens them a little
+// for ones who keep going, aiming for a run to end about every TARGET_PIPES
+// pipes. It goes by the median of the last RECENT_RUNS runs, so one lucky or
+// unlucky run barely moves it and it changes only between runs. Like the
+// other assists it marks runs as assisted, so they never count towards the
+// straight bests.
+
+use crate::history::Run;
+use crate::pipes::GAP_HEIGHT;
+
+// Pipes the tuning aims for players to pass before crashing
+const TARGET_PIPES: f32 = 15.0;
+const RECENT_RUNS: usize = 10;
+// Until there are this many runs to go by, it leaves the game as it is
+const MIN_RUNS: usize = 3;
+// The most the gaps grow, as a share of the normal gap height, and the
+// game slows down, at full ease
+const MAX_EXTRA_GAP_SHARE: f32 = 0.3;
+const MAX_SLOWDOWN: f32 = 0.2;
+// How far it goes the other way for players well past the target
+const MAX_HARDEN: f32 = 0.5;
+
+/// How much the adaptive assist changes the game for the next run.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Tuning {
+    // From -MAX_HARDEN (harder) through 0 (as it is) to 1 (easiest)
+    pub ease: f32,
+}
+
+impl Tuning {
+    /// Tuning for a player whose runs, oldest first, went like `history`.
+    pub fn for_history(history: &[Run]) -> Tuning {
+        let mut scores: Vec<i64> = history
+            .iter()
+            .rev()
+            .take(RECENT_RUNS)
+            .map(|run| run.score)
+            .collect();
+        if scores.len() < MIN_RUNS {
+            return Tuning::default();
+        }
+        scores.sort_unstable();
+        let median = scores[scores.len() / 2] as f32;
+        Tuning {
+            ease: ((TARGET_PIPES - median) / TARGET_PIPES).clamp(-MAX_HARDEN, 1.0),
+        }
+    }
+
+    /// Pixels added to every gap; less than 0 narrows them.
+    pub fn extra_gap(self) -> f32 {
+        GAP_HEIGHT * MAX_EXTRA_GAP_SHARE * self.ease
+    }
+
+    /// How fast the game runs, as a share of normal speed.
+    pub fn game_speed(self) -> f32 {
+        1.0 - MAX_SLOWDOWN * self.ease
+    }
+}
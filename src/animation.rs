@@ -0,0 +1,128 @@
+// Sprite sheet animations. A sheet is its frames side by side, all the same
+// size, with a row for each variant where there are several (the medals
+// have one per metal, bronze to platinum). How each plays is in
+// `animations.cfg`, next to the default sprites and optionally in a theme
+// folder to re-time the theme's own sheets:
+//
+//     # Frames in the sheet, and how many a second
+//     coin_frames = 8
+//     coin_fps = 12
+//     # Seconds the first frame is held between plays
+//     medal_pause = 2
+//
+// Keys a theme leaves out keep the default timing.
+
+use macroquad::prelude::*;
+
+use crate::kv::KeyValues;
+
+pub const ANIMATIONS_FILE: &str = "animations.cfg";
+const ANIMATION_NAMES: [&str; 2] = ["coin", "medal"];
+const MAX_FRAMES: u32 = 64;
+
+/// How one sheet plays. The default is a still of the whole sheet.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Animation {
+    pub frames: u32,
+    pub fps: f32,
+    pub pause: f32,
+}
+
+impl Default for Animation {
+    fn default() -> Animation {
+        Animation {
+            frames: 1,
+            fps: 1.0,
+            pause: 0.0,
+        }
+    }
+}
+
+impl Animation {
+    // `name`'s keys in `kv`, the rest from `fallback`
+    fn from_values(kv: &KeyValues, name: &str, fallback: Animation) -> Animation {
+        let key = |key: &str| format!("{name}_{key}");
+        Animation {
+            frames: kv
+                .get_parsed(&key("frames"))
+                .unwrap_or(fallback.frames)
+                .clamp(1, MAX_FRAMES),
+            fps: kv
+                .get_parsed(&key("fps"))
+                .filter(|&fps: &f32| fps > 0.0)
+                .unwrap_or(fallback.fps),
+            pause: kv
+                .get_parsed(&key("pause"))
+                .unwrap_or(fallback.pause)
+                .max(0.0),
+        }
+    }
+
+    /// The frame showing `seconds` into the animation.
+    pub fn frame(&self, seconds: f64) -> u32 {
+        let playing = f64::from(self.frames) / f64::from(self.fps);
+        let at = seconds.rem_euclid(playing + f64::from(self.pause));
+        if at >= playing {
+            return 0;
+        }
+        ((at * f64::from(self.fps)) as u32).min(self.frames - 1)
+    }
+
+    /// Draws row `row` of `rows` in `sheet` as it is `seconds` in, stretched
+    /// to `dest`.
+    pub fn draw(
+        &self,
+        sheet: &Texture2D,
+        row: u32,
+        rows: u32,
+        seconds: f64,
+        dest: Rect,
+        tint: Color,
+    ) {
+        let size = vec2(
+            sheet.width() / self.frames as f32,
+            sheet.height() / rows.max(1) as f32,
+        );
+        let source = Rect::new(
+            self.frame(seconds) as f32 * size.x,
+            row as f32 * size.y,
+            size.x,
+            size.y,
+        );
+        draw_texture_ex(
+            sheet,
+            dest.x,
+            dest.y,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(dest.size()),
+                source: Some(source),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Every animation a theme can re-time.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Animations {
+    pub coin: Animation,
+    pub medal: Animation,
+}
+
+impl Animations {
+    /// Reads the keys in `kv`, keeping `fallback`'s timing for the rest.
+    pub fn from_values(kv: &KeyValues, fallback: &Animations) -> Animations {
+        Animations {
+            coin: Animation::from_values(kv, "coin", fallback.coin),
+            medal: Animation::from_values(kv, "medal", fallback.medal),
+        }
+    }
+
+    /// Every key `animations.cfg` may have, for checking mod packages.
+    pub fn keys() -> impl Iterator<Item = String> {
+        ANIMATION_NAMES
+            .into_iter()
+            .flat_map(|name| ["frames", "fps", "pause"].map(|key| format!("{name}_{key}")))
+    }
+}
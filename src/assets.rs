@@ -0,0 +1,62 @@
+// What the build knew about the resources folder (see `build.rs`): the
+// default sprites packed into one atlas, and every file's size and
+// SHA-256. The atlas stands in for a default sprite that can't be loaded,
+// and the list lets the game say at start which files went missing or
+// were changed since it was built, rather than mysteriously looking wrong.
+
+use macroquad::prelude::*;
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+/// The built-in copy of default sprite `file`, from the atlas.
+pub fn sprite(file: &str) -> Option<Image> {
+    let &(_, [x, y, w, h]) = ATLAS.iter().find(|(name, _)| *name == file)?;
+    let atlas = Image::from_file_with_format(ATLAS_PNG, Some(ImageFormat::Png)).ok()?;
+    let rect = Rect::new(f32::from(x), f32::from(y), f32::from(w), f32::from(h));
+    Some(atlas.sub_image(rect))
+}
+
+/// The files in the resources folder that are missing or aren't what was
+/// built, in words. The web and Android builds can't look, so find none.
+pub fn check() -> Vec<String> {
+    #[cfg(any(target_os = "android", target_arch = "wasm32"))]
+    return Vec::new();
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    {
+        use crate::signing;
+
+        let root = std::path::Path::new("resources");
+        if !root.is_dir() {
+            return vec!["No resources folder, using built-in sprites".to_owned()];
+        }
+        MANIFEST
+            .iter()
+            .filter_map(|&(file, size, sum)| {
+                let bytes = match std::fs::read(root.join(file)) {
+                    Ok(bytes) => bytes,
+                    Err(_) => return Some(format!("resources/{file} is missing")),
+                };
+                let changed =
+                    bytes.len() as u64 != size || signing::hex(&signing::sha256(&[&bytes])) != sum;
+                changed.then(|| format!("resources/{file} has changed since the build"))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::SPRITE_FILES;
+
+    #[test]
+    fn the_atlas_has_every_default_sprite() {
+        for file in SPRITE_FILES.into_iter().filter(|&file| file != "panel.png") {
+            let bytes = std::fs::read(format!("resources/sprites/{file}")).unwrap();
+            let on_disk = Image::from_file_with_format(&bytes, Some(ImageFormat::Png)).unwrap();
+            let built_in = sprite(file).unwrap_or_else(|| panic!("{file} isn't in the atlas"));
+            assert_eq!(built_in.bytes, on_disk.bytes, "{file}");
+        }
+        assert!(check().is_empty(), "{:?}", check());
+    }
+}
@@ -0,0 +1,60 @@
+// Assist options, picked in the settings menu and combined freely. A run
+// with any of them on is marked as assisted on the HUD and in the history,
+// and its score counts towards a separate best.
+
+use crate::adaptive::Tuning;
+use crate::pipes::GAP_HEIGHT;
+
+const SLOW_SPEED: f32 = 0.75;
+// Gaps grow by this share of the normal gap height
+const WIDE_GAP_SHARE: f32 = 0.2;
+// One-switch play widens gaps on top of that, see `one_switch.rs`
+const ONE_SWITCH_EXTRA_GAP: f32 = 40.0;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Assists {
+    pub slow_speed: bool,
+    pub wide_gaps: bool,
+    // A shield on the bird as each run starts
+    pub start_shield: bool,
+    pub one_switch: bool,
+    // Adaptive difficulty, tuned from the player's recent runs
+    pub adaptive: Option<Tuning>,
+}
+
+impl Assists {
+    pub fn any(self) -> bool {
+        self.slow_speed
+            || self.wide_gaps
+            || self.start_shield
+            || self.one_switch
+            || self.adaptive.is_some()
+    }
+
+    /// What the HUD calls a run with these on.
+    pub fn label(self) -> &'static str {
+        if self.adaptive.is_some() {
+            "ADAPTIVE DIFFICULTY"
+        } else {
+            "ASSISTED"
+        }
+    }
+
+    /// How fast the game runs, as a share of normal speed.
+    pub fn game_speed(self) -> f32 {
+        let speed = if self.slow_speed { SLOW_SPEED } else { 1.0 };
+        speed * self.adaptive.map_or(1.0, Tuning::game_speed)
+    }
+
+    /// Pixels added to every gap.
+    pub fn extra_gap(self) -> f32 {
+        let mut extra = 0.0;
+        if self.wide_gaps {
+            extra += GAP_HEIGHT * WIDE_GAP_SHARE;
+        }
+        if self.one_switch {
+            extra += ONE_SWITCH_EXTRA_GAP;
+        }
+        extra + self.adaptive.map_or(0.0, Tuning::extra_gap)
+    }
+}
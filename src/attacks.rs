@@ -0,0 +1,42 @@
+// Garbage obstacles players send each other in versus mode. Every
+// PIPES_PER_ATTACK pipes passed in a race adds one to the opponent's queue,
+// and their spawner builds it into the next pipe pair (see `Game::incoming`).
+
+use macroquad::rand;
+
+pub const PIPES_PER_ATTACK: u32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Attack {
+    // A gap even smaller than the narrow-gap modifier's
+    NarrowGap,
+    // A downdraft while flying through the pair
+    Gust,
+}
+
+impl Attack {
+    pub const ALL: [Attack; 2] = [Attack::NarrowGap, Attack::Gust];
+
+    pub fn random() -> Attack {
+        Attack::ALL[rand::gen_range(0, Attack::ALL.len())]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Attack::NarrowGap => "Narrow gap",
+            Attack::Gust => "Wind gust",
+        }
+    }
+
+    /// One letter per attack, for packing a list into a packet.
+    pub fn letter(self) -> char {
+        match self {
+            Attack::NarrowGap => 'N',
+            Attack::Gust => 'G',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Attack> {
+        Attack::ALL.into_iter().find(|a| a.letter() == letter)
+    }
+}
@@ -0,0 +1,739 @@
+// Sound effects, played in response to game events, announcer lines from
+// the theme's `voice/` folder, background music from the `music/` folder,
+// and the theme's ambience: an `ambience.wav` loop under everything else
+// (birdsong for a day theme, crickets at night), turned down a little
+// while a run is over
+//
+// A music track is either one file or a folder of stems: the first file by
+// name is the base loop and each further one is a layer that fades in as
+// the score climbs and drops out when the bird crashes. Stems all start
+// together and loop in step, so they should be the same length. However
+// many tracks there are, only the playing one and the one up next are in
+// memory: the next is streamed in between runs, and any other is let go.
+//
+// Cues are panned to where they happen relative to the bird (see `pan.rs`):
+// a ding from the pair going past, but crashes and fanfares always from the
+// middle.
+//
+// Playback needs the `audio` cargo feature. Without it the cues are still
+// resolved (so theme packs are checked the same way) but nothing is played.
+
+#[cfg(feature = "audio")]
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "audio")]
+use macroquad::audio::{
+    PlaySoundParams, Sound, load_sound, load_sound_from_bytes, play_sound, play_sound_once,
+    set_sound_volume, stop_sound,
+};
+#[cfg(feature = "audio")]
+use macroquad::file::load_file;
+use macroquad::rand::ChooseRandom;
+
+use crate::WIDTH;
+use crate::events::GameEvent;
+use crate::game::Game;
+#[cfg(feature = "audio")]
+use crate::pan;
+use crate::shutdown::Shutdown;
+use crate::theme::Theme;
+
+const MUSIC_DIR: &str = "./music";
+// Sounds this far to the side of the bird are panned all the way
+const PAN_DISTANCE: f32 = WIDTH / 2.0;
+const MUSIC_EXTENSIONS: [&str; 2] = ["ogg", "wav"];
+// Keeps the music under the sound effects
+#[cfg(feature = "audio")]
+const MUSIC_VOLUME: f32 = 0.4;
+// Each layer past the base joins after this many more points
+const POINTS_PER_LAYER: i64 = 10;
+#[cfg(feature = "audio")]
+const LAYER_FADE_SECONDS: f32 = 1.5;
+// Music volume while the announcer speaks
+#[cfg(feature = "audio")]
+const DUCK_VOLUME: f32 = 0.3;
+#[cfg(feature = "audio")]
+const DUCK_FADE_SECONDS: f32 = 0.2;
+#[cfg(feature = "audio")]
+const AMBIENCE_VOLUME: f32 = 0.3;
+// Share of that left once the bird has crashed
+#[cfg(feature = "audio")]
+const AMBIENCE_DUCK_VOLUME: f32 = 0.6;
+#[cfg(feature = "audio")]
+const AMBIENCE_FADE_SECONDS: f32 = 0.8;
+const AMBIENCE_FILE: &str = "ambience";
+// For voice clips whose length can't be read
+#[cfg(feature = "audio")]
+const FALLBACK_LINE_SECONDS: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cue {
+    Flap,
+    Point,
+    Hit,
+    Milestone,
+    // For a run that beat the best score
+    Fanfare,
+}
+
+impl Cue {
+    const ALL: [Cue; 5] = [
+        Cue::Flap,
+        Cue::Point,
+        Cue::Hit,
+        Cue::Milestone,
+        Cue::Fanfare,
+    ];
+
+    /// The cue that plays for `event`, if any.
+    pub fn of(event: GameEvent) -> Option<Cue> {
+        match event {
+            GameEvent::Flapped => Some(Cue::Flap),
+            GameEvent::PipePassed { .. } => Some(Cue::Point),
+            GameEvent::Collided => Some(Cue::Hit),
+            GameEvent::MilestoneReached { .. } => Some(Cue::Milestone),
+            GameEvent::ScoreRolledOver { .. } => Some(Cue::Fanfare),
+            _ => None,
+        }
+    }
+
+    /// What the cue sounds like, for captions.
+    pub fn caption(self) -> &'static str {
+        match self {
+            Cue::Flap => "whoosh",
+            Cue::Point => "ding!",
+            Cue::Hit => "crash",
+            Cue::Milestone => "fanfare",
+            Cue::Fanfare => "big fanfare",
+        }
+    }
+
+    // File name (without extension) inside a theme or `resources/sounds`
+    fn file_stem(self) -> &'static str {
+        match self {
+            Cue::Flap => "flap",
+            Cue::Point => "point",
+            Cue::Hit => "hit",
+            Cue::Milestone => "milestone",
+            Cue::Fanfare => "fanfare",
+        }
+    }
+
+    // What plays instead when a theme has no sound of its own for the cue
+    fn fallback(self) -> Option<Cue> {
+        match self {
+            Cue::Fanfare => Some(Cue::Milestone),
+            _ => None,
+        }
+    }
+}
+
+/// The sound files a theme can have, by path inside its folder.
+pub fn theme_sound_files() -> Vec<String> {
+    let cues = Cue::ALL
+        .iter()
+        .map(|cue| format!("{}.wav", cue.file_stem()));
+    let lines = Line::ALL
+        .iter()
+        .map(|line| format!("voice/{}.wav", line.file_stem()));
+    let ambience = format!("{AMBIENCE_FILE}.wav");
+    cues.chain(lines).chain([ambience]).collect()
+}
+
+// Announcer lines. Each is optional: a theme without the clip stays quiet.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Line {
+    NewRecord,
+    SoClose,
+    KeepGoing,
+    LevelComplete,
+}
+
+impl Line {
+    const ALL: [Line; 4] = [
+        Line::NewRecord,
+        Line::SoClose,
+        Line::KeepGoing,
+        Line::LevelComplete,
+    ];
+
+    fn of(event: GameEvent) -> Option<Line> {
+        match event {
+            GameEvent::NearMiss => Some(Line::SoClose),
+            GameEvent::MilestoneReached { .. } => Some(Line::KeepGoing),
+            GameEvent::LevelCompleted => Some(Line::LevelComplete),
+            _ => None,
+        }
+    }
+
+    // File name (without extension) inside a theme's `voice` folder
+    fn file_stem(self) -> &'static str {
+        match self {
+            Line::NewRecord => "new_record",
+            Line::SoClose => "so_close",
+            Line::KeepGoing => "keep_going",
+            Line::LevelComplete => "level_complete",
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+struct Clip {
+    sound: Sound,
+    seconds: f32,
+}
+
+#[derive(Default)]
+pub struct Audio {
+    // Each cue mixed across the stereo field, left to right, or just the
+    // one sound if it couldn't be
+    #[cfg(feature = "audio")]
+    sounds: HashMap<Cue, Vec<Sound>>,
+    #[cfg(feature = "audio")]
+    lines: HashMap<Line, Clip>,
+    // Its own channel, looping from `start_ambience` until dropped
+    #[cfg(feature = "audio")]
+    ambience: Option<Sound>,
+    // How far the ambience is ducked, from 0 to 1
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    ambience_duck: f32,
+    // Seconds until the announcer is done; one line plays at a time
+    speaking: f32,
+}
+
+impl Audio {
+    pub async fn load(theme: &Theme) -> Self {
+        let mut audio = Audio::default();
+        for cue in Cue::ALL {
+            let path = theme.sound_path(cue.file_stem()).or_else(|| {
+                cue.fallback()
+                    .and_then(|fallback| theme.sound_path(fallback.file_stem()))
+            });
+            if let Some(path) = path {
+                audio.load_cue(cue, &path).await;
+            }
+        }
+        for line in Line::ALL {
+            if let Some(path) = theme.voice_path(line.file_stem()) {
+                audio.load_line(line, &path).await;
+            }
+        }
+        if let Some(path) = theme.sound_path(AMBIENCE_FILE) {
+            audio.load_ambience(&path).await;
+        }
+        audio
+    }
+
+    #[cfg(feature = "audio")]
+    async fn load_ambience(&mut self, path: &Path) {
+        match load_sound(&path.to_string_lossy()).await {
+            Ok(sound) => self.ambience = Some(sound),
+            Err(err) => eprintln!("Could not load {}: {err}", path.display()),
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    async fn load_ambience(&mut self, _path: &Path) {}
+
+    /// Starts the theme's ambience looping, if it has any.
+    pub fn start_ambience(&self) {
+        #[cfg(feature = "audio")]
+        if let Some(sound) = &self.ambience {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume: self.ambience_volume(),
+                },
+            );
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    fn ambience_volume(&self) -> f32 {
+        AMBIENCE_VOLUME * (1.0 - self.ambience_duck * (1.0 - AMBIENCE_DUCK_VOLUME))
+    }
+
+    #[cfg(feature = "audio")]
+    async fn load_line(&mut self, line: Line, path: &Path) {
+        let loaded = match load_file(&path.to_string_lossy()).await {
+            Ok(bytes) => load_sound_from_bytes(&bytes).await.map(|sound| Clip {
+                sound,
+                seconds: wav_seconds(&bytes).unwrap_or(FALLBACK_LINE_SECONDS),
+            }),
+            Err(err) => Err(err),
+        };
+        match loaded {
+            Ok(clip) => {
+                self.lines.insert(line, clip);
+            }
+            Err(err) => eprintln!("Could not load {}: {err}", path.display()),
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    async fn load_line(&mut self, _line: Line, _path: &Path) {}
+
+    #[cfg(feature = "audio")]
+    async fn load_cue(&mut self, cue: Cue, path: &Path) {
+        let bytes = match load_file(&path.to_string_lossy()).await {
+            Ok(bytes) => bytes,
+            Err(err) => return eprintln!("Could not load {}: {err}", path.display()),
+        };
+        let mut sounds = Vec::new();
+        for copy in pan::mix(&bytes).unwrap_or_else(|| vec![bytes]) {
+            match load_sound_from_bytes(&copy).await {
+                Ok(sound) => sounds.push(sound),
+                Err(err) => return eprintln!("Could not load {}: {err}", path.display()),
+            }
+        }
+        self.sounds.insert(cue, sounds);
+    }
+
+    #[cfg(not(feature = "audio"))]
+    async fn load_cue(&mut self, _cue: Cue, _path: &Path) {}
+
+    /// Plays what `event` sounds like, `pan` from -1 (left) to 1 (right)
+    /// of the bird; see `event_pan`.
+    pub fn handle(&mut self, event: GameEvent, pan: f32) {
+        if let Some(cue) = Cue::of(event) {
+            self.play_at(cue, pan);
+        }
+        if let Some(line) = Line::of(event) {
+            self.announce(line);
+        }
+    }
+
+    /// Says `line`, unless the announcer is still busy with another.
+    #[cfg(feature = "audio")]
+    pub fn announce(&mut self, line: Line) {
+        if self.speaking > 0.0 {
+            return;
+        }
+        if let Some(clip) = self.lines.get(&line) {
+            play_sound_once(&clip.sound);
+            self.speaking = clip.seconds;
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn announce(&mut self, _line: Line) {}
+
+    /// Counts down the announcer, and fades the ambience down while
+    /// `run_over` and back up after.
+    pub fn update(&mut self, dt: f32, run_over: bool) {
+        self.speaking = (self.speaking - dt).max(0.0);
+        let target = if run_over { 1.0 } else { 0.0 };
+        #[cfg(feature = "audio")]
+        if approach(&mut self.ambience_duck, target, dt / AMBIENCE_FADE_SECONDS)
+            && let Some(sound) = &self.ambience
+        {
+            set_sound_volume(sound, self.ambience_volume());
+        }
+        #[cfg(not(feature = "audio"))]
+        let _ = target;
+    }
+
+    /// Whether a voice line is playing, for ducking the music.
+    pub fn speaking(&self) -> bool {
+        self.speaking > 0.0
+    }
+
+    /// Plays `cue` from the middle.
+    pub fn play(&self, cue: Cue) {
+        self.play_at(cue, 0.0);
+    }
+
+    #[cfg(feature = "audio")]
+    fn play_at(&self, cue: Cue, pan: f32) {
+        // Crashes are always heard full on
+        let pan = if cue == Cue::Hit { 0.0 } else { pan };
+        if let Some(sounds) = self.sounds.get(&cue)
+            && let Some(sound) = sounds.get(pan::nearest(pan)).or(sounds.first())
+        {
+            play_sound_once(sound);
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn play_at(&self, _cue: Cue, _pan: f32) {}
+}
+
+/// Where `event` happened in `game`, from -1 (left of the bird) to 1
+/// (right of it). Events with nowhere in particular are in the middle.
+pub fn event_pan(event: GameEvent, game: &Game) -> f32 {
+    let bird = game.bird.rect().center().x;
+    let x = match event {
+        // The pair that was just passed
+        GameEvent::PipePassed { .. } => game
+            .pipes
+            .iter()
+            .rfind(|pipe| pipe.passed)
+            .map(|pipe| pipe.x + pipe.width / 2.0),
+        GameEvent::ShieldBroken { x, .. } => Some(x),
+        _ => None,
+    };
+    x.map_or(0.0, |x| ((x - bird) / PAN_DISTANCE).clamp(-1.0, 1.0))
+}
+
+// A theme's ambience stops along with it
+#[cfg(feature = "audio")]
+impl Drop for Audio {
+    fn drop(&mut self) {
+        if let Some(sound) = &self.ambience {
+            stop_sound(sound);
+        }
+    }
+}
+
+impl Shutdown for Audio {
+    // Cues and lines still playing, and the ambience
+    fn shutdown(&mut self) {
+        #[cfg(feature = "audio")]
+        {
+            let lines = self.lines.values().map(|clip| &clip.sound);
+            for sound in self.sounds.values().flatten().chain(lines) {
+                stop_sound(sound);
+            }
+            if let Some(sound) = self.ambience.take() {
+                stop_sound(&sound);
+            }
+        }
+    }
+}
+
+struct Track {
+    // The file or folder name, without an extension
+    name: String,
+    // The base loop, then the layers in the order they join
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    files: Vec<PathBuf>,
+    // Their sounds, while the track is streamed in; empty if they couldn't
+    // be loaded
+    #[cfg(feature = "audio")]
+    stems: Option<Vec<Sound>>,
+}
+
+// Music tracks played in a shuffled order, moving on at the start of each
+// run
+#[derive(Default)]
+pub struct Playlist {
+    tracks: Vec<Track>,
+    // Track indices still to come this time round, next one last
+    queue: Vec<usize>,
+    playing: Option<usize>,
+    // Whether the playing track has been started, once streamed in
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    started: bool,
+    // Layers past the base that should be heard right now
+    layers: usize,
+    // Set by the journey, whose biomes bring the layers in instead of the
+    // score
+    biome_layers: Option<usize>,
+    // How loud each stem of the playing track is, from 0 to 1
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    levels: Vec<f32>,
+    // How far the music is ducked under the announcer, from 0 to 1
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    duck: f32,
+}
+
+impl Playlist {
+    /// Finds the tracks in the music folder, without loading any yet.
+    pub fn load() -> Playlist {
+        let mut playlist = Playlist::default();
+        let Ok(entries) = fs::read_dir(MUSIC_DIR) else {
+            return playlist;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir() || is_music(path))
+            .collect();
+        paths.sort();
+        playlist.tracks = paths.iter().filter_map(|path| find_track(path)).collect();
+        playlist
+    }
+
+    /// Starts the next track when a run starts, returning its name, and
+    /// brings layers in and out with the score (or the journey's biome).
+    pub fn handle(&mut self, event: GameEvent) -> Option<&str> {
+        match event {
+            GameEvent::RunStarted => {
+                self.biome_layers = None;
+                return self.skip();
+            }
+            GameEvent::PipePassed { score, .. } => {
+                self.layers = self
+                    .biome_layers
+                    .unwrap_or((score.max(0) / POINTS_PER_LAYER) as usize);
+            }
+            GameEvent::BiomeReached { biome } => {
+                self.biome_layers = Some(biome.music_layers());
+                self.layers = biome.music_layers();
+            }
+            GameEvent::Collided => {
+                self.layers = 0;
+                self.biome_layers = None;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Stops the current track and moves on to the next one, returning its
+    /// name. It starts once `stream` has it in.
+    pub fn skip(&mut self) -> Option<&str> {
+        let next = self.up_next()?;
+        self.queue.pop();
+        if let Some(current) = self.playing {
+            stop_track(&self.tracks[current]);
+        }
+        self.playing = Some(next);
+        self.started = false;
+        Some(&self.tracks[next].name)
+    }
+
+    /// Switches to the track called `name`, or the next one for `next`,
+    /// returning its name, as a level's trigger does (see `triggers.rs`).
+    /// Nothing changes for a track that isn't there or is already playing.
+    pub fn cue(&mut self, name: &str) -> Option<&str> {
+        if name != "next" {
+            let track = self.tracks.iter().position(|track| track.name == name)?;
+            if self.playing == Some(track) {
+                return None;
+            }
+            self.queue.retain(|&queued| queued != track);
+            self.queue.push(track);
+        }
+        self.skip()
+    }
+
+    // The track `skip` goes to, starting a new round when this one's done
+    fn up_next(&mut self) -> Option<usize> {
+        if self.queue.is_empty() && !self.tracks.is_empty() {
+            self.queue = (0..self.tracks.len()).collect();
+            self.queue.shuffle();
+            // A new round shouldn't open with the track that just ended
+            if self.tracks.len() > 1 && self.queue.last() == self.playing.as_ref() {
+                self.queue.swap(0, self.tracks.len() - 1);
+            }
+        }
+        self.queue.last().copied()
+    }
+
+    /// Streams the playing track in and starts it, and while `between_runs`
+    /// the next one too, so a run starting doesn't wait on the disk. Every
+    /// other track is let go. Call once a frame.
+    #[cfg(feature = "audio")]
+    pub async fn stream(&mut self, between_runs: bool) {
+        let next = if between_runs {
+            self.up_next()
+        } else {
+            self.queue.last().copied()
+        };
+        for (i, track) in self.tracks.iter_mut().enumerate() {
+            if Some(i) != self.playing && Some(i) != next {
+                track.stems = None;
+            }
+        }
+        if let Some(playing) = self.playing
+            && !self.started
+        {
+            let track = &mut self.tracks[playing];
+            if track.stems.is_none() {
+                track.stems = Some(load_stems(&track.files).await);
+            }
+            self.levels = play_track(&self.tracks[playing], self.layers, self.volume());
+            self.started = true;
+        }
+        if between_runs
+            && let Some(next) = next
+            && self.tracks[next].stems.is_none()
+        {
+            let track = &mut self.tracks[next];
+            track.stems = Some(load_stems(&track.files).await);
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub async fn stream(&mut self, _between_runs: bool) {}
+
+    /// How many tracks are in memory, and how many there are.
+    pub fn streamed(&self) -> (usize, usize) {
+        #[cfg(feature = "audio")]
+        let loaded = self.tracks.iter().filter(|t| t.stems.is_some()).count();
+        #[cfg(not(feature = "audio"))]
+        let loaded = 0;
+        (loaded, self.tracks.len())
+    }
+
+    /// Fades layers towards what the score calls for, and the whole track
+    /// down while `ducked`.
+    #[cfg(feature = "audio")]
+    pub fn update(&mut self, dt: f32, ducked: bool) {
+        let Some(playing) = self.playing else {
+            return;
+        };
+        let target = if ducked { 1.0 } else { 0.0 };
+        let duck_moved = approach(&mut self.duck, target, dt / DUCK_FADE_SECONDS);
+        let volume = self.volume();
+        let step = dt / LAYER_FADE_SECONDS;
+        let Some(stems) = &self.tracks[playing].stems else {
+            return;
+        };
+        // The base loop, layer 0, is always up
+        for (layer, (stem, level)) in stems.iter().zip(&mut self.levels).enumerate() {
+            let target = if layer <= self.layers { 1.0 } else { 0.0 };
+            if approach(level, target, step) || duck_moved {
+                set_sound_volume(stem, *level * volume);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn update(&mut self, _dt: f32, _ducked: bool) {}
+
+    // A full stem's volume, after ducking
+    #[cfg(feature = "audio")]
+    fn volume(&self) -> f32 {
+        MUSIC_VOLUME * (1.0 - self.duck * (1.0 - DUCK_VOLUME))
+    }
+}
+
+impl Shutdown for Playlist {
+    fn shutdown(&mut self) {
+        if let Some(playing) = self.playing.take() {
+            stop_track(&self.tracks[playing]);
+        }
+    }
+}
+
+fn is_music(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| MUSIC_EXTENSIONS.iter().any(|known| ext == *known))
+}
+
+#[cfg(feature = "audio")]
+fn find_track(path: &Path) -> Option<Track> {
+    let files = if path.is_dir() {
+        let mut stems: Vec<PathBuf> = fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| is_music(path))
+            .collect();
+        stems.sort();
+        stems
+    } else {
+        vec![path.to_path_buf()]
+    };
+    if files.is_empty() {
+        return None;
+    }
+    Some(Track {
+        name: track_name(path),
+        files,
+        stems: None,
+    })
+}
+
+// Nothing would play, so there's no point announcing tracks
+#[cfg(not(feature = "audio"))]
+fn find_track(_path: &Path) -> Option<Track> {
+    None
+}
+
+// A track's stems, or none if any of them can't be loaded
+#[cfg(feature = "audio")]
+async fn load_stems(files: &[PathBuf]) -> Vec<Sound> {
+    let mut stems = Vec::new();
+    for file in files {
+        match load_sound(&file.to_string_lossy()).await {
+            Ok(sound) => stems.push(sound),
+            Err(err) => {
+                eprintln!("Could not load {}: {err}", file.display());
+                return Vec::new();
+            }
+        }
+    }
+    stems
+}
+
+#[cfg(feature = "audio")]
+fn track_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .replace('_', " ")
+}
+
+// Moves `value` up to `step` closer to `target`. Returns whether it moved.
+#[cfg(feature = "audio")]
+fn approach(value: &mut f32, target: f32, step: f32) -> bool {
+    if *value == target {
+        return false;
+    }
+    *value = if target > *value {
+        (*value + step).min(target)
+    } else {
+        (*value - step).max(target)
+    };
+    true
+}
+
+// How long a WAV file plays, from its header
+#[cfg(feature = "audio")]
+fn wav_seconds(bytes: &[u8]) -> Option<f32> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let u32_at = |at: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+    };
+    let (mut byte_rate, mut data) = (None, None);
+    let mut chunk = 12;
+    while chunk + 8 <= bytes.len() {
+        let size = u32_at(chunk + 4)? as usize;
+        match &bytes[chunk..chunk + 4] {
+            b"fmt " => byte_rate = u32_at(chunk + 16),
+            b"data" => data = Some(size),
+            _ => {}
+        }
+        // Chunks are padded to an even length; a size past the end of the
+        // file ends the loop rather than overflowing
+        chunk = chunk.saturating_add(8).saturating_add(size + size % 2);
+    }
+    let byte_rate = byte_rate.filter(|&rate| rate > 0)?;
+    Some(data? as f32 / byte_rate as f32)
+}
+
+// Starts every stem at once, with `layers` past the base already up at
+// `volume`. Returns the stems' levels.
+#[cfg(feature = "audio")]
+fn play_track(track: &Track, layers: usize, volume: f32) -> Vec<f32> {
+    let stems = track.stems.as_deref().unwrap_or_default();
+    let levels: Vec<f32> = (0..stems.len())
+        .map(|stem| if stem <= layers { 1.0 } else { 0.0 })
+        .collect();
+    for (stem, level) in stems.iter().zip(&levels) {
+        play_sound(
+            stem,
+            PlaySoundParams {
+                looped: true,
+                volume: level * volume,
+            },
+        );
+    }
+    levels
+}
+
+#[cfg(feature = "audio")]
+fn stop_track(track: &Track) {
+    for stem in track.stems.iter().flatten() {
+        stop_sound(stem);
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+fn stop_track(_track: &Track) {}
@@ -0,0 +1,128 @@
+// The backdrop behind the course: the theme's background image repeated
+// across the widest layout, drifting left at PARALLAX times the course's
+// speed for a little depth.
+//
+// The image is made to loop when the theme loads. Its last BAND columns
+// are crossfaded into its first ones and dropped, so one copy runs into the
+// next without a seam even when the art wasn't drawn to tile. Copies are
+// placed on whole pixels of the virtual screen, which is shown at
+// whole-number scales wherever it fits (see `viewport.rs`), so they never
+// crack apart or shimmer as they move, at any speed.
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::viewport::{FIELD_LEFT, FIELD_RIGHT};
+
+pub const PARALLAX: f32 = 0.2;
+// Columns crossfaded, or a quarter of a narrower image
+const BAND: u16 = 16;
+
+pub struct Backdrop {
+    texture: Texture2D,
+    // Crossfaded columns at the start of each copy
+    band: u16,
+}
+
+impl Backdrop {
+    /// A looping backdrop of `image`.
+    pub fn new(image: &Image) -> Backdrop {
+        let band = BAND.min(image.width / 4);
+        let texture = Texture2D::from_image(&looped(image, band));
+        texture.set_filter(FilterMode::Nearest);
+        Backdrop { texture, band }
+    }
+
+    pub fn texture(&self) -> &Texture2D {
+        &self.texture
+    }
+
+    // The left edge of each copy on screen, `scroll` pixels along the
+    // course, always a whole pixel
+    fn copies(&self, scroll: f32) -> impl Iterator<Item = f32> {
+        let width = self.texture.width();
+        let shift = (scroll * PARALLAX).round().rem_euclid(width);
+        let first = ((FIELD_LEFT + shift) / width).floor() as i32;
+        let last = ((FIELD_RIGHT + shift) / width).ceil() as i32;
+        (first..last).map(move |i| i as f32 * width - shift)
+    }
+
+    /// Draws the backdrop as it is `scroll` pixels along the course.
+    pub fn draw(&self, scroll: f32) {
+        for x in self.copies(scroll) {
+            draw_texture(&self.texture, x, 0.0, WHITE);
+        }
+    }
+
+    /// Outlines each copy and shades its crossfaded columns, for checking
+    /// the loop (see `debug_step.rs`).
+    pub fn draw_tiles(&self, scroll: f32) {
+        let width = self.texture.width();
+        for x in self.copies(scroll) {
+            let band = f32::from(self.band);
+            draw_rectangle(x, 0.0, band, HEIGHT, Color::new(1.0, 0.9, 0.0, 0.3));
+            draw_rectangle_lines(x, 0.0, width, HEIGHT, 1.0, RED);
+            draw_text(&format!("{x}"), x + 3.0, 12.0, 12.0, RED);
+        }
+    }
+}
+
+// `image` less its last `band` columns, which are faded into its first
+// ones instead, so its right edge runs on into its left
+fn looped(image: &Image, band: u16) -> Image {
+    let (width, height) = (image.width as usize, image.height as usize);
+    let band = band as usize;
+    let out_width = width - band;
+    let mut bytes = Vec::with_capacity(out_width * height * 4);
+    for y in 0..height {
+        let row = &image.bytes[y * width * 4..(y + 1) * width * 4];
+        for x in 0..out_width {
+            let here = &row[x * 4..x * 4 + 4];
+            if x >= band {
+                bytes.extend_from_slice(here);
+                continue;
+            }
+            // From the dropped columns at the band's left to the image's
+            // own at its right
+            let dropped = &row[(out_width + x) * 4..(out_width + x) * 4 + 4];
+            let t = (x as f32 + 0.5) / band as f32;
+            for (&from, &to) in dropped.iter().zip(here) {
+                bytes.push((f32::from(from) + (f32::from(to) - f32::from(from)) * t).round() as u8);
+            }
+        }
+    }
+    Image {
+        bytes,
+        width: out_width as u16,
+        height: height as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_loop_has_no_seam() {
+        // A ramp from black to white across, which doesn't tile at all
+        let (width, band) = (64_u16, 8);
+        let bytes = (0..width)
+            .flat_map(|x| {
+                let v = (x * 4) as u8;
+                [v, v, v, 255]
+            })
+            .collect();
+        let image = Image {
+            bytes,
+            width,
+            height: 1,
+        };
+        let looped = looped(&image, band);
+        let shade = |x: usize| i32::from(looped.bytes[x * 4]);
+        let last = looped.width as usize - 1;
+        // Wrapping round steps no further than any two columns beside each
+        // other do
+        let steepest = (0..last).map(|x| (shade(x + 1) - shade(x)).abs()).max();
+        assert!((shade(0) - shade(last)).abs() <= steepest.unwrap());
+    }
+}
@@ -0,0 +1,115 @@
+// Benchmark mode (`--bench`, plus `--headless` to skip the window): a
+// scripted session on a fixed course, flown by the kiosk autopilot with a
+// particle burst on every tick, timed frame by frame and summed up as one
+// line of JSON for scripts to compare between builds.
+//
+// Every frame runs exactly one tick, so both modes do the same work: a
+// minute of play. Windowed frame times include waiting for the display, so
+// the time spent working on each frame is reported alongside them.
+
+use macroquad::prelude::*;
+
+use crate::game::{Game, GameState};
+use crate::kiosk;
+use crate::particles::Particles;
+use crate::perf::{self, percentile};
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::viewport::{Viewport, WideView};
+
+const SEED: u64 = 0xbe4c;
+const TICKS: u32 = 60 * 60;
+// Particles thrown off the bird every tick, to keep plenty on screen
+const BURST_PARTICLES: usize = 30;
+
+struct Bench {
+    game: Game,
+    particles: Particles,
+    // Milliseconds for each frame, and spent working on it
+    frames: Vec<f32>,
+    work: Vec<f32>,
+    // Crashes along the way, which restart the run
+    crashes: u32,
+}
+
+impl Bench {
+    fn new() -> Bench {
+        Bench {
+            game: Game::from_seed(SEED),
+            particles: Particles::default(),
+            frames: Vec::new(),
+            work: Vec::new(),
+            crashes: 0,
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.game.state != GameState::Playing {
+            if self.game.state == GameState::GameOver {
+                self.crashes += 1;
+            }
+            self.game.reset();
+            self.game.flap();
+        }
+        if kiosk::autopilot_flap(&self.game) {
+            self.game.flap();
+        }
+        self.game.update();
+        self.game.events.drain();
+        let center = self.game.bird.rect().center();
+        self.particles.burst(center, BURST_PARTICLES, 200.0, GOLD);
+        self.particles.update(TICK_SECONDS);
+    }
+
+    fn report(&self, mode: &str) -> String {
+        let average = |samples: &[f32]| samples.iter().sum::<f32>() / samples.len().max(1) as f32;
+        format!(
+            "{{\"mode\":\"{mode}\",\"seed\":{SEED},\"frames\":{},\"avg_ms\":{:.3},\"p99_ms\":{:.3},\"work_avg_ms\":{:.3},\"work_p99_ms\":{:.3},\"crashes\":{}}}",
+            self.frames.len(),
+            average(&self.frames),
+            percentile(self.frames.clone(), 0.99),
+            average(&self.work),
+            percentile(self.work.clone(), 0.99),
+            self.crashes,
+        )
+    }
+}
+
+/// Runs the session without drawing anything, as fast as it goes.
+pub fn run_headless() -> String {
+    let mut bench = Bench::new();
+    for _ in 0..TICKS {
+        let started = perf::clock();
+        bench.tick();
+        let ms = ((perf::clock() - started) * 1000.0) as f32;
+        bench.frames.push(ms);
+        bench.work.push(ms);
+    }
+    bench.report("headless")
+}
+
+/// Runs the session in the window, drawn with `theme`.
+pub async fn run_windowed(theme: &Theme) -> String {
+    let mut bench = Bench::new();
+    let mut viewport = Viewport::new();
+    let mut frame_started = perf::clock();
+    for _ in 0..TICKS {
+        let started = perf::clock();
+        bench.tick();
+        viewport.fit(WideView::Extend);
+        bench.game.layout = viewport.layout();
+        viewport.begin();
+        clear_background(theme.sky_color);
+        theme.draw_background(bench.game.scrolled());
+        bench.game.draw(theme);
+        bench.particles.draw();
+        viewport.end(theme, bench.game.grade(theme));
+        bench.work.push(((perf::clock() - started) * 1000.0) as f32);
+
+        next_frame().await;
+        let now = perf::clock();
+        bench.frames.push(((now - frame_started) * 1000.0) as f32);
+        frame_started = now;
+    }
+    bench.report("windowed")
+}
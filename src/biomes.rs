@@ -0,0 +1,147 @@
+// Journey: an endless run through changing country, picked with C like the
+// other challenges (or `--mode journey`). Every BIOME_PIPES pipes the course
+// moves on to the next biome, and after the mountains it starts over in the
+// meadow. Each biome has its own colors and pipes and brings in another
+// music layer; the city has the enemy birds, the cave rock above and below
+// (see `terrain.rs`) and the mountains wind. Colors and the rock blend from
+// one biome into the next over the last pipes before it, and so does each
+// one's color grade (see `grading.rs`): the cave dim and hard, the
+// mountains' weather grey and cold.
+
+use macroquad::prelude::*;
+
+use crate::grading::Grade;
+
+use crate::pipes::PipeStyle;
+
+pub const BIOME_PIPES: u32 = 30;
+// Pipes' worth of course the change from one biome to the next takes
+const BLEND_PIPES: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Biome {
+    Meadow,
+    City,
+    Cave,
+    Mountains,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [Biome::Meadow, Biome::City, Biome::Cave, Biome::Mountains];
+
+    /// The biome pipe pair `number` (counted from 1) stands in.
+    pub fn for_pipe(number: u32) -> Biome {
+        let stretch = number.saturating_sub(1) / BIOME_PIPES;
+        Biome::ALL[stretch as usize % Biome::ALL.len()]
+    }
+
+    /// The biomes either side of `pipe` pipes along the course (counted
+    /// like `for_pipe`, so 2.5 is halfway between the second and third
+    /// pairs), and how far the change from the first to the second is.
+    pub fn blend_at(pipe: f32) -> (Biome, Biome, f32) {
+        // Biomes change halfway between their last and first pairs
+        let stretch = (pipe - 0.5) / BIOME_PIPES as f32;
+        let into = (stretch.fract() * BIOME_PIPES as f32 - (BIOME_PIPES as f32 - BLEND_PIPES))
+            / BLEND_PIPES;
+        let from = stretch.max(0.0) as usize;
+        let to = if into > 0.0 { from + 1 } else { from };
+        let count = Biome::ALL.len();
+        (
+            Biome::ALL[from % count],
+            Biome::ALL[to % count],
+            into.clamp(0.0, 1.0),
+        )
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Biome::Meadow => "Meadow",
+            Biome::City => "City",
+            Biome::Cave => "Cave",
+            Biome::Mountains => "Mountains",
+        }
+    }
+
+    /// Music layers past the base loop it brings in.
+    pub fn music_layers(self) -> usize {
+        self as usize
+    }
+
+    pub fn pipe_style(self) -> PipeStyle {
+        match self {
+            Biome::Meadow => PipeStyle::Tree,
+            Biome::City => PipeStyle::Pipe,
+            Biome::Cave | Biome::Mountains => PipeStyle::Stone,
+        }
+    }
+
+    pub fn has_enemies(self) -> bool {
+        self == Biome::City
+    }
+
+    pub fn windy(self) -> bool {
+        self == Biome::Mountains
+    }
+
+    // Washed over the world, giving each biome its colors
+    fn grade(self) -> Color {
+        match self {
+            Biome::Meadow => Color::new(0.45, 0.8, 0.3, 0.08),
+            Biome::City => Color::new(0.35, 0.4, 0.55, 0.25),
+            Biome::Cave => Color::new(0.08, 0.05, 0.12, 0.45),
+            Biome::Mountains => Color::new(0.75, 0.85, 1.0, 0.2),
+        }
+    }
+
+    // Put on the whole screen, on top of the theme's
+    fn mood(self) -> Grade {
+        match self {
+            Biome::Meadow => Grade {
+                saturation: 1.1,
+                ..Grade::default()
+            },
+            Biome::City => Grade {
+                tint: Color::new(0.95, 0.97, 1.0, 1.0),
+                saturation: 0.9,
+                ..Grade::default()
+            },
+            Biome::Cave => Grade {
+                tint: Color::new(0.9, 0.85, 1.0, 1.0),
+                saturation: 0.7,
+                contrast: 1.2,
+                brightness: -0.06,
+            },
+            Biome::Mountains => Grade {
+                tint: Color::new(0.88, 0.94, 1.0, 1.0),
+                saturation: 0.65,
+                contrast: 1.1,
+                brightness: 0.02,
+            },
+        }
+    }
+}
+
+/// How much of the cave's rock there is `pipe` pipes along, from 0 where
+/// there's none to 1 inside the cave.
+pub fn cave_at(pipe: f32) -> f32 {
+    let (from, to, t) = Biome::blend_at(pipe);
+    let cave = |biome| if biome == Biome::Cave { 1.0 } else { 0.0 };
+    cave(from) + (cave(to) - cave(from)) * t
+}
+
+/// The color grade `pipe` pipes along.
+pub fn mood_at(pipe: f32) -> Grade {
+    let (from, to, t) = Biome::blend_at(pipe);
+    from.mood().lerp(to.mood(), t)
+}
+
+/// Washes the biome's colors over everything drawn so far, between `left`
+/// and `right`. The bird's spot, `pipe` pipes along, sets them.
+pub fn draw_grade(pipe: f32, left: f32, right: f32, height: f32) {
+    let (from, to, t) = Biome::blend_at(pipe);
+    let (a, b) = (from.grade().to_vec(), to.grade().to_vec());
+    let color = Color::from_vec(a + (b - a) * t);
+    if color.a > 0.0 {
+        draw_rectangle(left, 0.0, right - left, height, color);
+    }
+}
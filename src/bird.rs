@@ -0,0 +1,138 @@
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+
+// The bird's hitbox, which is also the default sprite's size. A theme's
+// sprite of another size is stretched to it
+pub const BIRD_WIDTH: f32 = 34.0;
+pub const BIRD_HEIGHT: f32 = 24.0;
+// Upward speed right after a flap, with classic physics (see `physics.rs`)
+pub const JUMP_VELOCITY: f32 = -10.0;
+// Where the bird flies, and how far sideways momentum can take it from there
+pub const HOME_X: f32 = 100.0;
+pub const DRIFT_BAND: f32 = 30.0;
+
+// Coins within this distance are pulled in while the magnet is active
+pub const MAGNET_RADIUS: f32 = 90.0;
+
+/// A sprite drawn over the bird, like a hat, moving and scaling with it.
+pub struct Layer<'a> {
+    pub texture: &'a Texture2D,
+    // The part of `texture` to draw, or all of it
+    pub source: Option<Rect>,
+    // Where on the bird it goes, in hitbox pixels at the usual size
+    pub anchor: Vec2,
+    // The point in the layer's own pixels that goes on the anchor
+    pub pivot: Vec2,
+}
+
+// Bird struct
+#[derive(Clone)]
+pub struct Bird {
+    pub x: f32,
+    pub y: f32,
+    // Sideways speed is 0 except under icy physics
+    pub velocity: Vec2,
+    pub shield: bool,
+    // Seconds left during which pipes can't hurt the bird
+    pub invincible: f32,
+    // Seconds left on the magnet power-up
+    pub magnet: f32,
+    // Size compared to the sprite, for the tiny and giant bird mutators
+    pub scale: f32,
+}
+
+impl Bird {
+    pub fn new() -> Self {
+        Bird {
+            x: HOME_X,
+            y: HEIGHT / 2.0,
+            velocity: Vec2::ZERO,
+            shield: false,
+            invincible: 0.0,
+            magnet: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    /// Moves the bird on a tick, falling no faster than `max_fall`.
+    pub fn update(&mut self, gravity: f32, max_fall: f32) {
+        self.velocity.y = (self.velocity.y + gravity).min(max_fall);
+        self.y += self.velocity.y;
+        self.x += self.velocity.x;
+        let band = HOME_X - DRIFT_BAND..=HOME_X + DRIFT_BAND;
+        if !band.contains(&self.x) {
+            self.x = self.x.clamp(*band.start(), *band.end());
+            self.velocity.x = 0.0;
+        }
+    }
+
+    /// A flap at `strength` times the usual speed.
+    pub fn jump_with(&mut self, strength: f32) {
+        self.velocity.y = JUMP_VELOCITY * strength;
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            self.x,
+            self.y,
+            BIRD_WIDTH * self.scale,
+            BIRD_HEIGHT * self.scale,
+        )
+    }
+
+    pub fn draw(&self, texture: &Texture2D, tint: Color) {
+        self.draw_layered(texture, tint, &[]);
+    }
+
+    /// Draws the bird with `layers` over it, in order.
+    pub fn draw_layered(&self, texture: &Texture2D, tint: Color, layers: &[Layer]) {
+        // Blink while invincible
+        if self.invincible > 0.0 && (self.invincible * 10.0) as i32 % 2 == 0 {
+            return;
+        }
+        let rect = self.rect();
+        draw_texture_ex(
+            texture,
+            self.x,
+            self.y,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(rect.size()),
+                ..Default::default()
+            },
+        );
+        for layer in layers {
+            let size = layer
+                .source
+                .map_or(layer.texture.size(), |source| source.size());
+            let pos = rect.point() + (layer.anchor - layer.pivot) * self.scale;
+            draw_texture_ex(
+                layer.texture,
+                pos.x,
+                pos.y,
+                tint,
+                DrawTextureParams {
+                    dest_size: Some(size * self.scale),
+                    source: layer.source,
+                    ..Default::default()
+                },
+            );
+        }
+        if self.magnet > 0.0 {
+            let center = rect.center();
+            draw_circle_lines(
+                center.x,
+                center.y,
+                MAGNET_RADIUS,
+                1.0,
+                Color::new(1.0, 0.3, 0.3, 0.35),
+            );
+        }
+        if self.shield {
+            let (center, radius) = (rect.center(), rect.w * 0.7);
+            draw_circle(center.x, center.y, radius, Color::new(0.5, 0.8, 1.0, 0.25));
+            draw_circle_lines(center.x, center.y, radius, 1.5, SKYBLUE);
+        }
+    }
+}
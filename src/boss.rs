@@ -0,0 +1,176 @@
+// Boss encounter: a giant bird that charges across the screen a few times
+//
+// The fight is a small state machine: the boss flies in, then for each charge
+// it lines up with the player (telegraphed by a red band), locks its height
+// and charges across the screen, coming back in from the right for the next
+// one. Surviving every charge beats the boss.
+
+use macroquad::prelude::*;
+
+use crate::obstacle::Obstacle;
+use crate::sim_math;
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::{HEIGHT, WIDTH};
+
+const BOSS_WIDTH: f32 = 68.0;
+const BOSS_HEIGHT: f32 = 48.0;
+// Where the boss hovers between charges
+const HOME_X: f32 = WIDTH - BOSS_WIDTH - 12.0;
+const FLY_IN_SPEED: f32 = 2.0;
+const TELEGRAPH_SECONDS: f32 = 1.0;
+// The boss stops following the player this long before it charges
+const LOCK_SECONDS: f32 = 0.35;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Charge {
+    Straight,
+    Wavy,
+    Fast,
+}
+
+impl Charge {
+    fn speed(self) -> f32 {
+        match self {
+            Charge::Straight | Charge::Wavy => 6.0,
+            Charge::Fast => 9.0,
+        }
+    }
+}
+
+const PATTERN: [Charge; 3] = [Charge::Straight, Charge::Wavy, Charge::Fast];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    FlyIn,
+    Telegraph { timer: f32 },
+    Charging { base_y: f32, age: f32 },
+    Done,
+}
+
+#[derive(Clone)]
+pub struct Boss {
+    x: f32,
+    y: f32,
+    phase: Phase,
+    // Index into `PATTERN` of the current charge
+    charge: usize,
+    // The player's height, which the boss lines up with before charging
+    pub aim_y: f32,
+}
+
+impl Boss {
+    pub fn new() -> Self {
+        Boss {
+            x: WIDTH + 20.0,
+            y: HEIGHT / 2.0 - BOSS_HEIGHT / 2.0,
+            phase: Phase::FlyIn,
+            charge: 0,
+            aim_y: HEIGHT / 2.0,
+        }
+    }
+
+    /// True once every charge has been survived.
+    pub fn is_beaten(&self) -> bool {
+        self.phase == Phase::Done
+    }
+
+    fn rect(&self) -> Rect {
+        // A little smaller than the sprite so grazing a wing tip is forgiven
+        Rect::new(
+            self.x + 6.0,
+            self.y + 6.0,
+            BOSS_WIDTH - 12.0,
+            BOSS_HEIGHT - 12.0,
+        )
+    }
+}
+
+impl Obstacle for Boss {
+    fn update(&mut self) {
+        match self.phase {
+            Phase::FlyIn => {
+                self.x = (self.x - FLY_IN_SPEED).max(HOME_X);
+                if self.x == HOME_X {
+                    self.phase = Phase::Telegraph { timer: 0.0 };
+                }
+            }
+            Phase::Telegraph { timer } => {
+                if timer < TELEGRAPH_SECONDS - LOCK_SECONDS {
+                    let target = (self.aim_y - BOSS_HEIGHT / 2.0).clamp(0.0, HEIGHT - BOSS_HEIGHT);
+                    self.y += (target - self.y) * 0.15;
+                }
+                let timer = timer + TICK_SECONDS;
+                self.phase = if timer >= TELEGRAPH_SECONDS {
+                    Phase::Charging {
+                        base_y: self.y,
+                        age: 0.0,
+                    }
+                } else {
+                    Phase::Telegraph { timer }
+                };
+            }
+            Phase::Charging { base_y, age } => {
+                let charge = PATTERN[self.charge];
+                self.x -= charge.speed();
+                if charge == Charge::Wavy {
+                    self.y = base_y + sim_math::sin(age * 6.0) * 50.0;
+                }
+                self.phase = Phase::Charging {
+                    base_y,
+                    age: age + TICK_SECONDS,
+                };
+
+                if self.x + BOSS_WIDTH < 0.0 {
+                    self.charge += 1;
+                    if self.charge == PATTERN.len() {
+                        self.phase = Phase::Done;
+                    } else {
+                        self.x = WIDTH + 20.0;
+                        self.y = base_y;
+                        self.phase = Phase::FlyIn;
+                    }
+                }
+            }
+            Phase::Done => {}
+        }
+    }
+
+    fn hitboxes(&self) -> Vec<Rect> {
+        vec![self.rect()]
+    }
+
+    fn on_screen(&self) -> bool {
+        self.x < WIDTH && self.x + BOSS_WIDTH > 0.0
+    }
+
+    fn is_gone(&self) -> bool {
+        self.is_beaten()
+    }
+
+    fn draw(&self, theme: &Theme) {
+        if let Phase::Telegraph { timer } = self.phase {
+            // Warning band along the charge line, blinking faster near the end
+            if (timer * (4.0 + timer * 12.0)) as i32 % 2 == 0 {
+                draw_rectangle(
+                    0.0,
+                    self.y,
+                    WIDTH,
+                    BOSS_HEIGHT,
+                    Color::new(1.0, 0.0, 0.0, 0.18),
+                );
+            }
+        }
+        draw_texture_ex(
+            &theme.bird,
+            self.x,
+            self.y,
+            Color::new(0.75, 0.45, 1.0, 1.0),
+            DrawTextureParams {
+                dest_size: Some(vec2(BOSS_WIDTH, BOSS_HEIGHT)),
+                flip_x: true,
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Captions for sound cues ("ding!", "crash"), shown under the coin counter
+// for players who can't hear them. They follow the same events as the
+// sounds, muted or not.
+
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::events::GameEvent;
+
+const SHOW_SECONDS: f32 = 1.2;
+const MAX_LINES: usize = 3;
+const FONT_SIZE: f32 = 16.0;
+
+struct Caption {
+    cue: Cue,
+    // Times the cue played while this caption was up
+    count: u32,
+    age: f32,
+}
+
+#[derive(Default)]
+pub struct Captions {
+    // Newest last
+    lines: Vec<Caption>,
+}
+
+impl Captions {
+    pub fn handle(&mut self, event: GameEvent) {
+        if let Some(cue) = Cue::of(event) {
+            self.show(cue);
+        }
+    }
+
+    /// Captions a cue played outside the game's events.
+    pub fn show(&mut self, cue: Cue) {
+        // A cue that keeps playing, like flapping, stays on one line
+        if let Some(line) = self.lines.iter_mut().find(|line| line.cue == cue) {
+            line.count += 1;
+            line.age = 0.0;
+            return;
+        }
+        if self.lines.len() == MAX_LINES {
+            self.lines.remove(0);
+        }
+        self.lines.push(Caption {
+            cue,
+            count: 1,
+            age: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for line in &mut self.lines {
+            line.age += dt;
+        }
+        self.lines.retain(|line| line.age < SHOW_SECONDS);
+    }
+
+    pub fn draw(&self, color: Color) {
+        for (i, line) in self.lines.iter().enumerate() {
+            // Fades out over the last third
+            let alpha = ((SHOW_SECONDS - line.age) / (SHOW_SECONDS / 3.0)).min(1.0);
+            let text = match line.count {
+                1 => format!("[{}]", line.cue.caption()),
+                count => format!("[{} x{count}]", line.cue.caption()),
+            };
+            let y = 44.0 + i as f32 * (FONT_SIZE + 2.0);
+            draw_text(&text, 8.0, y, FONT_SIZE, Color { a: alpha, ..color });
+        }
+    }
+}
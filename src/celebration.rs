@@ -0,0 +1,90 @@
+// The results-card celebration for a run that beat the stored best: a burst
+// of confetti, a fanfare and a "NEW BEST" ribbon that unfurls above the
+// title. With reduced motion the ribbon is simply there, and the confetti
+// is left out.
+
+use macroquad::prelude::*;
+
+use crate::game::draw_centered_text;
+use crate::particles::Particles;
+use crate::tween::ease_out_cubic;
+use crate::{HEIGHT, WIDTH};
+
+const UNFURL_SECONDS: f32 = 0.5;
+const RIBBON_WIDTH: f32 = 150.0;
+const RIBBON_HEIGHT: f32 = 26.0;
+const RIBBON_Y: f32 = HEIGHT / 2.0 - 90.0;
+// How far the tails stick out past the band, and how deep their notch is
+const TAIL_WIDTH: f32 = 18.0;
+const NOTCH: f32 = 8.0;
+const RIBBON_COLOR: Color = Color::new(0.8, 0.1, 0.15, 1.0);
+const TAIL_COLOR: Color = Color::new(0.6, 0.05, 0.1, 1.0);
+
+#[derive(Default)]
+pub struct Celebration {
+    // None until the results card first shows
+    age: Option<f32>,
+}
+
+impl Celebration {
+    /// Runs while the results card is up. Returns true the first time, which
+    /// is when the fanfare should play.
+    pub fn update(&mut self, dt: f32, particles: &mut Particles, reduced_motion: bool) -> bool {
+        match &mut self.age {
+            Some(age) => {
+                *age += dt;
+                false
+            }
+            None => {
+                self.age = Some(0.0);
+                if !reduced_motion {
+                    let center = vec2(WIDTH / 2.0, RIBBON_Y);
+                    for color in [GOLD, RED, SKYBLUE, LIME, VIOLET] {
+                        particles.burst(center, 16, 240.0, color);
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    pub fn draw(&self, reduced_motion: bool) {
+        let Some(age) = self.age else {
+            return;
+        };
+        let (open, pulse) = if reduced_motion {
+            (1.0, 1.0)
+        } else {
+            let open = ease_out_cubic(age / UNFURL_SECONDS);
+            let pulse = 1.0 + 0.06 * (age * 5.0).sin() * open;
+            (open, pulse)
+        };
+
+        let half = RIBBON_WIDTH / 2.0 * open;
+        let (left, right) = (WIDTH / 2.0 - half, WIDTH / 2.0 + half);
+        let (top, bottom) = (RIBBON_Y, RIBBON_Y + RIBBON_HEIGHT);
+        // The tails sit a little lower, behind the band's ends
+        for (inner, outer) in [(left, left - TAIL_WIDTH), (right, right + TAIL_WIDTH)] {
+            let (tail_top, tail_bottom) = (top + 6.0, bottom + 6.0);
+            let notch = vec2(
+                outer + (inner - outer).signum() * NOTCH,
+                tail_top + RIBBON_HEIGHT / 2.0,
+            );
+            let corners = [
+                vec2(inner, tail_top),
+                vec2(outer, tail_top),
+                notch,
+                vec2(outer, tail_bottom),
+                vec2(inner, tail_bottom),
+            ];
+            draw_triangle(corners[0], corners[1], corners[2], TAIL_COLOR);
+            draw_triangle(corners[0], corners[2], corners[4], TAIL_COLOR);
+            draw_triangle(corners[2], corners[3], corners[4], TAIL_COLOR);
+        }
+        draw_rectangle(left, top, right - left, RIBBON_HEIGHT, RIBBON_COLOR);
+
+        if open > 0.6 {
+            draw_centered_text("NEW BEST", bottom - 7.0, 22.0 * pulse, GOLD);
+        }
+    }
+}
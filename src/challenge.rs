@@ -0,0 +1,75 @@
+// Built-in challenges, picked on the title screen with C (or `--mode
+// invisible_pipes`). Each is a fixed twist on the classic run with a best
+// score of its own in the profile.
+//
+// Weekly: this week's course and mutators, see `weekly.rs`.
+//
+// Journey: an endless run through changing biomes, see `biomes.rs`.
+//
+// Invisible pipes: each pair fades out soon after it scrolls on screen and
+// only flashes back now and then, so the course has to be remembered.
+
+const FADE_DELAY_TICKS: u32 = 20;
+const FADE_TICKS: u32 = 40;
+// Every pair flashes together, this often and for this long
+const FLASH_PERIOD_TICKS: u32 = 120;
+const FLASH_TICKS: u32 = 14;
+const FLASH_ALPHA: f32 = 0.8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Challenge {
+    InvisiblePipes,
+    Weekly,
+    Journey,
+}
+
+impl Challenge {
+    pub const ALL: [Challenge; 3] = [
+        Challenge::InvisiblePipes,
+        Challenge::Weekly,
+        Challenge::Journey,
+    ];
+
+    /// Stable name used in the profile file and for `--mode`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Challenge::InvisiblePipes => "invisible_pipes",
+            Challenge::Weekly => "weekly",
+            Challenge::Journey => "journey",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Challenge> {
+        Challenge::ALL
+            .into_iter()
+            .find(|challenge| challenge.id().eq_ignore_ascii_case(id))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Challenge::InvisiblePipes => "Invisible pipes",
+            Challenge::Weekly => "Weekly challenge",
+            Challenge::Journey => "Journey",
+        }
+    }
+
+    /// How visible a pipe pair is after `shown` ticks on screen, on tick
+    /// `ticks` of the run.
+    pub fn pipe_alpha(self, shown: u32, ticks: u32) -> f32 {
+        match self {
+            Challenge::InvisiblePipes => {
+                let fade = 1.0 - shown.saturating_sub(FADE_DELAY_TICKS) as f32 / FADE_TICKS as f32;
+                // Up and back down over the flash
+                let phase = (ticks % FLASH_PERIOD_TICKS) as f32 / FLASH_TICKS as f32;
+                let flash = if phase < 1.0 {
+                    FLASH_ALPHA * (1.0 - (phase * 2.0 - 1.0).abs())
+                } else {
+                    0.0
+                };
+                fade.max(flash).clamp(0.0, 1.0)
+            }
+            // Its mutators hide the pipes if anything does
+            Challenge::Weekly | Challenge::Journey => 1.0,
+        }
+    }
+}
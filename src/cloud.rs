@@ -0,0 +1,463 @@
+// Cloud sync: keeps the profile and run history in a WebDAV folder of the
+// player's own, so they follow them between computers. Set up in
+// `settings.cfg`, with no menu for it since it needs a password:
+//
+//     cloud_url = https://dav.example.com/flappy/
+//     cloud_user = sam
+//     cloud_password = hunter2
+//
+// Needs the `cloud` cargo feature, and isn't in web builds. Any WebDAV
+// server works, as does anything else that takes plain HTTP PUTs with
+// basic auth and sends ETag or Last-Modified back (S3 buckets need a
+// gateway in front, since S3 wants its own request signing).
+//
+// Syncing happens on its own thread, at launch, after every run and every
+// few minutes, so a slow or missing connection never holds up a frame.
+// `cloud.cfg` remembers what each file looked like on both ends at the
+// last sync. A file that only changed on one end is copied to the other;
+// one that changed on both goes with whichever changed last, and the other
+// copy is kept next to it locally as `<file>.conflict`.
+
+use crate::kv::KeyValues;
+use crate::shutdown::Shutdown;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct CloudEndpoint {
+    // The folder the files go in
+    pub url: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl CloudEndpoint {
+    /// Reads the `cloud_*` keys; without a url there's no syncing.
+    pub fn from_values(kv: &KeyValues) -> Option<CloudEndpoint> {
+        let url = kv.get("cloud_url").filter(|url| !url.is_empty())?;
+        Some(CloudEndpoint {
+            url: url.to_owned(),
+            user: kv.get("cloud_user").unwrap_or_default().to_owned(),
+            password: kv.get("cloud_password").unwrap_or_default().to_owned(),
+        })
+    }
+
+    pub fn write_values(&self, kv: &mut KeyValues) {
+        kv.set("cloud_url", &self.url);
+        kv.set("cloud_user", &self.user);
+        kv.set("cloud_password", &self.password);
+    }
+}
+
+// Only ever set by the sync thread
+#[cfg_attr(not(feature = "cloud"), allow(dead_code))]
+#[derive(Clone, PartialEq, Debug)]
+enum Status {
+    Syncing,
+    // With the clock time it finished at
+    Synced(f64),
+    Failed(String),
+}
+
+pub struct Cloud {
+    #[cfg(feature = "cloud")]
+    worker: Option<sync::Worker>,
+    // None while syncing is off
+    status: Option<Status>,
+}
+
+impl Cloud {
+    /// Starts syncing with `endpoint`, if there is one.
+    pub fn start(endpoint: Option<CloudEndpoint>) -> Cloud {
+        #[cfg(feature = "cloud")]
+        {
+            let worker = endpoint.map(sync::Worker::spawn);
+            let status = worker.as_ref().map(|_| Status::Syncing);
+            Cloud { worker, status }
+        }
+        #[cfg(not(feature = "cloud"))]
+        {
+            if endpoint.is_some() {
+                eprintln!("Cloud sync needs a build with the `cloud` feature");
+            }
+            Cloud { status: None }
+        }
+    }
+
+    /// Syncs again soon, as after a run has been saved.
+    pub fn request_sync(&self) {
+        #[cfg(feature = "cloud")]
+        if let Some(worker) = &self.worker {
+            worker.request();
+        }
+    }
+
+    /// Catches up with the sync thread. Returns true when it has brought
+    /// newer saves down, which the game should load again.
+    pub fn poll(&mut self) -> bool {
+        #[cfg(feature = "cloud")]
+        if let Some(worker) = &self.worker {
+            let mut pulled = false;
+            for report in worker.reports() {
+                self.status = Some(match report {
+                    sync::Report::Syncing => Status::Syncing,
+                    sync::Report::Synced { pulled: any } => {
+                        pulled |= any;
+                        Status::Synced(crate::perf::clock())
+                    }
+                    sync::Report::Failed(reason) => Status::Failed(reason),
+                });
+            }
+            return pulled;
+        }
+        false
+    }
+
+    /// A line for the menu, like "Cloud: synced 2 min ago".
+    pub fn status_line(&self) -> Option<String> {
+        Some(match self.status.as_ref()? {
+            Status::Syncing => "Cloud: syncing...".to_owned(),
+            Status::Synced(at) => {
+                let minutes = ((crate::perf::clock() - at) / 60.0) as u64;
+                match minutes {
+                    0 => "Cloud: synced just now".to_owned(),
+                    _ => format!("Cloud: synced {minutes} min ago"),
+                }
+            }
+            Status::Failed(reason) => format!("Cloud: sync failed ({reason})"),
+        })
+    }
+}
+
+impl Shutdown for Cloud {
+    // One last sync for the saves just written, if it's quick
+    fn shutdown(&mut self) {
+        #[cfg(feature = "cloud")]
+        if let Some(worker) = &self.worker {
+            worker.finish(crate::shutdown::NETWORK_WAIT);
+        }
+    }
+}
+
+#[cfg(feature = "cloud")]
+mod sync {
+    use std::fs;
+    use std::io::Read;
+    use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+    use std::thread;
+    use std::time::{Duration, Instant, UNIX_EPOCH};
+
+    use super::CloudEndpoint;
+    use crate::date::Date;
+    use crate::history::HISTORY_FILE;
+    use crate::kv::KeyValues;
+    use crate::platform;
+    use crate::profile::PROFILE_FILE;
+
+    const FILES: [&str; 2] = [PROFILE_FILE, HISTORY_FILE];
+    const RECORD_FILE: &str = "cloud.cfg";
+    const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+    const TIMEOUT: Duration = Duration::from_secs(20);
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    pub enum Report {
+        Syncing,
+        // Whether any file was brought down
+        Synced { pulled: bool },
+        Failed(String),
+    }
+
+    pub struct Worker {
+        requests: Sender<()>,
+        reports: Receiver<Report>,
+    }
+
+    impl Worker {
+        pub fn spawn(endpoint: CloudEndpoint) -> Worker {
+            let (requests, requested) = mpsc::channel();
+            let (reporter, reports) = mpsc::channel();
+            thread::spawn(move || run(Remote::new(endpoint), &requested, &reporter));
+            Worker { requests, reports }
+        }
+
+        pub fn request(&self) {
+            let _ = self.requests.send(());
+        }
+
+        pub fn reports(&self) -> impl Iterator<Item = Report> + '_ {
+            self.reports.try_iter()
+        }
+
+        /// Syncs again and waits up to `timeout` for that sync to finish.
+        pub fn finish(&self, timeout: Duration) {
+            // Anything reported so far is about older saves
+            while self.reports.try_recv().is_ok() {}
+            self.request();
+            let deadline = Instant::now() + timeout;
+            let mut started = false;
+            while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                match self.reports.recv_timeout(left) {
+                    Ok(Report::Syncing) => started = true,
+                    Ok(_) if started => return,
+                    Ok(_) => {}
+                    Err(_) => return,
+                }
+            }
+        }
+    }
+
+    // Syncs, then waits for a request or the interval, until the game
+    // drops its end
+    fn run(remote: Remote, requested: &Receiver<()>, reporter: &Sender<Report>) {
+        loop {
+            if reporter.send(Report::Syncing).is_err() {
+                return;
+            }
+            let report = match sync_all(&remote) {
+                Ok(pulled) => Report::Synced { pulled },
+                Err(err) => {
+                    eprintln!("Cloud sync failed: {err}");
+                    Report::Failed(err.reason())
+                }
+            };
+            if reporter.send(report).is_err() {
+                return;
+            }
+            match requested.recv_timeout(SYNC_INTERVAL) {
+                Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            // Runs ending close together only need the one sync
+            while requested.try_recv().is_ok() {}
+        }
+    }
+
+    // Syncs every file, keeping `cloud.cfg` up to date. Returns whether any
+    // local copy was replaced.
+    fn sync_all(remote: &Remote) -> Result<bool, SyncError> {
+        let path = platform::data_path(RECORD_FILE);
+        let mut record = KeyValues::load(&path).unwrap_or_default();
+        let mut pulled = false;
+        let result = FILES
+            .iter()
+            .try_for_each(|file| sync_file(remote, &mut record, file).map(|p| pulled |= p));
+        // Kept even after a failure, for the files that did sync
+        if let Err(err) = fs::write(&path, record.to_string()) {
+            eprintln!("Could not save {RECORD_FILE}: {err}");
+        }
+        result.map(|()| pulled)
+    }
+
+    // Brings `file` up to date on both ends. Returns whether the local
+    // copy was replaced.
+    fn sync_file(remote: &Remote, record: &mut KeyValues, file: &str) -> Result<bool, SyncError> {
+        let local_key = format!("{file}.local");
+        let remote_key = format!("{file}.remote");
+        let local = local_stamp(file);
+        let theirs = remote.head(file)?;
+        let local_changed =
+            local.map(|(stamp, _)| stamp.to_string()).as_deref() != record.get(&local_key);
+        let remote_changed =
+            theirs.as_ref().map(|info| info.tag.as_str()) != record.get(&remote_key);
+
+        let pull = match (&local, &theirs) {
+            (_, None) if local.is_none() => return Ok(false),
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(_), Some(_)) if !local_changed && !remote_changed => return Ok(false),
+            (Some(_), Some(_)) if !local_changed => true,
+            (Some(_), Some(_)) if !remote_changed => false,
+            (Some((_, local_secs)), Some(info)) => {
+                // Changed on both ends: the newer one wins, and the other
+                // is kept aside
+                let pull = info.modified.is_some_and(|secs| secs > *local_secs);
+                let loser = if pull {
+                    fs::read(platform::data_path(file))?
+                } else {
+                    remote.get(file)?
+                };
+                fs::write(platform::data_path(&format!("{file}.conflict")), loser)?;
+                pull
+            }
+        };
+
+        if pull {
+            write_local(file, &remote.get(file)?)?;
+        } else {
+            remote.put(file, &fs::read(platform::data_path(file))?)?;
+        }
+        // Stamps as they are now, after the copy
+        if let Some((stamp, _)) = local_stamp(file) {
+            record.set(&local_key, stamp);
+        }
+        if let Some(info) = remote.head(file)? {
+            record.set(&remote_key, info.tag);
+        }
+        Ok(pull)
+    }
+
+    // When the local copy of `file` last changed, in nanoseconds and in
+    // whole seconds since 1970
+    fn local_stamp(file: &str) -> Option<(u128, i64)> {
+        let modified = fs::metadata(platform::data_path(file))
+            .ok()?
+            .modified()
+            .ok()?;
+        let since = modified.duration_since(UNIX_EPOCH).ok()?;
+        Some((since.as_nanos(), since.as_secs() as i64))
+    }
+
+    // Written beside it first, so the game never reads half a file
+    fn write_local(file: &str, bytes: &[u8]) -> Result<(), SyncError> {
+        let path = platform::data_path(file);
+        let partial = platform::data_path(&format!("{file}.partial"));
+        fs::write(&partial, bytes)?;
+        fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    struct RemoteInfo {
+        // Changes whenever the file does: its ETag, or its Last-Modified
+        tag: String,
+        // Seconds since 1970
+        modified: Option<i64>,
+    }
+
+    struct Remote {
+        agent: ureq::Agent,
+        // Ends in a slash
+        base: String,
+        auth: String,
+    }
+
+    impl Remote {
+        fn new(endpoint: CloudEndpoint) -> Remote {
+            let mut base = endpoint.url;
+            if !base.ends_with('/') {
+                base.push('/');
+            }
+            let credentials = format!("{}:{}", endpoint.user, endpoint.password);
+            Remote {
+                agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+                base,
+                auth: format!("Basic {}", base64(credentials.as_bytes())),
+            }
+        }
+
+        fn request(&self, method: &str, file: &str) -> ureq::Request {
+            self.agent
+                .request(method, &format!("{}{file}", self.base))
+                .set("Authorization", &self.auth)
+        }
+
+        // None when the file isn't there yet
+        fn head(&self, file: &str) -> Result<Option<RemoteInfo>, SyncError> {
+            let response = match self.request("HEAD", file).call() {
+                Ok(response) => response,
+                Err(ureq::Error::Status(404, _)) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let last_modified = response.header("Last-Modified");
+            let tag = response
+                .header("ETag")
+                .or(last_modified)
+                .unwrap_or_default();
+            Ok(Some(RemoteInfo {
+                tag: tag.to_owned(),
+                modified: last_modified.and_then(http_date_seconds),
+            }))
+        }
+
+        fn get(&self, file: &str) -> Result<Vec<u8>, SyncError> {
+            let mut bytes = Vec::new();
+            self.request("GET", file)
+                .call()?
+                .into_reader()
+                .read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+
+        fn put(&self, file: &str, bytes: &[u8]) -> Result<(), SyncError> {
+            self.request("PUT", file).send_bytes(bytes)?;
+            Ok(())
+        }
+    }
+
+    enum SyncError {
+        Http(Box<ureq::Error>),
+        Io(std::io::Error),
+    }
+
+    impl SyncError {
+        // Few enough words for the menu
+        fn reason(&self) -> String {
+            match self {
+                SyncError::Http(err) => match err.as_ref() {
+                    ureq::Error::Status(code, _) => format!("HTTP {code}"),
+                    ureq::Error::Transport(_) => "can't connect".to_owned(),
+                },
+                SyncError::Io(_) => "can't write saves".to_owned(),
+            }
+        }
+    }
+
+    impl std::fmt::Display for SyncError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                SyncError::Http(err) => write!(f, "{err}"),
+                SyncError::Io(err) => write!(f, "{err}"),
+            }
+        }
+    }
+
+    impl From<ureq::Error> for SyncError {
+        fn from(err: ureq::Error) -> SyncError {
+            SyncError::Http(Box::new(err))
+        }
+    }
+
+    impl From<std::io::Error> for SyncError {
+        fn from(err: std::io::Error) -> SyncError {
+            SyncError::Io(err)
+        }
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        let mut text = String::new();
+        for chunk in bytes.chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    text.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    // Like "Sun, 06 Nov 1994 08:49:37 GMT", the one format servers send
+    fn http_date_seconds(text: &str) -> Option<i64> {
+        let mut parts = text.split_whitespace().skip(1);
+        let day: i64 = parts.next()?.parse().ok()?;
+        let month = parts.next()?;
+        let month = MONTHS.iter().position(|&m| m == month)? as i64 + 1;
+        let year: i64 = parts.next()?.parse().ok()?;
+        let mut time = parts
+            .next()?
+            .split(':')
+            .map(|part| part.parse::<i64>().ok());
+        let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+        let date = Date {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        };
+        Some(date.days() * 86_400 + hours * 3600 + minutes * 60 + seconds)
+    }
+}
@@ -0,0 +1,59 @@
+// Coins placed between pipes. Besides scrolling with the world they can be
+// pushed around by external forces (the magnet power-up).
+
+use macroquad::prelude::*;
+
+use crate::pipes::SCROLL_SPEED;
+use crate::theme::Theme;
+
+pub const COIN_RADIUS: f32 = 5.0;
+
+// Fraction of velocity kept each tick, so pulled coins don't orbit the bird
+const DRAG: f32 = 0.9;
+
+#[derive(Clone)]
+pub struct Coin {
+    pub pos: Vec2,
+    // Velocity from external forces, on top of the world scroll
+    pub vel: Vec2,
+    force: Vec2,
+    pub collected: bool,
+}
+
+impl Coin {
+    pub fn new(pos: Vec2) -> Self {
+        Coin {
+            pos,
+            vel: Vec2::ZERO,
+            force: Vec2::ZERO,
+            collected: false,
+        }
+    }
+
+    /// Pushes the coin this tick. Forces add up until the next `update`.
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    pub fn update(&mut self) {
+        self.vel = (self.vel + self.force) * DRAG;
+        self.force = Vec2::ZERO;
+        self.pos += self.vel;
+        self.pos.x -= SCROLL_SPEED;
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            self.pos.x - COIN_RADIUS,
+            self.pos.y - COIN_RADIUS,
+            COIN_RADIUS * 2.0,
+            COIN_RADIUS * 2.0,
+        )
+    }
+
+    /// Draws the coin spinning, from the theme's sheet.
+    pub fn draw(&self, theme: &Theme) {
+        let coin = &theme.animations.coin;
+        coin.draw(&theme.coin, 0, 1, get_time(), self.rect(), theme.tint);
+    }
+}
@@ -0,0 +1,209 @@
+// Collision and distance queries between the bird and the world
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::bird::Bird;
+use crate::boss::Boss;
+use crate::enemies::EnemyBird;
+use crate::mask::SpriteMasks;
+use crate::obstacle::Obstacle;
+use crate::pipes::{PairOfPipes, SCROLL_SPEED};
+use crate::terrain::Terrain;
+
+pub const DEFAULT_GRACE_TICKS: u32 = 2;
+pub const MAX_GRACE_TICKS: u32 = 3;
+
+// What the bird ran into
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hit {
+    Ground,
+    // Index into the pipe list, and whether it was the upper pipe
+    Pipe { index: usize, top: bool },
+    // Index into the enemy list
+    Enemy(usize),
+    Boss,
+    // The cave's rock, and whether it was the ceiling
+    Rock { ceiling: bool },
+}
+
+/// Finds what `bird` is touching, if anything. Obstacles are ignored while
+/// the bird is invincible. With `masks`, a pipe only counts once solid
+/// pixels of both sprites overlap.
+pub fn check(
+    bird: &Bird,
+    pipes: &[PairOfPipes],
+    enemies: &[EnemyBird],
+    boss: Option<&Boss>,
+    terrain: Option<&Terrain>,
+    masks: Option<&SpriteMasks>,
+) -> Option<Hit> {
+    let rect = bird.rect();
+    if rect.bottom() >= HEIGHT {
+        return Some(Hit::Ground);
+    }
+    if bird.invincible > 0.0 {
+        return None;
+    }
+    // A pair's hitboxes are its top pipe, then its bottom one
+    first_overlap(&rect, pipes)
+        .map(|(index, part)| Hit::Pipe {
+            index,
+            top: part == 0,
+        })
+        .filter(|&hit| {
+            // The masks are for the sprite at its own size, so only while
+            // the bird is that size too
+            let masks = masks.filter(|masks| masks.bird.size() == rect.size());
+            masks.is_none_or(|masks| pixels_touch(bird, pipes, hit, masks))
+        })
+        .or_else(|| first_overlap(&rect, enemies).map(|(index, _)| Hit::Enemy(index)))
+        .or_else(|| {
+            boss.and_then(|boss| first_overlap(&rect, std::slice::from_ref(boss)))
+                .map(|_| Hit::Boss)
+        })
+        .or_else(|| terrain.and_then(|terrain| rock_hit(&rect, terrain)))
+}
+
+// Rock is above the ceiling's line and below the floor's
+fn rock_hit(rect: &Rect, terrain: &Terrain) -> Option<Hit> {
+    let (left, right) = (rect.x, rect.right());
+    let ceiling = line_span(&terrain.ceiling, left, right);
+    let floor = line_span(&terrain.floor, left, right);
+    if ceiling.is_some_and(|(_, lowest)| lowest > rect.y) {
+        Some(Hit::Rock { ceiling: true })
+    } else if floor.is_some_and(|(highest, _)| highest < rect.bottom()) {
+        Some(Hit::Rock { ceiling: false })
+    } else {
+        None
+    }
+}
+
+/// The smallest and largest y `line` (points left to right) has between
+/// `left` and `right`, or None if it doesn't reach there. A straight
+/// segment's are at its ends, so only the points and where the segments
+/// cross `left` and `right` need looking at.
+pub fn line_span(line: &[Vec2], left: f32, right: f32) -> Option<(f32, f32)> {
+    line.windows(2)
+        .filter(|pair| pair[1].x > left && pair[0].x < right)
+        .flat_map(|pair| {
+            let (a, b) = (pair[0], pair[1]);
+            let at = |x: f32| a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x);
+            [at(a.x.max(left)), at(b.x.min(right))]
+        })
+        .fold(None, |span, y| match span {
+            None => Some((y, y)),
+            Some((low, high)) => Some((y.min(low), y.max(high))),
+        })
+}
+
+// The first obstacle touching `rect`, and which of its hitboxes did
+fn first_overlap(rect: &Rect, obstacles: &[impl Obstacle]) -> Option<(usize, usize)> {
+    obstacles.iter().enumerate().find_map(|(index, obstacle)| {
+        if !obstacle.on_screen() {
+            return None;
+        }
+        let part = obstacle
+            .hitboxes()
+            .iter()
+            .position(|hitbox| rect.overlaps(hitbox))?;
+        Some((index, part))
+    })
+}
+
+// Whether the sprites behind a hitbox hit overlap pixel for pixel. Only
+// the pipe the boxes found is tested; pipe pairs are too far apart for the
+// bird to touch two at once.
+fn pixels_touch(bird: &Bird, pipes: &[PairOfPipes], hit: Hit, masks: &SpriteMasks) -> bool {
+    let Hit::Pipe { index, top } = hit else {
+        return true;
+    };
+    let pipe = &pipes[index];
+    // The masks are for sprites at their own size too
+    let mask = masks.pipe(pipe.style);
+    if mask.size() != vec2(pipe.width, pipe.height) {
+        return true;
+    }
+    let (y, flip) = if top {
+        (pipe.top_y, true)
+    } else {
+        (pipe.bottom_y, false)
+    };
+    masks
+        .bird
+        .overlaps(vec2(bird.x, bird.y), mask, vec2(pipe.x, y), flip)
+}
+
+// Coyote time for pipe corners: a hit on a pipe the bird will have slid
+// clear of within the grace window, without flapping, isn't a crash yet
+#[derive(Clone, Copy)]
+pub struct Grace {
+    // How long a graze may last; 0 turns grazing off
+    pub ticks: u32,
+    // Ticks the current graze has lasted
+    used: u32,
+}
+
+impl Grace {
+    pub fn new(ticks: u32) -> Grace {
+        Grace { ticks, used: 0 }
+    }
+
+    /// Takes what `check` found this tick and returns the hit that counts,
+    /// letting off grazes that are already on their way out.
+    pub fn resolve(
+        &mut self,
+        hit: Option<Hit>,
+        bird: &Bird,
+        pipes: &[PairOfPipes],
+        gravity: f32,
+    ) -> Option<Hit> {
+        let Some(Hit::Pipe { index, top }) = hit else {
+            self.used = 0;
+            return hit;
+        };
+        let left = self.ticks.saturating_sub(self.used);
+        if left > 0 && escapes(bird, &pipes[index], top, left, gravity) {
+            self.used += 1;
+            return None;
+        }
+        hit
+    }
+}
+
+// Whether, falling freely with the world scrolling, `bird` stops touching
+// the top or bottom pipe of `pipe` within `ticks`
+fn escapes(bird: &Bird, pipe: &PairOfPipes, top: bool, ticks: u32, gravity: f32) -> bool {
+    let mut hitbox = if top {
+        pipe.top_rect()
+    } else {
+        pipe.bottom_rect()
+    };
+    let mut rect = bird.rect();
+    let mut velocity = bird.velocity.y;
+    for _ in 0..ticks {
+        velocity += gravity;
+        rect.y += velocity;
+        hitbox.x -= SCROLL_SPEED;
+        if !rect.overlaps(&hitbox) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Shortest distance between the edges of two rectangles (0 if they overlap).
+pub fn distance(a: &Rect, b: &Rect) -> f32 {
+    let dx = (b.x - a.right()).max(a.x - b.right()).max(0.0);
+    let dy = (b.y - a.bottom()).max(a.y - b.bottom()).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// How close `rect` is to any part of `obstacle`.
+pub fn obstacle_distance(rect: &Rect, obstacle: &impl Obstacle) -> f32 {
+    obstacle
+        .hitboxes()
+        .iter()
+        .map(|hitbox| distance(rect, hitbox))
+        .fold(f32::INFINITY, f32::min)
+}
@@ -0,0 +1,77 @@
+// Combo multiplier for consecutive centered passes
+
+use macroquad::prelude::*;
+
+// Perfect passes needed for each step up in multiplier
+const PASSES_PER_STEP: u32 = 3;
+const MAX_MULTIPLIER: u32 = 4;
+
+#[derive(Clone, Default)]
+pub struct Combo {
+    pub streak: u32,
+}
+
+impl Combo {
+    /// True when `bird_center` is within the middle third of the gap.
+    pub fn is_centered(bird_center: f32, gap_top: f32, gap_bottom: f32) -> bool {
+        let third = (gap_bottom - gap_top) / 3.0;
+        bird_center >= gap_top + third && bird_center <= gap_bottom - third
+    }
+
+    /// Extends the streak on a centered pass and resets it on a sloppy one.
+    pub fn record(&mut self, centered: bool) {
+        self.streak = if centered { self.streak + 1 } else { 0 };
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.streak / PASSES_PER_STEP).min(MAX_MULTIPLIER)
+    }
+
+    // Streak meter in the bottom-left corner: the multiplier plus one pip per
+    // perfect pass towards the next step
+    pub fn draw(&self, x: f32, y: f32, color: Color) {
+        if self.streak == 0 {
+            return;
+        }
+        let multiplier = self.multiplier();
+        draw_text(&format!("x{multiplier}"), x, y, 24.0, color);
+        if multiplier == MAX_MULTIPLIER {
+            return;
+        }
+        for i in 0..PASSES_PER_STEP {
+            let filled = i < self.streak % PASSES_PER_STEP;
+            let pip_color = if filled {
+                GOLD
+            } else {
+                Color::new(1.0, 1.0, 1.0, 0.3)
+            };
+            draw_rectangle(x + 32.0 + i as f32 * 10.0, y - 12.0, 7.0, 7.0, pip_color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_middle_third_counts_as_centered() {
+        assert!(Combo::is_centered(130.0, 100.0, 190.0));
+        assert!(Combo::is_centered(160.0, 100.0, 190.0));
+        assert!(!Combo::is_centered(129.0, 100.0, 190.0));
+        assert!(!Combo::is_centered(161.0, 100.0, 190.0));
+    }
+
+    #[test]
+    fn steps_up_every_few_passes_to_the_cap() {
+        let mut combo = Combo::default();
+        let mut multipliers = Vec::new();
+        for _ in 0..12 {
+            combo.record(true);
+            multipliers.push(combo.multiplier());
+        }
+        assert_eq!(multipliers, [1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4, 4]);
+        combo.record(false);
+        assert_eq!((combo.streak, combo.multiplier()), (0, 1));
+    }
+}
@@ -0,0 +1,337 @@
+// A drop-down developer console, with the `dev` cargo feature: backtick
+// opens and closes it, Up and Down go through what was typed before and Tab
+// completes commands. It understands
+//
+//     set gravity 0.4     set score 10     spawn pipe 120
+//     set max_fall 12     set flap_cooldown 6
+//     seed 42             state gameover   give coins 100
+//     speed 4             run <rhai>       help
+//     clear
+//
+// Gravity, score and pipes go through the same functions mode scripts
+// call (see `scripting.rs`), so they're held to the same limits, and `run`
+// runs any script line with them. The fall and flap limits (see
+// `physics.rs`) go into the settings file as well, so tuning them carries
+// on into the next runs. `speed` runs the simulation faster or slower like
+// the bracket keys (see `debug_step.rs`). The rest is what the menus do.
+
+use macroquad::prelude::*;
+
+use crate::game::{Game, GameState};
+use crate::physics::Limits;
+use crate::profile::Profile;
+use crate::screen::{Context, Screen, Transition};
+use crate::scripting::Scripting;
+use crate::seed_code;
+use crate::settings::Settings;
+use crate::timestep::{MAX_TEST_SPEED, MIN_TEST_SPEED, Timestep};
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+
+pub const TOGGLE_KEY: KeyCode = KeyCode::GraveAccent;
+const MAX_OUTPUT: usize = 8;
+const MAX_HISTORY: usize = 32;
+const LINE_HEIGHT: f32 = 13.0;
+// Everything Tab can complete to
+const COMPLETIONS: [&str; 15] = [
+    "set gravity ",
+    "set score ",
+    "set max_fall ",
+    "set flap_cooldown ",
+    "spawn pipe ",
+    "seed ",
+    "state ready",
+    "state playing",
+    "state paused",
+    "state gameover",
+    "give coins ",
+    "speed ",
+    "run ",
+    "help",
+    "clear",
+];
+
+#[derive(Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    // Lines entered, oldest first, and which one Up has gone back to
+    history: Vec<String>,
+    recalled: Option<usize>,
+    // Replies, oldest first, and whether each is an error
+    output: Vec<(String, bool)>,
+}
+
+/// Whether the console's key was pressed, when the console is built in.
+pub fn toggle_pressed() -> bool {
+    cfg!(feature = "dev") && is_key_pressed(TOGGLE_KEY)
+}
+
+impl Console {
+    pub fn show(&mut self) {
+        self.open = true;
+        // Or keys pressed with it would be typed in
+        while get_char_pressed().is_some() {}
+    }
+
+    /// Handles typing, and runs the line on Enter.
+    pub fn update(
+        &mut self,
+        game: &mut Game,
+        scripting: &mut Scripting,
+        profile: &mut Profile,
+        settings: &mut Settings,
+        timestep: &mut Timestep,
+    ) {
+        while let Some(c) = get_char_pressed() {
+            if c != '`' && !c.is_control() {
+                self.input.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.open = false;
+        }
+        if is_key_pressed(KeyCode::Up) && !self.history.is_empty() {
+            let back = self
+                .recalled
+                .unwrap_or(self.history.len())
+                .saturating_sub(1);
+            self.recall(Some(back));
+        }
+        if is_key_pressed(KeyCode::Down)
+            && let Some(i) = self.recalled
+        {
+            self.recall((i + 1 < self.history.len()).then_some(i + 1));
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            self.complete();
+        }
+        if !is_key_pressed(KeyCode::Enter) {
+            return;
+        }
+        let line = std::mem::take(&mut self.input);
+        let line = line.trim();
+        self.recalled = None;
+        if line.is_empty() {
+            return;
+        }
+        if self.history.last().is_none_or(|last| last != line) {
+            self.history.push(line.to_owned());
+            let extra = self.history.len().saturating_sub(MAX_HISTORY);
+            self.history.drain(..extra);
+        }
+        self.print(format!("> {line}"), false);
+        match self.execute(line, game, scripting, profile, settings, timestep) {
+            Ok(reply) if reply.is_empty() => {}
+            Ok(reply) => self.print(reply, false),
+            Err(err) => self.print(err, true),
+        }
+    }
+
+    // Fills in line `i` of the history, or an empty line past the newest
+    fn recall(&mut self, i: Option<usize>) {
+        self.recalled = i;
+        self.input = i.map(|i| self.history[i].clone()).unwrap_or_default();
+    }
+
+    // Completes as far as every command that fits agrees, listing them if
+    // there's more than one
+    fn complete(&mut self) {
+        let fits: Vec<&str> = COMPLETIONS
+            .into_iter()
+            .filter(|command| command.starts_with(self.input.as_str()))
+            .collect();
+        let Some(first) = fits.first() else {
+            return;
+        };
+        let shared = fits.iter().fold(first.len(), |shared, command| {
+            first
+                .bytes()
+                .zip(command.bytes())
+                .take(shared)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        self.input = first[..shared].to_owned();
+        if fits.len() > 1 {
+            let options: Vec<&str> = fits.iter().map(|command| command.trim_end()).collect();
+            self.print(options.join(", "), false);
+        }
+    }
+
+    fn print(&mut self, line: String, error: bool) {
+        self.output.push((line, error));
+        let extra = self.output.len().saturating_sub(MAX_OUTPUT);
+        self.output.drain(..extra);
+    }
+
+    fn execute(
+        &mut self,
+        line: &str,
+        game: &mut Game,
+        scripting: &mut Scripting,
+        profile: &mut Profile,
+        settings: &mut Settings,
+        timestep: &mut Timestep,
+    ) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["set", "gravity", value] => {
+                let gravity = number::<f64>(value)?;
+                scripting.eval(game, &format!("set_gravity({gravity:?})"))?;
+                Ok(format!("Gravity is {}", game.gravity))
+            }
+            ["set", "score", value] => {
+                let score = number::<i64>(value)?;
+                scripting.eval(game, &format!("set_score({score})"))?;
+                Ok(format!("Score is {}", game.score))
+            }
+            ["set", "max_fall", value] => {
+                let max_fall = number(value)?;
+                let limits = tune(
+                    game,
+                    settings,
+                    Limits {
+                        max_fall,
+                        ..settings.limits
+                    },
+                );
+                Ok(format!("Falling {} a tick at most", limits.max_fall))
+            }
+            ["set", "flap_cooldown", value] => {
+                let flap_cooldown = number(value)?;
+                let limits = Limits {
+                    flap_cooldown,
+                    ..settings.limits
+                };
+                let limits = tune(game, settings, limits);
+                Ok(format!("{} ticks between flaps", limits.flap_cooldown))
+            }
+            ["spawn", "pipe", value] => {
+                let gap_y = number::<f64>(value)?;
+                scripting.eval(game, &format!("spawn_pipe({gap_y:?})"))?;
+                Ok(String::new())
+            }
+            ["seed", value] => {
+                let seed = value
+                    .parse()
+                    .ok()
+                    .or_else(|| seed_code::parse(value))
+                    .ok_or_else(|| format!("{value} isn't a seed or a course code"))?;
+                game.fixed_seed = Some(seed);
+                game.reset();
+                Ok(format!("Flying course {}", seed_code::encode(seed)))
+            }
+            ["state", state] => {
+                match *state {
+                    "ready" => game.reset(),
+                    "playing" => match game.state {
+                        GameState::Ready => game.flap(),
+                        GameState::Paused => game.toggle_pause(),
+                        GameState::GameOver => {
+                            game.reset();
+                            game.flap();
+                        }
+                        GameState::Playing => {}
+                    },
+                    "paused" if game.state == GameState::Playing => game.toggle_pause(),
+                    "paused" => return Err("Only a run in progress can pause".to_owned()),
+                    "gameover" => game.end_run(),
+                    _ => return Err(format!("{state} isn't ready, playing, paused or gameover")),
+                }
+                Ok(String::new())
+            }
+            ["give", "coins", value] => {
+                profile.coins = profile.coins.saturating_add(number(value)?);
+                profile.save();
+                Ok(format!("{} coins", profile.coins))
+            }
+            ["speed", value] => {
+                let speed = number::<f32>(value)?;
+                if !(MIN_TEST_SPEED..=MAX_TEST_SPEED).contains(&speed) {
+                    return Err(format!(
+                        "Speed goes from {MIN_TEST_SPEED} to {MAX_TEST_SPEED}"
+                    ));
+                }
+                timestep.set_test_speed(speed);
+                Ok(format!("Simulation at {speed}x"))
+            }
+            ["run", ..] => scripting.eval(game, line["run".len()..].trim()),
+            ["help"] => Ok(COMPLETIONS.map(str::trim_end).join(", ")),
+            ["clear"] => {
+                self.output.clear();
+                Ok(String::new())
+            }
+            _ => Err(format!("Unknown command {line}; try help")),
+        }
+    }
+
+    pub fn draw(&self) {
+        let lines = self.output.len() as f32 + 1.0;
+        let height = lines * LINE_HEIGHT + 8.0;
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            height,
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+        let x = 4.0;
+        for (i, (line, error)) in self.output.iter().enumerate() {
+            let color = if *error { RED } else { LIGHTGRAY };
+            draw_text(line, x, (i + 1) as f32 * LINE_HEIGHT, 13.0, color);
+        }
+        let caret = if get_time().fract() < 0.5 { "_" } else { "" };
+        let prompt = format!("> {}{caret}", self.input);
+        draw_text(&prompt, x, lines * LINE_HEIGHT, 13.0, YELLOW);
+    }
+}
+
+/// The console while it's on the screen stack. What was typed and printed
+/// stays with the `Console` in the context, for the next time it opens.
+pub struct ConsoleScreen;
+
+impl Screen for ConsoleScreen {
+    fn on_enter(&mut self, cx: &mut Context) {
+        cx.console.show();
+    }
+
+    fn on_exit(&mut self, cx: &mut Context) {
+        cx.console.open = false;
+    }
+
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if toggle_pressed() {
+            return Transition::Pop;
+        }
+        cx.console
+            .update(cx.game, cx.scripting, cx.profile, cx.settings, cx.timestep);
+        if cx.console.open {
+            Transition::Stay
+        } else {
+            Transition::Pop
+        }
+    }
+
+    fn draw(&self, cx: &Context) {
+        cx.console.draw();
+    }
+
+    fn reads_text(&self) -> bool {
+        true
+    }
+}
+
+// Puts the limits into effect now and saves them for the next runs
+fn tune(game: &mut Game, settings: &mut Settings, limits: Limits) -> Limits {
+    settings.limits = limits.clamped();
+    settings.save();
+    game.limits = settings.limits;
+    settings.limits
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("{text} isn't a number"))
+}
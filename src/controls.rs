@@ -0,0 +1,181 @@
+// Control schemes, picked in the settings menu and kept in the profile:
+// the usual Space or click, right-click only for left-handed mice, any key
+// at all, or two buttons for a small and a full flap. Whichever it is,
+// flaps come as a button goes down, or as it's let go for players who set
+// that on the latency screen (see `latency.rs`).
+
+use macroquad::prelude::*;
+
+// A small flap's speed as a share of a full one's
+const SMALL_FLAP: f32 = 0.6;
+
+// Keys that keep their own job under `AnyKey`
+pub const RESERVED_KEYS: [KeyCode; 21] = [
+    KeyCode::Escape,
+    KeyCode::Back,
+    KeyCode::Tab,
+    KeyCode::P,
+    KeyCode::F,
+    KeyCode::S,
+    KeyCode::M,
+    KeyCode::C,
+    KeyCode::T,
+    KeyCode::O,
+    KeyCode::N,
+    KeyCode::H,
+    KeyCode::K,
+    KeyCode::B,
+    KeyCode::R,
+    KeyCode::V,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F12,
+];
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum ControlScheme {
+    #[default]
+    Standard,
+    RightMouse,
+    AnyKey,
+    TwoButton,
+}
+
+impl ControlScheme {
+    pub const ALL: [ControlScheme; 4] = [
+        ControlScheme::Standard,
+        ControlScheme::RightMouse,
+        ControlScheme::AnyKey,
+        ControlScheme::TwoButton,
+    ];
+
+    /// Stable name used in the profile file.
+    pub fn id(self) -> &'static str {
+        match self {
+            ControlScheme::Standard => "standard",
+            ControlScheme::RightMouse => "right_mouse",
+            ControlScheme::AnyKey => "any_key",
+            ControlScheme::TwoButton => "two_button",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<ControlScheme> {
+        ControlScheme::ALL
+            .into_iter()
+            .find(|scheme| scheme.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ControlScheme::Standard => "Space/click",
+            ControlScheme::RightMouse => "Right click",
+            ControlScheme::AnyKey => "Any key",
+            ControlScheme::TwoButton => "Two buttons",
+        }
+    }
+
+    /// Like `flap` or `flap_released`, going by `edge`.
+    pub fn flap_at(self, edge: FlapEdge) -> Option<f32> {
+        match edge {
+            FlapEdge::Down => self.flap(),
+            FlapEdge::Release => self.flap_released(),
+        }
+    }
+
+    /// How hard the player flapped this frame, as a share of a full flap.
+    /// Touches always flap in full, since a touchscreen has no buttons.
+    pub fn flap(self) -> Option<f32> {
+        self.flap_on(false)
+    }
+
+    /// Like `flap`, but for buttons let go of this frame, for the mirrored
+    /// controls mutator.
+    pub fn flap_released(self) -> Option<f32> {
+        self.flap_on(true)
+    }
+
+    fn flap_on(self, released: bool) -> Option<f32> {
+        let key = |key| {
+            if released {
+                is_key_released(key)
+            } else {
+                is_key_pressed(key)
+            }
+        };
+        let button = |button| {
+            if released {
+                is_mouse_button_released(button)
+            } else {
+                is_mouse_button_pressed(button)
+            }
+        };
+        let phase = if released {
+            TouchPhase::Ended
+        } else {
+            TouchPhase::Started
+        };
+        let touched = touches().iter().any(|touch| touch.phase == phase);
+        let full = match self {
+            ControlScheme::Standard => key(KeyCode::Space) || button(MouseButton::Left),
+            ControlScheme::RightMouse => button(MouseButton::Right) || touched,
+            ControlScheme::AnyKey => {
+                let keys = if released {
+                    get_keys_released()
+                } else {
+                    get_keys_pressed()
+                };
+                keys.iter().any(|key| !RESERVED_KEYS.contains(key)) || button(MouseButton::Left)
+            }
+            ControlScheme::TwoButton => {
+                // Z and a left click for small flaps, X, Space and a right
+                // click for full ones
+                if key(KeyCode::Z) || (button(MouseButton::Left) && !touched) {
+                    return Some(SMALL_FLAP);
+                }
+                key(KeyCode::X) || key(KeyCode::Space) || button(MouseButton::Right) || touched
+            }
+        };
+        full.then_some(1.0)
+    }
+}
+
+/// Which end of a button press flaps.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum FlapEdge {
+    #[default]
+    Down,
+    Release,
+}
+
+impl FlapEdge {
+    /// Stable name used in the settings file.
+    pub fn id(self) -> &'static str {
+        match self {
+            FlapEdge::Down => "down",
+            FlapEdge::Release => "release",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<FlapEdge> {
+        [FlapEdge::Down, FlapEdge::Release]
+            .into_iter()
+            .find(|edge| edge.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FlapEdge::Down => "Key down",
+            FlapEdge::Release => "Key release",
+        }
+    }
+
+    /// The other end, as the mirrored controls mutator flaps on.
+    pub fn other(self) -> FlapEdge {
+        match self {
+            FlapEdge::Down => FlapEdge::Release,
+            FlapEdge::Release => FlapEdge::Down,
+        }
+    }
+}
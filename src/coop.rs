@@ -0,0 +1,235 @@
+// Tethered co-op, opened from the title screen with T: two players share one
+// screen, each flapping their own bird, with the birds tied together by an
+// elastic rope. Both have to make it through every gap, and the rope snaps,
+// ending the run, if they drift too far apart.
+//
+// Like party mode, each bird flies its own `Game` on the same seed so the
+// pipes line up, and only the first bird's course is drawn. The tether is a
+// damped spring that only pulls once the rope is taut.
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::bird::{BIRD_HEIGHT, HOME_X};
+use crate::events::GameEvent;
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::theme::Theme;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+
+const KEYS: [(KeyCode, &str); 2] = [(KeyCode::A, "A"), (KeyCode::L, "L")];
+// Multiplied with the theme's tint
+const COLORS: [Color; 2] = [
+    Color::new(1.0, 1.0, 1.0, 1.0),
+    Color::new(0.5, 0.8, 1.0, 1.0),
+];
+// The birds fly side by side, this far either side of the usual spot
+const SPACING: f32 = 22.0;
+// Between the birds' centers: slack up to the rest length, a pull past it,
+// and a snap past the breaking length
+const REST_LENGTH: f32 = 70.0;
+const SNAP_LENGTH: f32 = 150.0;
+// Speed added per tick for each pixel of stretch, and for each unit of speed
+// the birds are moving apart at while the rope is taut
+const STIFFNESS: f32 = 0.006;
+const DAMPING: f32 = 0.03;
+const ROPE_SEGMENTS: usize = 16;
+const ROPE_COLOR: Color = Color::new(0.55, 0.4, 0.25, 1.0);
+// Keeps a crash from skipping straight past the results
+const RESULTS_SECONDS: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Ending {
+    Crashed,
+    Snapped,
+}
+
+pub struct Coop {
+    games: [Game; 2],
+    fixed_seed: Option<u64>,
+    // Set when the run ends, with the seconds since
+    ended: Option<(Ending, f32)>,
+}
+
+impl Coop {
+    /// Opens on a fresh pair of birds. Runs fly `fixed_seed` when set, or a
+    /// new course each.
+    pub fn new(fixed_seed: Option<u64>) -> Coop {
+        let mut coop = Coop {
+            games: [Game::new(), Game::new()],
+            fixed_seed,
+            ended: None,
+        };
+        coop.start_run();
+        coop
+    }
+
+    fn start_run(&mut self) {
+        let seed = self
+            .fixed_seed
+            .unwrap_or_else(|| u64::from(rand::rand()) << 32 | u64::from(rand::rand()));
+        for (i, game) in self.games.iter_mut().enumerate() {
+            *game = Game::with_seed(Some(seed));
+            game.bird.x = HOME_X + if i == 0 { -SPACING } else { SPACING };
+        }
+        self.ended = None;
+    }
+
+    /// Handles both players' keys. Either one's first flap starts both
+    /// birds together, so their courses stay in step.
+    pub fn update(&mut self, dt: f32) {
+        let pressed: Vec<usize> = (0..KEYS.len())
+            .filter(|&i| is_key_pressed(KEYS[i].0))
+            .collect();
+        match &mut self.ended {
+            Some((_, shown)) => {
+                *shown += dt;
+                if *shown > RESULTS_SECONDS && !pressed.is_empty() {
+                    self.start_run();
+                }
+            }
+            None if self.games[0].state == GameState::Ready => {
+                if !pressed.is_empty() {
+                    for game in &mut self.games {
+                        game.flap();
+                    }
+                }
+            }
+            None => {
+                for i in pressed {
+                    self.games[i].flap();
+                }
+            }
+        }
+    }
+
+    /// Runs one fixed-timestep update of both birds and the rope between.
+    pub fn tick(&mut self) {
+        if self.ended.is_some() || self.games[0].state != GameState::Playing {
+            return;
+        }
+        for game in &mut self.games {
+            game.update();
+        }
+        if self
+            .games
+            .iter()
+            .any(|game| game.state == GameState::GameOver)
+        {
+            self.end(Ending::Crashed);
+            return;
+        }
+
+        let [first, second] = &mut self.games;
+        let (a, b) = (first.bird.rect().center(), second.bird.rect().center());
+        let length = a.distance(b);
+        if length > SNAP_LENGTH {
+            self.end(Ending::Snapped);
+            return;
+        }
+        if length > REST_LENGTH {
+            let along = (b - a) / length;
+            let parting = (second.bird.velocity - first.bird.velocity).dot(along);
+            let pull = along * ((length - REST_LENGTH) * STIFFNESS + parting.max(0.0) * DAMPING);
+            first.bird.velocity += pull;
+            second.bird.velocity -= pull;
+        }
+    }
+
+    fn end(&mut self, ending: Ending) {
+        for game in &mut self.games {
+            // The partner of a crashed bird goes down with it
+            if game.state == GameState::Playing {
+                game.state = GameState::GameOver;
+            }
+        }
+        self.ended = Some((ending, 0.0));
+    }
+
+    /// Events from both birds' runs since the last call, for sound.
+    pub fn events(&mut self) -> Vec<GameEvent> {
+        self.games
+            .iter_mut()
+            .flat_map(|game| game.events.drain())
+            .collect()
+    }
+
+    // Either bird can be a pipe behind the other, so the team scores what
+    // the trailing one has passed
+    fn score(&self) -> i64 {
+        self.games[0].score.min(self.games[1].score)
+    }
+
+    pub fn draw(&self, theme: &Theme) {
+        self.games[0].draw_course(theme);
+        if self.ended.map(|(ending, _)| ending) != Some(Ending::Snapped) {
+            self.draw_rope();
+        }
+        for (game, color) in self.games.iter().zip(COLORS) {
+            let tint = theme.tint;
+            let tint = Color::new(tint.r * color.r, tint.g * color.g, tint.b * color.b, tint.a);
+            game.bird.draw(&theme.bird, tint);
+        }
+        draw_centered_text(&self.score().to_string(), 50.0, 40.0, theme.text_color);
+
+        match self.ended {
+            None if self.games[0].state == GameState::Ready => {
+                draw_centered_text("Tethered", HEIGHT / 2.0 - 80.0, 32.0, theme.text_color);
+                let keys = format!("{} and {} to flap", KEYS[0].1, KEYS[1].1);
+                draw_centered_text(&keys, HEIGHT / 2.0 + 60.0, 20.0, theme.text_color);
+                draw_centered_text(
+                    "Stay close or the rope snaps",
+                    HEIGHT / 2.0 + 84.0,
+                    16.0,
+                    theme.text_color,
+                );
+                draw_centered_text("Esc to leave", HEIGHT - 20.0, 16.0, GRAY);
+            }
+            None => {}
+            Some((ending, shown)) => {
+                draw_rectangle(
+                    FIELD_LEFT,
+                    0.0,
+                    FIELD_WIDTH,
+                    HEIGHT,
+                    Color::new(0.0, 0.0, 0.0, 0.6),
+                );
+                let title = match ending {
+                    Ending::Crashed => "Crashed!",
+                    Ending::Snapped => "The rope snapped!",
+                };
+                draw_centered_text(title, HEIGHT / 2.0 - 30.0, 28.0, WHITE);
+                let score = format!("Together: {}", self.score());
+                draw_centered_text(&score, HEIGHT / 2.0 + 10.0, 20.0, WHITE);
+                if shown > RESULTS_SECONDS {
+                    draw_centered_text("Flap to go again, Esc to leave", HEIGHT - 20.0, 16.0, GRAY);
+                }
+            }
+        }
+    }
+
+    // A curve between the birds that sags while slack, straightens as it
+    // pulls and reddens near breaking
+    fn draw_rope(&self) {
+        let (a, b) = (
+            self.games[0].bird.rect().center(),
+            self.games[1].bird.rect().center(),
+        );
+        let length = a.distance(b);
+        let sag = (REST_LENGTH - length).max(0.0) * 0.6 + BIRD_HEIGHT * 0.1;
+        let control = (a + b) / 2.0 + vec2(0.0, sag);
+        let strain = ((length - REST_LENGTH) / (SNAP_LENGTH - REST_LENGTH)).clamp(0.0, 1.0);
+        let color = Color::new(
+            ROPE_COLOR.r + (1.0 - ROPE_COLOR.r) * strain,
+            ROPE_COLOR.g * (1.0 - strain),
+            ROPE_COLOR.b * (1.0 - strain),
+            1.0,
+        );
+        let point = |t: f32| a * (1.0 - t) * (1.0 - t) + control * 2.0 * t * (1.0 - t) + b * t * t;
+        let mut from = a;
+        for i in 1..=ROPE_SEGMENTS {
+            let to = point(i as f32 / ROPE_SEGMENTS as f32);
+            draw_line(from.x, from.y, to.x, to.y, 2.0 - strain, color);
+            from = to;
+        }
+    }
+}
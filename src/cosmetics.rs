@@ -0,0 +1,362 @@
+// Cosmetics: hats and trail colors bought with coins in the wardrobe (B on
+// the title screen) and worn over whatever bird the theme has. They only
+// change how the bird looks.
+//
+// Hats come from the `hats.png` sheet, one HAT_WIDTH by HAT_HEIGHT frame
+// per hat in `Hat::ALL` order, and are put on the bird where
+// `anchors.cfg` says, in the bird sprite's own pixels:
+//
+//     # Where the bottom middle of a hat goes
+//     hat = 22, 3
+//     # Where a colored trail leaves the bird
+//     trail = 3, 13
+//
+// A theme's own `hats.png` and `anchors.cfg` go with its bird; anything it
+// leaves out comes from the default sprites. A colored trail takes the
+// place of the afterimages the trail setting leaves.
+
+use macroquad::prelude::*;
+
+use crate::bird::{BIRD_HEIGHT, BIRD_WIDTH, Bird, Layer};
+use crate::game::draw_centered_text;
+use crate::kv::KeyValues;
+use crate::profile::Profile;
+use crate::screen::{Context, Screen, Transition};
+use crate::theme::Theme;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+pub const ANCHORS_FILE: &str = "anchors.cfg";
+pub const ANCHOR_KEYS: [&str; 2] = ["hat", "trail"];
+pub const WARDROBE_KEY: KeyCode = KeyCode::B;
+pub const HAT_WIDTH: f32 = 16.0;
+pub const HAT_HEIGHT: f32 = 12.0;
+// The preview bird is drawn this many times its size
+const PREVIEW_SCALE: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hat {
+    Cap,
+    Crown,
+    Top,
+    Party,
+    Beanie,
+}
+
+impl Hat {
+    pub const ALL: [Hat; 5] = [Hat::Cap, Hat::Crown, Hat::Top, Hat::Party, Hat::Beanie];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hat::Cap => "Cap",
+            Hat::Crown => "Crown",
+            Hat::Top => "Top hat",
+            Hat::Party => "Party hat",
+            Hat::Beanie => "Beanie",
+        }
+    }
+
+    // Its frame in the hat sheet
+    fn frame(self) -> Rect {
+        Rect::new(self as usize as f32 * HAT_WIDTH, 0.0, HAT_WIDTH, HAT_HEIGHT)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TrailColor {
+    Gold,
+    Mint,
+    Rose,
+    Rainbow,
+}
+
+impl TrailColor {
+    pub const ALL: [TrailColor; 4] = [
+        TrailColor::Gold,
+        TrailColor::Mint,
+        TrailColor::Rose,
+        TrailColor::Rainbow,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TrailColor::Gold => "Gold trail",
+            TrailColor::Mint => "Mint trail",
+            TrailColor::Rose => "Rose trail",
+            TrailColor::Rainbow => "Rainbow trail",
+        }
+    }
+
+    /// The color of the trail's `i`th dot back from the bird.
+    pub fn color(self, i: usize) -> Color {
+        match self {
+            TrailColor::Gold => GOLD,
+            TrailColor::Mint => Color::new(0.55, 1.0, 0.8, 1.0),
+            TrailColor::Rose => Color::new(1.0, 0.5, 0.7, 1.0),
+            TrailColor::Rainbow => {
+                let rainbow = [RED, ORANGE, YELLOW, LIME, SKYBLUE, VIOLET];
+                rainbow[i % rainbow.len()]
+            }
+        }
+    }
+}
+
+/// Something the wardrobe sells.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Item {
+    Hat(Hat),
+    Trail(TrailColor),
+}
+
+impl Item {
+    pub const ALL: [Item; Hat::ALL.len() + TrailColor::ALL.len()] = [
+        Item::Hat(Hat::Cap),
+        Item::Hat(Hat::Crown),
+        Item::Hat(Hat::Top),
+        Item::Hat(Hat::Party),
+        Item::Hat(Hat::Beanie),
+        Item::Trail(TrailColor::Gold),
+        Item::Trail(TrailColor::Mint),
+        Item::Trail(TrailColor::Rose),
+        Item::Trail(TrailColor::Rainbow),
+    ];
+
+    /// Stable name used in the profile file.
+    pub fn id(self) -> &'static str {
+        match self {
+            Item::Hat(Hat::Cap) => "hat_cap",
+            Item::Hat(Hat::Crown) => "hat_crown",
+            Item::Hat(Hat::Top) => "hat_top_hat",
+            Item::Hat(Hat::Party) => "hat_party",
+            Item::Hat(Hat::Beanie) => "hat_beanie",
+            Item::Trail(TrailColor::Gold) => "trail_gold",
+            Item::Trail(TrailColor::Mint) => "trail_mint",
+            Item::Trail(TrailColor::Rose) => "trail_rose",
+            Item::Trail(TrailColor::Rainbow) => "trail_rainbow",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Item> {
+        Item::ALL.into_iter().find(|item| item.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Item::Hat(hat) => hat.name(),
+            Item::Trail(trail) => trail.name(),
+        }
+    }
+
+    /// What it costs, in coins.
+    pub fn price(self) -> u32 {
+        match self {
+            Item::Hat(Hat::Cap) | Item::Hat(Hat::Beanie) => 60,
+            Item::Hat(Hat::Party) => 90,
+            Item::Hat(Hat::Top) => 150,
+            Item::Hat(Hat::Crown) => 300,
+            Item::Trail(TrailColor::Rainbow) => 200,
+            Item::Trail(_) => 80,
+        }
+    }
+}
+
+/// What the bird is wearing.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Look {
+    pub hat: Option<Hat>,
+    pub trail: Option<TrailColor>,
+}
+
+impl Look {
+    /// The look with `items` on; the last hat or trail wins.
+    pub fn wearing(items: &[Item]) -> Look {
+        let mut look = Look::default();
+        for &item in items {
+            match item {
+                Item::Hat(hat) => look.hat = Some(hat),
+                Item::Trail(trail) => look.trail = Some(trail),
+            }
+        }
+        look
+    }
+
+    pub fn items(self) -> impl Iterator<Item = Item> {
+        let hat = self.hat.map(Item::Hat);
+        hat.into_iter().chain(self.trail.map(Item::Trail))
+    }
+
+    pub fn wears(self, item: Item) -> bool {
+        match item {
+            Item::Hat(hat) => self.hat == Some(hat),
+            Item::Trail(trail) => self.trail == Some(trail),
+        }
+    }
+
+    // Puts `item` on, or takes it off if it's already on
+    fn toggle(&mut self, item: Item) {
+        match item {
+            Item::Hat(hat) => self.hat = (self.hat != Some(hat)).then_some(hat),
+            Item::Trail(trail) => self.trail = (self.trail != Some(trail)).then_some(trail),
+        }
+    }
+}
+
+/// Where cosmetics go on a theme's bird, in the bird's hitbox pixels at
+/// its usual size.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Anchors {
+    pub hat: Vec2,
+    pub trail: Vec2,
+}
+
+impl Default for Anchors {
+    fn default() -> Anchors {
+        Anchors {
+            hat: vec2(BIRD_WIDTH * 0.65, BIRD_HEIGHT * 0.12),
+            trail: vec2(BIRD_WIDTH * 0.1, BIRD_HEIGHT * 0.55),
+        }
+    }
+}
+
+impl Anchors {
+    /// Reads the keys in `kv`, points in a bird sprite `sprite` pixels in
+    /// size, keeping `fallback`'s for the rest.
+    pub fn from_values(kv: &KeyValues, sprite: Vec2, fallback: &Anchors) -> Anchors {
+        // The sprite is stretched to the hitbox
+        let scale = vec2(BIRD_WIDTH, BIRD_HEIGHT) / sprite.max(Vec2::ONE);
+        let point = |key: &str| parse_point(kv.get(key)?).map(|point| point * scale);
+        Anchors {
+            hat: point("hat").unwrap_or(fallback.hat),
+            trail: point("trail").unwrap_or(fallback.trail),
+        }
+    }
+}
+
+/// Reads an `x, y` point.
+pub fn parse_point(text: &str) -> Option<Vec2> {
+    let (x, y) = text.split_once(',')?;
+    Some(vec2(x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Draws `bird` in `theme`'s sprite with `look`'s hat on.
+pub fn draw_bird(bird: &Bird, theme: &Theme, look: Look) {
+    let hat = look.hat.map(|hat| Layer {
+        texture: &theme.hats,
+        source: Some(hat.frame()),
+        anchor: theme.anchors.hat,
+        pivot: vec2(HAT_WIDTH / 2.0, HAT_HEIGHT),
+    });
+    bird.draw_layered(&theme.bird, theme.tint, hat.as_slice());
+}
+
+/// Picks what to buy and wear.
+#[derive(Default)]
+pub struct Wardrobe {
+    selected: usize,
+}
+
+impl Screen for Wardrobe {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(WARDROBE_KEY) {
+            return Transition::Pop;
+        }
+        if let Some(message) = self.shop(cx.profile) {
+            cx.toasts.push(message, GOLD);
+        }
+        cx.game.look = cx.profile.look;
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        self.draw_shelf(cx.profile, cx.theme);
+    }
+}
+
+impl Wardrobe {
+    // Handles the keys for picking and buying. Returns a message when
+    // something was bought, or couldn't be.
+    fn shop(&mut self, profile: &mut Profile) -> Option<String> {
+        let count = Item::ALL.len();
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if !is_key_pressed(KeyCode::Enter) && !is_key_pressed(KeyCode::Space) {
+            return None;
+        }
+        let item = Item::ALL[self.selected];
+        if profile.owned.contains(&item) {
+            profile.look.toggle(item);
+            profile.save();
+            return None;
+        }
+        let price = item.price();
+        if profile.coins < price {
+            return Some(format!(
+                "{} needs {} more coins",
+                item.name(),
+                price - profile.coins
+            ));
+        }
+        profile.coins -= price;
+        profile.owned.push(item);
+        profile.look.toggle(item);
+        profile.save();
+        Some(format!("Bought the {}", item.name().to_lowercase()))
+    }
+
+    fn draw_shelf(&self, profile: &Profile, theme: &Theme) {
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Wardrobe", 40.0, 32.0, WHITE);
+        draw_centered_text(&format!("{} coins", profile.coins), 60.0, 16.0, GOLD);
+
+        // The bird as it would look with the picked item on
+        let mut look = profile.look;
+        let item = Item::ALL[self.selected];
+        if !look.wears(item) {
+            look.toggle(item);
+        }
+        let mut bird = Bird::new();
+        bird.scale = PREVIEW_SCALE;
+        let size = bird.rect().size();
+        (bird.x, bird.y) = (WIDTH / 2.0 - size.x / 2.0, 96.0);
+        draw_bird(&bird, theme, look);
+        if let Some(trail) = look.trail {
+            let start = vec2(bird.x, bird.y) + theme.anchors.trail * PREVIEW_SCALE;
+            for i in 0..6 {
+                let mut color = trail.color(i);
+                color.a = 1.0 - i as f32 / 6.0;
+                draw_circle(start.x - 10.0 - i as f32 * 9.0, start.y, 4.0, color);
+            }
+        }
+
+        for (i, &item) in Item::ALL.iter().enumerate() {
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            let y = 196.0 + i as f32 * 18.0;
+            draw_text(item.name(), 20.0, y, 16.0, color);
+            let state = if profile.look.wears(item) {
+                "Worn".to_owned()
+            } else if profile.owned.contains(&item) {
+                "Owned".to_owned()
+            } else {
+                format!("{} coins", item.price())
+            };
+            let size = measure_text(&state, None, 16, 1.0);
+            draw_text(&state, WIDTH - 20.0 - size.width, y, 16.0, color);
+        }
+        draw_centered_text(
+            "Enter to buy or wear, B/Esc to close",
+            HEIGHT - 12.0,
+            14.0,
+            GRAY,
+        );
+    }
+}
@@ -0,0 +1,15 @@
+// Themed mouse cursor, drawn in place of the OS one while a menu is showing
+
+use macroquad::prelude::*;
+
+use crate::theme::Theme;
+
+/// Draws the theme's cursor with its tip at `pos` (virtual coordinates).
+pub fn draw(theme: &Theme, pos: Vec2) {
+    let sprite = if is_mouse_button_down(MouseButton::Left) {
+        &theme.cursor_pressed
+    } else {
+        &theme.cursor
+    };
+    draw_texture(sprite, pos.x.round(), pos.y.round(), WHITE);
+}
@@ -0,0 +1,146 @@
+// Calendar dates (UTC) without pulling in a date/time crate
+
+use macroquad::miniquad::date;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Date {
+        Date::from_days(today_days())
+    }
+
+    /// Converts days since 1970-01-01 to a date (Howard Hinnant's algorithm).
+    pub fn from_days(days: i64) -> Date {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Date { year, month, day }
+    }
+
+    /// The inverse of `from_days` (Howard Hinnant's algorithm).
+    pub fn days(self) -> i64 {
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Reads a date like `2024-01-31`.
+    pub fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.trim().split('-');
+        let date = Date {
+            year: parts.next()?.parse().ok()?,
+            month: parts.next()?.parse().ok()?,
+            day: parts.next()?.parse().ok()?,
+        };
+        // Days past the end of the month come back as another date
+        (parts.next().is_none() && Date::from_days(date.days()) == date).then_some(date)
+    }
+
+    /// Days since 1970-01-01 of the first of the month `days` falls in.
+    pub fn month_start(days: i64) -> i64 {
+        days - i64::from(Date::from_days(days).day) + 1
+    }
+
+    /// Monday is 0; 1970-01-01 was a Thursday.
+    pub fn weekday(days: i64) -> u32 {
+        (days + 3).rem_euclid(7) as u32
+    }
+
+    /// The date as a number like 20240131, used to seed that day's course.
+    pub fn seed(self) -> u64 {
+        self.year as u64 * 10_000 + u64::from(self.month) * 100 + u64::from(self.day)
+    }
+}
+
+/// Days since 1970-01-01, today.
+pub fn today_days() -> i64 {
+    // miniquad's clock, unlike SystemTime, also works in the browser
+    (date::now() as i64).div_euclid(86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leap_years() {
+        for (text, valid) in [
+            ("2024-02-29", true),
+            ("2023-02-29", false),
+            ("2000-02-29", true),
+            ("1900-02-29", false),
+            ("2023-04-31", false),
+            ("2023-13-01", false),
+            ("2023-12-31", true),
+            ("2023-12", false),
+            ("2023-12-31-1", false),
+        ] {
+            assert_eq!(Date::parse(text).is_some(), valid, "{text}");
+        }
+        let leap_day = Date::parse("2024-02-29").unwrap();
+        assert_eq!(
+            Date::from_days(leap_day.days() + 1),
+            Date::parse("2024-03-01").unwrap()
+        );
+        assert_eq!(leap_day.seed(), 20240229);
+    }
+
+    #[test]
+    fn days_round_trip() {
+        assert_eq!(
+            Date::from_days(0),
+            Date {
+                year: 1970,
+                month: 1,
+                day: 1
+            }
+        );
+        assert_eq!(
+            Date::from_days(-1),
+            Date {
+                year: 1969,
+                month: 12,
+                day: 31
+            }
+        );
+        for days in (-800_000..800_000).step_by(97) {
+            assert_eq!(Date::from_days(days).days(), days);
+        }
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // A Thursday, then the Wednesday before it
+        assert_eq!(Date::weekday(0), 3);
+        assert_eq!(Date::weekday(-1), 2);
+        // 2025-01-01 is a Wednesday, in the week that starts on 2024-12-30
+        // (ISO week 1 of 2025)
+        let new_year = Date::parse("2025-01-01").unwrap().days();
+        assert_eq!(Date::weekday(new_year), 2);
+        let monday = new_year - i64::from(Date::weekday(new_year));
+        assert_eq!(Date::from_days(monday), Date::parse("2024-12-30").unwrap());
+        // 2021-01-03 is the Sunday ending ISO week 53 of 2020
+        assert_eq!(Date::weekday(Date::parse("2021-01-03").unwrap().days()), 6);
+        let last = Date::parse("2024-12-31").unwrap().days();
+        assert_eq!(
+            Date::from_days(Date::month_start(last)),
+            Date::parse("2024-12-01").unwrap()
+        );
+    }
+}
@@ -0,0 +1,156 @@
+// Slow-motion replay of the moments before a crash, zoomed in on where the
+// bird hit, played before the results card. Any key or a tap skips it.
+//
+// `StateBuffer` keeps what was on screen for the last REPLAY_TICKS updates;
+// the replay draws those frames back, easing between them.
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::boss::Boss;
+use crate::enemies::EnemyBird;
+use crate::game::{Game, draw_centered_text};
+use crate::obstacle::Obstacle;
+use crate::pipes::{PairOfPipes, SCROLL_SPEED};
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::viewport::Viewport;
+use crate::{HEIGHT, WIDTH};
+
+// About a second and a half
+const REPLAY_TICKS: usize = 90;
+const SPEED: f32 = 0.25;
+const ZOOM: f32 = 2.0;
+const ZOOM_IN_SECONDS: f32 = 0.6;
+// The crash frame stays up this long before the results card
+const HOLD_SECONDS: f32 = 0.5;
+
+#[derive(Clone)]
+struct Frame {
+    // How far the course had scrolled
+    distance: f32,
+    bird: Rect,
+    pipes: Vec<PairOfPipes>,
+    enemies: Vec<EnemyBird>,
+    boss: Option<Boss>,
+}
+
+#[derive(Default)]
+pub struct StateBuffer {
+    frames: VecDeque<Frame>,
+    // The update the newest frame was taken after
+    last_tick: Option<u32>,
+}
+
+impl StateBuffer {
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last_tick = None;
+    }
+
+    pub fn count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Keeps what `game` looks like after its latest update, once per update.
+    pub fn record(&mut self, game: &Game) {
+        if self.last_tick == Some(game.ticks) {
+            return;
+        }
+        self.last_tick = Some(game.ticks);
+        self.frames.push_back(Frame {
+            distance: game.distance(),
+            bird: game.bird.rect(),
+            pipes: game.pipes.clone(),
+            enemies: game.enemies.clone(),
+            boss: game.boss.clone(),
+        });
+        if self.frames.len() > REPLAY_TICKS {
+            self.frames.pop_front();
+        }
+    }
+}
+
+pub struct DeathReplay {
+    frames: Vec<Frame>,
+    // Seconds since the replay started, in real time
+    time: f32,
+    impact: Vec2,
+}
+
+impl DeathReplay {
+    /// A replay of `buffer`, if it has anything to show.
+    pub fn start(buffer: &StateBuffer) -> Option<DeathReplay> {
+        let last = buffer.frames.back()?;
+        let impact = last.bird.center();
+        Some(DeathReplay {
+            frames: buffer.frames.iter().cloned().collect(),
+            time: 0.0,
+            impact,
+        })
+    }
+
+    /// Moves the replay on. Returns true once it's over or skipped.
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.time += dt;
+        let skipped = get_last_key_pressed().is_some()
+            || is_mouse_button_pressed(MouseButton::Left)
+            || !touches().is_empty();
+        skipped || self.time > self.length() + HOLD_SECONDS
+    }
+
+    // How long playback takes, not counting the hold
+    fn length(&self) -> f32 {
+        (self.frames.len() - 1) as f32 * TICK_SECONDS / SPEED
+    }
+
+    pub fn draw(&self, viewport: &Viewport, theme: &Theme) {
+        // Where playback is, in frames
+        let at = (self.time * SPEED / TICK_SECONDS).min((self.frames.len() - 1) as f32);
+        let index = at as usize;
+        let blend = at.fract();
+        let frame = &self.frames[index];
+        let next = self.frames.get(index + 1).unwrap_or(frame);
+
+        // Eases from the whole screen in on the impact point, kept framed
+        // so the zoomed view doesn't run off the edge of the world
+        let ease = (self.time / ZOOM_IN_SECONDS).min(1.0);
+        let zoom = 1.0 + (ZOOM - 1.0) * ease;
+        let half = vec2(WIDTH, HEIGHT) / (2.0 * zoom);
+        let screen_center = vec2(WIDTH, HEIGHT) / 2.0;
+        let center = screen_center.lerp(self.impact, ease);
+        viewport.begin_zoomed(center.clamp(half, vec2(WIDTH, HEIGHT) - half), zoom);
+
+        clear_background(theme.sky_color);
+        theme.draw_background(frame.distance + (next.distance - frame.distance) * blend);
+        // The world between frames is mostly still scrolling
+        let shift = blend * SCROLL_SPEED;
+        for pipe in &frame.pipes {
+            let mut pipe = pipe.clone();
+            pipe.x -= shift;
+            pipe.draw(theme);
+        }
+        for enemy in &frame.enemies {
+            enemy.draw(theme);
+        }
+        if let Some(boss) = &frame.boss {
+            boss.draw(theme);
+        }
+        let bird = frame.bird.point().lerp(next.bird.point(), blend);
+        draw_texture_ex(
+            &theme.bird,
+            bird.x,
+            bird.y,
+            theme.tint,
+            DrawTextureParams {
+                dest_size: Some(frame.bird.size()),
+                ..Default::default()
+            },
+        );
+
+        viewport.begin();
+        draw_centered_text("Replay", 30.0, 20.0, theme.text_color);
+        draw_centered_text("Any key to skip", HEIGHT - 20.0, 14.0, GRAY);
+    }
+}
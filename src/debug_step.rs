@@ -0,0 +1,105 @@
+// Frame-advance stepping for chasing collision edge cases, with the `dev`
+// cargo feature: while a run is paused, each press of STEP_KEY runs exactly
+// one tick, and an overlay shows the tick, the bird's velocity and where
+// the nearest pair of pipes is from it. TILES_KEY outlines the backdrop's
+// copies and their crossfades. The bracket keys halve and double
+// the simulation speed, for running through courses faster (see
+// `timestep.rs`). Without the feature it's all off, except for the tick
+// counter, which is a setting for anyone who wants to see exact ticks.
+
+use macroquad::prelude::*;
+
+use crate::game::{Game, GameState};
+use crate::theme::Theme;
+use crate::timestep::Timestep;
+use crate::{HEIGHT, WIDTH};
+
+pub const STEP_KEY: KeyCode = KeyCode::Period;
+// Shows where the backdrop's copies meet, see `backdrop.rs`
+pub const TILES_KEY: KeyCode = KeyCode::F7;
+
+/// Whether to run one tick of a paused `game` this frame.
+pub fn step_pressed(game: &Game) -> bool {
+    cfg!(feature = "dev") && game.state == GameState::Paused && is_key_pressed(STEP_KEY)
+}
+
+/// Whether to turn the backdrop's tile outlines on or off this frame.
+pub fn tiles_pressed() -> bool {
+    cfg!(feature = "dev") && is_key_pressed(TILES_KEY)
+}
+
+/// Halves or doubles the simulation speed on the bracket keys, returning
+/// the new speed when it changed.
+pub fn speed_keys(timestep: &mut Timestep) -> Option<f32> {
+    if !cfg!(feature = "dev") {
+        return None;
+    }
+    let speed = timestep.test_speed();
+    if is_key_pressed(KeyCode::LeftBracket) {
+        timestep.set_test_speed(speed / 2.0);
+    }
+    if is_key_pressed(KeyCode::RightBracket) {
+        timestep.set_test_speed(speed * 2.0);
+    }
+    (timestep.test_speed() != speed).then(|| timestep.test_speed())
+}
+
+/// Shows the simulation speed in the corner when it isn't the usual.
+pub fn draw_speed(timestep: &Timestep) {
+    let speed = timestep.test_speed();
+    if speed != 1.0 {
+        let label = format!("SIM {speed}x");
+        let size = measure_text(&label, None, 14, 1.0);
+        draw_text(&label, WIDTH - size.width - 4.0, 14.0, 14.0, ORANGE);
+    }
+}
+
+/// The run's tick and how many ran this frame, in the bottom corner.
+pub fn draw_ticks(game: &Game, ran: u32, theme: &Theme) {
+    let label = format!("tick {} +{ran}", game.ticks);
+    let size = measure_text(&label, None, 14, 1.0);
+    let x = game.layout.right() - size.width - 6.0;
+    draw_text(&label, x, HEIGHT - 6.0, 14.0, theme.text_color);
+}
+
+/// The tick, the bird's velocity and the nearest pipes, while paused.
+pub fn draw(game: &Game, theme: &Theme) {
+    if !cfg!(feature = "dev") || game.state != GameState::Paused {
+        return;
+    }
+    let bird = game.bird.rect();
+    let mut lines = vec![
+        format!("tick {}", game.ticks),
+        format!(
+            "velocity {:.2}, {:.2}",
+            game.bird.velocity.x, game.bird.velocity.y
+        ),
+    ];
+    // The first pair the bird hasn't left behind yet
+    match game.pipes.iter().find(|pipe| pipe.right() > bird.left()) {
+        Some(pipe) => {
+            // Less than 0 once the bird is in among the pipes
+            lines.push(format!("pipe ahead {:.1}", pipe.x - bird.right()));
+            lines.push(format!("gap top {:.1} above", bird.top() - pipe.gap_top()));
+            lines.push(format!(
+                "gap bottom {:.1} below",
+                pipe.gap_bottom() - bird.bottom()
+            ));
+        }
+        None => lines.push("no pipe ahead".to_owned()),
+    }
+    lines.push(format!("{STEP_KEY:?} steps one tick"));
+
+    let (x, y) = (game.layout.left() + 6.0, 90.0);
+    let height = lines.len() as f32 * 14.0 + 6.0;
+    draw_rectangle(
+        x - 2.0,
+        y - 12.0,
+        150.0,
+        height,
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x, y + i as f32 * 14.0, 14.0, theme.text_color);
+    }
+}
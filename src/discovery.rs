@@ -0,0 +1,73 @@
+// Finding games on the local network. Hosts broadcast `GAME <open> <mode>`
+// to DISCOVERY_PORT every ANNOUNCE_INTERVAL_SECONDS from their game socket,
+// so the sender's address is the one to join, or to watch once `open` is 0
+// and both players are in. The online menu listens there and lists what it
+// hears as nearby games.
+
+use std::net::SocketAddr;
+
+use crate::net::Link;
+
+pub const DISCOVERY_PORT: u16 = 47_099;
+pub const ANNOUNCE_INTERVAL_SECONDS: f32 = 1.0;
+// Games not heard from for this long have stopped hosting
+const FORGET_SECONDS: f32 = 3.5;
+// Enough for any one network; more would not fit on screen anyway
+const MAX_GAMES: usize = 6;
+
+pub fn announcement(open: bool, mode: &str) -> String {
+    format!("GAME {} {mode}", u8::from(open))
+}
+
+pub struct NearbyGame {
+    pub address: SocketAddr,
+    pub mode: String,
+    // Still waiting for an opponent; otherwise it can only be watched
+    pub open: bool,
+    // Seconds since its last announcement
+    silence: f32,
+}
+
+pub struct Discovery {
+    link: Link,
+    pub games: Vec<NearbyGame>,
+}
+
+impl Discovery {
+    /// Starts listening, or returns None if the port is taken (e.g. by
+    /// another copy of the game on this machine).
+    pub fn listen() -> Option<Discovery> {
+        let link = Link::bind(DISCOVERY_PORT).ok()?;
+        Some(Discovery {
+            link,
+            games: Vec::new(),
+        })
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for game in &mut self.games {
+            game.silence += dt;
+        }
+        while let Some((text, address)) = self.link.receive() {
+            let Some((open, mode)) = text.strip_prefix("GAME ").and_then(|t| t.split_once(' '))
+            else {
+                continue;
+            };
+            let (open, mode) = (open == "1", mode.trim().to_owned());
+            match self.games.iter().position(|g| g.address == address) {
+                Some(index) => {
+                    let game = &mut self.games[index];
+                    (game.open, game.mode, game.silence) = (open, mode, 0.0);
+                }
+                None if self.games.len() < MAX_GAMES => self.games.push(NearbyGame {
+                    address,
+                    mode,
+                    open,
+                    silence: 0.0,
+                }),
+                None => {}
+            }
+        }
+        self.games.retain(|game| game.silence < FORGET_SECONDS);
+    }
+}
@@ -0,0 +1,108 @@
+// The game as a widget, for other macroquad projects to run inside their
+// own: a mini-game in a launcher, say, or an Easter egg. A FlappyGame is the
+// bird and the course in the default theme, with none of the rest of the
+// game around it (no menus, settings, sound or saves). The host passes it
+// the frame time and the player's input and shows what it draws wherever it
+// likes; `examples/embedded.rs` puts one on an arcade cabinet in a room.
+//
+// This and `play` are all the library makes public, so a host can rely on
+// them alone. API_VERSION goes up with any change to either that could
+// break one.
+
+use macroquad::prelude::*;
+
+use crate::game::{Game, GameState};
+use crate::theme::{DEFAULT_THEME, Theme};
+use crate::timestep::Timestep;
+use crate::{HEIGHT, WIDTH};
+
+/// The version of the embedding API, raised with every change that could
+/// break a host.
+pub const API_VERSION: u32 = 1;
+
+/// What the player did, for `FlappyGame::input`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Input {
+    /// Starts a run or flaps, and resumes a paused run or starts another
+    /// after a crash.
+    Flap,
+    /// Pauses a run in progress, or resumes a paused one.
+    Pause,
+}
+
+/// One game of Flappy Bird for embedding. See the module comment.
+pub struct FlappyGame {
+    game: Game,
+    theme: Theme,
+    timestep: Timestep,
+}
+
+impl FlappyGame {
+    /// The size of what `render` draws, in pixels. Targets of any size are
+    /// filled, but this one (or a whole multiple of it) keeps the pixel art
+    /// crisp.
+    pub const SIZE: (u32, u32) = (WIDTH as u32, HEIGHT as u32);
+
+    /// A game whose runs all fly the course for `seed`, or a new course
+    /// each run without one. New courses come from macroquad's `rand`,
+    /// which is seeded from the clock here, as the full game does at start.
+    /// Call once the window is up, since it loads textures. The sprites come
+    /// from `resources/` like the full game's, and the built-in copies stand
+    /// in for any that aren't there.
+    pub async fn new(seed: Option<u64>) -> FlappyGame {
+        if seed.is_none() {
+            rand::srand((miniquad::date::now() * 1000.0) as u64);
+        }
+        FlappyGame {
+            game: Game::with_seed(seed),
+            theme: Theme::load(DEFAULT_THEME).await,
+            timestep: Timestep::new(),
+        }
+    }
+
+    /// Runs the game on by `seconds` of real time, in the same fixed ticks
+    /// as the full game. Call once a frame with the frame time.
+    pub fn step(&mut self, seconds: f32) {
+        for _ in 0..self.timestep.ticks(seconds) {
+            self.game.update();
+        }
+        self.game.blend = self.timestep.blend();
+        // Nothing here listens for them
+        self.game.events.drain();
+    }
+
+    /// Passes on what the player did. It takes effect right away, not at
+    /// the next `step`.
+    pub fn input(&mut self, input: Input) {
+        match input {
+            Input::Flap => self.game.flap(),
+            Input::Pause => self.game.toggle_pause(),
+        }
+    }
+
+    /// Draws the game into `target`, filling it, then sets the default
+    /// camera again.
+    pub fn render(&self, target: &RenderTarget) {
+        set_camera(&Camera2D {
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0),
+            zoom: vec2(2.0 / WIDTH, 2.0 / HEIGHT),
+            render_target: Some(target.clone()),
+            ..Default::default()
+        });
+        clear_background(self.theme.sky_color);
+        self.theme.draw_background(self.game.scrolled());
+        self.game.draw(&self.theme);
+        self.game.draw_prompt(&self.theme);
+        set_default_camera();
+    }
+
+    /// The score of the run in progress, or of the last one once it's over.
+    pub fn score(&self) -> i64 {
+        self.game.score
+    }
+
+    /// Whether the last run has ended, until the next `Input::Flap`.
+    pub fn is_over(&self) -> bool {
+        self.game.state == GameState::GameOver
+    }
+}
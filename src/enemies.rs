@@ -0,0 +1,105 @@
+// Small enemy birds that fly at the player from the right
+
+use crate::sim_rng::SimRng;
+use macroquad::prelude::*;
+
+use crate::obstacle::Obstacle;
+use crate::pipes::SCROLL_SPEED;
+use crate::sim_math;
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::viewport::FIELD_LEFT;
+use crate::{HEIGHT, WIDTH};
+
+const ENEMY_WIDTH: f32 = 24.0;
+const ENEMY_HEIGHT: f32 = 17.0;
+// Spawned this far past the right edge, which gives about a second of warning
+const SPAWN_DISTANCE: f32 = 400.0;
+// Share of enemies that bob up and down instead of flying straight
+const WAVY_CHANCE: f32 = 0.4;
+
+#[derive(Clone)]
+pub struct EnemyBird {
+    pub x: f32,
+    pub y: f32,
+    // Own speed on top of the world scroll
+    speed: f32,
+    base_y: f32,
+    amplitude: f32,
+    age: f32,
+    pub alive: bool,
+}
+
+impl EnemyBird {
+    pub fn random(rng: &SimRng) -> Self {
+        let base_y = rng.gen_range(40.0, HEIGHT - 80.0);
+        let amplitude = if rng.gen_range(0.0, 1.0) < WAVY_CHANCE {
+            rng.gen_range(15.0, 40.0)
+        } else {
+            0.0
+        };
+        EnemyBird {
+            x: WIDTH + SPAWN_DISTANCE,
+            y: base_y,
+            speed: rng.gen_range(1.5, 3.0),
+            base_y,
+            amplitude,
+            age: 0.0,
+            alive: true,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, ENEMY_WIDTH, ENEMY_HEIGHT)
+    }
+
+    /// Blinking arrow on the right edge, at world x `right`, while the enemy
+    /// is still off-screen.
+    pub fn draw_warning(&self, right: f32) {
+        if self.x <= right || (self.age * 6.0) as i32 % 2 == 1 {
+            return;
+        }
+        let y = self.y + ENEMY_HEIGHT / 2.0;
+        draw_triangle(
+            vec2(right - 16.0, y),
+            vec2(right - 4.0, y - 7.0),
+            vec2(right - 4.0, y + 7.0),
+            RED,
+        );
+    }
+}
+
+impl Obstacle for EnemyBird {
+    fn update(&mut self) {
+        self.age += TICK_SECONDS;
+        self.x -= SCROLL_SPEED + self.speed;
+        self.y = self.base_y + sim_math::sin(self.age * 4.0) * self.amplitude;
+    }
+
+    fn hitboxes(&self) -> Vec<Rect> {
+        vec![self.rect()]
+    }
+
+    fn on_screen(&self) -> bool {
+        self.x < WIDTH && self.x + ENEMY_WIDTH > 0.0
+    }
+
+    fn is_gone(&self) -> bool {
+        !self.alive || self.x + ENEMY_WIDTH < FIELD_LEFT
+    }
+
+    fn draw(&self, theme: &Theme) {
+        // The bird sprite, shrunk, facing left and tinted red
+        draw_texture_ex(
+            &theme.bird,
+            self.x,
+            self.y,
+            Color::new(1.0, 0.45, 0.45, 1.0),
+            DrawTextureParams {
+                dest_size: Some(vec2(ENEMY_WIDTH, ENEMY_HEIGHT)),
+                flip_x: true,
+                ..Default::default()
+            },
+        );
+    }
+}
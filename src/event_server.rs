@@ -0,0 +1,182 @@
+// A local feed of game events for stream overlays and home-made hardware,
+// with the `event-server` cargo feature. While the game runs it serves
+// `http://127.0.0.1:<port>/events` (DEFAULT_PORT, or `event_port` in
+// `settings.cfg`) as server-sent events, one JSON object each:
+//
+//     data: {"event":"pipe_passed","tick":812,"score":12,"points":1}
+//
+// An OBS browser source can listen with `new EventSource(...)`, and
+// anything else that speaks HTTP can read the lines as they come, as
+// `curl -N` does. The events are run_started, flap, pipe_passed,
+// milestone, near_miss, coin, power_up, boss_started, boss_beaten,
+// checkpoint, respawned, level_completed, new_best (once a run, when the
+// score goes past the best before it) and died.
+//
+// Only this machine can connect. Without the feature, or on the web,
+// nothing is served.
+
+#[cfg(all(feature = "event-server", not(target_arch = "wasm32")))]
+use std::sync::mpsc::Sender;
+
+use crate::events::GameEvent;
+use crate::game::Game;
+
+pub const DEFAULT_PORT: u16 = 8757;
+
+pub struct EventServer {
+    // Lines for the thread writing to whoever's listening
+    #[cfg(all(feature = "event-server", not(target_arch = "wasm32")))]
+    feed: Option<Sender<String>>,
+    // This run's new_best has gone out
+    announced_best: bool,
+}
+
+impl EventServer {
+    /// Starts serving on `port`, when the game is built to.
+    pub fn start(port: u16) -> EventServer {
+        #[cfg(not(all(feature = "event-server", not(target_arch = "wasm32"))))]
+        let _ = port;
+        EventServer {
+            #[cfg(all(feature = "event-server", not(target_arch = "wasm32")))]
+            feed: server::serve(port)
+                .inspect_err(|err| eprintln!("Could not serve events on port {port}: {err}"))
+                .ok(),
+            announced_best: false,
+        }
+    }
+
+    /// Sends `event` on, with what the run's at. `best` is the best
+    /// before this run.
+    pub fn handle(&mut self, event: GameEvent, game: &Game, best: i64) {
+        if event == GameEvent::RunStarted {
+            self.announced_best = false;
+        }
+        let (name, fields) = match event {
+            GameEvent::RunStarted => ("run_started", String::new()),
+            GameEvent::Flapped => ("flap", String::new()),
+            GameEvent::PipePassed { score, points } => (
+                "pipe_passed",
+                format!(r#","score":{score},"points":{points}"#),
+            ),
+            GameEvent::MilestoneReached { pipes } => ("milestone", format!(r#","pipes":{pipes}"#)),
+            GameEvent::NearMiss => ("near_miss", String::new()),
+            GameEvent::CoinCollected => {
+                let coins = game.coins_collected;
+                ("coin", format!(r#","coins":{coins}"#))
+            }
+            GameEvent::PowerUpCollected { kind } => {
+                ("power_up", format!(r#","kind":"{}""#, kind.id()))
+            }
+            GameEvent::BossStarted => ("boss_started", String::new()),
+            GameEvent::BossBeaten { bonus } => ("boss_beaten", format!(r#","bonus":{bonus}"#)),
+            GameEvent::CheckpointReached => ("checkpoint", String::new()),
+            GameEvent::Respawned => ("respawned", format!(r#","deaths":{}"#, game.deaths)),
+            GameEvent::LevelCompleted => {
+                let stars = game.stars().unwrap_or(0);
+                let score = game.score;
+                (
+                    "level_completed",
+                    format!(r#","score":{score},"stars":{stars}"#),
+                )
+            }
+            GameEvent::Collided => {
+                let (score, pipes) = (game.score, game.pipes_passed);
+                let fields = format!(r#","score":{score},"pipes":{pipes},"best":{best}"#);
+                ("died", fields)
+            }
+            _ => return,
+        };
+        self.send(name, &fields, game);
+        if !self.announced_best && best > 0 && game.score > best {
+            self.announced_best = true;
+            let score = game.score;
+            self.send(
+                "new_best",
+                &format!(r#","score":{score},"previous":{best}"#),
+                game,
+            );
+        }
+    }
+
+    // `fields` starts with a comma when there are any
+    fn send(&self, name: &str, fields: &str, game: &Game) {
+        let line = format!(r#"{{"event":"{name}","tick":{}{fields}}}"#, game.ticks);
+        #[cfg(all(feature = "event-server", not(target_arch = "wasm32")))]
+        if let Some(feed) = &self.feed {
+            let _ = feed.send(line);
+        }
+        #[cfg(not(all(feature = "event-server", not(target_arch = "wasm32"))))]
+        let _ = line;
+    }
+}
+
+#[cfg(all(feature = "event-server", not(target_arch = "wasm32")))]
+mod server {
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    // A listener that stops reading for this long is dropped, so it can't
+    // hold up the rest
+    const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+    const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Listens on `port`, returning where to send lines for everyone
+    /// listening.
+    pub fn serve(port: u16) -> io::Result<Sender<String>> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let listening: Arc<Mutex<Vec<TcpStream>>> = Arc::default();
+        let joining = listening.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Ok(Some(stream)) = answer(stream) {
+                    joining.lock().unwrap().push(stream);
+                }
+            }
+        });
+        let (sender, lines) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in lines {
+                let message = format!("data: {line}\n\n");
+                listening
+                    .lock()
+                    .unwrap()
+                    .retain_mut(|stream| stream.write_all(message.as_bytes()).is_ok());
+            }
+        });
+        Ok(sender)
+    }
+
+    // Reads the request and starts the event stream if it's for /events,
+    // giving back the connection to write events to
+    fn answer(mut stream: TcpStream) -> io::Result<Option<TcpStream>> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+        // Headers, up to the blank line after them
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+        if request.starts_with("GET ") && path == "/events" {
+            stream.write_all(
+                b"HTTP/1.1 200 OK\r\n\
+                  Content-Type: text/event-stream\r\n\
+                  Cache-Control: no-cache\r\n\
+                  Access-Control-Allow-Origin: *\r\n\
+                  \r\n\
+                  : flappy bird events\n\n",
+            )?;
+            Ok(Some(stream))
+        } else {
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")?;
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Game events, queued during an update and handled by the main loop afterwards
+
+use crate::biomes::Biome;
+use crate::powerups::PowerUpKind;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    RunStarted,
+    Flapped,
+    // `points` is what the pair scored, bonuses included
+    PipePassed { score: i64, points: i64 },
+    MilestoneReached { pipes: u32 },
+    // The score went past another multiple of 1000, `laps` of them now
+    ScoreRolledOver { laps: i64 },
+    NearMiss,
+    CoinCollected,
+    BossStarted,
+    BossBeaten { bonus: i64 },
+    PowerUpCollected { kind: PowerUpKind },
+    // The shield absorbed a hit at this position
+    ShieldBroken { x: f32, y: f32 },
+    Collided,
+    // The game was quit in the middle of a run, which doesn't count as a
+    // crash or as a run played
+    RunAbandoned,
+    // The bird crossed the finish line of a level
+    LevelCompleted,
+    // The bird crossed one of a level's checkpoints
+    CheckpointReached,
+    // A practice crash took the bird back to the last checkpoint instead
+    // of ending the run
+    Respawned,
+    // The bird passed a level's pipe pair with triggers on it
+    TriggerFired { pipe: u32 },
+    // The journey moved on to another biome, or started in the first
+    BiomeReached { biome: Biome },
+}
+
+#[derive(Clone, Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    /// Takes every event queued since the last drain.
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.queue)
+    }
+}
@@ -0,0 +1,78 @@
+// A level's checkpoint flags and finish line (see `level.rs`), placed half
+// a spacing after their pipe pairs and scrolling with the world. Crossing
+// a checkpoint in practice (`--practice`) is where a crash takes the bird
+// back to, and crossing the finish ends the level. The sprites are the
+// theme's `checkpoint.png`, a flag on a pole standing on the ground, and
+// `finish.png`, a tile repeated down the whole height of the screen.
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::pipes::SCROLL_SPEED;
+use crate::theme::Theme;
+
+// How big the checkpoint flag is drawn, whatever the sprite's size
+const FLAG_SIZE: Vec2 = vec2(16.0, 32.0);
+// The finish tile's width on screen
+const FINISH_WIDTH: f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FlagKind {
+    Checkpoint,
+    Finish,
+}
+
+#[derive(Clone)]
+pub struct Flag {
+    pub kind: FlagKind,
+    pub x: f32,
+    pub crossed: bool,
+}
+
+impl Flag {
+    pub fn new(kind: FlagKind, x: f32) -> Flag {
+        Flag {
+            kind,
+            x,
+            crossed: false,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.x -= SCROLL_SPEED;
+    }
+
+    /// Draws the flag, paler until the bird has crossed it, or the finish
+    /// line.
+    pub fn draw(&self, theme: &Theme) {
+        match self.kind {
+            FlagKind::Checkpoint => {
+                let color = if self.crossed {
+                    GOLD
+                } else {
+                    Color::new(1.0, 1.0, 1.0, 0.6)
+                };
+                draw_line(self.x, 0.0, self.x, HEIGHT, 1.0, Color { a: 0.25, ..color });
+                let params = DrawTextureParams {
+                    dest_size: Some(FLAG_SIZE),
+                    ..Default::default()
+                };
+                let y = HEIGHT - FLAG_SIZE.y;
+                draw_texture_ex(&theme.checkpoint, self.x - 1.5, y, color, params);
+            }
+            FlagKind::Finish => {
+                let tile = theme.finish.size();
+                let height = (FINISH_WIDTH * tile.y / tile.x).max(1.0);
+                let params = DrawTextureParams {
+                    dest_size: Some(vec2(FINISH_WIDTH, height)),
+                    ..Default::default()
+                };
+                let mut y = 0.0;
+                while y < HEIGHT {
+                    draw_texture_ex(&theme.finish, self.x, y, theme.tint, params.clone());
+                    y += height;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// Entry points for fuzzing the file loaders, one per kind of file. Each
+// takes whatever bytes it's given, the way a `cargo fuzz` target does, and
+// must come back without panicking; what they load is thrown away. Built
+// for the tests and under `--cfg fuzzing`, which cargo-fuzz sets.
+//
+// Under `--cfg fuzzing` the module is public, so a cargo-fuzz crate can
+// call them as `flappy_bird_rust::fuzz::level(data)` and so on.
+
+use crate::history;
+use crate::level::Level;
+use crate::mod_pack::Package;
+use crate::profile::Profile;
+use crate::replay::Replay;
+use crate::settings::Settings;
+
+pub fn level(data: &[u8]) {
+    let _ = Level::parse(data, "fuzzed");
+}
+
+pub fn replay(data: &[u8]) {
+    let _ = Replay::parse(data);
+}
+
+pub fn mod_package(data: &[u8]) {
+    let _ = Package::parse(data);
+}
+
+// Saves and settings get past storage as text, so anything that isn't
+// stops there
+pub fn settings(data: &[u8]) {
+    if let Ok(text) = std::str::from_utf8(data) {
+        Settings::parse(text);
+    }
+}
+
+pub fn profile(data: &[u8]) {
+    if let Ok(text) = std::str::from_utf8(data) {
+        Profile::parse(text);
+    }
+}
+
+pub fn history(data: &[u8]) {
+    if let Ok(text) = std::str::from_utf8(data) {
+        history::parse(text);
+    }
+}
+
+/// Every loader in turn, for a single fuzz target covering them all.
+pub fn all(data: &[u8]) {
+    level(data);
+    replay(data);
+    mod_package(data);
+    settings(data);
+    profile(data);
+    history(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    // Real files, for cutting short and scribbling over
+    const SAMPLES: [&str; 3] = [
+        include_str!("../levels/warmup.level"),
+        include_str!("../replays/golden/short.replay"),
+        "runs = 12\nbest_score = 40\nachievements = first_flight, \ndeaths = 3:12.5, 7:-4:ground\n",
+    ];
+
+    proptest! {
+        #[test]
+        fn loaders_take_any_bytes(data: Vec<u8>) {
+            all(&data);
+        }
+
+        #[test]
+        fn loaders_take_damaged_files(
+            sample in 0..SAMPLES.len(),
+            cut: prop::sample::Index,
+            scribbles in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 0..8),
+        ) {
+            let mut data = SAMPLES[sample].as_bytes().to_vec();
+            data.truncate(cut.index(data.len() + 1));
+            for (at, byte) in scribbles {
+                if !data.is_empty() {
+                    let at = at.index(data.len());
+                    data[at] = byte;
+                }
+            }
+            all(&data);
+        }
+    }
+}
@@ -0,0 +1,1600 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use macroquad::color::hsl_to_rgb;
+use macroquad::prelude::*;
+
+use crate::assists::Assists;
+use crate::attacks::Attack;
+use crate::biomes::{self, Biome};
+use crate::bird::{Bird, MAGNET_RADIUS};
+use crate::boss::Boss;
+use crate::challenge::Challenge;
+use crate::coins::{COIN_RADIUS, Coin};
+use crate::collision::{self, DEFAULT_GRACE_TICKS, Grace, Hit};
+use crate::combo::Combo;
+use crate::cosmetics::{self, Look};
+use crate::enemies::EnemyBird;
+use crate::events::{EventBus, GameEvent};
+use crate::flags::{Flag, FlagKind};
+use crate::gaps::{GapGenerator, GapParams};
+use crate::grading::Grade;
+use crate::level::Level;
+use crate::mask::SpriteMasks;
+use crate::medals::Medal;
+use crate::modifiers::Modifier;
+use crate::mutators::Mutators;
+use crate::obstacle::Obstacle;
+use crate::perf;
+use crate::physics::{Limits, Physics};
+use crate::pipes::{
+    GAP_HEIGHT, MAX_PIPE_WIDTH, PIPE_SPACING, PairOfPipes, PipeSizes, PipeStyle, SCROLL_SPEED,
+};
+use crate::powerups::{PowerUp, PowerUpKind};
+use crate::seed_code;
+use crate::sim_math;
+use crate::sim_rng::SimRng;
+use crate::terrain::Terrain;
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::triggers::Trigger;
+use crate::viewport::{FIELD_LEFT, FIELD_RIGHT, Layout};
+use crate::{HEIGHT, WIDTH};
+
+pub const DEFAULT_GRAVITY: f32 = 0.5;
+
+// A milestone banner is shown every this many pipes
+pub const MILESTONE_INTERVAL: u32 = 25;
+
+// Where the first pair starts, far enough out that the opening flap's arc
+// has settled before it arrives
+const FIRST_PIPE_X: f32 = WIDTH + PIPE_SPACING / 2.0;
+
+// Clearing a pipe by less than this many pixels is a near miss
+const NEAR_MISS_DISTANCE: f32 = 4.0;
+const NEAR_MISS_BONUS: i64 = 1;
+const NEAR_MISS_FLASH_SECONDS: f32 = 0.6;
+
+// Chance that a new pipe pair carries a shield in its gap
+const SHIELD_CHANCE: f32 = 0.08;
+const SHIELD_INVINCIBLE_SECONDS: f32 = 1.0;
+// And after going back to a checkpoint in practice
+const RESPAWN_INVINCIBLE_SECONDS: f32 = 1.5;
+const MAGNET_CHANCE: f32 = 0.05;
+const MAGNET_SECONDS: f32 = 6.0;
+// Strongest pull (pixels per tick squared), felt by coins right next to the bird
+const MAGNET_PULL: f32 = 1.2;
+
+// Chance of a row of coins between two pipe pairs
+const COIN_ROW_CHANCE: f32 = 0.5;
+const COINS_PER_ROW: usize = 3;
+
+// Enemy birds start showing up after this many pipes, every few seconds
+const ENEMY_MIN_PIPES: u32 = 3;
+const ENEMY_MIN_SECONDS: f32 = 4.0;
+const ENEMY_MAX_SECONDS: f32 = 9.0;
+
+// A boss fight replaces the pipes every this many pipes
+const BOSS_INTERVAL: u32 = 50;
+const BOSS_BONUS: i64 = 10;
+
+// Modifier tuning: wind gusts swing between up and down over WIND_PERIOD
+const WIND_STRENGTH: f32 = 0.2;
+const WIND_PERIOD_SECONDS: f32 = 3.0;
+const DRIFT_SPEED: f32 = 0.8;
+const NARROW_GAP_HEIGHT: f32 = 80.0;
+
+// Versus attacks: the gust pushes down from GUST_RANGE before its pair until
+// the bird is through
+const ATTACK_GAP_HEIGHT: f32 = 70.0;
+const GUST_STRENGTH: f32 = 0.3;
+const GUST_RANGE: f32 = 80.0;
+
+// The HUD counter has three digits: past 999 it rolls over, with the
+// thousands beside it and a rainbow for a few seconds
+pub const SCORE_ROLLOVER: i64 = 1000;
+const ROLLOVER_FLASH_SECONDS: f32 = 4.0;
+// Widest the score up top gets before it's drawn smaller
+const HUD_SCORE_WIDTH: f32 = 120.0;
+
+// Gaps shown by the preview strip, and rolled ahead of time for it
+const PREVIEW_GAPS: usize = 5;
+// Mixed into the seed for the gap rolls, so they aren't the course's rolls
+const GAP_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameState {
+    Ready,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+// Everything that makes up one run
+#[derive(Clone)]
+pub struct Game {
+    pub bird: Bird,
+    pub pipes: Vec<PairOfPipes>,
+    pub powerups: Vec<PowerUp>,
+    pub coins: Vec<Coin>,
+    pub enemies: Vec<EnemyBird>,
+    // While a boss is active no new pipes or enemies are spawned
+    pub boss: Option<Boss>,
+    // Seconds until the next enemy bird
+    enemy_timer: f32,
+    // Coins picked up this run
+    pub coins_collected: u32,
+    // Where the spawner puts the next pipe pair (scrolls with the world)
+    next_pipe_x: f32,
+    // Pipe pairs added this run, for numbering them
+    pipes_spawned: u32,
+    pub score: i64,
+    // Pipes actually flown through, unlike `score` which modes can change
+    pub pipes_passed: u32,
+    pub gravity: f32,
+    // Picked before the run, kept from run to run; see `set_physics`
+    pub physics: Physics,
+    // Top falling speed and flap cooldown, likewise; see `set_limits`
+    pub limits: Limits,
+    pub combo: Combo,
+    pub state: GameState,
+    pub events: EventBus,
+    // Changes how the run plays until replaced, see `modifiers.rs`
+    pub modifier: Option<Modifier>,
+    // Seconds the wind has been blowing, for the gust cycle
+    wind_time: f32,
+    // The level's own wind, as a share of the modifier's, set by its
+    // triggers (see `triggers.rs`)
+    level_wind: f32,
+    // Seconds left on the "Close!" label
+    near_miss_flash: f32,
+    // Seconds left of the rainbow after the score rolled over
+    rollover_flash: f32,
+    // Everything random about the course comes from here, except the gaps
+    rng: SimRng,
+    // Gap positions come from their own generator, drawn PREVIEW_GAPS
+    // ahead of the spawner so the preview can show them
+    gaps: GapGenerator,
+    // What shapes `gaps`, kept from run to run
+    pub gap_params: GapParams,
+    upcoming_gaps: VecDeque<f32>,
+    // Attacks from a versus opponent, each waiting for the next pipe pair
+    pub incoming: VecDeque<Attack>,
+    // When set, every run flies the same course (e.g. for challenge links)
+    pub fixed_seed: Option<u64>,
+    // The course this run flies, fixed or not
+    pub seed: u64,
+    // Updates since the run started
+    pub ticks: u32,
+    pub flaps: u32,
+    // Updates run before each flap, for replays (see `replay.rs`)
+    pub flap_ticks: Vec<u32>,
+    // What ended the run
+    pub crash: Option<Hit>,
+    // A bird that can't crash, for spectators following someone else's run
+    pub ghost: bool,
+    // Forgiveness for clipped pipe corners
+    pub grace: Grace,
+    // Sprite masks when pixel-perfect collision is on
+    pub masks: Option<Rc<SpriteMasks>>,
+    // How big stones and trees are, from the theme's sprites
+    pub pipe_sizes: PipeSizes,
+    // Flown instead of the endless course when set
+    pub level: Option<Rc<Level>>,
+    // The cave's rock, laid all through the journey and flat outside the
+    // cave
+    pub terrain: Option<Terrain>,
+    // The run ended by crossing the level's finish line
+    pub completed: bool,
+    // The level's checkpoints and finish line that have spawned
+    flags: Vec<Flag>,
+    // With a level, a crash goes back to the last checkpoint crossed (or
+    // the start) instead of ending the run
+    pub practice: bool,
+    // The run as it was there, to go back to
+    respawn: Option<Box<Game>>,
+    // Times the bird has gone back this run
+    pub deaths: u32,
+    // Coins that have spawned or been handed over, for the level's stars
+    pub coins_offered: u32,
+    // Assist options, kept from run to run; see `set_assists`
+    pub assists: Assists,
+    // Picked before the run, kept from run to run; see `set_mutators`
+    pub mutators: Mutators,
+    // The challenge being played, if any, kept from run to run
+    pub challenge: Option<Challenge>,
+    // How much of the field is on screen; only changes how the game is
+    // drawn, never how it plays
+    pub layout: Layout,
+    // What the bird wears, kept from run to run; only changes how it's drawn
+    pub look: Look,
+    // Streamer mode (see `settings.rs`): the course code isn't shown
+    pub streamer_mode: bool,
+    // How far the frame is from the last tick to the next, from 0 to 1;
+    // below 1 the game is drawn part of the way back from the last tick
+    // towards the one before it, so it moves smoothly whatever the frame
+    // rate. Only changes how the game is drawn, see `draw_world`
+    pub blend: f32,
+    // Where the bird was before the last tick, for `blend`
+    previous_bird: Option<Vec2>,
+    // Seconds spent checking for hits since the performance overlay last
+    // took them
+    pub collision_seconds: f64,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game::with_seed(None)
+    }
+
+    /// A game whose runs all fly the course for `seed`.
+    pub fn from_seed(seed: u64) -> Self {
+        Game::with_seed(Some(seed))
+    }
+
+    /// A game whose runs all use `fixed_seed`, or a fresh course each run.
+    pub fn with_seed(fixed_seed: Option<u64>) -> Self {
+        Game::with_level(fixed_seed, None)
+    }
+
+    /// Like `with_seed`, but flying `level`'s pipes when there is one. The
+    /// seed still picks everything else, like power-ups and coins.
+    pub fn with_level(fixed_seed: Option<u64>, level: Option<Rc<Level>>) -> Self {
+        Game::with_course(fixed_seed, level, GapParams::default())
+    }
+
+    /// Like `with_level`, with `gap_params` shaping random gaps when the
+    /// seed isn't fixed.
+    pub fn with_course(
+        fixed_seed: Option<u64>,
+        level: Option<Rc<Level>>,
+        gap_params: GapParams,
+    ) -> Self {
+        let seed = fixed_seed.unwrap_or_else(fresh_seed);
+        let mut game = Game::unspawned(fixed_seed, seed, level, gap_params);
+        game.fill_course();
+        game
+    }
+
+    // A run on `seed` with no course laid yet, so the challenge can be set
+    // before it is
+    fn unspawned(
+        fixed_seed: Option<u64>,
+        seed: u64,
+        level: Option<Rc<Level>>,
+        gap_params: GapParams,
+    ) -> Self {
+        let rng = SimRng::new(seed);
+        // Shared courses always use the default gaps, so everyone on the
+        // same seed flies the same one
+        let gap_params = if fixed_seed.is_some() {
+            GapParams::default()
+        } else {
+            gap_params
+        };
+        Game {
+            bird: Bird::new(),
+            pipes: Vec::new(),
+            powerups: Vec::new(),
+            coins: Vec::new(),
+            enemies: Vec::new(),
+            boss: None,
+            enemy_timer: ENEMY_MIN_SECONDS,
+            coins_collected: 0,
+            next_pipe_x: FIRST_PIPE_X,
+            pipes_spawned: 0,
+            score: 0,
+            pipes_passed: 0,
+            gravity: DEFAULT_GRAVITY,
+            physics: Physics::Classic,
+            limits: Limits::default(),
+            combo: Combo::default(),
+            state: GameState::Ready,
+            events: EventBus::default(),
+            modifier: None,
+            wind_time: 0.0,
+            level_wind: 0.0,
+            near_miss_flash: 0.0,
+            rollover_flash: 0.0,
+            rng,
+            gaps: GapGenerator::new(seed ^ GAP_SEED_SALT),
+            gap_params,
+            upcoming_gaps: VecDeque::new(),
+            incoming: VecDeque::new(),
+            fixed_seed,
+            seed,
+            ticks: 0,
+            flaps: 0,
+            flap_ticks: Vec::new(),
+            crash: None,
+            ghost: false,
+            grace: Grace::new(DEFAULT_GRACE_TICKS),
+            masks: None,
+            pipe_sizes: PipeSizes::default(),
+            level,
+            terrain: None,
+            completed: false,
+            flags: Vec::new(),
+            practice: false,
+            respawn: None,
+            deaths: 0,
+            coins_offered: 0,
+            assists: Assists::default(),
+            mutators: Mutators::default(),
+            challenge: None,
+            layout: Layout::default(),
+            look: Look::default(),
+            streamer_mode: false,
+            blend: 1.0,
+            previous_bird: None,
+            collision_seconds: 0.0,
+        }
+    }
+
+    // Draws the gaps the preview shows and spawns the first pairs
+    fn fill_course(&mut self) {
+        while self.upcoming_gaps.len() < PREVIEW_GAPS {
+            self.queue_gap();
+        }
+        self.spawn_pipes();
+        if self.challenge == Some(Challenge::Journey) {
+            self.terrain = Some(Terrain::new(self.seed));
+            self.lay_terrain();
+        }
+    }
+
+    // Lays the journey's rock up to the right edge. It stops while a boss
+    // is out, so the pairs after it come back to an open cave
+    fn lay_terrain(&mut self) {
+        let Some(terrain) = &mut self.terrain else {
+            return;
+        };
+        let (spawned, next_x) = (self.pipes_spawned, self.next_pipe_x);
+        let boss = self.boss.is_some();
+        terrain.extend(&self.pipes, |x| {
+            if boss {
+                0.0
+            } else {
+                biomes::cave_at(course_pipe(spawned, next_x, x))
+            }
+        });
+    }
+
+    /// Starts a fresh run, keeping the event queue.
+    pub fn reset(&mut self) {
+        let events = std::mem::take(&mut self.events);
+        let (assists, mutators) = (self.assists, self.mutators);
+        let seed = self.fixed_seed.unwrap_or_else(fresh_seed);
+        *self = Game {
+            challenge: self.challenge,
+            layout: self.layout,
+            look: self.look,
+            streamer_mode: self.streamer_mode,
+            blend: self.blend,
+            ghost: self.ghost,
+            grace: Grace::new(self.grace.ticks),
+            masks: self.masks.take(),
+            pipe_sizes: self.pipe_sizes,
+            gravity: self.physics.gravity(),
+            physics: self.physics,
+            limits: self.limits,
+            practice: self.practice,
+            ..Game::unspawned(self.fixed_seed, seed, self.level.take(), self.gap_params)
+        };
+        self.fill_course();
+        self.events = events;
+        self.set_assists(assists);
+        self.set_mutators(mutators);
+    }
+
+    /// Flies `level` from now on, or the endless course for none, starting
+    /// a fresh run on it.
+    pub fn set_level(&mut self, level: Option<Rc<Level>>) {
+        self.level = level;
+        self.reset();
+    }
+
+    /// Switches challenges between runs; a run in progress keeps its own.
+    /// The journey lays its own course, so going in or out of it starts a
+    /// fresh one.
+    pub fn set_challenge(&mut self, challenge: Option<Challenge>) {
+        if self.state != GameState::Ready || self.challenge == challenge {
+            return;
+        }
+        let journey = Some(Challenge::Journey);
+        let new_course = self.challenge == journey || challenge == journey;
+        self.challenge = challenge;
+        if new_course {
+            self.reset();
+        }
+    }
+
+    /// Changes the physics for runs that haven't started yet. The course
+    /// waiting is laid again, so its gaps are in reach with them.
+    pub fn set_physics(&mut self, physics: Physics) {
+        if self.state == GameState::Ready && self.physics != physics {
+            self.physics = physics;
+            self.reset();
+        }
+    }
+
+    /// Changes the falling and flapping limits for runs that haven't
+    /// started yet, laying the course waiting again like `set_physics`.
+    pub fn set_limits(&mut self, limits: Limits) {
+        if self.state == GameState::Ready && self.limits != limits {
+            self.limits = limits;
+            self.reset();
+        }
+    }
+
+    /// The fastest the bird can fall.
+    pub fn max_fall(&self) -> f32 {
+        self.limits.max_fall_with(self.physics)
+    }
+
+    /// Whether a flap now would count, rather than come too soon after the
+    /// last.
+    pub fn flap_ready(&self) -> bool {
+        self.state != GameState::Playing
+            || self
+                .flap_ticks
+                .last()
+                .is_none_or(|&last| self.ticks >= last + self.limits.flap_cooldown)
+    }
+
+    /// Changes the mutators for runs that haven't started yet.
+    pub fn set_mutators(&mut self, mutators: Mutators) {
+        if self.state == GameState::Ready {
+            self.mutators = mutators;
+            self.bird.scale = mutators.bird_scale();
+        }
+    }
+
+    /// Changes the assist options, resizing the gaps already on screen to
+    /// match. A run that hasn't started gets its starting shield.
+    pub fn set_assists(&mut self, assists: Assists) {
+        let extra = assists.extra_gap() - self.assists.extra_gap();
+        for pipe in &mut self.pipes {
+            pipe.widen(extra);
+        }
+        if self.state == GameState::Ready {
+            self.bird.shield = assists.start_shield;
+        }
+        self.assists = assists;
+    }
+
+    pub fn flap(&mut self) {
+        self.flap_with(1.0);
+    }
+
+    /// Flaps at `strength` times the usual speed, for small flaps.
+    pub fn flap_with(&mut self, strength: f32) {
+        if !self.flap_ready() {
+            return;
+        }
+        if matches!(self.state, GameState::Ready | GameState::Playing) {
+            self.flap_ticks.push(self.ticks);
+        }
+        match self.state {
+            GameState::Ready => {
+                self.state = GameState::Playing;
+                self.events.emit(GameEvent::RunStarted);
+                if let Some(biome) = self.biome() {
+                    self.events.emit(GameEvent::BiomeReached { biome });
+                }
+                self.bird.velocity = self.flap_velocity(strength);
+                self.flaps += 1;
+                self.events.emit(GameEvent::Flapped);
+            }
+            GameState::Playing => {
+                self.bird.velocity = self.flap_velocity(strength);
+                self.flaps += 1;
+                self.events.emit(GameEvent::Flapped);
+            }
+            // Flapping while paused only resumes, so the bird doesn't lurch
+            GameState::Paused => self.state = GameState::Playing,
+            GameState::GameOver => self.reset(),
+        }
+    }
+
+    fn flap_velocity(&self, strength: f32) -> Vec2 {
+        let jump_velocity = self.physics.jump_velocity();
+        self.mutators
+            .flap_velocity(self.bird.velocity, jump_velocity, strength)
+    }
+
+    /// Pauses a run in progress, or resumes a paused one.
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            state => state,
+        };
+    }
+
+    /// Runs one tick of a paused run, which stays paused unless it ended.
+    pub fn step(&mut self) {
+        if self.state != GameState::Paused {
+            return;
+        }
+        self.state = GameState::Playing;
+        self.update();
+        if self.state == GameState::Playing {
+            self.state = GameState::Paused;
+        }
+    }
+
+    // Keeps one pipe pair queued beyond the right edge of the widest layout,
+    // like an endless course
+    fn spawn_pipes(&mut self) {
+        if self.boss.is_some() {
+            return;
+        }
+        while self.next_pipe_x < FIELD_RIGHT + PIPE_SPACING {
+            let gap = match &self.level {
+                Some(level) => match level.gaps.get(self.pipes_spawned as usize) {
+                    Some(&gap) => gap,
+                    // Only the finish line is left
+                    None => {
+                        if !self.flags.iter().any(|flag| flag.kind == FlagKind::Finish) {
+                            let x = self.next_pipe_x - PIPE_SPACING / 2.0;
+                            self.flags.push(Flag::new(FlagKind::Finish, x));
+                        }
+                        return;
+                    }
+                },
+                None => {
+                    self.queue_gap();
+                    self.upcoming_gaps.pop_front().unwrap_or_default()
+                }
+            };
+            let mut pipe = PairOfPipes::with_gap(self.next_pipe_x, gap);
+            let (style, width) = self.next_pipe_style();
+            let mut size = self.pipe_sizes.of(style);
+            size.x = width.unwrap_or(size.x);
+            if (style, size) != (pipe.style, vec2(pipe.width, pipe.height)) {
+                pipe.set_style(style, size);
+            }
+            match self.modifier {
+                Some(Modifier::MovingPipes) => {
+                    pipe.drift = if self.rng.gen_range(0, 2) == 0 {
+                        DRIFT_SPEED
+                    } else {
+                        -DRIFT_SPEED
+                    };
+                }
+                Some(Modifier::NarrowGap) => pipe.set_gap_height(NARROW_GAP_HEIGHT),
+                _ => {}
+            }
+            pipe.attack = self.incoming.pop_front();
+            if pipe.attack == Some(Attack::NarrowGap) {
+                pipe.set_gap_height(ATTACK_GAP_HEIGHT);
+            }
+            pipe.widen(self.assists.extra_gap());
+            let gap_center = (pipe.gap_top() + pipe.gap_bottom()) / 2.0;
+            let roll = self.rng.gen_range(0.0, 1.0);
+            let powerup = if roll < SHIELD_CHANCE {
+                Some(PowerUpKind::Shield)
+            } else if roll < SHIELD_CHANCE + MAGNET_CHANCE {
+                Some(PowerUpKind::Magnet)
+            } else {
+                None
+            };
+            if let Some(kind) = powerup {
+                let center = vec2(pipe.x + pipe.width / 2.0, gap_center);
+                self.powerups.push(PowerUp::new(kind, center));
+            }
+            // Coins sit halfway to the next pipe, level with this gap
+            if self.rng.gen_range(0.0, 1.0) < COIN_ROW_CHANCE {
+                let row_x = pipe.right() + (PIPE_SPACING - pipe.width) / 2.0;
+                for i in 0..COINS_PER_ROW {
+                    let offset = (i as f32 - (COINS_PER_ROW - 1) as f32 / 2.0) * 20.0;
+                    self.coins.push(Coin::new(vec2(row_x + offset, gap_center)));
+                }
+                self.coins_offered += COINS_PER_ROW as u32;
+            }
+            let checkpoint_x = pipe.x + PIPE_SPACING / 2.0;
+            self.add_pipe(pipe);
+            if let Some(level) = &self.level
+                && level.checkpoints.contains(&self.pipes_spawned)
+            {
+                self.flags
+                    .push(Flag::new(FlagKind::Checkpoint, checkpoint_x));
+            }
+            self.next_pipe_x += PIPE_SPACING;
+        }
+    }
+
+    // Draws the gap for the pair after the queued ones. Each follows on
+    // from the one before it, so the course stays flyable even as gravity
+    // changes
+    fn queue_gap(&mut self) {
+        let gap = self.gaps.next(
+            &self.spawn_gap_params(),
+            self.gravity,
+            self.physics,
+            self.limits,
+        );
+        self.upcoming_gaps.push_back(gap);
+    }
+
+    // What the gap generator is given: the journey's stones and trees are
+    // always in the mix, so its gaps leave room for them
+    fn spawn_gap_params(&self) -> GapParams {
+        match self.challenge {
+            Some(Challenge::Journey) => GapParams {
+                pipe_variety: 1.0,
+                ..self.gap_params
+            },
+            _ => self.gap_params,
+        }
+    }
+
+    // The next pair's style, and its width if the level sets one. Random
+    // courses only roll for it with `pipe_variety` on, so classic courses
+    // draw the same numbers as ever
+    fn next_pipe_style(&self) -> (PipeStyle, Option<f32>) {
+        if let Some(level) = &self.level {
+            let i = self.pipes_spawned as usize;
+            return (
+                level.styles.get(i).copied().unwrap_or_default(),
+                level.widths.get(i).copied(),
+            );
+        }
+        if self.challenge == Some(Challenge::Journey) {
+            let biome = Biome::for_pipe(self.pipes_spawned + 1);
+            return (biome.pipe_style(), None);
+        }
+        let variety = self.gap_params.pipe_variety;
+        if variety > 0.0 && self.rng.gen_range(0.0, 1.0) < variety {
+            let style = if self.rng.gen_range(0, 2) == 0 {
+                PipeStyle::Stone
+            } else {
+                PipeStyle::Tree
+            };
+            return (style, None);
+        }
+        (PipeStyle::Pipe, None)
+    }
+
+    /// Adds a pipe pair just off the right edge with its gap at `gap_y`.
+    pub fn spawn_pipe(&mut self, gap_y: f32) {
+        self.add_pipe(PairOfPipes::with_gap(FIELD_RIGHT, gap_y));
+    }
+
+    /// The biome the journey is in, or None outside it: the one the next
+    /// pair to fly through stands in.
+    pub fn biome(&self) -> Option<Biome> {
+        (self.challenge == Some(Challenge::Journey)).then(|| Biome::for_pipe(self.pipes_passed + 1))
+    }
+
+    // How many pipes along the course `x` on screen is, counted like
+    // `Biome::blend_at`
+    fn course_pipe_at(&self, x: f32) -> f32 {
+        course_pipe(self.pipes_spawned, self.next_pipe_x, x)
+    }
+
+    /// How far the course has scrolled this run, in pixels.
+    pub fn distance(&self) -> f32 {
+        self.ticks as f32 * SCROLL_SPEED
+    }
+
+    /// Like `distance`, but as far as it's drawn, blended back towards the
+    /// tick before (see `blend`).
+    pub fn scrolled(&self) -> f32 {
+        self.distance() - self.behind() * SCROLL_SPEED
+    }
+
+    // The scroll distance that brings pipe pair `pipes` (counted from 1, so
+    // 2.5 is halfway between the second and third) level with the bird
+    fn level_distance(&self, pipes: f32) -> f32 {
+        FIRST_PIPE_X + (pipes - 1.0) * PIPE_SPACING - self.bird.x
+    }
+
+    /// The next few gaps ahead of the bird as (top, bottom) edges, nearest
+    /// first: those already on the course, then the ones still to spawn.
+    pub fn upcoming_gaps(&self) -> Vec<(f32, f32)> {
+        let spawned = self
+            .pipes
+            .iter()
+            .filter(|pipe| pipe.right() > self.bird.x)
+            .map(|pipe| (pipe.gap_top(), pipe.gap_bottom()));
+        let planned: Vec<f32> = match &self.level {
+            Some(level) => level
+                .gaps
+                .get(self.pipes_spawned as usize..)
+                .unwrap_or_default()
+                .to_vec(),
+            None => self.upcoming_gaps.iter().copied().collect(),
+        };
+        spawned
+            .chain(planned.into_iter().map(|top| (top, top + GAP_HEIGHT)))
+            .take(PREVIEW_GAPS)
+            .collect()
+    }
+
+    fn add_pipe(&mut self, mut pipe: PairOfPipes) {
+        self.pipes_spawned += 1;
+        pipe.number = self.pipes_spawned;
+        self.pipes.push(pipe);
+    }
+
+    /// Runs up to `ticks` updates without flapping, stopping early if the
+    /// run ends. Returns how many ran.
+    #[cfg(test)]
+    pub fn fast_forward(&mut self, ticks: u32) -> u32 {
+        let mut ran = 0;
+        while ran < ticks && self.state == GameState::Playing {
+            self.update();
+            ran += 1;
+        }
+        ran
+    }
+
+    pub fn update(&mut self) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        if self.practice && self.level.is_some() && self.respawn.is_none() {
+            self.save_respawn();
+        }
+        self.ticks += 1;
+        self.previous_bird = Some(vec2(self.bird.x, self.bird.y));
+
+        // Update bird (mutable borrow)
+        self.bird.velocity.x = self.mutators.drift(self.bird.velocity.x, self.bird.x);
+        self.bird.update(self.gravity, self.max_fall());
+        let wind = self.wind();
+        if wind > 0.0 {
+            self.wind_time += TICK_SECONDS;
+            let phase = self.wind_time / WIND_PERIOD_SECONDS * std::f32::consts::TAU;
+            self.bird.velocity.y += WIND_STRENGTH * wind * sim_math::sin(phase);
+            self.bird.velocity.x += self.mutators.wind_drift(phase) * wind;
+        }
+        let gusting = self.pipes.iter().any(|pipe| {
+            pipe.attack == Some(Attack::Gust) && !pipe.passed && pipe.x - self.bird.x < GUST_RANGE
+        });
+        if gusting {
+            self.bird.velocity.y += GUST_STRENGTH;
+        }
+        if self.bird.y < 0.0 {
+            self.bird.y = 0.0;
+            self.bird.velocity.y = 0.0;
+        }
+
+        self.near_miss_flash = (self.near_miss_flash - TICK_SECONDS).max(0.0);
+        self.rollover_flash = (self.rollover_flash - TICK_SECONDS).max(0.0);
+        self.bird.invincible = (self.bird.invincible - TICK_SECONDS).max(0.0);
+        self.bird.magnet = (self.bird.magnet - TICK_SECONDS).max(0.0);
+
+        self.next_pipe_x -= SCROLL_SPEED;
+        if self.cross_flags() {
+            return;
+        }
+        self.spawn_pipes();
+        self.pipes.retain(|pipe| !pipe.is_gone());
+        if let Some(terrain) = &mut self.terrain {
+            terrain.scroll();
+        }
+        self.lay_terrain();
+
+        // Update pipes (mutable borrow of each pipe)
+        let bird = self.bird.rect();
+        for pipe in &mut self.pipes {
+            pipe.update();
+            if pipe.x < WIDTH {
+                pipe.shown += 1;
+            }
+            // Pipes further out than the portrait screen stay hidden, so
+            // seeing more of the field doesn't help
+            if let Some(challenge) = self.challenge {
+                pipe.alpha = if pipe.shown == 0 {
+                    0.0
+                } else {
+                    challenge.pipe_alpha(pipe.shown, self.ticks)
+                };
+            }
+            if pipe.passed || !pipe.on_screen() {
+                continue;
+            }
+            pipe.closest = pipe.closest.min(collision::obstacle_distance(&bird, pipe));
+            if !pipe.judged && pipe.x + pipe.width / 2.0 <= bird.center().x {
+                pipe.judged = true;
+                let centered =
+                    Combo::is_centered(bird.center().y, pipe.gap_top(), pipe.gap_bottom());
+                self.combo.record(centered);
+            }
+            if pipe.right() < self.bird.x {
+                pipe.passed = true;
+                let near_miss = pipe.closest > 0.0 && pipe.closest < NEAR_MISS_DISTANCE;
+                let worth = self
+                    .level
+                    .as_ref()
+                    .map_or(1, |level| level.score_for(pipe.number));
+                let mut points = i64::from(worth * self.combo.multiplier());
+                if near_miss {
+                    points += NEAR_MISS_BONUS;
+                }
+                let before = self.score;
+                self.score += points;
+                if let Some(laps) = rolled_over(before, self.score) {
+                    self.rollover_flash = ROLLOVER_FLASH_SECONDS;
+                    self.events.emit(GameEvent::ScoreRolledOver { laps });
+                }
+                self.pipes_passed += 1;
+                self.events.emit(GameEvent::PipePassed {
+                    score: self.score,
+                    points,
+                });
+                if let Some(level) = &self.level {
+                    self.coins_offered += level.coins_for(pipe.number);
+                    for _ in 0..level.coins_for(pipe.number) {
+                        self.coins_collected += 1;
+                        self.events.emit(GameEvent::CoinCollected);
+                    }
+                    let mut fired = false;
+                    for trigger in level.triggers_at(pipe.number) {
+                        if let Trigger::Wind(strength) = *trigger {
+                            self.level_wind = strength;
+                        }
+                        fired = true;
+                    }
+                    if fired {
+                        self.events
+                            .emit(GameEvent::TriggerFired { pipe: pipe.number });
+                    }
+                }
+                let biome = Biome::for_pipe(self.pipes_passed + 1);
+                if self.challenge == Some(Challenge::Journey)
+                    && biome != Biome::for_pipe(self.pipes_passed)
+                {
+                    self.events.emit(GameEvent::BiomeReached { biome });
+                }
+                if near_miss {
+                    self.near_miss_flash = NEAR_MISS_FLASH_SECONDS;
+                    self.events.emit(GameEvent::NearMiss);
+                }
+                if self.pipes_passed.is_multiple_of(MILESTONE_INTERVAL) {
+                    self.events.emit(GameEvent::MilestoneReached {
+                        pipes: self.pipes_passed,
+                    });
+                }
+                if self.pipes_passed.is_multiple_of(BOSS_INTERVAL) && self.level.is_none() {
+                    self.boss = Some(Boss::new());
+                    self.events.emit(GameEvent::BossStarted);
+                }
+            }
+        }
+
+        self.update_boss();
+        self.update_enemies();
+        self.update_powerups();
+        self.update_coins();
+
+        if self.ghost {
+            return;
+        }
+        let started = perf::clock();
+        let hit = collision::check(
+            &self.bird,
+            &self.pipes,
+            &self.enemies,
+            self.boss.as_ref(),
+            self.terrain.as_ref(),
+            self.masks.as_deref(),
+        );
+        self.collision_seconds += perf::clock() - started;
+        if let Some(hit) = self
+            .grace
+            .resolve(hit, &self.bird, &self.pipes, self.gravity)
+        {
+            if self.bird.shield {
+                self.absorb_hit(hit);
+            } else if !self.respawn() {
+                self.crash = Some(hit);
+                self.end_run();
+            }
+        }
+    }
+
+    // Scrolls the level's flags along, marking any the bird gets to. True
+    // once it's over the finish line, which ends the run
+    fn cross_flags(&mut self) -> bool {
+        let mut checkpoint = false;
+        for flag in &mut self.flags {
+            flag.update();
+            if flag.crossed || flag.x > self.bird.x {
+                continue;
+            }
+            flag.crossed = true;
+            match flag.kind {
+                FlagKind::Checkpoint => checkpoint = true,
+                FlagKind::Finish => {
+                    self.state = GameState::GameOver;
+                    self.completed = true;
+                    self.events.emit(GameEvent::LevelCompleted);
+                    return true;
+                }
+            }
+        }
+        if checkpoint {
+            self.events.emit(GameEvent::CheckpointReached);
+            if self.practice {
+                self.save_respawn();
+            }
+        }
+        false
+    }
+
+    // Keeps the run as it is now for a practice crash to go back to
+    fn save_respawn(&mut self) {
+        self.respawn = None;
+        let mut saved = self.clone();
+        saved.events = EventBus::default();
+        self.respawn = Some(Box::new(saved));
+    }
+
+    // Goes back to where `save_respawn` kept the run, a death up, if it
+    // kept one
+    fn respawn(&mut self) -> bool {
+        let Some(saved) = self.respawn.take() else {
+            return false;
+        };
+        let mut game = (*saved).clone();
+        game.respawn = Some(saved);
+        game.deaths = self.deaths + 1;
+        game.events = std::mem::take(&mut self.events);
+        game.layout = self.layout;
+        game.previous_bird = None;
+        game.bird.invincible = RESPAWN_INVINCIBLE_SECONDS;
+        *self = game;
+        self.events.emit(GameEvent::Respawned);
+        true
+    }
+
+    /// For a finished level, from one to three stars: one for finishing,
+    /// one for not dying on the way and one for every coin.
+    pub fn stars(&self) -> Option<u32> {
+        self.completed.then(|| {
+            1 + u32::from(self.deaths == 0) + u32::from(self.coins_collected >= self.coins_offered)
+        })
+    }
+
+    // How hard the wind blows, as a share of the modifier's
+    fn wind(&self) -> f32 {
+        if self.modifier == Some(Modifier::Wind) || self.biome().is_some_and(Biome::windy) {
+            1.0
+        } else {
+            self.level_wind
+        }
+    }
+
+    /// Ends a run in progress as if it had crashed, whether or not it hit
+    /// anything.
+    pub fn end_run(&mut self) {
+        if !matches!(self.state, GameState::Playing | GameState::Paused) {
+            return;
+        }
+        self.state = GameState::GameOver;
+        // The results show the course that was hidden
+        for pipe in &mut self.pipes {
+            pipe.alpha = 1.0;
+        }
+        self.events.emit(GameEvent::Collided);
+    }
+
+    /// Ends a run in progress without it counting, as when the game is quit
+    /// in the middle of one.
+    pub fn abandon_run(&mut self) {
+        if !matches!(self.state, GameState::Playing | GameState::Paused) {
+            return;
+        }
+        self.state = GameState::GameOver;
+        self.events.emit(GameEvent::RunAbandoned);
+    }
+
+    fn update_boss(&mut self) {
+        let Some(boss) = &mut self.boss else {
+            return;
+        };
+        boss.aim_y = self.bird.rect().center().y;
+        boss.update();
+        if boss.is_gone() {
+            self.boss = None;
+            let before = self.score;
+            self.score += BOSS_BONUS;
+            if let Some(laps) = rolled_over(before, self.score) {
+                self.rollover_flash = ROLLOVER_FLASH_SECONDS;
+                self.events.emit(GameEvent::ScoreRolledOver { laps });
+            }
+            // Pipes come back from the right edge
+            self.next_pipe_x = FIELD_RIGHT;
+            self.events
+                .emit(GameEvent::BossBeaten { bonus: BOSS_BONUS });
+        }
+    }
+
+    fn update_enemies(&mut self) {
+        // Levels are only what their file lists, and the journey only has
+        // them in some biomes
+        let wanted = self.level.is_none() && self.biome().is_none_or(Biome::has_enemies);
+        if self.pipes_passed >= ENEMY_MIN_PIPES && self.boss.is_none() && wanted {
+            self.enemy_timer -= TICK_SECONDS;
+            if self.enemy_timer <= 0.0 {
+                self.enemies.push(EnemyBird::random(&self.rng));
+                self.enemy_timer = self.rng.gen_range(ENEMY_MIN_SECONDS, ENEMY_MAX_SECONDS);
+            }
+        }
+        for enemy in &mut self.enemies {
+            enemy.update();
+        }
+        self.enemies.retain(|enemy| !enemy.is_gone());
+    }
+
+    fn update_powerups(&mut self) {
+        let bird = self.bird.rect();
+        for powerup in &mut self.powerups {
+            powerup.update();
+            if !powerup.taken && bird.overlaps(&powerup.rect()) {
+                powerup.taken = true;
+                match powerup.kind {
+                    PowerUpKind::Shield => self.bird.shield = true,
+                    PowerUpKind::Magnet => self.bird.magnet = MAGNET_SECONDS,
+                }
+                self.events
+                    .emit(GameEvent::PowerUpCollected { kind: powerup.kind });
+            }
+        }
+        self.powerups
+            .retain(|powerup| !powerup.taken && powerup.pos.x > FIELD_LEFT - MAX_PIPE_WIDTH);
+    }
+
+    fn update_coins(&mut self) {
+        let bird = self.bird.rect();
+        let center = bird.center();
+        for coin in &mut self.coins {
+            if self.bird.magnet > 0.0 {
+                let offset = center - coin.pos;
+                let distance = offset.length();
+                if distance > 0.0 && distance < MAGNET_RADIUS {
+                    // Smoothstep falloff: gentle at the edge, strong up close
+                    let t = 1.0 - distance / MAGNET_RADIUS;
+                    let strength = MAGNET_PULL * t * t * (3.0 - 2.0 * t);
+                    coin.apply_force(offset / distance * strength);
+                }
+            }
+            coin.update();
+            if !coin.collected && bird.overlaps(&coin.rect()) {
+                coin.collected = true;
+                self.coins_collected += 1;
+                self.events.emit(GameEvent::CoinCollected);
+            }
+        }
+        self.coins
+            .retain(|coin| !coin.collected && coin.pos.x > FIELD_LEFT - MAX_PIPE_WIDTH);
+    }
+
+    // The shield breaks instead of the bird: it blinks for a moment and is
+    // moved out of whatever it hit so it isn't hit again straight away
+    fn absorb_hit(&mut self, hit: Hit) {
+        let center = self.bird.rect().center();
+        self.bird.shield = false;
+        self.bird.invincible = SHIELD_INVINCIBLE_SECONDS;
+        match hit {
+            Hit::Ground => {
+                self.bird.y = HEIGHT - self.bird.rect().h - 1.0;
+                self.bird.velocity.y = self.physics.jump_velocity();
+            }
+            Hit::Enemy(index) => self.enemies[index].alive = false,
+            Hit::Boss => {}
+            Hit::Rock { .. } => {
+                let rect = self.bird.rect();
+                if let Some(terrain) = &self.terrain {
+                    let (ceiling, floor) = terrain.opening(rect.x, rect.right());
+                    let lowest = floor - rect.h;
+                    self.bird.y = self.bird.y.clamp(ceiling, lowest.max(ceiling));
+                }
+                self.bird.velocity = Vec2::ZERO;
+            }
+            Hit::Pipe { index, .. } => {
+                let pipe = &self.pipes[index];
+                let lowest = pipe.gap_bottom() - self.bird.rect().h;
+                self.bird.y = self
+                    .bird
+                    .y
+                    .clamp(pipe.gap_top(), lowest.max(pipe.gap_top()));
+                self.bird.velocity = Vec2::ZERO;
+            }
+        }
+        self.events.emit(GameEvent::ShieldBroken {
+            x: center.x,
+            y: center.y,
+        });
+    }
+
+    pub fn draw(&self, theme: &Theme) {
+        self.draw_world(theme);
+
+        if self.near_miss_flash > 0.0 {
+            let alpha = self.near_miss_flash / NEAR_MISS_FLASH_SECONDS;
+            let color = Color::new(1.0, 0.85, 0.2, alpha);
+            draw_text("Close!", self.bird.x - 4.0, self.bird.y - 8.0, 20.0, color);
+        }
+
+        self.draw_score(theme);
+        if self.assists.any() {
+            draw_centered_text(self.assists.label(), 66.0, 14.0, ORANGE);
+        }
+        self.draw_level_progress(theme);
+        self.combo.draw(8.0, HEIGHT - 10.0, theme.text_color);
+        if self.coins_collected > 0 {
+            draw_circle(14.0, 16.0, COIN_RADIUS, GOLD);
+            let coins = self.coins_collected.to_string();
+            draw_text(&coins, 24.0, 21.0, 18.0, theme.text_color);
+        }
+        self.draw_attack_warning();
+    }
+
+    // The score up top. Past 999 it shows the last three digits with the
+    // thousands after them, like 042 +2000
+    fn draw_score(&self, theme: &Theme) {
+        let laps = self.score.div_euclid(SCORE_ROLLOVER);
+        let color = if self.rollover_flash > 0.0 {
+            rainbow(get_time() as f32)
+        } else {
+            theme.text_color
+        };
+        if laps <= 0 {
+            draw_fitted_text(&self.score.to_string(), 50.0, 40.0, HUD_SCORE_WIDTH, color);
+            return;
+        }
+        let digits = format!("{:03}", self.score.rem_euclid(SCORE_ROLLOVER));
+        let width = draw_fitted_text(&digits, 50.0, 40.0, HUD_SCORE_WIDTH, color);
+        let thousands = format!("+{}", laps * SCORE_ROLLOVER);
+        draw_text(&thousands, (WIDTH + width) / 2.0 + 4.0, 50.0, 14.0, color);
+    }
+
+    // A blinking hazard sign while an attack is queued or its pipe pair is
+    // still coming up, with how many there are
+    fn draw_attack_warning(&self) {
+        let ahead = self
+            .pipes
+            .iter()
+            .filter(|pipe| pipe.attack.is_some() && pipe.x > self.bird.x + GUST_RANGE)
+            .count();
+        let count = self.incoming.len() + ahead;
+        if count == 0 || get_time().fract() > 0.7 {
+            return;
+        }
+        let (x, y) = (WIDTH - 26.0, 10.0);
+        draw_triangle(
+            vec2(x + 10.0, y),
+            vec2(x, y + 18.0),
+            vec2(x + 20.0, y + 18.0),
+            RED,
+        );
+        draw_text("!", x + 7.5, y + 16.0, 18.0, WHITE);
+        if count > 1 {
+            draw_text(&format!("x{count}"), x - 18.0, y + 16.0, 16.0, RED);
+        }
+    }
+
+    // A thin bar along the top with a tick per checkpoint and a flag at
+    // the end
+    fn draw_level_progress(&self, theme: &Theme) {
+        let Some(level) = &self.level else {
+            return;
+        };
+        let finish = self.level_distance(level.length() as f32 + 0.5);
+        let (y, height, right) = (3.0, 4.0, WIDTH - 14.0);
+        let progress = (self.distance() / finish).clamp(0.0, 1.0);
+        draw_rectangle(4.0, y, right - 4.0, height, Color::new(0.0, 0.0, 0.0, 0.35));
+        draw_rectangle(4.0, y, (right - 4.0) * progress, height, theme.text_color);
+        for &checkpoint in &level.checkpoints {
+            let at = self.level_distance(checkpoint as f32 + 0.5) / finish;
+            let x = 4.0 + (right - 4.0) * at.clamp(0.0, 1.0);
+            draw_line(x, y - 2.0, x, y + height + 2.0, 2.0, GOLD);
+        }
+        draw_line(right, y - 2.0, right, y + 12.0, 1.0, theme.text_color);
+        draw_checkers(right + 1.0, y - 2.0, 2, 2, 4.0);
+    }
+
+    /// A strip of small columns in the top right, one per upcoming gap,
+    /// each a tiny picture of the screen's height with the gap lit up.
+    pub fn draw_gap_preview(&self, theme: &Theme) {
+        let (column, height, spacing, top) = (8.0, 64.0, 3.0, 34.0);
+        let scale = height / HEIGHT;
+        let left = WIDTH - 8.0 - PREVIEW_GAPS as f32 * (column + spacing);
+        for (i, (gap_top, gap_bottom)) in self.upcoming_gaps().into_iter().enumerate() {
+            let x = left + i as f32 * (column + spacing);
+            draw_rectangle(x, top, column, height, Color::new(0.0, 0.0, 0.0, 0.3));
+            let gap = ((gap_bottom - gap_top) * scale).max(2.0);
+            draw_rectangle(x, top + gap_top * scale, column, gap, theme.text_color);
+        }
+    }
+
+    /// The color grade for the screen: the theme's, with the biome's on
+    /// top in a journey.
+    pub fn grade(&self, theme: &Theme) -> Grade {
+        let grade = if self.challenge == Some(Challenge::Journey) {
+            let bird = self.course_pipe_at(self.bird.rect().center().x);
+            theme.grade.then(biomes::mood_at(bird))
+        } else {
+            theme.grade
+        };
+        if self.rollover_flash <= 0.0 {
+            return grade;
+        }
+        // The rollover's rainbow, fading out over its last second
+        let rainbow = Grade {
+            tint: rainbow(get_time() as f32 * 0.5),
+            saturation: 1.4,
+            ..Grade::default()
+        };
+        grade.lerp(grade.then(rainbow), self.rollover_flash.min(1.0))
+    }
+
+    /// The bird and the course, without the HUD, blended back towards
+    /// the tick before (see `blend`). Only the bird and the scrolling are
+    /// blended; everything else rides along with the scrolling.
+    pub fn draw_world(&self, theme: &Theme) {
+        if self.challenge == Some(Challenge::Journey) {
+            let bird = self.course_pipe_at(self.bird.rect().center().x);
+            biomes::draw_grade(bird, self.layout.left(), self.layout.right(), HEIGHT);
+        }
+        let mut bird = self.bird.clone();
+        let mut shown = vec2(bird.x, bird.y);
+        if let Some(previous) = self.previous_bird {
+            shown = shown.lerp(previous, self.behind());
+        }
+        // On whole pixels, so the sprite doesn't shimmer as it moves
+        (bird.x, bird.y) = (shown.x.round(), shown.y.round());
+        cosmetics::draw_bird(&bird, theme, self.look);
+        self.draw_course(theme);
+    }
+
+    // How much of a tick back the frame is drawn, 0 outside a run
+    fn behind(&self) -> f32 {
+        if self.state == GameState::Playing && self.previous_bird.is_some() {
+            1.0 - self.blend.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Everything but the bird and the score.
+    pub fn draw_course(&self, theme: &Theme) {
+        // A tick back, the world hadn't scrolled as far yet. Whole pixels
+        // only, so what's scrolling keeps the same place between pixels
+        // and holds its shape
+        let shift = (self.behind() * SCROLL_SPEED).round();
+        unsafe {
+            let gl = get_internal_gl().quad_gl;
+            gl.push_model_matrix(Mat4::from_translation(vec3(shift, 0.0, 0.0)));
+        }
+        self.draw_scrolling(theme);
+        unsafe {
+            get_internal_gl().quad_gl.pop_model_matrix();
+        }
+    }
+
+    fn draw_scrolling(&self, theme: &Theme) {
+        for flag in &self.flags {
+            if flag.x < self.layout.right() {
+                flag.draw(theme);
+            }
+        }
+        // Immutable borrow of pipes for rendering
+        if self.state != GameState::Playing || self.mutators.pipes_visible(self.ticks) {
+            for pipe in &self.pipes {
+                pipe.draw(theme);
+            }
+            self.draw_pipe_worth(theme);
+        }
+        if let Some(terrain) = &self.terrain {
+            terrain.draw();
+        }
+        for enemy in &self.enemies {
+            enemy.draw(theme);
+            enemy.draw_warning(self.layout.right());
+        }
+        if let Some(boss) = &self.boss {
+            boss.draw(theme);
+        }
+        for powerup in &self.powerups {
+            powerup.draw();
+        }
+        for coin in &self.coins {
+            coin.draw(theme);
+        }
+    }
+
+    // What a level's pairs worth more or less than a point score, in their
+    // gaps
+    fn draw_pipe_worth(&self, theme: &Theme) {
+        let Some(level) = &self.level else {
+            return;
+        };
+        for pipe in self.pipes.iter().filter(|pipe| !pipe.passed) {
+            let worth = level.score_for(pipe.number);
+            if worth != 1 {
+                let label = format!("+{worth}");
+                let size = measure_text(&label, None, 16, 1.0);
+                let x = pipe.x + (pipe.width - size.width) / 2.0;
+                let y = (pipe.gap_top() + pipe.gap_bottom() + size.height) / 2.0;
+                draw_text(&label, x, y, 16.0, theme.text_color);
+            }
+        }
+    }
+
+    /// The title, pause and game over text for the current state.
+    pub fn draw_prompt(&self, theme: &Theme) {
+        match self.state {
+            GameState::Ready => {
+                let prompt = if cfg!(target_os = "android") {
+                    "Tap to start"
+                } else {
+                    "Press Space"
+                };
+                draw_centered_text(prompt, HEIGHT / 2.0 - 40.0, 24.0, theme.text_color);
+                if let Some(level) = &self.level {
+                    let name = if self.practice {
+                        format!("{} (practice)", level.name)
+                    } else {
+                        level.name.clone()
+                    };
+                    draw_centered_text(&name, HEIGHT / 2.0 - 72.0, 20.0, theme.text_color);
+                }
+            }
+            GameState::Paused => {
+                draw_centered_text("Paused", HEIGHT / 2.0 - 40.0, 30.0, theme.text_color);
+                if !cfg!(target_os = "android") {
+                    let hint = "P to resume, F for photo mode";
+                    draw_centered_text(hint, HEIGHT / 2.0 - 12.0, 16.0, theme.text_color);
+                }
+            }
+            GameState::GameOver => {
+                let title = if self.completed {
+                    "Level complete!"
+                } else {
+                    "Game Over"
+                };
+                draw_centered_text(title, HEIGHT / 2.0 - 40.0, 30.0, theme.text_color);
+                if let Some(cause) = self.crash_cause() {
+                    draw_centered_text(&cause, HEIGHT / 2.0 - 12.0, 18.0, theme.text_color);
+                }
+                let seconds = (self.ticks as f32 * TICK_SECONDS) as u32;
+                let mut summary =
+                    format!("{}:{:02}, {} flaps", seconds / 60, seconds % 60, self.flaps);
+                if self.assists.adaptive.is_some() {
+                    summary.push_str(", adaptive");
+                } else if self.assists.any() {
+                    summary.push_str(", assisted");
+                }
+                if self.physics != Physics::Classic {
+                    summary.push_str(&format!(", {}", self.physics.id()));
+                }
+                draw_centered_text(&summary, HEIGHT / 2.0 + 8.0, 16.0, theme.text_color);
+                if let Some(label) = self.mutators.label() {
+                    draw_centered_text(&label, HEIGHT / 2.0 + 28.0, 14.0, theme.text_color);
+                }
+                if let Some(stars) = self.stars() {
+                    for i in 0..3 {
+                        let x = WIDTH / 2.0 + (i as f32 - 1.0) * 30.0;
+                        let color = if i < stars {
+                            GOLD
+                        } else {
+                            Color::new(0.0, 0.0, 0.0, 0.35)
+                        };
+                        draw_star(vec2(x, HEIGHT / 2.0 + 56.0), 12.0, color);
+                    }
+                    let deaths = match self.deaths {
+                        0 => "No deaths".to_owned(),
+                        1 => "1 death".to_owned(),
+                        deaths => format!("{deaths} deaths"),
+                    };
+                    let coins = format!(
+                        "{deaths}, {}/{} coins",
+                        self.coins_collected, self.coins_offered
+                    );
+                    draw_centered_text(&coins, HEIGHT / 2.0 + 84.0, 14.0, theme.text_color);
+                } else if let Some(medal) = Medal::for_score(self.score) {
+                    medal.draw(theme, vec2(WIDTH / 2.0, HEIGHT / 2.0 + 56.0));
+                    let name = format!("{} medal", medal.name());
+                    draw_centered_text(&name, HEIGHT / 2.0 + 84.0, 14.0, theme.text_color);
+                }
+                if let Some(code) = seed_code::for_run(self) {
+                    let label = if self.streamer_mode {
+                        "Course code hidden".to_owned()
+                    } else {
+                        format!("Course code {code}")
+                    };
+                    draw_centered_text(&label, HEIGHT / 2.0 + 100.0, 14.0, theme.text_color);
+                }
+            }
+            GameState::Playing => {}
+        }
+    }
+
+    // What the run ended on, in words
+    fn crash_cause(&self) -> Option<String> {
+        let cause = match self.crash? {
+            Hit::Pipe { index, top } => {
+                let side = if top { "top" } else { "bottom" };
+                // Mode scripts can clear the pipes once the run is over
+                match self.pipes.get(index) {
+                    Some(pipe) => format!("Hit {side} pipe #{}", pipe.number),
+                    None => format!("Hit a {side} pipe"),
+                }
+            }
+            Hit::Ground => "Clipped the ground".to_owned(),
+            Hit::Enemy(_) => "Flew into an enemy bird".to_owned(),
+            Hit::Boss => "Crashed into the boss".to_owned(),
+            Hit::Rock { ceiling: true } => "Hit the cave roof".to_owned(),
+            Hit::Rock { ceiling: false } => "Scraped the cave floor".to_owned(),
+        };
+        Some(cause)
+    }
+}
+
+// How many pipes along the course `x` on screen is, with `spawned` pairs
+// out and the next due at `next_x`
+fn course_pipe(spawned: u32, next_x: f32, x: f32) -> f32 {
+    (spawned + 1) as f32 + (x - next_x) / PIPE_SPACING
+}
+
+// Fresh seeds are 40 bits, so their course codes stay eight letters
+fn fresh_seed() -> u64 {
+    u64::from(rand::rand()) << 8 | u64::from(rand::rand() & 0xff)
+}
+
+// The number of times the score went past a multiple of SCORE_ROLLOVER,
+// if going from `before` to `after` took it past another
+fn rolled_over(before: i64, after: i64) -> Option<i64> {
+    let laps = after.div_euclid(SCORE_ROLLOVER);
+    (laps > before.div_euclid(SCORE_ROLLOVER) && laps > 0).then_some(laps)
+}
+
+// A color going round the hues, once every two seconds of `t`
+fn rainbow(t: f32) -> Color {
+    hsl_to_rgb((t * 0.5).fract(), 0.9, 0.6)
+}
+
+/// Draws `text` centered at `y`, smaller than `font_size` if that's what it
+/// takes to fit in `max_width`. Returns how wide it came out.
+pub fn draw_fitted_text(text: &str, y: f32, font_size: f32, max_width: f32, color: Color) -> f32 {
+    let width = measure_text(text, None, font_size as u16, 1.0).width;
+    let size = if width > max_width {
+        (font_size * max_width / width).floor()
+    } else {
+        font_size
+    };
+    draw_centered_text(text, y, size, color);
+    width.min(max_width)
+}
+
+/// A five-pointed star standing on two of its points.
+pub fn draw_star(center: Vec2, radius: f32, color: Color) {
+    let point = |i: usize| {
+        let angle = i as f32 * std::f32::consts::PI / 5.0 - std::f32::consts::FRAC_PI_2;
+        let reach = if i.is_multiple_of(2) {
+            radius
+        } else {
+            radius * 0.45
+        };
+        center + Vec2::from_angle(angle) * reach
+    };
+    for i in 0..10 {
+        draw_triangle(center, point(i), point(i + 1), color);
+    }
+}
+
+// The finish line's black and white squares, `square` pixels each
+fn draw_checkers(x: f32, y: f32, columns: u32, rows: u32, square: f32) {
+    for row in 0..rows {
+        for column in 0..columns {
+            let color = if (row + column) % 2 == 0 {
+                WHITE
+            } else {
+                BLACK
+            };
+            let (fx, fy) = (x + column as f32 * square, y + row as f32 * square);
+            draw_rectangle(fx, fy, square, square, color);
+        }
+    }
+}
+
+pub fn draw_centered_text(text: &str, y: f32, font_size: f32, color: Color) {
+    let size = measure_text(text, None, font_size as u16, 1.0);
+    draw_text(text, (WIDTH - size.width) / 2.0, y, font_size, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::bird::JUMP_VELOCITY;
+    use crate::timestep::Timestep;
+
+    // Frames from a 240 Hz display up to a hitch, and how far below the
+    // top of the next gap the bird aims on each tick
+    fn frames() -> impl Strategy<Value = Vec<f32>> {
+        prop::collection::vec(1.0 / 240.0..0.3f32, 1..120)
+    }
+
+    fn aims() -> impl Strategy<Value = Vec<f32>> {
+        prop::collection::vec(-10.0..20.0f32, 1..32)
+    }
+
+    // A rough player: flaps on the way down once a flap would top out
+    // around its aim, just inside the next gap, so runs get through some
+    // pipes and clip others
+    fn wants_flap(game: &Game, aims: &[f32]) -> bool {
+        let Some(pipe) = game.pipes.iter().find(|pipe| !pipe.passed) else {
+            return false;
+        };
+        let hop = JUMP_VELOCITY * JUMP_VELOCITY / (2.0 * game.gravity);
+        let aim = pipe.gap_top() + aims[game.ticks as usize % aims.len()];
+        game.bird.velocity.y > 0.0 && game.bird.y - hop > aim
+    }
+
+    // Flies `game` through `frames` at whatever rate they come, calling
+    // `after_tick` after every tick
+    fn fly(game: &mut Game, frames: &[f32], aims: &[f32], mut after_tick: impl FnMut(&Game)) {
+        let mut timestep = Timestep::new();
+        game.flap();
+        for &dt in frames {
+            for _ in 0..timestep.ticks(dt) {
+                if game.state != GameState::Playing {
+                    return;
+                }
+                if wants_flap(game, aims) {
+                    game.flap();
+                }
+                game.update();
+                after_tick(game);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn bird_never_passes_through_a_pipe(seed: u64, frames in frames(), aims in aims()) {
+            let mut game = Game::from_seed(seed);
+            // Grazes pass through pipe corners on purpose
+            game.grace = Grace::new(0);
+            let mut inside = None;
+            fly(&mut game, &frames, &aims, |game| {
+                if game.state != GameState::Playing || game.bird.invincible > 0.0 {
+                    return;
+                }
+                let bird = game.bird.rect();
+                let touching = game.pipes.iter().filter(|pipe| pipe.on_screen()).find(|pipe| {
+                    bird.overlaps(&pipe.top_rect()) || bird.overlaps(&pipe.bottom_rect())
+                });
+                if let Some(pipe) = touching {
+                    inside.get_or_insert((game.ticks, pipe.number));
+                }
+            });
+            prop_assert_eq!(inside, None);
+        }
+
+        #[test]
+        fn score_never_goes_down(seed: u64, frames in frames(), aims in aims()) {
+            let mut game = Game::from_seed(seed);
+            let mut scores = Vec::new();
+            fly(&mut game, &frames, &aims, |game| scores.push(game.score));
+            prop_assert!(scores.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+
+        #[test]
+        fn unflown_bird_always_comes_down(seed: u64) {
+            let mut game = Game::from_seed(seed);
+            game.flap();
+            let ran = game.fast_forward(60 * 60);
+            prop_assert_eq!(game.state, GameState::GameOver);
+            prop_assert!(ran < 60 * 60);
+        }
+    }
+}
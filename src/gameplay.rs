@@ -0,0 +1,319 @@
+// The title screen and the runs played from it, the screen at the bottom of
+// the stack (see `screen.rs`). The title screen picks the mode, the
+// challenge and the course, and opens the menus. A paused run has `Paused`
+// over it, which photo mode opens from, and a finished one `Results`, the
+// game over card. Each goes on once the game is in its state, however it
+// got there (the console can pause a run too), and comes off when it
+// leaves it.
+//
+// Kiosks and online matches fly the course with input of their own, from
+// the main loop, which draws them with `draw_run` as well.
+
+use macroquad::prelude::*;
+
+use crate::challenge::Challenge;
+use crate::cosmetics::{self, Wardrobe};
+use crate::debug_step;
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::latency::{self, LatencyScreen};
+use crate::level_select::{self, LevelSelect};
+use crate::lobby::Lobby;
+use crate::mod_manager::ModManager;
+use crate::mutators::MutatorScreen;
+use crate::one_switch::SwitchAssist;
+use crate::photo::PhotoMode;
+use crate::platform;
+use crate::replay_viewer::{self, ReplayViewer};
+use crate::screen::{Context, Request, Screen, Transition};
+use crate::seed_code::{self, SeedEntry};
+use crate::settings::Settings;
+use crate::settings_menu::SettingsMenu;
+use crate::share_card;
+use crate::stats_screen::StatsScreen;
+use crate::theme::Theme;
+use crate::ticker::Ticker;
+use crate::{HEIGHT, WIDTH};
+
+/// The course as it's flown, with the debug views but without any prompt.
+pub fn draw_run(game: &Game, theme: &Theme, settings: &Settings, ticks: u32) {
+    game.draw(theme);
+    // The preview would give away a challenge's hidden pipes
+    if settings.gap_preview && game.state == GameState::Playing && game.challenge.is_none() {
+        game.draw_gap_preview(theme);
+    }
+    debug_step::draw(game, theme);
+    if settings.tick_counter {
+        debug_step::draw_ticks(game, ticks, theme);
+    }
+}
+
+// Space, a click or a tap flaps, or whatever the profile's control scheme
+// says; one-switch play takes any key
+fn flap(cx: &mut Context, switch_assist: &mut SwitchAssist) {
+    if cx.settings.one_switch {
+        if switch_assist.update(get_frame_time(), cx.settings.switch_repeat, cx.game) {
+            cx.game.flap();
+        }
+    } else if let Some(strength) = cx.profile.controls.flap_at(if cx.game.mutators.mirrored() {
+        cx.settings.flap_edge.other()
+    } else {
+        cx.settings.flap_edge
+    }) {
+        cx.game.flap_with(strength);
+        if cx.game.state == GameState::Playing {
+            cx.latency.note_flap();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Gameplay {
+    switch_assist: SwitchAssist,
+    ticker: Ticker,
+    // Since it last opened something over itself; the menu demo and the
+    // ticker wait under menus
+    covered: bool,
+}
+
+impl Gameplay {
+    fn open(&mut self, screen: impl Screen + 'static) -> Transition {
+        self.covered = true;
+        Transition::Push(Box::new(screen))
+    }
+
+    // Picks a game mode or opens a menu before the run starts
+    fn title_keys(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Left) {
+            cx.scripting.cycle_mode(-1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            cx.scripting.cycle_mode(1);
+        }
+        // C steps through the challenges and back to none
+        if is_key_pressed(KeyCode::C) {
+            let next = cx.challenge.map_or(0, |current| {
+                Challenge::ALL
+                    .iter()
+                    .position(|&c| c == current)
+                    .unwrap_or(0)
+                    + 1
+            });
+            let was_weekly = *cx.challenge == Some(Challenge::Weekly);
+            *cx.challenge = Challenge::ALL.get(next).copied();
+            // The weekly challenge flies its own course
+            if was_weekly || *cx.challenge == Some(Challenge::Weekly) {
+                cx.game.fixed_seed = cx.weekly.course_seed(*cx.challenge, cx.options);
+                cx.game.reset();
+            }
+        }
+        if is_key_pressed(KeyCode::P) {
+            cx.request = Some(Request::Party);
+        }
+        if is_key_pressed(KeyCode::T) {
+            cx.request = Some(Request::Coop);
+        }
+        if is_key_pressed(KeyCode::U)
+            && let Some(page) = cx.updates.found().and_then(|new| new.page.as_deref())
+            && !platform::open_url(page)
+        {
+            cx.toasts.push(page.to_owned(), SKYBLUE);
+        }
+
+        if is_key_pressed(KeyCode::Tab) {
+            self.open(SettingsMenu::default())
+        } else if is_key_pressed(KeyCode::S) {
+            self.open(StatsScreen::default())
+        } else if is_key_pressed(latency::OPEN_KEY) {
+            self.open(LatencyScreen::default())
+        } else if is_key_pressed(KeyCode::M) {
+            self.open(MutatorScreen::default())
+        } else if is_key_pressed(cosmetics::WARDROBE_KEY) {
+            self.open(Wardrobe::default())
+        } else if is_key_pressed(level_select::OPEN_KEY) {
+            self.open(LevelSelect::new())
+        } else if is_key_pressed(seed_code::ENTRY_KEY) {
+            self.open(SeedEntry::default())
+        } else if is_key_pressed(KeyCode::G) {
+            self.open(ModManager::new(&cx.profile.mods, cx.scripting))
+        } else if is_key_pressed(KeyCode::O) {
+            self.open(Lobby::default())
+        } else if is_key_pressed(replay_viewer::OPEN_KEY)
+            && let Some(replay) = cx.menu_replay
+        {
+            self.open(ReplayViewer::new(replay.replay()))
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn draw_title(&self, cx: &Context) {
+        let theme = cx.theme;
+        cx.game.draw_prompt(theme);
+        let label = format!("< {} >", cx.scripting.active_name());
+        draw_centered_text(&label, HEIGHT / 2.0, 20.0, theme.text_color);
+        if *cx.challenge == Some(Challenge::Weekly) {
+            let days = cx.weekly.days_left();
+            let label = format!("Weekly: {} ({days}d left)", cx.weekly.name);
+            draw_centered_text(&label, HEIGHT / 2.0 + 22.0, 16.0, GOLD);
+        } else if let Some(challenge) = *cx.challenge {
+            let label = format!("Challenge: {}", challenge.name());
+            draw_centered_text(&label, HEIGHT / 2.0 + 22.0, 16.0, GOLD);
+        }
+        if let Some(seed) = cx.game.fixed_seed {
+            let label = if cx.settings.streamer_mode {
+                "Course code hidden".to_owned()
+            } else {
+                format!("Course code {}", seed_code::encode(seed))
+            };
+            draw_centered_text(&label, HEIGHT / 2.0 + 38.0, 14.0, theme.text_color);
+        }
+        if !self.covered {
+            if let Some(replay) = cx.menu_replay.as_ref() {
+                let center = vec2(WIDTH / 2.0, HEIGHT / 2.0 + 106.0);
+                replay.draw(cx.viewport, theme, center);
+            }
+            self.ticker.draw(cx.profile, theme.text_color);
+        }
+        if let Some(error) = cx.scripting.errors.last() {
+            draw_text(&error.to_string(), 4.0, HEIGHT - 8.0, 12.0, RED);
+        } else if let Some(new) = cx.updates.found() {
+            let banner = match &new.page {
+                Some(page) => format!("New version {} available: U ({page})", new.version),
+                None => format!("New version {} available", new.version),
+            };
+            draw_text(&banner, 4.0, HEIGHT - 8.0, 12.0, SKYBLUE);
+        }
+    }
+}
+
+impl Screen for Gameplay {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        self.covered = false;
+        match cx.game.state {
+            GameState::Paused => return self.open(Paused::default()),
+            GameState::GameOver => return self.open(Results::default()),
+            GameState::Ready | GameState::Playing => {}
+        }
+        flap(cx, &mut self.switch_assist);
+        if cx.game.state == GameState::Ready {
+            let transition = self.title_keys(cx);
+            if !matches!(transition, Transition::Stay) {
+                return transition;
+            }
+        }
+
+        // Check for quit (the close button is checked in the main loop)
+        if is_key_pressed(KeyCode::Escape) {
+            cx.request = Some(Request::Quit);
+            return Transition::Stay;
+        }
+        // P pauses a run, and Android's back button does too, or leaves
+        // from the title screen
+        let back = is_key_pressed(KeyCode::Back);
+        if cx.game.state == GameState::Playing && (is_key_pressed(KeyCode::P) || back) {
+            cx.game.toggle_pause();
+            return self.open(Paused::default());
+        }
+        if back {
+            cx.request = Some(Request::Quit);
+        }
+        Transition::Stay
+    }
+
+    fn update(&mut self, cx: &mut Context, dt: f32) {
+        if cx.game.state == GameState::Ready && !self.covered {
+            if let Some(replay) = cx.menu_replay {
+                replay.update(dt);
+            }
+            self.ticker.update(dt);
+        }
+    }
+
+    fn draw(&self, cx: &Context) {
+        draw_run(cx.game, cx.theme, cx.settings, cx.ticks);
+        match cx.game.state {
+            GameState::Ready => self.draw_title(cx),
+            GameState::Playing | GameState::Paused => cx.quests.draw_chip(cx.profile, cx.theme),
+            GameState::GameOver => {}
+        }
+    }
+}
+
+/// Over a paused run: P, Back or a flap carries on, and F opens photo mode.
+#[derive(Default)]
+pub struct Paused {
+    switch_assist: SwitchAssist,
+}
+
+impl Screen for Paused {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if cx.game.state != GameState::Paused {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::F) {
+            return Transition::Push(Box::new(PhotoMode::new()));
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            cx.request = Some(Request::Quit);
+            return Transition::Stay;
+        }
+        flap(cx, &mut self.switch_assist);
+        if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Back) {
+            cx.game.toggle_pause();
+        }
+        if cx.game.state == GameState::Paused {
+            Transition::Stay
+        } else {
+            Transition::Pop
+        }
+    }
+
+    fn draw(&self, cx: &Context) {
+        cx.game.draw_prompt(cx.theme);
+    }
+}
+
+/// The game over card. A flap goes again; a click on the Share button only
+/// makes the card.
+#[derive(Default)]
+pub struct Results {
+    switch_assist: SwitchAssist,
+}
+
+impl Screen for Results {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if cx.game.state != GameState::GameOver {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Back) {
+            cx.request = Some(Request::Quit);
+            return Transition::Stay;
+        }
+        if share_card::pressed(cx.viewport) {
+            let name = cx
+                .themes
+                .iter()
+                .find(|t| t.id == cx.theme_id)
+                .map_or(cx.theme_id, |t| t.name.as_str());
+            match share_card::save(cx.game, cx.theme, name) {
+                Ok(file) => cx.toasts.push(format!("Saved {file}"), WHITE),
+                Err(err) => cx.toasts.push(format!("Share card failed: {err}"), RED),
+            }
+        } else {
+            flap(cx, &mut self.switch_assist);
+        }
+        if cx.game.state == GameState::GameOver {
+            Transition::Stay
+        } else {
+            Transition::Pop
+        }
+    }
+
+    fn draw(&self, cx: &Context) {
+        cx.game.draw_prompt(cx.theme);
+        share_card::draw_button(cx.viewport, cx.theme);
+        if let Some(cheer) = cx.celebration {
+            cheer.draw(cx.settings.reduced_motion);
+        }
+    }
+}
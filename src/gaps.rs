@@ -0,0 +1,337 @@
+// Where random pipe gaps go: a weighted draw that avoids long runs at the
+// edges, sometimes a short staircase or zigzag, and never a gap the bird
+// can't get to from the one before. The knobs live in `settings.cfg`:
+//
+//     # Range for the top edge of a gap, in pixels from the top of the screen
+//     gap_min = 150
+//     gap_max = 300
+//     # Draws averaged per gap; 1 is even, more lean towards the middle
+//     gap_weighting = 2
+//     # Gaps in a row allowed near the top or bottom of the range
+//     gap_max_extremes = 1
+//     # Chance per gap of starting a pattern, and its step in pixels
+//     gap_pattern_chance = 0.08
+//     gap_pattern_step = 30
+//     # Chance per pair of stones or a tree instead of pipes, see `pipes.rs`
+//     pipe_variety = 0
+
+use crate::sim_rng::SimRng;
+
+use crate::bird::BIRD_WIDTH;
+use crate::kv::KeyValues;
+use crate::physics::{Limits, Physics};
+use crate::pipes::{
+    MAX_PIPE_WIDTH, PIPE_HEIGHT, PIPE_SPACING, PIPE_WIDTH, SCROLL_SPEED, gap_top_range,
+};
+
+// Gaps this far into either end of the range count as extremes
+const EXTREME_SHARE: f32 = 0.15;
+// Share of the bird's reach that a gap may use, leaving room for human
+// timing
+const REACH_MARGIN: f32 = 0.75;
+const MIN_PATTERN_GAPS: u32 = 4;
+const MAX_PATTERN_GAPS: u32 = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GapParams {
+    pub min: f32,
+    pub max: f32,
+    pub weighting: u32,
+    pub max_extremes: u32,
+    pub pattern_chance: f32,
+    pub pattern_step: f32,
+    pub pipe_variety: f32,
+}
+
+impl Default for GapParams {
+    fn default() -> Self {
+        GapParams {
+            min: 150.0,
+            max: 300.0,
+            weighting: 2,
+            max_extremes: 1,
+            pattern_chance: 0.08,
+            pattern_step: 30.0,
+            pipe_variety: 0.0,
+        }
+    }
+}
+
+impl GapParams {
+    /// Reads the `gap_*` keys, keeping every gap on screen and walled in
+    /// by its pipes.
+    pub fn from_values(kv: &KeyValues) -> GapParams {
+        let default = GapParams::default();
+        let range = gap_top_range(PIPE_HEIGHT);
+        let (lowest, highest) = (*range.start(), *range.end());
+        let min: f32 = kv.get_parsed("gap_min").unwrap_or(default.min);
+        let max: f32 = kv.get_parsed("gap_max").unwrap_or(default.max);
+        let (min, max) = (
+            min.min(max).clamp(lowest, highest),
+            max.max(min).clamp(lowest, highest),
+        );
+        GapParams {
+            min,
+            max,
+            weighting: kv
+                .get_parsed("gap_weighting")
+                .unwrap_or(default.weighting)
+                .max(1),
+            max_extremes: kv
+                .get_parsed("gap_max_extremes")
+                .unwrap_or(default.max_extremes),
+            pattern_chance: kv
+                .get_parsed("gap_pattern_chance")
+                .unwrap_or(default.pattern_chance)
+                .clamp(0.0, 1.0),
+            pattern_step: kv
+                .get_parsed("gap_pattern_step")
+                .unwrap_or(default.pattern_step)
+                .abs(),
+            pipe_variety: kv
+                .get_parsed("pipe_variety")
+                .unwrap_or(default.pipe_variety)
+                .clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn write_values(&self, kv: &mut KeyValues) {
+        kv.set("gap_min", self.min);
+        kv.set("gap_max", self.max);
+        kv.set("gap_weighting", self.weighting);
+        kv.set("gap_max_extremes", self.max_extremes);
+        kv.set("gap_pattern_chance", self.pattern_chance);
+        kv.set("gap_pattern_step", self.pattern_step);
+        kv.set("pipe_variety", self.pipe_variety);
+    }
+
+    // Half a pattern step, or less if the range is narrower than a step
+    fn half_step(&self) -> f32 {
+        (self.pattern_step / 2.0).min((self.max - self.min) / 2.0)
+    }
+
+    fn is_extreme(&self, gap: f32) -> bool {
+        let edge = (self.max - self.min) * EXTREME_SHARE;
+        gap < self.min + edge || gap > self.max - edge
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Pattern {
+    // Each gap `step` pixels on from the last
+    Staircase { step: f32 },
+    // Alternating either side of `center`
+    Zigzag { center: f32, above: bool },
+}
+
+#[derive(Clone)]
+pub struct GapGenerator {
+    rng: SimRng,
+    previous: Option<f32>,
+    // Extremes drawn in a row, up to and including `previous`
+    extremes: u32,
+    pattern: Option<(Pattern, u32)>,
+}
+
+impl GapGenerator {
+    pub fn new(seed: u64) -> GapGenerator {
+        let rng = SimRng::new(seed);
+        GapGenerator {
+            rng,
+            previous: None,
+            extremes: 0,
+            pattern: None,
+        }
+    }
+
+    /// Picks where the next gap starts (see `PairOfPipes::with_gap`), in
+    /// reach of the last for a bird flying with `physics` and `limits`
+    /// under `gravity`.
+    pub fn next(
+        &mut self,
+        params: &GapParams,
+        gravity: f32,
+        physics: Physics,
+        limits: Limits,
+    ) -> f32 {
+        let gap = match self.pattern_gap(params) {
+            Some(gap) if !self.too_extreme(params, gap) => gap,
+            _ => {
+                // A pattern that runs into an edge once too often ends there
+                self.pattern = None;
+                self.random_gap(params)
+            }
+        };
+        let gap = match self.previous {
+            Some(previous) => {
+                let (climb, drop) = reach(params, gravity, physics, limits);
+                gap.clamp(previous - climb, previous + drop)
+            }
+            None => gap,
+        };
+        self.extremes = if params.is_extreme(gap) {
+            self.extremes + 1
+        } else {
+            0
+        };
+        self.previous = Some(gap);
+        gap
+    }
+
+    fn random_gap(&mut self, params: &GapParams) -> f32 {
+        let draws = params.weighting;
+        let sum: f32 = (0..draws)
+            .map(|_| self.rng.gen_range(params.min, params.max))
+            .sum();
+        let gap = sum / draws as f32;
+        if self.too_extreme(params, gap) {
+            let edge = (params.max - params.min) * EXTREME_SHARE;
+            return self.rng.gen_range(params.min + edge, params.max - edge);
+        }
+        gap
+    }
+
+    // Whether `gap` would make one extreme too many in a row
+    fn too_extreme(&self, params: &GapParams, gap: f32) -> bool {
+        params.is_extreme(gap) && self.extremes >= params.max_extremes
+    }
+
+    // The next gap of a pattern, starting one now and then
+    fn pattern_gap(&mut self, params: &GapParams) -> Option<f32> {
+        let previous = self.previous?;
+        if self.pattern.is_none() && self.rng.gen_range(0.0, 1.0) < params.pattern_chance {
+            let length = self.rng.gen_range(MIN_PATTERN_GAPS, MAX_PATTERN_GAPS + 1);
+            let pattern = if self.rng.gen_range(0, 2) == 0 {
+                // Heads for whichever end has more room
+                let middle = (params.min + params.max) / 2.0;
+                let step = if previous > middle {
+                    -params.pattern_step
+                } else {
+                    params.pattern_step
+                };
+                Pattern::Staircase { step }
+            } else {
+                let half_step = params.half_step();
+                // Rounding can put the ends of a narrow range out of order
+                let lowest = params.min + half_step;
+                Pattern::Zigzag {
+                    center: previous.clamp(lowest, (params.max - half_step).max(lowest)),
+                    above: true,
+                }
+            };
+            self.pattern = Some((pattern, length));
+        }
+
+        let (pattern, left) = self.pattern.as_mut()?;
+        let gap = match pattern {
+            Pattern::Staircase { step } => previous + *step,
+            Pattern::Zigzag { center, above } => {
+                *above = !*above;
+                let half_step = params.half_step();
+                if *above {
+                    *center - half_step
+                } else {
+                    *center + half_step
+                }
+            }
+        };
+        *left -= 1;
+        if *left == 0 || !(params.min..=params.max).contains(&gap) {
+            self.pattern = None;
+        }
+        Some(gap.clamp(params.min, params.max))
+    }
+}
+
+// How far the bird can climb and drop while flying from one pair to the
+// next: it must leave the first pipe before it can change height and be
+// level with the next gap as it gets there. Stones and trees can be wider
+// than pipes, which leaves less room
+fn reach(params: &GapParams, gravity: f32, physics: Physics, limits: Limits) -> (f32, f32) {
+    let widest = if params.pipe_variety > 0.0 {
+        MAX_PIPE_WIDTH
+    } else {
+        PIPE_WIDTH
+    };
+    let ticks = (PIPE_SPACING - widest - BIRD_WIDTH) / SCROLL_SPEED;
+    let flap = -physics.jump_velocity();
+    let climb = if gravity <= 0.0 {
+        f32::INFINITY
+    } else if ticks <= flap / gravity {
+        // One flap is still rising on arrival
+        flap * ticks - gravity * ticks * ticks / 2.0
+    } else {
+        // Flapping at the top of each hop climbs half the flap speed per
+        // tick. When the cooldown is longer than a hop, the bird falls back
+        // a little before it can flap again.
+        let hop = (flap / gravity).max(limits.flap_cooldown as f32);
+        (flap - gravity * hop / 2.0) * ticks
+    };
+    // Falling from a standstill, without flapping, until it can't fall any
+    // faster
+    let max_fall = limits.max_fall_with(physics);
+    let drop = if gravity <= 0.0 || ticks <= max_fall / gravity {
+        (gravity * ticks * ticks / 2.0).max(0.0)
+    } else {
+        max_fall * max_fall / (2.0 * gravity) + max_fall * (ticks - max_fall / gravity)
+    };
+    (climb * REACH_MARGIN, drop * REACH_MARGIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::physics::{MAX_FLAP_COOLDOWN, MAX_MAX_FALL, MIN_MAX_FALL};
+
+    fn params() -> impl Strategy<Value = GapParams> {
+        let range = gap_top_range(PIPE_HEIGHT);
+        let (lowest, highest) = (*range.start(), *range.end());
+        (
+            lowest..highest,
+            lowest..highest,
+            1..5u32,
+            0..4u32,
+            0.0..1.0f32,
+            0.0..80.0f32,
+            prop_oneof![Just(0.0), 0.0..1.0f32],
+        )
+            .prop_map(
+                |(a, b, weighting, max_extremes, pattern_chance, pattern_step, pipe_variety)| {
+                    GapParams {
+                        min: a.min(b),
+                        max: a.max(b),
+                        weighting,
+                        max_extremes,
+                        pattern_chance,
+                        pattern_step,
+                        pipe_variety,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn every_gap_is_reachable_from_the_last(
+            seed: u64,
+            params in params(),
+            gravity in 0.2..1.2f32,
+            physics in prop::sample::select(Physics::ALL.to_vec()),
+            max_fall in MIN_MAX_FALL..=MAX_MAX_FALL,
+            flap_cooldown in 0..=MAX_FLAP_COOLDOWN,
+        ) {
+            let limits = Limits { max_fall, flap_cooldown };
+            let mut gaps = GapGenerator::new(seed);
+            let (climb, drop) = reach(&params, gravity, physics, limits);
+            let mut previous = gaps.next(&params, gravity, physics, limits);
+            for _ in 0..200 {
+                let gap = gaps.next(&params, gravity, physics, limits);
+                prop_assert!(gap >= previous - climb - 0.01, "{previous} to {gap}");
+                prop_assert!(gap <= previous + drop + 0.01, "{previous} to {gap}");
+                previous = gap;
+            }
+        }
+    }
+}
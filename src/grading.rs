@@ -0,0 +1,157 @@
+// Color grading: the last step before the virtual screen goes to the window
+// (see `Viewport::end`) runs every pixel through a tint, a saturation and a
+// contrast and brightness change, so a night theme or the cave can have a
+// mood of its own without art of its own. A theme sets its grade in
+// `theme.cfg`:
+//
+//     grade_tint = #c8d0ff
+//     grade_saturation = 0.8
+//     grade_contrast = 1.15
+//     grade_brightness = -0.05
+//
+// and each Journey biome adds its own on top (see `biomes.rs`), blending
+// from one into the next like the biomes' colors. A plain grade skips the
+// shader altogether.
+
+use macroquad::prelude::*;
+
+use crate::kv::KeyValues;
+use crate::photo::VERTEX_SHADER;
+
+const FRAGMENT_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec3 Tint;
+uniform float Saturation;
+uniform float Contrast;
+uniform float Brightness;
+
+void main() {
+    vec4 color = texture2D(Texture, uv);
+    vec3 rgb = color.rgb * Tint;
+    float grey = dot(rgb, vec3(0.299, 0.587, 0.114));
+    rgb = mix(vec3(grey), rgb, Saturation);
+    rgb = (rgb - 0.5) * Contrast + 0.5 + Brightness;
+    gl_FragColor = vec4(clamp(rgb, 0.0, 1.0), color.a);
+}"#;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Grade {
+    // Multiplied into every pixel; only the color counts, not the alpha
+    pub tint: Color,
+    // 0 is greyscale, 1 leaves colors be and more is more vivid
+    pub saturation: f32,
+    // Spread around the middle grey, 1 leaving it be
+    pub contrast: f32,
+    // Added to every channel, from -1 to 1
+    pub brightness: f32,
+}
+
+impl Default for Grade {
+    fn default() -> Grade {
+        Grade {
+            tint: WHITE,
+            saturation: 1.0,
+            contrast: 1.0,
+            brightness: 0.0,
+        }
+    }
+}
+
+impl Grade {
+    /// The grade a theme's manifest sets, plain for anything left out.
+    pub fn from_manifest(manifest: &KeyValues) -> Grade {
+        let plain = Grade::default();
+        Grade {
+            tint: manifest.get_color("grade_tint").unwrap_or(plain.tint),
+            saturation: manifest
+                .get_parsed("grade_saturation")
+                .unwrap_or(plain.saturation),
+            contrast: manifest
+                .get_parsed("grade_contrast")
+                .unwrap_or(plain.contrast),
+            brightness: manifest
+                .get_parsed("grade_brightness")
+                .unwrap_or(plain.brightness),
+        }
+    }
+
+    /// This grade with `other` put on top.
+    pub fn then(self, other: Grade) -> Grade {
+        let tint = self.tint.to_vec().truncate() * other.tint.to_vec().truncate();
+        Grade {
+            tint: Color::new(tint.x, tint.y, tint.z, 1.0),
+            saturation: self.saturation * other.saturation,
+            contrast: self.contrast * other.contrast,
+            brightness: self.brightness + other.brightness,
+        }
+    }
+
+    /// `t` of the way from this grade to `other`.
+    pub fn lerp(self, other: Grade, t: f32) -> Grade {
+        let (a, b) = (self.tint.to_vec(), other.tint.to_vec());
+        Grade {
+            tint: Color::from_vec(a + (b - a) * t),
+            saturation: self.saturation + (other.saturation - self.saturation) * t,
+            contrast: self.contrast + (other.contrast - self.contrast) * t,
+            brightness: self.brightness + (other.brightness - self.brightness) * t,
+        }
+    }
+
+    fn is_plain(&self) -> bool {
+        let Color { r, g, b, .. } = self.tint;
+        (r, g, b) == (1.0, 1.0, 1.0)
+            && self.saturation == 1.0
+            && self.contrast == 1.0
+            && self.brightness == 0.0
+    }
+}
+
+/// The grading shader.
+pub struct Grading {
+    // Missing if the shader didn't compile, which leaves frames ungraded
+    material: Option<Material>,
+}
+
+impl Grading {
+    pub fn new() -> Grading {
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: VERTEX_SHADER,
+                fragment: FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![
+                    UniformDesc::new("Tint", UniformType::Float3),
+                    UniformDesc::new("Saturation", UniformType::Float1),
+                    UniformDesc::new("Contrast", UniformType::Float1),
+                    UniformDesc::new("Brightness", UniformType::Float1),
+                ],
+                ..Default::default()
+            },
+        )
+        .inspect_err(|err| eprintln!("Color grading is unavailable: {err}"))
+        .ok();
+        Grading { material }
+    }
+
+    /// Runs `draw` with `grade` on everything it draws, or just runs it for
+    /// a plain grade.
+    pub fn apply(&self, grade: Grade, draw: impl FnOnce()) {
+        let Some(material) = self.material.as_ref().filter(|_| !grade.is_plain()) else {
+            draw();
+            return;
+        };
+        let Color { r, g, b, .. } = grade.tint;
+        material.set_uniform("Tint", vec3(r, g, b));
+        material.set_uniform("Saturation", grade.saturation);
+        material.set_uniform("Contrast", grade.contrast);
+        material.set_uniform("Brightness", grade.brightness);
+        gl_use_material(material);
+        draw();
+        gl_use_default_material();
+    }
+}
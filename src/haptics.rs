@@ -0,0 +1,143 @@
+// Vibration feedback, driven by game events like the sound effects
+//
+// Phones vibrate on Android. Gamepads rumble with the `rumble` cargo feature
+// (needs libudev development files on Linux). Elsewhere this does nothing.
+
+#[cfg(feature = "rumble")]
+use gilrs::Gilrs;
+#[cfg(feature = "rumble")]
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks};
+
+use crate::events::GameEvent;
+
+#[derive(Clone, Copy)]
+enum Pulse {
+    // Barely there, for each pipe passed
+    Tick,
+    // A short bump when the bird crashes
+    Crash,
+}
+
+impl Pulse {
+    #[cfg(any(target_os = "android", feature = "rumble"))]
+    fn millis(self) -> u32 {
+        match self {
+            Pulse::Tick => 15,
+            Pulse::Crash => 150,
+        }
+    }
+
+    // How hard to vibrate, from 0 to 1
+    #[cfg(feature = "rumble")]
+    fn strength(self) -> f32 {
+        match self {
+            Pulse::Tick => 0.3,
+            Pulse::Crash => 1.0,
+        }
+    }
+}
+
+pub struct Haptics {
+    #[cfg(feature = "rumble")]
+    gilrs: Option<Gilrs>,
+    // Kept alive until it finishes; dropping an effect stops it
+    #[cfg(feature = "rumble")]
+    playing: Option<Effect>,
+}
+
+impl Haptics {
+    pub fn new() -> Self {
+        Haptics {
+            #[cfg(feature = "rumble")]
+            gilrs: Gilrs::new()
+                .inspect_err(|err| eprintln!("Gamepad rumble unavailable: {err}"))
+                .ok(),
+            #[cfg(feature = "rumble")]
+            playing: None,
+        }
+    }
+
+    pub fn handle(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::PipePassed { .. } => self.pulse(Pulse::Tick),
+            GameEvent::Collided => self.pulse(Pulse::Crash),
+            _ => {}
+        }
+    }
+
+    /// Keeps the connected gamepad list current. Call once per frame.
+    #[cfg(feature = "rumble")]
+    pub fn update(&mut self) {
+        if let Some(gilrs) = &mut self.gilrs {
+            while gilrs.next_event().is_some() {}
+        }
+    }
+
+    #[cfg(not(feature = "rumble"))]
+    pub fn update(&mut self) {}
+
+    #[cfg(any(target_os = "android", feature = "rumble"))]
+    fn pulse(&mut self, pulse: Pulse) {
+        #[cfg(target_os = "android")]
+        vibrate_phone(pulse.millis());
+        #[cfg(feature = "rumble")]
+        self.rumble(pulse);
+    }
+
+    #[cfg(not(any(target_os = "android", feature = "rumble")))]
+    fn pulse(&mut self, _pulse: Pulse) {}
+
+    #[cfg(feature = "rumble")]
+    fn rumble(&mut self, pulse: Pulse) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        let pads: Vec<_> = gilrs
+            .gamepads()
+            .filter(|(_, pad)| pad.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect();
+        if pads.is_empty() {
+            return;
+        }
+        let magnitude = (pulse.strength() * f32::from(u16::MAX)) as u16;
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong { magnitude },
+                scheduling: Replay {
+                    play_for: Ticks::from_ms(pulse.millis()),
+                    ..Default::default()
+                },
+                envelope: Default::default(),
+            })
+            .gamepads(&pads)
+            .finish(gilrs);
+        match effect.and_then(|effect| effect.play().map(|()| effect)) {
+            Ok(effect) => self.playing = Some(effect),
+            Err(err) => eprintln!("Could not rumble: {err}"),
+        }
+    }
+}
+
+// Vibrator.vibrate(ms) through the activity's "vibrator" system service
+#[cfg(target_os = "android")]
+fn vibrate_phone(millis: u32) {
+    use macroquad::miniquad::native::android::{ACTIVITY, attach_jni_env};
+    use macroquad::miniquad::{call_object_method, call_void_method};
+
+    unsafe {
+        let env = attach_jni_env();
+        let name = c"vibrator";
+        let name = (**env).NewStringUTF.unwrap()(env, name.as_ptr());
+        let vibrator = call_object_method!(
+            env,
+            ACTIVITY,
+            "getSystemService",
+            "(Ljava/lang/String;)Ljava/lang/Object;",
+            name
+        );
+        if !vibrator.is_null() {
+            call_void_method!(env, vibrator, "vibrate", "(J)V", i64::from(millis));
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Every finished run, kept as `history.csv` and exported from the stats
+// screen as CSV and JSON for spreadsheets and scripts
+
+use std::io;
+
+use macroquad::miniquad::date;
+
+use crate::collision::Hit;
+use crate::date::Date;
+use crate::game::Game;
+use crate::load_error::LoadError;
+use crate::storage;
+use crate::timestep::TICK_SECONDS;
+
+pub const HISTORY_FILE: &str = "history.csv";
+const EXPORT_DIR: &str = "exports";
+const CSV_HEADER: &str = "date,score,duration,seed,cause,assisted";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Cause {
+    Ground,
+    Pipe,
+    Enemy,
+    Boss,
+    Rock,
+    // Runs ended by anything else, like a mode script
+    Other,
+}
+
+impl Cause {
+    pub const ALL: [Cause; 6] = [
+        Cause::Ground,
+        Cause::Pipe,
+        Cause::Enemy,
+        Cause::Boss,
+        Cause::Rock,
+        Cause::Other,
+    ];
+
+    fn of(hit: Option<Hit>) -> Cause {
+        match hit {
+            Some(Hit::Ground) => Cause::Ground,
+            Some(Hit::Pipe { .. }) => Cause::Pipe,
+            Some(Hit::Enemy(_)) => Cause::Enemy,
+            Some(Hit::Boss) => Cause::Boss,
+            Some(Hit::Rock { .. }) => Cause::Rock,
+            None => Cause::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Cause::Ground => "ground",
+            Cause::Pipe => "pipe",
+            Cause::Enemy => "enemy",
+            Cause::Boss => "boss",
+            Cause::Rock => "rock",
+            Cause::Other => "other",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Run {
+    // Unix time the run ended, in seconds
+    pub ended: i64,
+    pub score: i64,
+    pub seconds: f32,
+    pub seed: u64,
+    pub cause: Cause,
+    // Played with any assist on
+    pub assisted: bool,
+}
+
+impl Run {
+    /// The run `game` just finished.
+    pub fn of(game: &Game) -> Run {
+        Run {
+            ended: date::now() as i64,
+            score: game.score,
+            seconds: game.ticks as f32 * TICK_SECONDS,
+            seed: game.seed,
+            cause: Cause::of(game.crash),
+            assisted: game.assists.any(),
+        }
+    }
+
+    // Like `2024-01-31 18:05:09`, in UTC
+    fn date_time(self) -> String {
+        let Date { year, month, day } = Date::from_days(self.ended.div_euclid(86_400));
+        let time = self.ended.rem_euclid(86_400);
+        format!(
+            "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )
+    }
+
+    // The saved form: the unix time, then the same columns as an export
+    fn saved_line(self) -> String {
+        format!("{},{}", self.ended, self.csv_line())
+    }
+
+    fn csv_line(self) -> String {
+        format!(
+            "{},{},{:.2},{},{},{}",
+            self.date_time(),
+            self.score,
+            self.seconds,
+            self.seed,
+            self.cause.name(),
+            self.assisted
+        )
+    }
+
+    fn parse(line: &str) -> Option<Run> {
+        let mut fields = line.split(',');
+        let ended = fields.next()?.parse().ok()?;
+        // The readable date is only there for people opening the file
+        fields.next()?;
+        let score = fields.next()?.parse().ok()?;
+        let seconds = fields.next()?.parse().ok()?;
+        let seed = fields.next()?.parse().ok()?;
+        let cause = fields.next()?;
+        let cause = Cause::ALL
+            .into_iter()
+            .find(|c| c.name() == cause)
+            .unwrap_or(Cause::Other);
+        // Missing from runs saved before the column was added
+        let assisted = fields.next() == Some("true");
+        Some(Run {
+            ended,
+            score,
+            seconds,
+            seed,
+            cause,
+            assisted,
+        })
+    }
+}
+
+/// The saved history, oldest first.
+pub fn load() -> Result<Vec<Run>, LoadError> {
+    let text = storage::load_save(HISTORY_FILE)?.unwrap_or_default();
+    Ok(parse(&text))
+}
+
+/// Reads saved history, skipping lines that don't make sense, like the
+/// header or one cut off by a crash.
+pub fn parse(text: &str) -> Vec<Run> {
+    text.lines().filter_map(Run::parse).collect()
+}
+
+/// Adds `run` to the saved history.
+pub fn record(history: &mut Vec<Run>, run: Run) {
+    history.push(run);
+    let mut text = format!("unix_time,{CSV_HEADER}\n");
+    for run in history.iter() {
+        text.push_str(&run.saved_line());
+        text.push('\n');
+    }
+    if let Err(err) = storage::backend().save_text(HISTORY_FILE, &text) {
+        eprintln!("Could not save run history: {err}");
+    }
+}
+
+/// Writes `history` as CSV and JSON under `exports/`, or downloads them on
+/// the web, returning the folder and file stem used.
+pub fn export(history: &[Run]) -> io::Result<String> {
+    let stem = format!("{EXPORT_DIR}/runs-{}", date::now() as u64);
+
+    let mut csv = format!("{CSV_HEADER}\n");
+    for run in history {
+        csv.push_str(&run.csv_line());
+        csv.push('\n');
+    }
+    storage::backend().export_blob(&format!("{stem}.csv"), csv.as_bytes())?;
+
+    // Everything in it is a number or a fixed word, so nothing needs escaping
+    let rows: Vec<String> = history
+        .iter()
+        .map(|run| {
+            format!(
+                "  {{\"date\": \"{}\", \"score\": {}, \"duration\": {:.2}, \"seed\": {}, \"cause\": \"{}\", \"assisted\": {}}}",
+                run.date_time(),
+                run.score,
+                run.seconds,
+                run.seed,
+                run.cause.name(),
+                run.assisted
+            )
+        })
+        .collect();
+    let json = format!("[\n{}\n]\n", rows.join(",\n"));
+    storage::backend().export_blob(&format!("{stem}.json"), json.as_bytes())?;
+    Ok(stem)
+}
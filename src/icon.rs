@@ -0,0 +1,41 @@
+// Window icon made from the bird sprite
+
+use macroquad::miniquad::conf::Icon;
+use macroquad::prelude::*;
+
+// Embedded so the icon doesn't depend on the working directory (the window is
+// created before any assets are loaded)
+const BIRD_PNG: &[u8] = include_bytes!("../resources/sprites/bird.png");
+
+pub fn bird_icon() -> Option<Icon> {
+    let image = Image::from_file_with_format(BIRD_PNG, Some(ImageFormat::Png)).ok()?;
+    Some(Icon {
+        small: fit::<{ 16 * 16 * 4 }>(&image, 16),
+        medium: fit::<{ 32 * 32 * 4 }>(&image, 32),
+        big: fit::<{ 64 * 64 * 4 }>(&image, 64),
+    })
+}
+
+// Scales `image` (nearest neighbour) to fit a `size`x`size` square, centered
+// and keeping its aspect ratio
+fn fit<const N: usize>(image: &Image, size: usize) -> [u8; N] {
+    let mut pixels = [0; N];
+    let (width, height) = (image.width as usize, image.height as usize);
+    let scale = size as f32 / width.max(height) as f32;
+    let offset_x = (size as f32 - width as f32 * scale) / 2.0;
+    let offset_y = (size as f32 - height as f32 * scale) / 2.0;
+
+    for y in 0..size {
+        for x in 0..size {
+            let src_x = (x as f32 + 0.5 - offset_x) / scale;
+            let src_y = (y as f32 + 0.5 - offset_y) / scale;
+            if src_x < 0.0 || src_y < 0.0 || src_x >= width as f32 || src_y >= height as f32 {
+                continue;
+            }
+            let src = (src_y as usize * width + src_x as usize) * 4;
+            let dst = (y * size + x) * 4;
+            pixels[dst..dst + 4].copy_from_slice(&image.bytes[src..src + 4]);
+        }
+    }
+    pixels
+}
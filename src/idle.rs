@@ -0,0 +1,106 @@
+// Idle timeouts, so a machine left alone doesn't sit on a stale screen:
+// after `menu` seconds without input on the results screen the game goes
+// back to the title screen, closing any menus, and after `attract` seconds
+// there the attract demo comes on (see `kiosk.rs`) until the next key,
+// click or touch. Both are set in `settings.cfg`, 0 turning them off:
+//
+//     idle_menu_seconds = 120
+//     idle_attract_seconds = 120
+//
+// Kiosk mode has timings of its own, and matches and parties never idle.
+
+use macroquad::prelude::*;
+
+use crate::game::{Game, GameState};
+use crate::kiosk::AttractDemo;
+use crate::kv::KeyValues;
+
+const DEFAULT_SECONDS: f32 = 120.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct IdleTimeouts {
+    // From the results screen back to the title screen
+    pub menu: f32,
+    // From the title screen to the attract demo
+    pub attract: f32,
+}
+
+impl Default for IdleTimeouts {
+    fn default() -> Self {
+        IdleTimeouts {
+            menu: DEFAULT_SECONDS,
+            attract: DEFAULT_SECONDS,
+        }
+    }
+}
+
+impl IdleTimeouts {
+    /// Reads the timeouts from `settings.cfg`'s keys, rounding negatives
+    /// up to 0 (off).
+    pub fn from_values(kv: &KeyValues) -> IdleTimeouts {
+        let seconds = |key: &str| {
+            kv.get_parsed::<f32>(key)
+                .unwrap_or(DEFAULT_SECONDS)
+                .max(0.0)
+        };
+        IdleTimeouts {
+            menu: seconds("idle_menu_seconds"),
+            attract: seconds("idle_attract_seconds"),
+        }
+    }
+
+    pub fn write_values(&self, kv: &mut KeyValues) {
+        kv.set("idle_menu_seconds", self.menu);
+        kv.set("idle_attract_seconds", self.attract);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IdleAction {
+    // Time to leave the results screen for the title screen
+    ToMenu,
+    // The attract demo just came on
+    Attract,
+    // Input ended the attract demo; it shouldn't count for anything else
+    Woke,
+}
+
+#[derive(Default)]
+pub struct Idle {
+    // Seconds since the last input on the current screen
+    seconds: f32,
+    pub attract: Option<AttractDemo>,
+}
+
+impl Idle {
+    /// Counts the time since the last input while `game` is on the title
+    /// or results screen, starting the attract demo when it's due.
+    pub fn update(&mut self, dt: f32, game: &Game, timeouts: IdleTimeouts) -> Option<IdleAction> {
+        let input = get_last_key_pressed().is_some()
+            || is_mouse_button_pressed(MouseButton::Left)
+            || is_mouse_button_pressed(MouseButton::Right)
+            || touches()
+                .iter()
+                .any(|touch| touch.phase == TouchPhase::Started);
+        if input || !matches!(game.state, GameState::Ready | GameState::GameOver) {
+            self.seconds = 0.0;
+            return self.attract.take().map(|_| IdleAction::Woke);
+        }
+        if self.attract.is_some() {
+            return None;
+        }
+        self.seconds += dt;
+        let (timeout, action) = match game.state {
+            GameState::GameOver => (timeouts.menu, IdleAction::ToMenu),
+            _ => (timeouts.attract, IdleAction::Attract),
+        };
+        if timeout <= 0.0 || self.seconds < timeout {
+            return None;
+        }
+        self.seconds = 0.0;
+        if action == IdleAction::Attract {
+            self.attract = Some(AttractDemo::new());
+        }
+        Some(action)
+    }
+}
@@ -0,0 +1,160 @@
+// Arcade cabinet mode (`--kiosk`)
+//
+// While nobody is playing an attract demo flies itself. Any button flaps,
+// Escape does nothing, and the results screen returns to the demo on its
+// own. The operator leaves with Ctrl+Shift+Q. Outside kiosk mode the same
+// demo comes on when the title screen is left alone (see `idle.rs`).
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::bird::{BIRD_HEIGHT, BIRD_WIDTH};
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::pipes::SCROLL_SPEED;
+use crate::theme::Theme;
+
+// How long the results screen stays up before the demo comes back
+const RESULTS_SECONDS: f32 = 6.0;
+// Buttons are ignored this long after a crash so a panicked flap doesn't
+// skip the results
+const RESULTS_LOCKOUT_SECONDS: f32 = 1.0;
+// Ticks the demo bird looks ahead when deciding whether to flap
+const AUTOPILOT_HORIZON: u32 = 40;
+
+pub struct Kiosk {
+    demo: AttractDemo,
+    // Seconds spent on the current results screen
+    results_time: f32,
+}
+
+impl Kiosk {
+    pub fn new() -> Self {
+        Kiosk {
+            demo: AttractDemo::new(),
+            results_time: 0.0,
+        }
+    }
+
+    /// Whether the player pressed anything that should count as a flap.
+    pub fn button_pressed(&self, game: &Game) -> bool {
+        if game.state == GameState::GameOver && self.results_time < RESULTS_LOCKOUT_SECONDS {
+            return false;
+        }
+        get_last_key_pressed().is_some()
+            || is_mouse_button_pressed(MouseButton::Left)
+            || touches()
+                .iter()
+                .any(|touch| touch.phase == TouchPhase::Started)
+    }
+
+    pub fn operator_exit() -> bool {
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        ctrl && shift && is_key_pressed(KeyCode::Q)
+    }
+
+    pub fn tick_demo(&mut self) {
+        self.demo.tick();
+    }
+
+    /// Counts down the results screen. Returns true once it's time to go
+    /// back to the attract demo.
+    pub fn results_over(&mut self, game: &Game, dt: f32) -> bool {
+        if game.state != GameState::GameOver {
+            self.results_time = 0.0;
+            return false;
+        }
+        self.results_time += dt;
+        self.results_time >= RESULTS_SECONDS
+    }
+
+    pub fn draw_demo(&self, theme: &Theme) {
+        self.demo.draw(theme);
+    }
+}
+
+/// A run the autopilot flies for show.
+pub struct AttractDemo {
+    game: Game,
+}
+
+impl AttractDemo {
+    pub fn new() -> Self {
+        AttractDemo { game: Game::new() }
+    }
+
+    /// Advances the demo by one tick, restarting it when it crashes.
+    pub fn tick(&mut self) {
+        if self.game.state != GameState::Playing {
+            self.game.reset();
+            self.game.flap();
+        }
+        if autopilot_flap(&self.game) {
+            self.game.flap();
+        }
+        self.game.update();
+        // The demo never counts towards stats, sounds or scripts
+        self.game.events.drain();
+    }
+
+    pub fn draw(&self, theme: &Theme) {
+        self.game.draw(theme);
+        // Blink the prompt about once a second
+        if get_time().fract() < 0.6 {
+            draw_centered_text("PRESS ANY BUTTON", HEIGHT / 2.0, 24.0, theme.text_color);
+        }
+    }
+}
+
+// Flaps when the best plan that flaps now outlasts every plan that waits.
+// Plans have at most two flaps, which is enough to get through the next gap.
+// Enemies and the boss are ignored; the demo just has to look plausible.
+pub fn autopilot_flap(game: &Game) -> bool {
+    if !game.flap_ready() {
+        return false;
+    }
+    let cooldown = game.limits.flap_cooldown.max(1);
+    let best_from = |first: u32| {
+        // Second flaps are only tried every other tick to keep this cheap
+        (first + cooldown..AUTOPILOT_HORIZON)
+            .step_by(2)
+            .map(|second| survival(game, &[first, second]))
+            .max()
+            .unwrap_or(0)
+            .max(survival(game, &[first]))
+    };
+    let wait = (1..AUTOPILOT_HORIZON)
+        .map(best_from)
+        .max()
+        .unwrap_or(0)
+        .max(survival(game, &[]));
+    best_from(0) > wait
+}
+
+// Ticks until the bird hits a pipe or the ground if it flaps on the given
+// ticks from now
+fn survival(game: &Game, flaps: &[u32]) -> u32 {
+    let bird = &game.bird;
+    let (mut y, mut velocity) = (bird.y, bird.velocity.y);
+    for tick in 0..AUTOPILOT_HORIZON {
+        if flaps.contains(&tick) {
+            velocity = game.physics.jump_velocity();
+        }
+        velocity = (velocity + game.gravity).min(game.max_fall());
+        y += velocity;
+        if y < 0.0 {
+            y = 0.0;
+            velocity = 0.0;
+        }
+        let scroll = SCROLL_SPEED * (tick + 1) as f32;
+        let rect = Rect::new(bird.x + scroll, y, BIRD_WIDTH, BIRD_HEIGHT);
+        let hit = game
+            .pipes
+            .iter()
+            .any(|pipe| rect.overlaps(&pipe.top_rect()) || rect.overlaps(&pipe.bottom_rect()));
+        if hit || rect.bottom() >= HEIGHT {
+            return tick;
+        }
+    }
+    AUTOPILOT_HORIZON
+}
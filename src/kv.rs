@@ -0,0 +1,146 @@
+// Tiny `key = value` text format shared by settings files and theme manifests
+//
+// Blank lines and lines starting with `#` are ignored. Keys keep the order
+// they were read or set in so saved files stay stable between runs.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use macroquad::prelude::Color;
+
+use crate::load_error::LoadError;
+
+#[derive(Default)]
+pub struct KeyValues {
+    entries: Vec<(String, String)>,
+}
+
+impl KeyValues {
+    pub fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+            .collect();
+        KeyValues { entries }
+    }
+
+    /// Parses a file's raw contents, which have to be UTF-8.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        let text = std::str::from_utf8(bytes).map_err(|_| LoadError::NotText)?;
+        Ok(Self::parse(text))
+    }
+
+    /// Reads `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every key, in order, once each.
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in &self.entries {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|value| value.parse().ok())
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_parsed(key)
+    }
+
+    /// Reads a `#rrggbb` or `#rrggbbaa` color.
+    pub fn get_color(&self, key: &str) -> Option<Color> {
+        let hex = self.get(key)?.strip_prefix('#')?;
+        let rgba = match hex.len() {
+            6 => u32::from_str_radix(hex, 16).ok()? << 8 | 0xff,
+            8 => u32::from_str_radix(hex, 16).ok()?,
+            _ => return None,
+        };
+        Some(Color::from_rgba(
+            (rgba >> 24) as u8,
+            (rgba >> 16) as u8,
+            (rgba >> 8) as u8,
+            rgba as u8,
+        ))
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        let value = value.to_string();
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key.to_owned(), value)),
+        }
+    }
+}
+
+impl fmt::Display for KeyValues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in &self.entries {
+            writeln!(f, "{key} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_lines_that_arent_key_value() {
+        let kv = KeyValues::parse(
+            "# a comment\n\nname = Canyon\njust some words\n  speed=3  \nspeed = 4\nsum = 1 + 1 = 2\n= no key\n",
+        );
+        assert_eq!(kv.keys(), ["name", "speed", "sum", ""]);
+        assert_eq!(kv.get("name"), Some("Canyon"));
+        // The last one read wins
+        assert_eq!(kv.get_parsed::<u32>("speed"), Some(4));
+        assert_eq!(kv.get("sum"), Some("1 + 1 = 2"));
+        assert_eq!(kv.get("just some words"), None);
+        assert_eq!(kv.get_parsed::<u32>("name"), None);
+        assert_eq!(kv.get_bool("name"), None);
+        assert!(KeyValues::from_bytes(&[0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn reads_colors_and_round_trips() {
+        let kv = KeyValues::parse("sky = #4ec0ca\nfog = #ffffff80\nbad = #fff\nworse = 4ec0ca\n");
+        assert_eq!(
+            kv.get_color("sky"),
+            Some(Color::from_rgba(0x4e, 0xc0, 0xca, 0xff))
+        );
+        assert_eq!(
+            kv.get_color("fog"),
+            Some(Color::from_rgba(0xff, 0xff, 0xff, 0x80))
+        );
+        assert_eq!(kv.get_color("bad"), None);
+        assert_eq!(kv.get_color("worse"), None);
+
+        let mut kv = KeyValues::default();
+        kv.set("runs", 12);
+        kv.set("name", "bird");
+        kv.set("runs", 13);
+        let text = kv.to_string();
+        assert_eq!(text, "runs = 13\nname = bird\n");
+        assert_eq!(KeyValues::parse(&text).get_parsed::<u32>("runs"), Some(13));
+    }
+}
@@ -0,0 +1,149 @@
+// Input latency diagnostics, a screen left off the title screen's hints and
+// opened there with OPEN_KEY. It estimates how long a flap takes to reach
+// the screen, a piece at a time: waiting to be read at the start of a frame
+// (half a frame on average), waiting for the next tick (a frame the clock
+// runs none on, as happens a lot above 60 Hz, holds the flap back), the
+// frame that draws it, and the frames the display holds on to before
+// showing it. The tick waits are measured on the flaps of real runs and
+// the rest worked out from the frame rate, since there's no seeing when
+// the photons actually leave. `LatencyMeter` keeps the measurements,
+// whether the screen is open or not.
+//
+// Tapping while it's open measures how long taps are held down, which is
+// what flapping on release adds, and Tab switches which end of a press
+// flaps (see `controls.rs`).
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::controls::FlapEdge;
+use crate::game::draw_centered_text;
+use crate::screen::{Context, Screen, Transition};
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+
+pub const OPEN_KEY: KeyCode = KeyCode::F4;
+// How many of each measurement the averages go over
+const FRAMES_KEPT: usize = 120;
+const FLAPS_KEPT: usize = 60;
+const TAPS_KEPT: usize = 20;
+// With vsync a finished frame waits for the next refresh to be swapped in
+const BUFFERED_FRAMES: f32 = 1.0;
+
+#[derive(Default)]
+pub struct LatencyMeter {
+    // Seconds each of the latest frames took
+    frames: VecDeque<f32>,
+    // Seconds the latest flaps waited for a tick, and how long the one
+    // still waiting has so far
+    tick_waits: VecDeque<f32>,
+    waiting: Option<f32>,
+}
+
+impl LatencyMeter {
+    /// Notes a flap read this frame, to time how long it waits for a tick.
+    pub fn note_flap(&mut self) {
+        self.waiting.get_or_insert(0.0);
+    }
+
+    /// Notes a frame that took `dt` and ran `ticks` ticks.
+    pub fn note_frame(&mut self, dt: f32, ticks: u32) {
+        keep(&mut self.frames, dt, FRAMES_KEPT);
+        if let Some(waited) = self.waiting {
+            if ticks > 0 {
+                keep(&mut self.tick_waits, waited, FLAPS_KEPT);
+                self.waiting = None;
+            } else {
+                self.waiting = Some(waited + dt);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LatencyScreen {
+    // Seconds the latest taps were held, and when the one held now went down
+    holds: VecDeque<f32>,
+    pressed_at: Option<f64>,
+}
+
+impl Screen for LatencyScreen {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(OPEN_KEY) {
+            return Transition::Pop;
+        }
+        let controls = cx.profile.controls;
+        if controls.flap().is_some() {
+            self.pressed_at = Some(get_time());
+        }
+        if controls.flap_released().is_some()
+            && let Some(at) = self.pressed_at.take()
+        {
+            keep(&mut self.holds, (get_time() - at) as f32, TAPS_KEPT);
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            cx.settings.flap_edge = cx.settings.flap_edge.other();
+            cx.settings_changed = true;
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        let (meter, edge) = (&*cx.latency, cx.settings.flap_edge);
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+        draw_centered_text("Latency", 60.0, 32.0, WHITE);
+
+        let frame = average(&meter.frames).unwrap_or(1.0 / 60.0);
+        let tick_wait = average(&meter.tick_waits);
+        let total = frame / 2.0 + tick_wait.unwrap_or(0.0) + frame + BUFFERED_FRAMES * frame;
+        let mut lines = vec![
+            format!("Frames take {} ({:.0} fps)", ms(frame), 1.0 / frame),
+            format!("Read at the next frame: ~{}", ms(frame / 2.0)),
+            match tick_wait {
+                Some(wait) => format!("Waiting for a tick: {}", ms(wait)),
+                None => "Waiting for a tick: fly a run to measure".to_owned(),
+            },
+            format!("Drawn that frame: ~{}", ms(frame)),
+            format!(
+                "Held by the display: ~{BUFFERED_FRAMES} frame, {}",
+                ms(BUFFERED_FRAMES * frame)
+            ),
+            format!("About {} from flap to screen", ms(total)),
+            String::new(),
+            format!("Flapping on: {}", edge.name()),
+        ];
+        lines.push(match average(&self.holds) {
+            Some(hold) => format!("Your taps are held {}", ms(hold)),
+            None => "Tap to see how long you hold".to_owned(),
+        });
+        if edge == FlapEdge::Release {
+            lines.push("Flapping on release adds as much".to_owned());
+        }
+        for (i, line) in lines.iter().enumerate() {
+            draw_centered_text(line, 110.0 + i as f32 * 22.0, 16.0, WHITE);
+        }
+        draw_centered_text("Tab flap edge, Esc close", HEIGHT - 20.0, 16.0, GRAY);
+    }
+}
+
+fn keep(values: &mut VecDeque<f32>, value: f32, kept: usize) {
+    if values.len() == kept {
+        values.pop_front();
+    }
+    values.push_back(value);
+}
+
+fn average(values: &VecDeque<f32>) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+fn ms(seconds: f32) -> String {
+    format!("{:.1} ms", seconds * 1000.0)
+}
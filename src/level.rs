@@ -0,0 +1,194 @@
+// Fixed-length levels, loaded from `levels/<name>.level` with `--level`
+//
+// A level is a `key = value` file (see `kv.rs`) listing its pipe pairs in
+// order; the finish line comes half a spacing after the last one (see
+// `flags.rs`):
+//
+//     name = Warm-up
+//     # Top edge of each gap, in pixels from the top of the screen
+//     pipes = 180, 200, 220
+//     # Pipe pairs, counted from 1, that have a checkpoint just after them
+//     checkpoints = 2
+//     # Optional, per pipe pair: pipe, stone or tree, and a width in pixels
+//     # instead of the sprite's own. Pairs past the end of either list get
+//     # plain pipes at the sprite's width
+//     styles = pipe, stone, tree
+//     widths = 52, 80
+//     # Optional, per pipe pair: points for passing it (before the combo
+//     # multiplier) instead of 1, and coins it hands over as it's passed
+//     scores = 1, 1, 5
+//     coins = 0, 2
+//     # Optional: what happens as the bird passes a pair, see `triggers.rs`
+//     triggers = 2: wind 0.5, 3: music storm
+
+use std::fs;
+use std::path::Path;
+
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::pipes::{MAX_PIPE_WIDTH, MIN_PIPE_WIDTH, PIPE_HEIGHT, PipeStyle, gap_top_range};
+use crate::triggers::Trigger;
+
+pub const LEVELS_DIR: &str = "./levels";
+const LEVEL_EXTENSION: &str = "level";
+
+#[derive(Clone)]
+pub struct Level {
+    // The file name without the extension, which the profile keeps its
+    // stars under
+    pub id: String,
+    pub name: String,
+    pub gaps: Vec<f32>,
+    pub checkpoints: Vec<u32>,
+    pub styles: Vec<PipeStyle>,
+    pub widths: Vec<f32>,
+    pub scores: Vec<u32>,
+    pub coins: Vec<u32>,
+    // In the order the file lists them, by pipe pair counted from 1
+    pub triggers: Vec<(u32, Trigger)>,
+}
+
+impl Level {
+    /// Loads `name` from the levels folder, or from a file at `name`.
+    pub fn load(name: &str) -> Result<Level, LoadError> {
+        let path = Path::new(name);
+        let path = if path.is_file() {
+            path.to_path_buf()
+        } else {
+            Path::new(LEVELS_DIR).join(format!("{name}.{LEVEL_EXTENSION}"))
+        };
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        Level::parse(&fs::read(&path)?, &stem)
+    }
+
+    /// Reads a level file's contents, for the level `id` (its file name),
+    /// which is also its name if it doesn't name itself.
+    pub fn parse(bytes: &[u8], id: &str) -> Result<Level, LoadError> {
+        let kv = KeyValues::from_bytes(bytes)?;
+        let gaps: Vec<f32> = list(kv.get("pipes").unwrap_or_default())
+            .map_err(|_| LoadError::invalid("pipes must be numbers"))?;
+        if gaps.is_empty() {
+            return Err(LoadError::invalid("a level needs at least one pipe pair"));
+        }
+        let range = gap_top_range(PIPE_HEIGHT);
+        if let Some(pipe) = gaps.iter().position(|gap| !range.contains(gap)) {
+            return Err(LoadError::invalid(format!(
+                "pipe {} is off the screen (gaps go from {} to {})",
+                pipe + 1,
+                range.start(),
+                range.end()
+            )));
+        }
+        let checkpoints: Vec<u32> = list(kv.get("checkpoints").unwrap_or_default())
+            .map_err(|_| LoadError::invalid("checkpoints must be pipe numbers"))?;
+        if let Some(&checkpoint) = checkpoints
+            .iter()
+            .find(|&&pipe| pipe == 0 || pipe as usize > gaps.len())
+        {
+            return Err(LoadError::invalid(format!(
+                "checkpoint {checkpoint} isn't one of the level's pipes"
+            )));
+        }
+        let styles = list::<String>(kv.get("styles").unwrap_or_default())
+            .unwrap_or_default()
+            .iter()
+            .map(|id| {
+                PipeStyle::from_id(id).ok_or_else(|| {
+                    LoadError::invalid(format!("{id} isn't a pipe style (pipe, stone or tree)"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let widths: Vec<f32> = list(kv.get("widths").unwrap_or_default())
+            .map_err(|_| LoadError::invalid("widths must be numbers"))?;
+        if let Some(pipe) = widths
+            .iter()
+            .position(|width| !(MIN_PIPE_WIDTH..=MAX_PIPE_WIDTH).contains(width))
+        {
+            return Err(LoadError::invalid(format!(
+                "pipe {} is too narrow or too wide (widths go from {MIN_PIPE_WIDTH} to {MAX_PIPE_WIDTH})",
+                pipe + 1
+            )));
+        }
+        let scores: Vec<u32> = list(kv.get("scores").unwrap_or_default())
+            .map_err(|_| LoadError::invalid("scores must be whole numbers"))?;
+        let coins: Vec<u32> = list(kv.get("coins").unwrap_or_default())
+            .map_err(|_| LoadError::invalid("coins must be whole numbers"))?;
+        let lists = [styles.len(), widths.len(), scores.len(), coins.len()];
+        if lists.iter().any(|&len| len > gaps.len()) {
+            return Err(LoadError::invalid(
+                "styles, widths, scores and coins can't list more pipes than pipes does",
+            ));
+        }
+        let triggers = triggers(kv.get("triggers").unwrap_or_default(), gaps.len())?;
+        Ok(Level {
+            id: id.to_owned(),
+            name: kv.get("name").unwrap_or(id).to_owned(),
+            gaps,
+            checkpoints,
+            styles,
+            widths,
+            scores,
+            coins,
+            triggers,
+        })
+    }
+
+    /// How many pipe pairs there are before the finish line.
+    pub fn length(&self) -> u32 {
+        self.gaps.len() as u32
+    }
+
+    /// What passing pipe pair `pipe` (counted from 1) scores before the
+    /// combo multiplier.
+    pub fn score_for(&self, pipe: u32) -> u32 {
+        self.scores.get(pipe as usize - 1).copied().unwrap_or(1)
+    }
+
+    /// The coins passing pipe pair `pipe` hands over.
+    pub fn coins_for(&self, pipe: u32) -> u32 {
+        self.coins.get(pipe as usize - 1).copied().unwrap_or(0)
+    }
+
+    /// What happens as the bird passes pipe pair `pipe`, in file order.
+    pub fn triggers_at(&self, pipe: u32) -> impl Iterator<Item = &Trigger> {
+        self.triggers
+            .iter()
+            .filter(move |(at, _)| *at == pipe)
+            .map(|(_, trigger)| trigger)
+    }
+}
+
+// Like `3: wind 0.5, 8: music storm`, for a level of `pipes` pairs
+fn triggers(text: &str, pipes: usize) -> Result<Vec<(u32, Trigger)>, LoadError> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (pipe, trigger) = item.split_once(':').ok_or_else(|| {
+                LoadError::invalid(format!(
+                    "trigger {item} needs a pipe number, like 3: {item}"
+                ))
+            })?;
+            let pipe = pipe
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&pipe| pipe > 0 && pipe as usize <= pipes)
+                .ok_or_else(|| {
+                    LoadError::invalid(format!("trigger {item} isn't at one of the level's pipes"))
+                })?;
+            let trigger = Trigger::parse(trigger)
+                .map_err(|err| LoadError::invalid(format!("trigger at pipe {pipe}: {err}")))?;
+            Ok((pipe, trigger))
+        })
+        .collect()
+}
+
+// A comma-separated list, where an empty string is an empty list
+fn list<T: std::str::FromStr>(text: &str) -> Result<Vec<T>, T::Err> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::parse)
+        .collect()
+}
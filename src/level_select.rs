@@ -0,0 +1,210 @@
+// Level select, opened from the title screen with OPEN_KEY: a grid of the
+// levels that come with the game, in BUNDLED order, each showing the most
+// stars it's earned (see `Game::stars`). The first is open from the start
+// and each after it opens once the one before is finished. The last tile
+// goes back to the endless course, and Tab turns practice (see `flags.rs`)
+// on and off for whichever level is picked.
+
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use crate::game::{draw_centered_text, draw_star};
+use crate::level::Level;
+use crate::profile::Profile;
+use crate::screen::{Context, Screen, Transition};
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+pub const OPEN_KEY: KeyCode = KeyCode::V;
+/// The levels in the levels folder that come with the game, easiest first.
+pub const BUNDLED: [&str; 6] = ["warmup", "steps", "zigzag", "canyon", "storm", "summit"];
+const COLUMNS: usize = 2;
+const TILE_WIDTH: f32 = 112.0;
+const TILE_HEIGHT: f32 = 62.0;
+const TILE_SPACING: f32 = 8.0;
+const GRID_TOP: f32 = 76.0;
+
+pub struct LevelSelect {
+    // Each bundled level, or why it couldn't be loaded
+    levels: Vec<Result<Rc<Level>, String>>,
+    // Which tile is picked; one past the levels is the endless course
+    selected: usize,
+}
+
+impl LevelSelect {
+    pub fn new() -> LevelSelect {
+        let levels = BUNDLED
+            .iter()
+            .map(|&id| Level::load(id).map(Rc::new).map_err(|err| err.to_string()))
+            .collect();
+        LevelSelect {
+            levels,
+            selected: 0,
+        }
+    }
+
+    fn tiles(&self) -> usize {
+        self.levels.len() + 1
+    }
+
+    fn tile_rect(&self, tile: usize) -> Rect {
+        let (column, row) = (tile % COLUMNS, tile / COLUMNS);
+        let grid_width = COLUMNS as f32 * (TILE_WIDTH + TILE_SPACING) - TILE_SPACING;
+        Rect::new(
+            WIDTH / 2.0 - grid_width / 2.0 + column as f32 * (TILE_WIDTH + TILE_SPACING),
+            GRID_TOP + row as f32 * (TILE_HEIGHT + TILE_SPACING),
+            TILE_WIDTH,
+            TILE_HEIGHT,
+        )
+    }
+
+    // Tile `tile` can be played, the endless course included
+    fn unlocked(profile: &Profile, tile: usize) -> bool {
+        tile == 0 || tile >= BUNDLED.len() || profile.level_stars.contains_key(BUNDLED[tile - 1])
+    }
+
+    // Starts the picked tile's course, or says why it can't
+    fn play(&self, cx: &mut Context) -> Transition {
+        let Some(level) = self.levels.get(self.selected) else {
+            cx.game.set_level(None);
+            cx.toasts.push("Endless course", WHITE);
+            return Transition::Pop;
+        };
+        if !LevelSelect::unlocked(cx.profile, self.selected) {
+            let before = match &self.levels[self.selected - 1] {
+                Ok(level) => level.name.clone(),
+                Err(_) => "the level before".to_owned(),
+            };
+            cx.toasts
+                .push(format!("Finish {before} to open this one"), ORANGE);
+            return Transition::Stay;
+        }
+        match level {
+            Ok(level) => {
+                cx.game.set_level(Some(level.clone()));
+                Transition::Pop
+            }
+            Err(err) => {
+                cx.toasts
+                    .push(format!("Level {}: {err}", BUNDLED[self.selected]), RED);
+                Transition::Stay
+            }
+        }
+    }
+}
+
+impl Screen for LevelSelect {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(OPEN_KEY) {
+            return Transition::Pop;
+        }
+        let count = self.tiles();
+        if is_key_pressed(KeyCode::Left) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if is_key_pressed(KeyCode::Up) && self.selected >= COLUMNS {
+            self.selected -= COLUMNS;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + COLUMNS).min(count - 1);
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            cx.game.practice = !cx.game.practice;
+        }
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let mouse = cx.viewport.mouse_position();
+            if let Some(tile) = (0..count).find(|&tile| self.tile_rect(tile).contains(mouse)) {
+                self.selected = tile;
+                return self.play(cx);
+            }
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            return self.play(cx);
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        let profile = &*cx.profile;
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.75),
+        );
+        draw_centered_text("Levels", 40.0, 32.0, WHITE);
+        let practice = if cx.game.practice {
+            "Practice on: crashes go back to the last checkpoint"
+        } else {
+            "Practice off"
+        };
+        draw_centered_text(practice, 60.0, 12.0, GRAY);
+
+        for tile in 0..self.tiles() {
+            let rect = self.tile_rect(tile);
+            let unlocked = LevelSelect::unlocked(profile, tile);
+            let fill = if unlocked {
+                Color::new(1.0, 1.0, 1.0, 0.12)
+            } else {
+                Color::new(0.0, 0.0, 0.0, 0.4)
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+            if tile == self.selected {
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, YELLOW);
+            }
+            let center = rect.x + rect.w / 2.0;
+            let label = |text: &str, y: f32, size: f32, color: Color| {
+                let width = measure_text(text, None, size as u16, 1.0).width;
+                draw_text(text, center - width / 2.0, rect.y + y, size, color);
+            };
+            let Some((level, id)) = self.levels.get(tile).zip(BUNDLED.get(tile)) else {
+                label("Endless", 36.0, 20.0, WHITE);
+                continue;
+            };
+            let name = match level {
+                Ok(level) => level.name.as_str(),
+                Err(_) => "Missing",
+            };
+            label(&format!("{}. {name}", tile + 1), 20.0, 16.0, WHITE);
+            if !unlocked {
+                label("Locked", 44.0, 14.0, GRAY);
+                continue;
+            }
+            let stars = profile.level_stars.get(*id).copied();
+            for i in 0..3 {
+                let color = match stars {
+                    Some(stars) if i < stars => GOLD,
+                    _ => Color::new(1.0, 1.0, 1.0, 0.2),
+                };
+                let x = center + (i as f32 - 1.0) * 22.0;
+                draw_star(vec2(x, rect.y + 40.0), 8.0, color);
+            }
+            match profile.level_bests.get(*id) {
+                _ if stars.is_none() => label("Not finished", 58.0, 12.0, GRAY),
+                Some(best) => label(&format!("Best {best}"), 58.0, 12.0, GRAY),
+                None => {}
+            }
+        }
+
+        let hint = "Arrows pick, Enter plays, Tab practice, Esc close";
+        draw_centered_text(hint, HEIGHT - 12.0, 12.0, GRAY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_bundled_level_loads() {
+        for id in BUNDLED {
+            let level = Level::load(id).unwrap_or_else(|err| panic!("{id}: {err}"));
+            assert_eq!(level.id, id);
+        }
+    }
+}
@@ -0,0 +1,1041 @@
+//! Flappy Bird, built with macroquad.
+//!
+//! [`play`] runs the whole game, as the `flappy_bird_rust` binary does.
+//! [`FlappyGame`] is the bird and the course alone, for embedding as a
+//! mini-game in another macroquad project (see `examples/embedded.rs`).
+//! The optional parts, like sound, Steam and the event feed, are cargo
+//! features listed in `Cargo.toml`.
+
+mod achievements;
+mod adaptive;
+mod animation;
+mod assets;
+mod assists;
+mod attacks;
+mod audio;
+mod backdrop;
+mod bench;
+mod biomes;
+mod bird;
+mod boss;
+mod captions;
+mod celebration;
+mod challenge;
+mod cloud;
+mod coins;
+mod collision;
+mod combo;
+mod console;
+mod controls;
+mod coop;
+mod cosmetics;
+mod cursor;
+mod date;
+mod death_replay;
+mod debug_step;
+mod discovery;
+mod embed;
+mod enemies;
+mod event_server;
+mod events;
+mod flags;
+#[cfg(any(test, fuzzing))]
+pub mod fuzz;
+mod game;
+mod gameplay;
+mod gaps;
+mod grading;
+mod haptics;
+mod history;
+mod icon;
+mod idle;
+mod kiosk;
+mod kv;
+mod latency;
+mod level;
+mod level_select;
+mod load_error;
+mod lobby;
+mod mask;
+mod medals;
+mod menu_replay;
+mod mod_manager;
+mod mod_pack;
+mod modifiers;
+mod mutators;
+mod net;
+mod obstacle;
+mod one_switch;
+mod options;
+#[cfg(feature = "audio")]
+mod pan;
+mod particles;
+mod party;
+mod perf;
+mod photo;
+mod physics;
+mod pipes;
+mod platform;
+mod popups;
+mod powerups;
+mod presence;
+mod profile;
+mod quality;
+mod quests;
+mod replay;
+mod replay_viewer;
+mod screen;
+mod screenshot;
+mod scripting;
+mod season;
+mod seed_code;
+mod session;
+mod settings;
+mod settings_menu;
+mod share_card;
+mod shutdown;
+mod signing;
+mod sim_math;
+mod sim_rng;
+mod soak;
+mod spectator;
+mod stats_screen;
+mod steam;
+mod storage;
+// Nothing sends submissions yet, see `submission.rs`
+#[cfg_attr(not(test), allow(dead_code))]
+mod submission;
+mod telemetry;
+mod terrain;
+mod theme;
+mod ticker;
+mod timestep;
+mod toast;
+mod trail;
+mod triggers;
+mod tutorial;
+mod tween;
+mod twitch;
+mod update;
+mod versus;
+mod viewport;
+mod weekly;
+mod window;
+
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use assists::Assists;
+use audio::{Audio, Cue, Line, Playlist};
+use captions::Captions;
+use celebration::Celebration;
+use challenge::Challenge;
+use cloud::Cloud;
+use collision::Grace;
+use console::{Console, ConsoleScreen};
+use controls::ControlScheme;
+use coop::Coop;
+use death_replay::{DeathReplay, StateBuffer};
+use event_server::EventServer;
+use events::GameEvent;
+use game::{Game, GameState, SCORE_ROLLOVER};
+use gameplay::Gameplay;
+use grading::Grade;
+use haptics::Haptics;
+use idle::{Idle, IdleAction};
+use kiosk::Kiosk;
+use latency::LatencyMeter;
+use level::Level;
+use lobby::Online;
+use menu_replay::MenuReplay;
+use mutators::Mutators;
+use options::Options;
+use particles::Particles;
+use party::Party;
+use perf::{Perf, Section};
+use physics::{Limits, Physics};
+use pipes::SCROLL_SPEED;
+use popups::ScorePopups;
+use presence::Presence;
+use profile::Profile;
+use quality::QualityScaler;
+use quests::QuestTracker;
+use replay::Replay;
+use screen::{Context, Request, ScreenStack};
+use scripting::{MODS_DIR, Scripting};
+use session::Session;
+use settings::Settings;
+use spectator::Spectator;
+use steam::Steam;
+use telemetry::Telemetry;
+use theme::Theme;
+use timestep::Timestep;
+use toast::Toasts;
+use trail::Trail;
+use triggers::Trigger;
+use tutorial::Tutorial;
+use twitch::TwitchVotes;
+use update::UpdateCheck;
+use versus::{Course, Versus};
+use viewport::Viewport;
+use weekly::{Weekly, WeeklyFetch};
+use window::WindowState;
+
+pub use embed::{API_VERSION, FlappyGame, Input};
+
+const WIDTH: f32 = 267.0;
+const HEIGHT: f32 = 400.0;
+
+fn window_conf() -> Conf {
+    let (window_width, window_height) = WindowState::load().size();
+    Conf {
+        window_title: "Flappy Bird".to_owned(),
+        window_width,
+        window_height,
+        icon: icon::bird_icon(),
+        ..Default::default()
+    }
+}
+
+/// Runs the whole game, as the `flappy_bird_rust` binary does: reads the
+/// command line, opens the window and plays until it's closed.
+pub fn play() {
+    let options = Options::from_env();
+    // The headless benchmark never opens a window
+    if options.bench && options.headless {
+        println!("{}", bench::run_headless());
+        return;
+    }
+    if let Some(dir) = &options.pack_mod {
+        std::process::exit(mod_pack::pack_command(dir));
+    }
+    if let Some(hours) = options.soak {
+        std::process::exit(soak::soak_command(hours));
+    }
+    macroquad::Window::from_config(window_conf(), run(options));
+}
+
+async fn run(options: Options) {
+    let mut steam = Steam::connect();
+    steam.pull_saves();
+    // Files that couldn't be loaded, told about once the title screen is up
+    let mut load_errors = Vec::new();
+    let mut settings = Settings::load().unwrap_or_else(|err| {
+        load_errors.push(format!("Settings {err}, using defaults"));
+        Settings::parse("")
+    });
+    let mut profile = Profile::load().unwrap_or_else(|err| {
+        load_errors.push(format!("Profile {err}, starting afresh"));
+        Profile::default()
+    });
+    profile.history = history::load().unwrap_or_else(|err| {
+        load_errors.push(format!("Run history {err}"));
+        Vec::new()
+    });
+    let mut window_state = WindowState::load();
+    match assets::check().as_slice() {
+        [] => {}
+        [problem] => load_errors.push(problem.clone()),
+        problems => {
+            for problem in problems {
+                eprintln!("{problem}");
+            }
+            let count = problems.len();
+            load_errors.push(format!("{count} resource files missing or changed"));
+        }
+    }
+    let mut cloud = Cloud::start(settings.cloud.clone());
+    let mut updates = UpdateCheck::start(&settings.updates);
+    let mut telemetry = Telemetry::load(settings.telemetry_url.as_deref());
+    let mut weekly = Weekly::load();
+    let mut weekly_fetch = WeeklyFetch::start(settings.weekly_url.as_deref());
+    profile.start_week(weekly.week);
+    let mut quests = QuestTracker::new(quests::load());
+    steam.unlock(&profile.achievements);
+    window_state.restore(settings.fullscreen, settings.fullscreen_display);
+    let mut fullscreen = (settings.fullscreen, settings.fullscreen_display);
+
+    // Load the selected theme (sprites and sounds, with default fallbacks)
+    let mut themes = Theme::available(&profile.mods);
+    let mut theme_id = season::theme_for_today(&settings.theme, settings.seasonal, &themes);
+    let mut theme = Theme::load(&theme_id).await;
+    let mut audio = Audio::load(&theme).await;
+    if !options.mute {
+        audio.start_ambience();
+    }
+    if options.bench {
+        println!("{}", bench::run_windowed(&theme).await);
+        return;
+    }
+    // Music doesn't change with the theme, so it's loaded just once
+    let mut playlist = if options.mute {
+        Playlist::default()
+    } else {
+        Playlist::load()
+    };
+    let mut haptics = Haptics::new();
+    let mut captions = Captions::default();
+    let mut screens = ScreenStack::new(Gameplay::default());
+    let mut latency = LatencyMeter::default();
+    let mut idle = Idle::default();
+    let mut console = Console::default();
+    // The backdrop's tile outlines, a debug view (see `debug_step.rs`)
+    let mut show_tiles = false;
+    // Picked on the mutator screen; they last until turned off again
+    let mut mutators = Mutators::default();
+    // Picked with C on the title screen, or `--mode`
+    let mut challenge = options.mode.as_deref().and_then(Challenge::from_id);
+    // The last moments of the run, for the replay after a crash
+    let mut state_buffer = StateBuffer::default();
+    let mut death_replay: Option<DeathReplay> = None;
+    let mut menu_replay = MenuReplay::load();
+
+    // Create the game (owns the bird and the pipes). A seed from the options
+    // replays one course; otherwise each launch gets new ones.
+    rand::srand((miniquad::date::now() * 1000.0) as u64);
+    let level = options.level.as_deref().and_then(|name| {
+        Level::load(name)
+            .inspect_err(|err| load_errors.push(format!("Level {name}: {err}")))
+            .ok()
+            .map(Rc::new)
+    });
+    let course_seed = weekly.course_seed(challenge, &options);
+    let mut game = Game::with_course(course_seed, level, settings.gaps);
+    game.grace = Grace::new(settings.corner_grace);
+    game.masks = settings.pixel_collision.then(|| theme.masks.clone());
+    game.pipe_sizes = theme.pipe_sizes();
+    game.look = profile.look;
+    game.practice = options.practice;
+    let mut timestep = Timestep::new();
+
+    // Game modes from the mods folder
+    let mut scripting = Scripting::new();
+    scripting.load_mods(MODS_DIR, &profile.mods);
+    if let Some(mode) = options.mode.as_deref()
+        && mode != "daily"
+        && Challenge::from_id(mode).is_none()
+        && !scripting.select(mode)
+    {
+        eprintln!("Unknown game mode {mode}");
+    }
+
+    let mut toasts = Toasts::default();
+    for error in load_errors {
+        eprintln!("{error}");
+        toasts.push(error, RED);
+    }
+    // The daily reminder: a streak from yesterday ends unless there's a run
+    // today
+    let today = date::today_days();
+    let streak = profile.streak(today);
+    if streak > 0 && !profile.played_on(today) && !options.kiosk {
+        toasts.push(
+            format!("Play today to keep your {streak}-day streak!"),
+            GOLD,
+        );
+    }
+    let mut particles = Particles::default();
+    let mut popups = ScorePopups::default();
+    let mut trail = Trail::default();
+    let mut viewport = Viewport::new();
+    // The themed cursor replaces the OS one over the window
+    show_mouse(false);
+    // Closing the window and Ctrl-C go through the shutdown phase, like
+    // Escape (see `shutdown.rs`)
+    prevent_quit();
+    shutdown::catch_interrupt();
+    let mut session = Session::default();
+    let mut presence = Presence::connect(&steam);
+    let mut event_server =
+        EventServer::start(settings.event_port.unwrap_or(event_server::DEFAULT_PORT));
+    let mut kiosk = options.kiosk.then(Kiosk::new);
+    let mut twitch = options.twitch.as_deref().map(TwitchVotes::connect);
+    let mut party: Option<Party> = None;
+    let mut coop: Option<Coop> = None;
+    let versus = match (options.host, options.join.as_deref()) {
+        (Some(port), _) => Some(Versus::host(
+            port,
+            Course::from_options(&options),
+            scripting.active_name(),
+        )),
+        (None, Some(address)) => Some(Versus::join(address)),
+        (None, None) => None,
+    };
+    let mut versus = versus.and_then(|versus| {
+        versus
+            .inspect_err(|err| eprintln!("Could not start online play: {err}"))
+            .ok()
+    });
+    let mut spectator = options.watch.as_deref().and_then(|address| {
+        Spectator::watch(address)
+            .inspect_err(|err| eprintln!("Could not watch {address}: {err}"))
+            .ok()
+    });
+    let mut tutorial = ((!profile.tutorial_done || options.tutorial)
+        && kiosk.is_none()
+        && versus.is_none()
+        && spectator.is_none())
+    .then(Tutorial::new);
+    // Whether this run already announced beating the best score
+    let mut announced_best = false;
+    // Set when a run that beat the best ends, for the results card
+    let mut celebration: Option<Celebration> = None;
+    let mut perf = Perf::default();
+    let mut quality = QualityScaler::default();
+    // Simulation ticks run this frame
+    let mut ticks = 0;
+    // What the screens see of the game, made afresh wherever they need it
+    macro_rules! screen_context {
+        () => {
+            Context {
+                game: &mut game,
+                settings: &mut settings,
+                profile: &mut profile,
+                mutators: &mut mutators,
+                toasts: &mut toasts,
+                scripting: &mut scripting,
+                timestep: &mut timestep,
+                latency: &mut latency,
+                console: &mut console,
+                challenge: &mut challenge,
+                menu_replay: &mut menu_replay,
+                theme: &theme,
+                theme_id: &theme_id,
+                themes: &themes,
+                cloud: &cloud,
+                telemetry: &telemetry,
+                viewport: &viewport,
+                updates: &updates,
+                options: &options,
+                weekly: &weekly,
+                quests: &quests,
+                particles: &particles,
+                celebration: &celebration,
+                ticks,
+                settings_changed: false,
+                mods_changed: false,
+                request: None,
+            }
+        };
+    }
+
+    loop {
+        if is_quit_requested() || shutdown::interrupted() {
+            break;
+        }
+        perf.next_frame(game.state == GameState::Playing);
+        if let Some(tier) = quality.update(get_frame_time(), settings.quality) {
+            toasts.push(
+                format!("Slow frames: effects set to {}", tier.name()),
+                ORANGE,
+            );
+        }
+        let effects = settings.quality.unwrap_or(quality.tier);
+        particles.quality = effects;
+        // One-switch play takes any key, whatever the profile's scheme
+        let controls = if settings.one_switch {
+            ControlScheme::AnyKey
+        } else {
+            profile.controls
+        };
+        if let Some(fetched) = weekly_fetch.fetched() {
+            weekly = fetched;
+            profile.start_week(weekly.week);
+            // A run in progress keeps its course; the next one gets the new one
+            if challenge == Some(Challenge::Weekly) {
+                game.fixed_seed = Some(weekly.seed);
+                if game.state == GameState::Ready {
+                    game.reset();
+                }
+            }
+        }
+        if cloud.poll() {
+            // Newer saves came down from another device
+            let history = history::load().unwrap_or_else(|_| std::mem::take(&mut profile.history));
+            if let Ok(loaded) = Profile::load() {
+                profile = loaded;
+                profile.start_week(weekly.week);
+            }
+            profile.history = history;
+            toasts.push("Synced saves from the cloud", SKYBLUE);
+        }
+        // Left alone, the results go back to the title screen and that to
+        // the attract demo, closing whatever was open
+        let unattended = kiosk.is_none()
+            && tutorial.is_none()
+            && versus.is_none()
+            && party.is_none()
+            && coop.is_none()
+            && spectator.is_none()
+            && !screens.keeps_awake();
+        let idle_action = if unattended {
+            idle.update(get_frame_time(), &game, settings.idle)
+        } else {
+            None
+        };
+        if matches!(idle_action, Some(IdleAction::ToMenu | IdleAction::Attract)) {
+            screens.close_all(&mut screen_context!());
+        }
+        if idle_action == Some(IdleAction::ToMenu) {
+            death_replay = None;
+            celebration = None;
+            game.reset();
+        }
+        // The run being played alone, under whatever's open over it, or
+        // just what's open while another way to play has the game
+        let playing_alone = kiosk.is_none()
+            && tutorial.is_none()
+            && party.is_none()
+            && coop.is_none()
+            && spectator.is_none()
+            && versus.is_none();
+        let console_was_open = console.open;
+        if idle.attract.is_some() || idle_action == Some(IdleAction::Woke) {
+            // The input that wakes the demo does nothing else
+        } else if screens.is_open() || (playing_alone && death_replay.is_none()) {
+            let mut cx = screen_context!();
+            screens.update(&mut cx, get_frame_time());
+            let (settings_changed, mods_changed) = (cx.settings_changed, cx.mods_changed);
+            let request = cx.request.take();
+            if settings_changed {
+                settings.save();
+                profile.save();
+                if !settings.telemetry {
+                    telemetry.discard();
+                }
+                game.grace = Grace::new(settings.corner_grace);
+                let selected = season::theme_for_today(&settings.theme, settings.seasonal, &themes);
+                if selected != theme_id {
+                    theme_id = selected;
+                    theme = Theme::load(&theme_id).await;
+                    audio = Audio::load(&theme).await;
+                    if !options.mute {
+                        audio.start_ambience();
+                    }
+                }
+                game.masks = settings.pixel_collision.then(|| theme.masks.clone());
+                game.pipe_sizes = theme.pipe_sizes();
+                if (settings.fullscreen, settings.fullscreen_display) != fullscreen {
+                    fullscreen = (settings.fullscreen, settings.fullscreen_display);
+                    window::set_fullscreen(settings.fullscreen, settings.fullscreen_display);
+                }
+            }
+            if mods_changed {
+                themes = Theme::available(&profile.mods);
+                let selected = season::theme_for_today(&settings.theme, settings.seasonal, &themes);
+                if selected != theme_id {
+                    theme_id = selected;
+                    theme = Theme::load(&theme_id).await;
+                    audio = Audio::load(&theme).await;
+                    if !options.mute {
+                        audio.start_ambience();
+                    }
+                    game.masks = settings.pixel_collision.then(|| theme.masks.clone());
+                    game.pipe_sizes = theme.pipe_sizes();
+                }
+            }
+            match request {
+                Some(Request::Quit) => break,
+                Some(Request::Party) => party = Some(Party::new(options.course_seed())),
+                Some(Request::Coop) => coop = Some(Coop::new(options.course_seed())),
+                Some(Request::Online(Online::Play(started))) => versus = Some(started),
+                Some(Request::Online(Online::Watch(started))) => spectator = Some(started),
+                None => {}
+            }
+        } else if let Some(kiosk) = &kiosk {
+            // Single-button play, and only the operator can quit
+            if kiosk.button_pressed(&game) {
+                game.flap();
+            }
+            if Kiosk::operator_exit() {
+                break;
+            }
+        } else if let Some(lesson) = &mut tutorial {
+            // Skipping counts as having seen it
+            if is_key_pressed(KeyCode::Escape) {
+                tutorial = None;
+                profile.tutorial_done = true;
+                profile.save();
+            } else {
+                lesson.input(controls);
+            }
+        } else if let Some(local) = &mut party {
+            // Escape goes back to solo play from anywhere in party mode
+            if is_key_pressed(KeyCode::Escape) {
+                party = None;
+            } else {
+                local.update(get_frame_time());
+            }
+        } else if let Some(tethered) = &mut coop {
+            if is_key_pressed(KeyCode::Escape) {
+                coop = None;
+            } else {
+                tethered.update(get_frame_time());
+            }
+        } else if spectator.is_some() {
+            // Watching is view-only; Escape goes back to the title screen
+            if is_key_pressed(KeyCode::Escape) {
+                spectator = None;
+            }
+        } else if let Some(online) = &mut versus {
+            // Escape leaves the match and goes back to solo play
+            if is_key_pressed(KeyCode::Escape) {
+                versus = None;
+                game.fixed_seed = weekly.course_seed(challenge, &options);
+                game.reset();
+            } else {
+                online.input(&mut game, &mut scripting, controls);
+            }
+        } else if let Some(replay) = &mut death_replay
+            && replay.update(get_frame_time())
+        {
+            death_replay = None;
+        }
+        // After the input, so the key isn't read again by the console as
+        // it opens, or closes it
+        if console::toggle_pressed() && !console_was_open {
+            screens.open(ConsoleScreen);
+        }
+        updates.poll();
+
+        // Assists are for solo play; matches and parties are played straight
+        let solo = versus.is_none() && party.is_none() && coop.is_none() && spectator.is_none();
+        let assists = if solo {
+            settings.assists(&profile.history)
+        } else {
+            Assists::default()
+        };
+        game.set_assists(assists);
+        game.set_mutators(match challenge {
+            _ if !solo => Mutators::default(),
+            Some(Challenge::Weekly) => weekly.mutators,
+            _ => mutators,
+        });
+        game.set_challenge(challenge.filter(|_| solo));
+        let (physics, limits) = match challenge {
+            None if solo => (settings.physics, settings.limits),
+            _ => (Physics::Classic, Limits::default()),
+        };
+        game.set_physics(physics);
+        game.set_limits(limits);
+        timestep.set_speed(assists.game_speed());
+        let update_started = perf::clock();
+        ticks = timestep.ticks(get_frame_time());
+        latency.note_frame(get_frame_time(), ticks);
+        for _ in 0..ticks {
+            if let Some(party) = &mut party {
+                party.tick();
+                continue;
+            }
+            if let Some(tethered) = &mut coop {
+                tethered.tick();
+                continue;
+            }
+            if let Some(lesson) = &mut tutorial {
+                lesson.tick();
+                continue;
+            }
+            game.update();
+            state_buffer.record(&game);
+            if game.state == GameState::Playing {
+                scripting.tick(&mut game);
+                trail.record(&game.bird, SCROLL_SPEED);
+            }
+            if let Some(kiosk) = &mut kiosk
+                && game.state == GameState::Ready
+            {
+                kiosk.tick_demo();
+            }
+            if let Some(demo) = &mut idle.attract {
+                demo.tick();
+            }
+        }
+        if !console.open
+            && let Some(speed) = debug_step::speed_keys(&mut timestep)
+        {
+            toasts.push(format!("Simulation at {speed}x"), ORANGE);
+        }
+        if debug_step::tiles_pressed() {
+            show_tiles = !show_tiles;
+        }
+        if debug_step::step_pressed(&game) {
+            game.step();
+            state_buffer.record(&game);
+        }
+        let collision = std::mem::take(&mut game.collision_seconds);
+        perf.time(Section::Collision, collision);
+        perf.time(Section::Update, perf::clock() - update_started - collision);
+        if let Some(kiosk) = &mut kiosk
+            && kiosk.results_over(&game, get_frame_time())
+        {
+            game.reset();
+        }
+        if let Some(twitch) = &mut twitch {
+            twitch.poll();
+        }
+        if let Some(versus) = &mut versus
+            && let Some(notice) = versus.update(&mut game, &mut scripting, get_frame_time())
+        {
+            toasts.push(notice, WHITE);
+        }
+        if let Some(spectator) = &mut spectator
+            && let Some(notice) = spectator.update(get_frame_time())
+        {
+            toasts.push(notice, WHITE);
+        }
+        if let Some(lesson) = &mut tutorial
+            && lesson.update(get_frame_time())
+        {
+            tutorial = None;
+            profile.tutorial_done = true;
+            profile.save();
+            toasts.push("Press Space to play", WHITE);
+        }
+        // Party, co-op and tutorial runs only make sounds
+        let side_events = match (&mut party, &mut coop, &mut tutorial) {
+            (Some(party), _, _) => party.events(),
+            (None, Some(tethered), _) => tethered.events(),
+            (None, None, Some(lesson)) => lesson.events(),
+            (None, None, None) => Vec::new(),
+        };
+        for event in side_events {
+            if !options.mute {
+                audio.handle(event, 0.0);
+            }
+            captions.handle(event);
+        }
+        for event in game.events.drain() {
+            if !options.mute {
+                audio.handle(event, audio::event_pan(event, &game));
+            }
+            if settings.haptics {
+                haptics.handle(event);
+            }
+            captions.handle(event);
+            if let Some(track) = playlist.handle(event) {
+                toasts.push(format!("Now playing: {track}"), WHITE);
+            }
+            scripting.handle(&mut game, event);
+            presence.handle(event, &game, profile.best_for(&game));
+            event_server.handle(event, &game, profile.best_for(&game));
+            if let Some(versus) = &mut versus
+                && let Some(sent) = versus.handle(event)
+            {
+                toasts.push(sent, ORANGE);
+            }
+            if let Some(twitch) = &mut twitch
+                && let Some(modifier) = twitch.handle(event, &mut game)
+            {
+                toasts.push(format!("Chat picked: {}", modifier.name()), VIOLET);
+            }
+            match event {
+                GameEvent::RunStarted => {
+                    perf.start_run();
+                    announced_best = false;
+                    celebration = None;
+                    trail.clear();
+                    state_buffer.clear();
+                }
+                GameEvent::Collided if kiosk.is_none() && versus.is_none() => {
+                    death_replay = DeathReplay::start(&state_buffer);
+                    if let Some(replay) = Replay::of_run(&game) {
+                        menu_replay = Some(MenuReplay::keep(replay));
+                    }
+                }
+                GameEvent::MilestoneReached { pipes } => {
+                    toasts.push(format!("{pipes}! Keep going!"), GOLD);
+                }
+                GameEvent::PipePassed { points, .. }
+                    if !settings.reduced_motion && effects.popups() =>
+                {
+                    let bird = game.bird.rect();
+                    popups.push(points, vec2(bird.center().x, bird.y - 4.0));
+                }
+                GameEvent::NearMiss if settings.near_miss_slowmo => {
+                    timestep.slow_motion(0.2, 0.3);
+                }
+                GameEvent::BossStarted => toasts.push("Boss incoming!", RED),
+                GameEvent::BiomeReached { biome } => {
+                    let name = biome.name().to_lowercase();
+                    toasts.push(format!("Entering the {name}"), SKYBLUE);
+                }
+                GameEvent::CheckpointReached => toasts.push("Checkpoint", GOLD),
+                GameEvent::Respawned => toasts.push("Back to the checkpoint", WHITE),
+                GameEvent::TriggerFired { pipe } => {
+                    let music = game.level.iter().flat_map(|level| level.triggers_at(pipe));
+                    for trigger in music {
+                        if let Trigger::Music(name) = trigger
+                            && let Some(track) = playlist.cue(name)
+                        {
+                            toasts.push(format!("Now playing: {track}"), WHITE);
+                        }
+                    }
+                }
+                GameEvent::ScoreRolledOver { laps } => {
+                    let points = laps * SCORE_ROLLOVER;
+                    toasts.push(format!("{points} points! Round the counter again"), GOLD);
+                    if !settings.reduced_motion {
+                        let center = vec2(WIDTH / 2.0, 50.0);
+                        for color in [RED, ORANGE, YELLOW, LIME, SKYBLUE, VIOLET] {
+                            particles.burst(center, 24, 260.0, color);
+                        }
+                    }
+                }
+                GameEvent::LevelCompleted => {
+                    toasts.push("Level complete!", GOLD);
+                    let center = game.bird.rect().center();
+                    for color in [GOLD, RED, SKYBLUE, LIME] {
+                        particles.burst(center, 20, 220.0, color);
+                    }
+                }
+                GameEvent::BossBeaten { bonus } => {
+                    toasts.push(format!("Boss beaten! +{bonus}"), GOLD);
+                }
+                GameEvent::ShieldBroken { x, y } => {
+                    particles.burst(vec2(x, y), 24, 160.0, SKYBLUE);
+                }
+                _ => {}
+            }
+            let best = profile.best_for(&game);
+            if !announced_best && best > 0 && game.score > best {
+                announced_best = true;
+                let toast = if game.assists.any() {
+                    "New assisted best!"
+                } else {
+                    "New high score!"
+                };
+                if !settings.streamer_mode {
+                    toasts.push(toast, LIME);
+                }
+                if !options.mute {
+                    audio.announce(Line::NewRecord);
+                }
+            }
+            if announced_best && matches!(event, GameEvent::Collided | GameEvent::LevelCompleted) {
+                celebration = Some(Celebration::default());
+            }
+            // Before the profile's own, which saves it when the run ends.
+            // Streamer mode keeps these toasts off the screen, not out of
+            // the save
+            if kiosk.is_none()
+                && let Some(quest) = quests.handle(event, &game, &mut profile)
+            {
+                if !settings.streamer_mode {
+                    toasts.push(format!("Quest done: +{} coins", quest.reward), GOLD);
+                }
+                session.coins += quest.reward;
+            }
+            session.handle(event, &game);
+            if let Some(achievement) = profile.handle(event, &game) {
+                if !settings.streamer_mode {
+                    toasts.push(format!("Unlocked: {}", achievement.title()), SKYBLUE);
+                }
+                steam.unlock(&[achievement]);
+            }
+            if event == GameEvent::Collided {
+                let run = history::Run::of(&game);
+                telemetry.record(&run, game.assists, settings.telemetry);
+            }
+            // The profile has just been saved
+            if matches!(event, GameEvent::Collided | GameEvent::LevelCompleted) {
+                cloud.request_sync();
+                steam.push_saves();
+            }
+        }
+        // The celebration waits for the results card, after any replay
+        if let Some(cheer) = &mut celebration
+            && game.state == GameState::GameOver
+            && death_replay.is_none()
+            && cheer.update(get_frame_time(), &mut particles, settings.reduced_motion)
+        {
+            if !options.mute {
+                audio.play(Cue::Fanfare);
+            }
+            captions.show(Cue::Fanfare);
+        }
+        presence.update(get_frame_time());
+        steam.update();
+        telemetry.poll();
+        haptics.update();
+        window_state.update(get_frame_time(), settings.fullscreen);
+        let run_over = game.state == GameState::GameOver || death_replay.is_some();
+        audio.update(get_frame_time(), run_over);
+        playlist.update(get_frame_time(), audio.speaking());
+        playlist.stream(game.state != GameState::Playing).await;
+        // They're moved on another thread while the frame is drawn, and
+        // held still while paused, for photos
+        if game.state != GameState::Paused {
+            particles.update(get_frame_time());
+        }
+
+        // Render
+        let render_started = perf::clock();
+        viewport.fit(settings.wide_view);
+        game.layout = viewport.layout();
+        game.streamer_mode = settings.streamer_mode;
+        game.blend = if settings.interpolation {
+            timestep.blend()
+        } else {
+            1.0
+        };
+        viewport.begin();
+        clear_background(theme.sky_color);
+        theme.draw_background(game.scrolled());
+        if show_tiles {
+            theme.backdrop.draw_tiles(game.scrolled());
+        }
+        if settings.trail
+            && effects.trail()
+            && !settings.reduced_motion
+            && game.state == GameState::Playing
+        {
+            trail.draw(&theme, game.look);
+        }
+        match &kiosk {
+            Some(kiosk) if game.state == GameState::Ready => kiosk.draw_demo(&theme),
+            _ if idle.attract.is_some() => {
+                if let Some(demo) = &idle.attract {
+                    demo.draw(&theme);
+                }
+            }
+            _ if tutorial.is_some() => {
+                if let Some(lesson) = &tutorial {
+                    lesson.draw(&theme);
+                }
+            }
+            _ if party.is_some() => {
+                if let Some(party) = &party {
+                    party.draw(&theme);
+                }
+            }
+            _ if coop.is_some() => {
+                if let Some(tethered) = &coop {
+                    tethered.draw(&theme);
+                }
+            }
+            _ if death_replay.is_some() => {
+                if let Some(replay) = &death_replay {
+                    replay.draw(&viewport, &theme);
+                }
+            }
+            _ if spectator.is_some() => {
+                if let Some(spectator) = &spectator {
+                    spectator.draw(&theme);
+                }
+            }
+            _ if kiosk.is_some() || versus.is_some() => {
+                if let Some(versus) = &versus {
+                    versus.draw_opponent(&theme);
+                }
+                gameplay::draw_run(&game, &theme, &settings, ticks);
+                match &versus {
+                    Some(versus) => versus.draw_hud(&theme, settings.streamer_mode),
+                    None => game.draw_prompt(&theme),
+                }
+                if versus.is_some() && matches!(game.state, GameState::Playing | GameState::Paused)
+                {
+                    quests.draw_chip(&profile, &theme);
+                }
+                if let Some(cheer) = &celebration
+                    && game.state == GameState::GameOver
+                {
+                    cheer.draw(settings.reduced_motion);
+                }
+            }
+            _ => screens.draw_bottom(&screen_context!()),
+        }
+        // Including any wait for the particle thread
+        let particles_started = perf::clock();
+        particles.draw();
+        let particles_seconds = perf::clock() - particles_started;
+        popups.update(get_frame_time());
+        popups.draw(theme.text_color);
+        captions.update(get_frame_time());
+        if settings.captions {
+            captions.draw(theme.text_color);
+        }
+        if let Some(twitch) = &twitch {
+            twitch.draw(theme.text_color);
+        }
+        screens.draw_over(&screen_context!());
+
+        // N skips the track, except while a code is being typed
+        if !screens.reads_text()
+            && is_key_pressed(KeyCode::N)
+            && let Some(track) = playlist.skip()
+        {
+            toasts.push(format!("Now playing: {track}"), WHITE);
+        }
+
+        if is_key_pressed(KeyCode::F12) {
+            match screenshot::save(&viewport.capture()) {
+                Ok(path) => toasts.push(format!("Saved {path}"), WHITE),
+                Err(err) => toasts.push(format!("Screenshot failed: {err}"), RED),
+            }
+        }
+
+        if is_key_pressed(KeyCode::F3) {
+            perf.overlay = !perf.overlay;
+        }
+
+        // Toasts run independently of the game state
+        toasts.update(get_frame_time());
+        toasts.draw();
+        let (streamed, tracks) = playlist.streamed();
+        perf.draw(&format!(
+            "textures {}, music {streamed}/{tracks} in, effects {}",
+            perf::megabytes(theme.texture_bytes()),
+            effects.name()
+        ));
+        debug_step::draw_speed(&timestep);
+
+        if kiosk.is_none() && (screens.is_open() || game.state != GameState::Playing) {
+            cursor::draw(&theme, viewport.mouse_position());
+        }
+        let grade = if effects.grading() {
+            game.grade(&theme)
+        } else {
+            Grade::default()
+        };
+        viewport.end(&theme, grade);
+        perf.time(Section::Particles, particles_seconds);
+        perf.time(
+            Section::Draw,
+            perf::clock() - render_started - particles_seconds,
+        );
+
+        next_frame().await;
+    }
+
+    // A run cut short isn't recorded, but the time in it was still played
+    game.abandon_run();
+    for event in game.events.drain() {
+        if kiosk.is_none()
+            && let Some(quest) = quests.handle(event, &game, &mut profile)
+        {
+            session.coins += quest.reward;
+        }
+        session.handle(event, &game);
+        if let Some(achievement) = profile.handle(event, &game) {
+            steam.unlock(&[achievement]);
+        }
+    }
+    if kiosk.is_none() && !shutdown::interrupted() {
+        session.show_summary(&viewport, &theme).await;
+    }
+    shutdown::run(&mut [
+        &mut profile,
+        &mut settings,
+        &mut window_state,
+        &mut telemetry,
+        &mut cloud,
+        &mut steam,
+        &mut presence,
+        &mut audio,
+        &mut playlist,
+    ]);
+    if options.perf_report {
+        print!("{}", perf.report());
+    }
+}
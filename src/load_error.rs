@@ -0,0 +1,55 @@
+// Why a file couldn't be loaded. Every loader (levels, replays, saves and
+// settings) returns one of these for bad input instead of panicking, so a
+// damaged or half-written file only ever costs what was in it. The title
+// screen shows them as toasts.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Missing,
+    // The file is there but reading it failed
+    Unreadable(io::Error),
+    // Not UTF-8, as when it has been cut off mid-character or overwritten
+    NotText,
+    // Read fine, but what it says doesn't make sense
+    Invalid(String),
+}
+
+impl LoadError {
+    pub fn invalid(what: impl Into<String>) -> LoadError {
+        LoadError::Invalid(what.into())
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> LoadError {
+        match err.kind() {
+            io::ErrorKind::NotFound => LoadError::Missing,
+            io::ErrorKind::InvalidData => LoadError::NotText,
+            _ => LoadError::Unreadable(err),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Missing => write!(f, "not found"),
+            LoadError::Unreadable(err) => write!(f, "couldn't be read ({err})"),
+            LoadError::NotText => write!(f, "isn't a text file"),
+            LoadError::Invalid(what) => write!(f, "{what}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Unreadable(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// Online play menu, opened from the title screen with O: host a game and
+// get a join code, type in someone else's to play or watch, or pick one of
+// the games hosted nearby. The match itself, including readying up, lives in
+// `versus.rs`, and watching in `spectator.rs`.
+
+use std::io;
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::discovery::Discovery;
+use crate::game::draw_centered_text;
+use crate::net;
+use crate::screen::{Context, Request, Screen, Transition};
+use crate::spectator::Spectator;
+use crate::versus::{Course, Versus};
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+
+// Tried first so join codes stay the same between sessions
+const HOST_PORT: u16 = 47_100;
+// Dashes are left out while typing
+const MAX_CODE_LENGTH: usize = 10;
+
+// Rows of the first screen, followed by one per nearby game
+const HOST_ROW: usize = 0;
+const JOIN_ROW: usize = 1;
+const WATCH_ROW: usize = 2;
+const FIRST_GAME_ROW: usize = 3;
+const ROW_LABELS: [&str; FIRST_GAME_ROW] = ["Host a game", "Join with a code", "Watch with a code"];
+
+/// What the menu started.
+// Only held for a moment on its way out of the menu, so the size gap
+// between a versus match and a spectator doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum Online {
+    Play(Versus),
+    Watch(Spectator),
+}
+
+enum Page {
+    Choose(usize),
+    EnterCode { code: String, watch: bool },
+}
+
+pub struct Lobby {
+    page: Page,
+    // Listens while the menu is open
+    nearby: Option<Discovery>,
+    error: Option<String>,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Lobby {
+            page: Page::Choose(HOST_ROW),
+            nearby: None,
+            error: None,
+        }
+    }
+}
+
+impl Screen for Lobby {
+    /// Handles menu input. Once hosting, joining or watching has started,
+    /// the menu closes and hands the match to the main loop.
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        let (options, mode) = (cx.options, cx.scripting.active_name());
+        if self.nearby.is_none() {
+            self.nearby = Discovery::listen();
+        }
+        if let Some(nearby) = &mut self.nearby {
+            nearby.update(get_frame_time());
+        }
+        let games = self.nearby.as_ref().map_or(&[][..], |n| &n.games);
+
+        let started: io::Result<Online> = match &mut self.page {
+            Page::Choose(selected) => {
+                if is_key_pressed(KeyCode::Escape) {
+                    return Transition::Pop;
+                }
+                let rows = FIRST_GAME_ROW + games.len();
+                // Games that stopped hosting can take the selection with them
+                *selected = (*selected).min(rows - 1);
+                if is_key_pressed(KeyCode::Up) {
+                    *selected = (*selected + rows - 1) % rows;
+                }
+                if is_key_pressed(KeyCode::Down) {
+                    *selected = (*selected + 1) % rows;
+                }
+                if !(is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space)) {
+                    return Transition::Stay;
+                }
+                match *selected {
+                    HOST_ROW => {
+                        let course = Course::from_options(options);
+                        Versus::host(HOST_PORT, course, mode)
+                            .or_else(|_| Versus::host(0, course, mode))
+                            .map(Online::Play)
+                    }
+                    JOIN_ROW | WATCH_ROW => {
+                        self.page = Page::EnterCode {
+                            code: String::new(),
+                            watch: *selected == WATCH_ROW,
+                        };
+                        self.error = None;
+                        return Transition::Stay;
+                    }
+                    // Full games can still be watched
+                    row => {
+                        let game = &games[row - FIRST_GAME_ROW];
+                        let address = game.address.to_string();
+                        if game.open {
+                            Versus::join(&address).map(Online::Play)
+                        } else {
+                            Spectator::watch(&address).map(Online::Watch)
+                        }
+                    }
+                }
+            }
+            Page::EnterCode { code, watch } => {
+                // Read typed characters every frame so they don't pile up
+                while let Some(c) = get_char_pressed() {
+                    if c.is_ascii_alphanumeric() && code.len() < MAX_CODE_LENGTH {
+                        code.push(c.to_ascii_uppercase());
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    code.pop();
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    let row = if *watch { WATCH_ROW } else { JOIN_ROW };
+                    self.page = Page::Choose(row);
+                    return Transition::Stay;
+                }
+                if !is_key_pressed(KeyCode::Enter) || code.is_empty() {
+                    return Transition::Stay;
+                }
+                if net::parse_join_code(code).is_none() {
+                    self.error = Some("That isn't a join code".to_owned());
+                    return Transition::Stay;
+                }
+                if *watch {
+                    Spectator::watch(code).map(Online::Watch)
+                } else {
+                    Versus::join(code).map(Online::Play)
+                }
+            }
+        };
+        match started {
+            Ok(online) => {
+                cx.request = Some(Request::Online(online));
+                Transition::Pop
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                Transition::Stay
+            }
+        }
+    }
+
+    fn draw(&self, _cx: &Context) {
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Play online", 60.0, 32.0, WHITE);
+
+        match &self.page {
+            Page::Choose(selected) => {
+                for (i, label) in ROW_LABELS.iter().enumerate() {
+                    let color = if i == *selected { YELLOW } else { WHITE };
+                    draw_centered_text(label, 110.0 + i as f32 * 30.0, 20.0, color);
+                }
+
+                draw_centered_text("Nearby games", 220.0, 16.0, GRAY);
+                let games = self.nearby.as_ref().map_or(&[][..], |n| &n.games);
+                if games.is_empty() {
+                    let note = if self.nearby.is_some() {
+                        "Looking..."
+                    } else {
+                        "Can't search from here"
+                    };
+                    draw_centered_text(note, 244.0, 16.0, GRAY);
+                }
+                for (i, game) in games.iter().enumerate() {
+                    let color = if FIRST_GAME_ROW + i == *selected {
+                        YELLOW
+                    } else {
+                        WHITE
+                    };
+                    let action = if game.open { "" } else { " (watch)" };
+                    let label = format!("{} at {}{action}", game.mode, game.address.ip());
+                    draw_centered_text(&label, 244.0 + i as f32 * 20.0, 16.0, color);
+                }
+                draw_centered_text("Enter to pick, Esc to close", HEIGHT - 20.0, 16.0, GRAY);
+            }
+            Page::EnterCode { code, watch } => {
+                let title = if *watch {
+                    "Code of the game to watch"
+                } else {
+                    "Join code"
+                };
+                draw_centered_text(title, 120.0, 20.0, WHITE);
+                // A blinking caret shows where typing goes
+                let caret = if get_time().fract() < 0.5 { "_" } else { " " };
+                draw_centered_text(&format!("{code}{caret}"), 160.0, 32.0, YELLOW);
+                draw_centered_text("Enter to go, Esc to go back", HEIGHT - 20.0, 16.0, GRAY);
+            }
+        }
+
+        if let Some(error) = &self.error {
+            draw_centered_text(error, HEIGHT - 60.0, 14.0, RED);
+        }
+    }
+
+    fn reads_text(&self) -> bool {
+        matches!(self.page, Page::EnterCode { .. })
+    }
+
+    // Looking for nearby games goes on without any input
+    fn keeps_awake(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,85 @@
+// Alpha masks for pixel-perfect collision: which pixels of a sprite are
+// solid, packed into bits when the theme loads
+
+use macroquad::prelude::*;
+
+use crate::pipes::PipeStyle;
+
+// Pixels at least this opaque are solid
+const ALPHA_THRESHOLD: u8 = 128;
+
+pub struct Mask {
+    width: i32,
+    height: i32,
+    // Rows of `words_per_row` 64-bit words, lowest bit leftmost
+    words_per_row: usize,
+    bits: Vec<u64>,
+}
+
+// Masks for the sprites the bird can crash into
+pub struct SpriteMasks {
+    pub bird: Mask,
+    // One for each `PipeStyle`
+    pub pipes: [Mask; PipeStyle::ALL.len()],
+}
+
+impl SpriteMasks {
+    pub fn pipe(&self, style: PipeStyle) -> &Mask {
+        &self.pipes[style.index()]
+    }
+}
+
+impl Mask {
+    pub fn from_image(image: &Image) -> Mask {
+        let (width, height) = (usize::from(image.width), usize::from(image.height));
+        let words_per_row = width.div_ceil(64);
+        let mut bits = vec![0; words_per_row * height];
+        for y in 0..height {
+            for x in 0..width {
+                if image.bytes[(y * width + x) * 4 + 3] >= ALPHA_THRESHOLD {
+                    bits[y * words_per_row + x / 64] |= 1 << (x % 64);
+                }
+            }
+        }
+        Mask {
+            width: width as i32,
+            height: height as i32,
+            words_per_row,
+            bits,
+        }
+    }
+
+    /// The sprite's size in pixels.
+    pub fn size(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32)
+    }
+
+    fn solid(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return false;
+        }
+        let (x, y) = (x as usize, y as usize);
+        self.bits[y * self.words_per_row + x / 64] >> (x % 64) & 1 == 1
+    }
+
+    /// Whether this sprite drawn at `at` and `other` drawn at `other_at`
+    /// share a solid pixel. `flip_other` is for sprites drawn upside down,
+    /// like the top pipe.
+    pub fn overlaps(&self, at: Vec2, other: &Mask, other_at: Vec2, flip_other: bool) -> bool {
+        let (ax, ay) = (at.x.round() as i32, at.y.round() as i32);
+        let (bx, by) = (other_at.x.round() as i32, other_at.y.round() as i32);
+        for y in ay.max(by)..(ay + self.height).min(by + other.height) {
+            let other_y = if flip_other {
+                other.height - 1 - (y - by)
+            } else {
+                y - by
+            };
+            for x in ax.max(bx)..(ax + self.width).min(bx + other.width) {
+                if self.solid(x - ax, y - ay) && other.solid(x - bx, other_y) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,58 @@
+// Medals shown on the game over screen for the run's score, as in the
+// original game
+
+use macroquad::prelude::*;
+
+use crate::theme::Theme;
+
+pub const MEDAL_SIZE: f32 = 24.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    // Best first, with the score each needs
+    const THRESHOLDS: [(Medal, i64); 4] = [
+        (Medal::Platinum, 40),
+        (Medal::Gold, 30),
+        (Medal::Silver, 20),
+        (Medal::Bronze, 10),
+    ];
+
+    pub fn for_score(score: i64) -> Option<Medal> {
+        Medal::THRESHOLDS
+            .iter()
+            .find(|&&(_, needed)| score >= needed)
+            .map(|&(medal, _)| medal)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Medal::Bronze => "Bronze",
+            Medal::Silver => "Silver",
+            Medal::Gold => "Gold",
+            Medal::Platinum => "Platinum",
+        }
+    }
+
+    /// Draws the medal centered on `center`, shining now and then.
+    pub fn draw(self, theme: &Theme, center: Vec2) {
+        let dest = Rect::new(
+            center.x - MEDAL_SIZE / 2.0,
+            center.y - MEDAL_SIZE / 2.0,
+            MEDAL_SIZE,
+            MEDAL_SIZE,
+        );
+        // The sheet has a row for each medal, bronze first
+        let rows = Medal::THRESHOLDS.len() as u32;
+        theme
+            .animations
+            .medal
+            .draw(&theme.medal, self as u32, rows, get_time(), dest, WHITE);
+    }
+}
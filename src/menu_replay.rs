@@ -0,0 +1,124 @@
+// A miniature of the player's last run on the title screen, flown again
+// from its replay (see `replay.rs`) over and over in a small frame. The
+// replay is kept as `last_run.replay`, so it's there at the next launch.
+// Runs a replay can't fly the same aren't shown; the one before stays up.
+// R opens it full size in the replay viewer (see `replay_viewer.rs`).
+
+use macroquad::prelude::*;
+
+use crate::replay::{Playback, Replay};
+use crate::storage;
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::viewport::Viewport;
+use crate::{HEIGHT, WIDTH};
+
+const LAST_RUN_FILE: &str = "last_run.replay";
+// Of the whole screen
+const SCALE: f32 = 0.3;
+// The crash stays up this long before the run starts over
+const HOLD_SECONDS: f32 = 1.0;
+const BORDER: f32 = 2.0;
+
+pub struct MenuReplay {
+    replay: Replay,
+    playback: Playback,
+    // Real time not yet run as updates, or held on the crash
+    time: f32,
+    target: RenderTarget,
+}
+
+impl MenuReplay {
+    /// The last run kept from before, if there is one this version can fly.
+    pub fn load() -> Option<MenuReplay> {
+        let text = storage::load_save(LAST_RUN_FILE).ok().flatten()?;
+        let replay = Replay::parse(text.as_bytes())
+            .inspect_err(|err| eprintln!("Could not load {LAST_RUN_FILE}: {err}"))
+            .ok()?;
+        replay.is_current().then(|| MenuReplay::new(replay))
+    }
+
+    /// Shows `replay` from now on, and keeps it for next time.
+    pub fn keep(replay: Replay) -> MenuReplay {
+        if let Err(err) = storage::backend().save_text(LAST_RUN_FILE, &replay.to_text()) {
+            eprintln!("Could not save the last run: {err}");
+        }
+        MenuReplay::new(replay)
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    fn new(replay: Replay) -> MenuReplay {
+        let target = render_target(WIDTH as u32, HEIGHT as u32);
+        target.texture.set_filter(FilterMode::Linear);
+        MenuReplay {
+            playback: replay.play(),
+            replay,
+            time: 0.0,
+            target,
+        }
+    }
+
+    /// Moves the run on by `dt` seconds of real time, starting it over a
+    /// moment after it ends.
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        while self.time >= TICK_SECONDS {
+            if !self.playback.step() {
+                if self.time >= HOLD_SECONDS {
+                    self.playback = self.replay.play();
+                    self.time = 0.0;
+                }
+                return;
+            }
+            self.time -= TICK_SECONDS;
+        }
+    }
+
+    /// Draws the run in a frame centered on `center`, going back to
+    /// `viewport` afterwards.
+    pub fn draw(&self, viewport: &Viewport, theme: &Theme, center: Vec2) {
+        // Positive y zoom keeps render targets upright, as in `Viewport`
+        set_camera(&Camera2D {
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0),
+            zoom: vec2(2.0 / WIDTH, 2.0 / HEIGHT),
+            render_target: Some(self.target.clone()),
+            ..Default::default()
+        });
+        clear_background(theme.sky_color);
+        theme.draw_background(self.playback.game().scrolled());
+        self.playback.game().draw_world(theme);
+
+        viewport.begin();
+        let size = vec2(WIDTH, HEIGHT) * SCALE;
+        let corner = center - size / 2.0;
+        draw_rectangle(
+            corner.x - BORDER,
+            corner.y - BORDER,
+            size.x + BORDER * 2.0,
+            size.y + BORDER * 2.0,
+            theme.text_color,
+        );
+        draw_texture_ex(
+            &self.target.texture,
+            corner.x,
+            corner.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(size),
+                ..Default::default()
+            },
+        );
+        let label = format!("Last run: {}  (R to watch)", self.replay.score());
+        let text = measure_text(&label, None, 12, 1.0);
+        draw_text(
+            &label,
+            center.x - text.width / 2.0,
+            corner.y + size.y + 14.0,
+            12.0,
+            theme.text_color,
+        );
+    }
+}
@@ -0,0 +1,326 @@
+// Mod manager, opened from the title screen with G: every game mode script
+// in `mods/` and theme pack in `themes/`, each of which can be turned off
+// or moved up and down the load order. Load order is the order modes come
+// up in on the title screen and themes in the settings menu. Mods that
+// failed to load say why. What's picked is kept in the profile:
+//
+//     mod_order = mode:moon, theme:winter, theme:halloween
+//     mods_disabled = theme:halloween
+//
+// Mods not in `mod_order` yet, like ones just dropped in, go on the end.
+// Packages made with `--pack-mod` and put in `mods/` are listed after the
+// mods, to be installed with Space.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+
+use crate::game::draw_centered_text;
+use crate::kv::KeyValues;
+use crate::mod_pack::{self, Package};
+use crate::screen::{Context, Screen, Transition};
+use crate::scripting::{self, Scripting};
+use crate::theme::Theme;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+const VISIBLE_ROWS: usize = 9;
+const ROW_SPACING: f32 = 26.0;
+const FIRST_ROW_Y: f32 = 106.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ModKind {
+    // A `.rhai` game mode, see `scripting.rs`
+    Mode,
+    // A theme pack, see `theme.rs`
+    Theme,
+}
+
+impl ModKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            ModKind::Mode => "mode",
+            ModKind::Theme => "theme",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ModKind::Mode => "Mode",
+            ModKind::Theme => "Theme",
+        }
+    }
+}
+
+/// The id a mod is saved under, like `mode:moon`.
+pub fn mod_id(kind: ModKind, name: &str) -> String {
+    format!("{}:{name}", kind.prefix())
+}
+
+/// Load order and which mods are turned off, saved in the profile.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ModList {
+    order: Vec<String>,
+    disabled: Vec<String>,
+}
+
+impl ModList {
+    pub fn from_values(kv: &KeyValues) -> ModList {
+        let ids = |key| -> Vec<String> {
+            kv.get(key)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_owned)
+                .collect()
+        };
+        ModList {
+            order: ids("mod_order"),
+            disabled: ids("mods_disabled"),
+        }
+    }
+
+    pub fn write_values(&self, kv: &mut KeyValues) {
+        kv.set("mod_order", self.order.join(", "));
+        kv.set("mods_disabled", self.disabled.join(", "));
+    }
+
+    pub fn is_enabled(&self, id: &str) -> bool {
+        !self.disabled.iter().any(|disabled| disabled == id)
+    }
+
+    /// Sorts `found` into load order, `key` giving each one's id. Ones in
+    /// the saved order keep their place; the rest follow as they came.
+    pub fn arrange<T>(&self, found: &mut [T], key: impl Fn(&T) -> String) {
+        found.sort_by_key(|item| {
+            let id = key(item);
+            self.order
+                .iter()
+                .position(|placed| *placed == id)
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    fn toggle(&mut self, id: &str) {
+        match self.disabled.iter().position(|disabled| disabled == id) {
+            Some(index) => {
+                self.disabled.remove(index);
+            }
+            None => self.disabled.push(id.to_owned()),
+        }
+    }
+}
+
+// A mod as listed on the screen
+struct Entry {
+    id: String,
+    kind: ModKind,
+    name: String,
+    // Why it didn't load, if it didn't
+    error: Option<String>,
+    // A package in `mods/` still to be installed, see `mod_pack.rs`
+    package: Option<PathBuf>,
+}
+
+#[derive(Default)]
+pub struct ModManager {
+    selected: usize,
+    // In load order
+    entries: Vec<Entry>,
+}
+
+impl ModManager {
+    /// The screen on what's installed right now, with the errors
+    /// `scripting` and the theme manifests ran into.
+    pub fn new(mods: &ModList, scripting: &Scripting) -> ModManager {
+        let mut manager = ModManager::default();
+        manager.list(mods, scripting);
+        manager
+    }
+
+    fn list(&mut self, mods: &ModList, scripting: &Scripting) {
+        let modes = scripting::mode_files(scripting::MODS_DIR)
+            .into_iter()
+            .map(|(name, _)| Entry {
+                id: mod_id(ModKind::Mode, &name),
+                kind: ModKind::Mode,
+                error: scripting.error_for(&name).map(str::to_owned),
+                name,
+                package: None,
+            });
+        let themes = Theme::installed().into_iter().map(|theme| Entry {
+            id: mod_id(ModKind::Theme, &theme.id),
+            kind: ModKind::Theme,
+            name: theme.name,
+            error: theme.error,
+            package: None,
+        });
+        self.entries = modes.chain(themes).collect();
+        mods.arrange(&mut self.entries, |entry| entry.id.clone());
+        // Packages go after everything installed, outside the load order
+        let packages = mod_pack::waiting().into_iter().map(|path| Entry {
+            id: String::new(),
+            kind: ModKind::Mode,
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            error: None,
+            package: Some(path),
+        });
+        self.entries.extend(packages);
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    // Returns true when `mods` changed, so the game should load its modes
+    // and themes again
+    fn change(&mut self, mods: &mut ModList) -> bool {
+        let count = self.entries.len();
+        if count == 0 {
+            return false;
+        }
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let step = if is_key_pressed(KeyCode::Up) {
+            -1
+        } else if is_key_pressed(KeyCode::Down) {
+            1
+        } else {
+            0
+        };
+        let next = (self.selected as i32 + step).rem_euclid(count as i32) as usize;
+        if shift && step != 0 {
+            // Moves the selected mod rather than the selection, without
+            // wrapping around or passing the packages
+            let installed = self.entries.iter().filter(|e| e.package.is_none()).count();
+            let moved = (self.selected as i32 + step).clamp(0, installed as i32 - 1) as usize;
+            if moved == self.selected || self.selected >= installed {
+                return false;
+            }
+            self.entries.swap(self.selected, moved);
+            self.selected = moved;
+            mods.order = self.entries.iter().map(|entry| entry.id.clone()).collect();
+            return true;
+        }
+        self.selected = next;
+        let toggled = is_key_pressed(KeyCode::Space)
+            || is_key_pressed(KeyCode::Enter)
+            || is_key_pressed(KeyCode::Left)
+            || is_key_pressed(KeyCode::Right);
+        if !toggled {
+            return false;
+        }
+        let entry = &mut self.entries[self.selected];
+        match &entry.package {
+            Some(path) => match install(path) {
+                Ok(id) => {
+                    // A reinstalled mod that had been turned off stays off
+                    if !mods.order.contains(&id) {
+                        mods.order.push(id);
+                    }
+                    true
+                }
+                Err(err) => {
+                    entry.error = Some(err);
+                    false
+                }
+            },
+            None => {
+                mods.toggle(&entry.id);
+                true
+            }
+        }
+    }
+}
+
+impl Screen for ModManager {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::G) {
+            return Transition::Pop;
+        }
+        if self.change(&mut cx.profile.mods) {
+            cx.profile.save();
+            let active = cx.scripting.active_name().to_owned();
+            *cx.scripting = Scripting::new();
+            cx.scripting
+                .load_mods(scripting::MODS_DIR, &cx.profile.mods);
+            cx.scripting.select(&active);
+            // Brings in the errors of any mod just turned on
+            self.list(&cx.profile.mods, cx.scripting);
+            cx.mods_changed = true;
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        let mods = &cx.profile.mods;
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Mods", 60.0, 32.0, WHITE);
+        if self.entries.is_empty() {
+            draw_centered_text("Nothing in mods/ or themes/", HEIGHT / 2.0, 16.0, GRAY);
+        }
+
+        let first = self
+            .selected
+            .saturating_sub(VISIBLE_ROWS / 2)
+            .min(self.entries.len().saturating_sub(VISIBLE_ROWS));
+        let rows = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_ROWS);
+        for (i, entry) in rows {
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            let y = FIRST_ROW_Y + (i - first) as f32 * ROW_SPACING;
+            let kind = match entry.package {
+                Some(_) => "Package",
+                None => entry.kind.name(),
+            };
+            draw_text(kind, 20.0, y, 14.0, GRAY);
+            draw_text(&entry.name, 70.0, y, 20.0, color);
+            let value = match (&entry.package, mods.is_enabled(&entry.id), &entry.error) {
+                (Some(_), _, _) => "< Install >",
+                (None, false, _) => "< Off >",
+                (None, true, Some(_)) => "< Failed >",
+                (None, true, None) => "< On >",
+            };
+            let size = measure_text(value, None, 20, 1.0);
+            draw_text(value, WIDTH - 20.0 - size.width, y, 20.0, color);
+        }
+
+        if let Some(error) = self
+            .entries
+            .get(self.selected)
+            .and_then(|e| e.error.as_ref())
+        {
+            let y = FIRST_ROW_Y + VISIBLE_ROWS as f32 * ROW_SPACING;
+            draw_text(error, 20.0, y, 12.0, RED);
+        }
+        draw_centered_text(
+            "Space: on/off  Shift+Up/Down: order  G/Esc: close",
+            HEIGHT - 20.0,
+            16.0,
+            GRAY,
+        );
+    }
+}
+
+// Installs the package at `path`, then removes it from `mods/`
+fn install(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    let package = Package::parse(&bytes).map_err(|err| err.to_string())?;
+    let id = package.install().map_err(|err| err.to_string())?;
+    if let Err(err) = fs::remove_file(path) {
+        eprintln!("Could not remove {}: {err}", path.display());
+    }
+    Ok(id)
+}
@@ -0,0 +1,405 @@
+// Mod packages: a mod folder checked over and rolled into one
+// `.flappymod` file for sharing, made with `--pack-mod <dir>`. Dropping
+// one in `mods/` lists it in the mod manager, which installs it.
+//
+// The folder needs a `mod.cfg` manifest:
+//
+//     id = moon_walk
+//     name = Moon Walk
+//     version = 1.2
+//     kind = mode
+//     author = sam
+//
+// `kind` is `theme` for a theme pack, whose files are the ones `theme.rs`
+// knows about and install as `themes/<id>/`, or `mode` for a game mode,
+// one `.rhai` script that installs as `mods/<id>.rhai`. Packing fails on
+// anything that wouldn't load the way it's meant to: unknown manifest keys,
+// files the game wouldn't look at, images that don't decode, sounds that
+// aren't WAV files, colors it can't read and scripts that don't compile.
+//
+// The package is a line of text per file, giving its size and path,
+// followed by its bytes:
+//
+//     flappymod 1
+//     file 52 mod.cfg
+//     <52 bytes>
+//     file 1834 bird.png
+//     <1834 bytes>
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::animation::{ANIMATIONS_FILE, Animations};
+use crate::audio;
+use crate::cosmetics::{self, ANCHOR_KEYS, ANCHORS_FILE};
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::mod_manager::{ModKind, mod_id};
+use crate::scripting::{MODS_DIR, Scripting};
+use crate::theme::{self, THEMES_DIR};
+
+pub const PACKAGE_EXTENSION: &str = "flappymod";
+const HEADER: &str = "flappymod 1";
+const MANIFEST_FILE: &str = "mod.cfg";
+const MANIFEST_KEYS: [&str; 6] = ["id", "name", "version", "kind", "author", "description"];
+const MAX_ID_LENGTH: usize = 32;
+// Packages bigger than this are refused, so a bad size can't eat memory
+const MAX_PACKAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// A mod's files, by path inside its folder, checked over.
+pub struct Package {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    kind: ModKind,
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl Package {
+    /// Reads and checks the mod folder `dir`. Every problem found comes
+    /// back, not just the first.
+    pub fn from_dir(dir: &Path) -> Result<Package, Vec<String>> {
+        let mut files = Vec::new();
+        read_dir_into(dir, "", &mut files)
+            .map_err(|err| vec![format!("{}: {err}", dir.display())])?;
+        Package::check(files)
+    }
+
+    /// Reads a `.flappymod` file's contents.
+    pub fn parse(bytes: &[u8]) -> Result<Package, LoadError> {
+        if bytes.len() > MAX_PACKAGE_BYTES {
+            return Err(LoadError::invalid("package is too big"));
+        }
+        let mut rest = bytes
+            .strip_prefix(HEADER.as_bytes())
+            .and_then(|rest| rest.strip_prefix(b"\n"))
+            .ok_or_else(|| LoadError::invalid("not a mod package"))?;
+        let mut files = Vec::new();
+        while !rest.is_empty() {
+            let end = rest
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .ok_or_else(|| LoadError::invalid("package is cut short"))?;
+            let line = std::str::from_utf8(&rest[..end]).map_err(|_| LoadError::NotText)?;
+            let (size, path) = line
+                .strip_prefix("file ")
+                .and_then(|line| line.split_once(' '))
+                .ok_or_else(|| LoadError::invalid(format!("bad file entry {line:?}")))?;
+            let size: usize = size
+                .parse()
+                .map_err(|_| LoadError::invalid(format!("bad size for {path}")))?;
+            rest = &rest[end + 1..];
+            if size > rest.len() {
+                return Err(LoadError::invalid(format!("{path} is cut short")));
+            }
+            files.push((path.to_owned(), rest[..size].to_vec()));
+            rest = &rest[size..];
+        }
+        Package::check(files).map_err(|problems| LoadError::Invalid(problems.join("; ")))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!("{HEADER}\n").into_bytes();
+        for (path, contents) in &self.files {
+            bytes.extend(format!("file {} {path}\n", contents.len()).into_bytes());
+            bytes.extend(contents);
+        }
+        bytes
+    }
+
+    /// Writes the mod where the game looks for its kind, over any older
+    /// copy. Returns its mod manager id.
+    pub fn install(&self) -> std::io::Result<String> {
+        match self.kind {
+            ModKind::Theme => {
+                let dir = Path::new(THEMES_DIR).join(&self.id);
+                for (path, contents) in &self.files {
+                    let path = dir.join(path);
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(path, contents)?;
+                }
+            }
+            ModKind::Mode => {
+                fs::create_dir_all(MODS_DIR)?;
+                let script = self.files.iter().find(|(path, _)| path.ends_with(".rhai"));
+                if let Some((_, source)) = script {
+                    fs::write(
+                        Path::new(MODS_DIR).join(format!("{}.rhai", self.id)),
+                        source,
+                    )?;
+                }
+            }
+        }
+        Ok(mod_id(self.kind, &self.id))
+    }
+
+    // Everything that makes a mod folder one the game can load
+    fn check(mut files: Vec<(String, Vec<u8>)>) -> Result<Package, Vec<String>> {
+        files.sort();
+        let mut problems = Vec::new();
+        // Nothing gets to be installed outside the mod's own folder
+        let part = |part: &str| {
+            !part.is_empty()
+                && !part.starts_with('.')
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+        };
+        for (path, _) in &files {
+            if !path.split('/').all(part) {
+                problems.push(format!("{path:?} isn't a plain file name"));
+            }
+        }
+        let manifest = match files.iter().find(|(path, _)| path == MANIFEST_FILE) {
+            Some((_, contents)) => KeyValues::from_bytes(contents).unwrap_or_else(|err| {
+                problems.push(format!("{MANIFEST_FILE} {err}"));
+                KeyValues::default()
+            }),
+            None => {
+                problems.push(format!("{MANIFEST_FILE} is missing"));
+                KeyValues::default()
+            }
+        };
+        for key in manifest.keys() {
+            if !MANIFEST_KEYS.contains(&key) {
+                problems.push(format!("{MANIFEST_FILE}: unknown key {key}"));
+            }
+        }
+        let mut required = |key: &str| -> String {
+            let value = manifest.get(key).unwrap_or_default();
+            if value.is_empty() {
+                problems.push(format!("{MANIFEST_FILE}: {key} is missing"));
+            }
+            value.to_owned()
+        };
+        let (id, name, version, kind) = (
+            required("id"),
+            required("name"),
+            required("version"),
+            required("kind"),
+        );
+        let id_chars =
+            |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-';
+        if !id.is_empty() && (id.len() > MAX_ID_LENGTH || !id.chars().all(id_chars)) {
+            problems.push(format!(
+                "{MANIFEST_FILE}: id must be up to {MAX_ID_LENGTH} of a-z, 0-9, _ and -"
+            ));
+        }
+        let dotted = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        if !version.is_empty() && !version.split('.').all(dotted) {
+            problems.push(format!("{MANIFEST_FILE}: version must be like 1.2.0"));
+        }
+        let kind = match kind.as_str() {
+            "theme" => Some(ModKind::Theme),
+            "mode" => Some(ModKind::Mode),
+            "" => None,
+            other => {
+                problems.push(format!(
+                    "{MANIFEST_FILE}: kind must be theme or mode, not {other}"
+                ));
+                None
+            }
+        };
+
+        let contents = files
+            .iter()
+            .filter(|(path, _)| path != MANIFEST_FILE)
+            .map(|(path, contents)| (path.as_str(), contents.as_slice()));
+        match kind {
+            Some(ModKind::Theme) => check_theme(contents, &mut problems),
+            Some(ModKind::Mode) => check_mode(contents, &mut problems),
+            None => {}
+        }
+
+        match kind {
+            Some(kind) if problems.is_empty() => Ok(Package {
+                id,
+                name,
+                version,
+                kind,
+                files,
+            }),
+            _ => Err(problems),
+        }
+    }
+}
+
+fn check_theme<'a>(files: impl Iterator<Item = (&'a str, &'a [u8])>, problems: &mut Vec<String>) {
+    let files: Vec<_> = files.collect();
+    let manifest = files
+        .iter()
+        .find(|(path, _)| *path == theme::MANIFEST_FILE)
+        .map(|(_, contents)| {
+            KeyValues::from_bytes(contents).unwrap_or_else(|err| {
+                problems.push(format!("{} {err}", theme::MANIFEST_FILE));
+                KeyValues::default()
+            })
+        })
+        .unwrap_or_default();
+    for key in manifest.keys() {
+        if !theme::MANIFEST_KEYS.contains(&key) {
+            problems.push(format!("{}: unknown key {key}", theme::MANIFEST_FILE));
+        }
+    }
+    for key in theme::COLOR_KEYS {
+        if manifest.get(key).is_some() && manifest.get_color(key).is_none() {
+            problems.push(format!(
+                "{}: {key} must be like #rrggbb",
+                theme::MANIFEST_FILE
+            ));
+        }
+    }
+    for key in theme::NUMBER_KEYS {
+        if manifest.get(key).is_some() && manifest.get_parsed::<f32>(key).is_none() {
+            problems.push(format!("{}: {key} must be a number", theme::MANIFEST_FILE));
+        }
+    }
+    let background = manifest.get("background");
+    if let Some(background) = background
+        && !files.iter().any(|(path, _)| *path == background)
+    {
+        problems.push(format!(
+            "{} names {background}, which is missing",
+            theme::MANIFEST_FILE
+        ));
+    }
+
+    let sounds = audio::theme_sound_files();
+    for (path, contents) in &files {
+        let sprite = theme::SPRITE_FILES.contains(path) || background == Some(*path);
+        if sprite {
+            if let Err(err) = image::load_from_memory(contents) {
+                problems.push(format!("{path} isn't an image that loads ({err})"));
+            }
+        } else if sounds.iter().any(|sound| sound == path) {
+            if contents.get(0..4) != Some(b"RIFF") || contents.get(8..12) != Some(b"WAVE") {
+                problems.push(format!("{path} isn't a WAV file"));
+            }
+        } else if *path == ANIMATIONS_FILE {
+            check_animations(contents, problems);
+        } else if *path == ANCHORS_FILE {
+            check_anchors(contents, problems);
+        } else if *path != theme::MANIFEST_FILE {
+            problems.push(format!("{path} isn't a file themes use"));
+        }
+    }
+}
+
+fn check_animations(contents: &[u8], problems: &mut Vec<String>) {
+    let kv = match KeyValues::from_bytes(contents) {
+        Ok(kv) => kv,
+        Err(err) => return problems.push(format!("{ANIMATIONS_FILE} {err}")),
+    };
+    let keys: Vec<String> = Animations::keys().collect();
+    for key in kv.keys() {
+        if !keys.iter().any(|known| known == key) {
+            problems.push(format!("{ANIMATIONS_FILE}: unknown key {key}"));
+        } else if kv.get_parsed::<f32>(key).is_none_or(|value| value < 0.0) {
+            problems.push(format!(
+                "{ANIMATIONS_FILE}: {key} must be a number, 0 or more"
+            ));
+        }
+    }
+}
+
+fn check_anchors(contents: &[u8], problems: &mut Vec<String>) {
+    let kv = match KeyValues::from_bytes(contents) {
+        Ok(kv) => kv,
+        Err(err) => return problems.push(format!("{ANCHORS_FILE} {err}")),
+    };
+    for key in kv.keys() {
+        if !ANCHOR_KEYS.contains(&key) {
+            problems.push(format!("{ANCHORS_FILE}: unknown key {key}"));
+        } else if kv.get(key).and_then(cosmetics::parse_point).is_none() {
+            problems.push(format!("{ANCHORS_FILE}: {key} must be a point, like 22, 3"));
+        }
+    }
+}
+
+fn check_mode<'a>(files: impl Iterator<Item = (&'a str, &'a [u8])>, problems: &mut Vec<String>) {
+    let mut scripts = 0;
+    for (path, contents) in files {
+        if !path.ends_with(".rhai") || path.contains('/') {
+            problems.push(format!("{path} isn't a file modes use"));
+            continue;
+        }
+        scripts += 1;
+        let source = String::from_utf8_lossy(contents);
+        if let Err(err) = Scripting::new().check(&source) {
+            problems.push(format!("{path}: {err}"));
+        }
+    }
+    if scripts != 1 {
+        problems.push(format!(
+            "a mode needs exactly one .rhai script, not {scripts}"
+        ));
+    }
+}
+
+// Adds every file under `dir` to `files`, by path from the mod folder
+fn read_dir_into(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = format!("{prefix}{name}");
+        if entry.file_type()?.is_dir() {
+            read_dir_into(&entry.path(), &format!("{path}/"), files)?;
+        } else {
+            files.push((path, fs::read(entry.path())?));
+        }
+    }
+    Ok(())
+}
+
+/// `--pack-mod <dir>`: checks the mod in `dir` and writes its package to
+/// the current folder. Returns the exit code.
+pub fn pack_command(dir: &str) -> i32 {
+    let package = match Package::from_dir(Path::new(dir)) {
+        Ok(package) => package,
+        Err(problems) => {
+            eprintln!("{dir} can't be packed:");
+            for problem in problems {
+                eprintln!("  {problem}");
+            }
+            return 1;
+        }
+    };
+    let path = PathBuf::from(format!(
+        "{}-{}.{PACKAGE_EXTENSION}",
+        package.id, package.version
+    ));
+    match fs::write(&path, package.to_bytes()) {
+        Ok(()) => {
+            println!(
+                "Packed {} {} as {}",
+                package.name,
+                package.version,
+                path.display()
+            );
+            0
+        }
+        Err(err) => {
+            eprintln!("Could not write {}: {err}", path.display());
+            1
+        }
+    }
+}
+
+/// Every `.flappymod` file in `mods/`, waiting to be installed.
+pub fn waiting() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(MODS_DIR) else {
+        return Vec::new();
+    };
+    let mut packages: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == PACKAGE_EXTENSION))
+        .collect();
+    packages.sort();
+    packages
+}
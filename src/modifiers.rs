@@ -0,0 +1,23 @@
+// Obstacle modifiers that change the run for a stretch, picked by Twitch chat
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Modifier {
+    // Gusts push the bird up and down
+    Wind,
+    // New pipe pairs bob up and down
+    MovingPipes,
+    // New pipe pairs have a smaller gap
+    NarrowGap,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 3] = [Modifier::Wind, Modifier::MovingPipes, Modifier::NarrowGap];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::Wind => "Wind",
+            Modifier::MovingPipes => "Moving pipes",
+            Modifier::NarrowGap => "Narrow gap",
+        }
+    }
+}
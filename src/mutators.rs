@@ -0,0 +1,243 @@
+// Mutators: twists on a run picked before it starts (M on the title
+// screen), in any combination. Unlike `Modifier`s they last the whole run,
+// and the ones picked are listed on the results card.
+
+use macroquad::prelude::*;
+
+use crate::bird::HOME_X;
+use crate::game::draw_centered_text;
+use crate::screen::{Context, Screen, Transition};
+use crate::sim_math;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+const TINY_SCALE: f32 = 0.6;
+const GIANT_SCALE: f32 = 1.5;
+// Share of the bird's speed an icy flap keeps, and the fastest it can climb
+const ICY_CARRY: f32 = 0.6;
+const ICY_MAX_CLIMB: f32 = 1.5;
+// On ice a flap also pushes sideways: forwards when flapped late, on the
+// way down, and backwards when flapped early, still rising
+const ICY_TIMING_PUSH: f32 = 0.06;
+// Sideways speed kept each tick, and the pull back towards the bird's spot
+const ICY_FRICTION: f32 = 0.97;
+const ICY_RETURN: f32 = 0.002;
+// Sideways push from the wind modifier at its strongest
+const ICY_WIND_DRIFT: f32 = 0.05;
+// Invisible pipes show for FLASH_TICKS out of every FLASH_PERIOD_TICKS
+const FLASH_PERIOD_TICKS: u32 = 90;
+const FLASH_TICKS: u32 = 15;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mutator {
+    TinyBird,
+    GiantBird,
+    // Flaps add to the bird's speed instead of replacing it, and the bird
+    // slides back and forth
+    IcyMomentum,
+    // Pipes only show in brief flashes
+    InvisiblePipes,
+    // Flaps happen when the button is let go instead of pressed
+    MirroredControls,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 5] = [
+        Mutator::TinyBird,
+        Mutator::GiantBird,
+        Mutator::IcyMomentum,
+        Mutator::InvisiblePipes,
+        Mutator::MirroredControls,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mutator::TinyBird => "Tiny bird",
+            Mutator::GiantBird => "Giant bird",
+            Mutator::IcyMomentum => "Icy momentum",
+            Mutator::InvisiblePipes => "Invisible pipes",
+            Mutator::MirroredControls => "Mirrored controls",
+        }
+    }
+
+    /// Stable name used in weekly challenge manifests.
+    pub fn id(self) -> &'static str {
+        match self {
+            Mutator::TinyBird => "tiny",
+            Mutator::GiantBird => "giant",
+            Mutator::IcyMomentum => "icy",
+            Mutator::InvisiblePipes => "invisible",
+            Mutator::MirroredControls => "mirrored",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Mutator> {
+        Mutator::ALL
+            .into_iter()
+            .find(|mutator| mutator.id().eq_ignore_ascii_case(id))
+    }
+
+    // For the label on the results card
+    fn short_name(self) -> &'static str {
+        match self {
+            Mutator::TinyBird => "Tiny",
+            Mutator::GiantBird => "Giant",
+            Mutator::IcyMomentum => "Icy",
+            Mutator::InvisiblePipes => "Invisible",
+            Mutator::MirroredControls => "Mirrored",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The set of mutators on for a run, with the hooks the game calls.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Mutators {
+    bits: u8,
+}
+
+impl Mutators {
+    pub fn of(mutators: &[Mutator]) -> Mutators {
+        Mutators {
+            bits: mutators.iter().fold(0, |bits, m| bits | m.bit()),
+        }
+    }
+
+    pub fn has(self, mutator: Mutator) -> bool {
+        self.bits & mutator.bit() != 0
+    }
+
+    pub fn any(self) -> bool {
+        self.bits != 0
+    }
+
+    pub fn toggle(&mut self, mutator: Mutator) {
+        self.bits ^= mutator.bit();
+    }
+
+    /// The mutators on, like "Tiny + Icy".
+    pub fn label(self) -> Option<String> {
+        let names: Vec<&str> = Mutator::ALL
+            .into_iter()
+            .filter(|&m| self.has(m))
+            .map(Mutator::short_name)
+            .collect();
+        (!names.is_empty()).then(|| names.join(" + "))
+    }
+
+    /// The bird's size, as a share of the sprite's. Tiny and giant
+    /// together cancel out, more or less.
+    pub fn bird_scale(self) -> f32 {
+        let mut scale = 1.0;
+        if self.has(Mutator::TinyBird) {
+            scale *= TINY_SCALE;
+        }
+        if self.has(Mutator::GiantBird) {
+            scale *= GIANT_SCALE;
+        }
+        scale
+    }
+
+    /// The bird's speed after a flap at `strength`, moving at `velocity`,
+    /// where a full flap throws it up at `jump_velocity`.
+    pub fn flap_velocity(self, velocity: Vec2, jump_velocity: f32, strength: f32) -> Vec2 {
+        let flap = jump_velocity * strength;
+        if self.has(Mutator::IcyMomentum) {
+            vec2(
+                velocity.x + velocity.y * ICY_TIMING_PUSH,
+                (velocity.y * ICY_CARRY + flap).max(jump_velocity * ICY_MAX_CLIMB),
+            )
+        } else {
+            vec2(velocity.x, flap)
+        }
+    }
+
+    /// The bird's sideways speed for the next tick, at `x` and moving
+    /// sideways at `speed`.
+    pub fn drift(self, speed: f32, x: f32) -> f32 {
+        if self.has(Mutator::IcyMomentum) {
+            speed * ICY_FRICTION + (HOME_X - x) * ICY_RETURN
+        } else {
+            0.0
+        }
+    }
+
+    /// Extra sideways speed from the wind modifier at `phase` (radians).
+    pub fn wind_drift(self, phase: f32) -> f32 {
+        if self.has(Mutator::IcyMomentum) {
+            ICY_WIND_DRIFT * sim_math::cos(phase)
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether pipes are drawn on tick `ticks` of the run.
+    pub fn pipes_visible(self, ticks: u32) -> bool {
+        !self.has(Mutator::InvisiblePipes) || ticks % FLASH_PERIOD_TICKS < FLASH_TICKS
+    }
+
+    /// Whether flaps come from letting go of the button.
+    pub fn mirrored(self) -> bool {
+        self.has(Mutator::MirroredControls)
+    }
+}
+
+#[derive(Default)]
+pub struct MutatorScreen {
+    selected: usize,
+}
+
+impl Screen for MutatorScreen {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::M) {
+            return Transition::Pop;
+        }
+        let count = Mutator::ALL.len();
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        let toggled = is_key_pressed(KeyCode::Space)
+            || is_key_pressed(KeyCode::Enter)
+            || is_key_pressed(KeyCode::Left)
+            || is_key_pressed(KeyCode::Right);
+        if toggled {
+            cx.mutators.toggle(Mutator::ALL[self.selected]);
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        let mutators = *cx.mutators;
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Mutators", 60.0, 32.0, WHITE);
+
+        for (i, &mutator) in Mutator::ALL.iter().enumerate() {
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            let y = 106.0 + i as f32 * 26.0;
+            draw_text(mutator.name(), 20.0, y, 20.0, color);
+            let value = if mutators.has(mutator) {
+                "< On >"
+            } else {
+                "< Off >"
+            };
+            let size = measure_text(value, None, 20, 1.0);
+            draw_text(value, WIDTH - 20.0 - size.width, y, 20.0, color);
+        }
+
+        let label = mutators.label().unwrap_or_else(|| "None picked".to_owned());
+        draw_centered_text(&label, 106.0 + 5.5 * 26.0, 14.0, GRAY);
+        draw_centered_text("Space to toggle, M/Esc to close", HEIGHT - 20.0, 16.0, GRAY);
+    }
+}
@@ -0,0 +1,170 @@
+// UDP off the render loop: a `Link` owns a socket served by two background
+// threads, one blocking on the outgoing queue and one on the socket, so a
+// slow network never holds up a frame. Datagrams are single lines of text.
+//
+// Also here: join codes, which spell a LAN address as ten letters and digits
+// so players don't have to read out IPs and ports.
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+const MAX_DATAGRAM: usize = 512;
+// How often the receiving thread checks whether the link was dropped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Crockford's base32: no I, L, O or U, which are easy to misread
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CODE_LENGTH: usize = 10;
+
+pub struct Link {
+    outgoing: Sender<(String, SocketAddr)>,
+    incoming: Receiver<(String, SocketAddr)>,
+    local_addr: SocketAddr,
+    closed: Arc<AtomicBool>,
+}
+
+impl Link {
+    /// Binds `port` on every interface (0 picks a free one) and starts the
+    /// network threads.
+    pub fn bind(port: u16) -> io::Result<Link> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        // For LAN discovery, see `discovery.rs`
+        socket.set_broadcast(true)?;
+        let local_addr = socket.local_addr()?;
+        let sender = socket.try_clone()?;
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let (outgoing, queue) = mpsc::channel::<(String, SocketAddr)>();
+        thread::spawn(move || {
+            // Ends once the link is dropped and the queue is empty
+            for (text, to) in queue {
+                // Lost datagrams are expected; callers repeat what matters
+                let _ = sender.send_to(text.as_bytes(), to);
+            }
+        });
+
+        let (received, incoming) = mpsc::channel();
+        let thread_closed = closed.clone();
+        thread::spawn(move || {
+            let mut buffer = [0; MAX_DATAGRAM];
+            while !thread_closed.load(Ordering::Relaxed) {
+                // Timeouts, and on Windows the errors left by unreachable
+                // peers, just mean another lap
+                let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+                    continue;
+                };
+                if let Ok(text) = std::str::from_utf8(&buffer[..len])
+                    && received.send((text.to_owned(), from)).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Link {
+            outgoing,
+            incoming,
+            local_addr,
+            closed,
+        })
+    }
+
+    /// Queues `text` for `to` without waiting for the network.
+    pub fn send(&self, text: String, to: SocketAddr) {
+        let _ = self.outgoing.send((text, to));
+    }
+
+    /// Queues `text` for every machine on the local network listening on
+    /// `port`.
+    pub fn broadcast(&self, text: String, port: u16) {
+        self.send(text, SocketAddr::from((Ipv4Addr::BROADCAST, port)));
+    }
+
+    /// The next datagram that arrived, if any.
+    pub fn receive(&self) -> Option<(String, SocketAddr)> {
+        self.incoming.try_recv().ok()
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Looks up a join code, or an address like `192.168.1.5:4000`.
+pub fn resolve(address: &str) -> io::Result<SocketAddr> {
+    if let Some(addr) = parse_join_code(address) {
+        return Ok(SocketAddr::V4(addr));
+    }
+    address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such host"))
+}
+
+/// This machine's address on the local network, or None when offline.
+pub fn lan_ip() -> Option<Ipv4Addr> {
+    // Connecting a UDP socket sends nothing but makes the OS pick the
+    // interface it would route through
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(("192.0.2.1", 9)).ok()?;
+    match socket.local_addr().ok()? {
+        SocketAddr::V4(addr) if !addr.ip().is_unspecified() => Some(*addr.ip()),
+        _ => None,
+    }
+}
+
+/// Spells `addr` as a join code like `1K8R4-0PZ2E`.
+pub fn join_code(addr: SocketAddrV4) -> String {
+    let value = u64::from(addr.ip().to_bits()) << 16 | u64::from(addr.port());
+    let mut code = String::new();
+    for i in (0..CODE_LENGTH).rev() {
+        code.push(code_char(value >> (i * 5)));
+        if i == CODE_LENGTH / 2 {
+            code.push('-');
+        }
+    }
+    code
+}
+
+/// Reads a join code back, forgiving case, dashes and look-alike letters.
+pub fn parse_join_code(code: &str) -> Option<SocketAddrV4> {
+    let mut value = 0_u64;
+    let mut digits = 0;
+    for c in code.chars().filter(|c| !matches!(c, '-' | ' ')) {
+        value = value << 5 | code_digit(c)?;
+        digits += 1;
+    }
+    if digits != CODE_LENGTH || value >> 48 != 0 {
+        return None;
+    }
+    let ip = Ipv4Addr::from_bits((value >> 16) as u32);
+    Some(SocketAddrV4::new(ip, value as u16))
+}
+
+/// The code letter for the low five bits of `value`.
+pub fn code_char(value: u64) -> char {
+    CODE_ALPHABET[value as usize & 31] as char
+}
+
+/// The value of a code letter, forgiving case and look-alike letters.
+pub fn code_digit(c: char) -> Option<u64> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    let digit = CODE_ALPHABET.iter().position(|&a| a as char == c)?;
+    Some(digit as u64)
+}
@@ -0,0 +1,21 @@
+// Shared interface for everything that scrolls towards the bird and can kill it
+
+use macroquad::prelude::*;
+
+use crate::theme::Theme;
+
+pub trait Obstacle {
+    /// Advances one simulation tick.
+    fn update(&mut self);
+
+    /// The rectangles that hurt the bird.
+    fn hitboxes(&self) -> Vec<Rect>;
+
+    /// True while any part of the obstacle is visible.
+    fn on_screen(&self) -> bool;
+
+    /// True once the obstacle has left the screen for good and can be dropped.
+    fn is_gone(&self) -> bool;
+
+    fn draw(&self, theme: &Theme);
+}
@@ -0,0 +1,119 @@
+// One-switch play, an accessibility setting for players with limited motor
+// control: any key, click or tap is the switch, gaps are wider, and holding
+// the switch can keep flapping on its own. Runs played this way are flagged
+// as assisted in the history.
+//
+// The repeat is calibrated from the current gravity rather than set in
+// seconds: a flap takes `2 * speed / gravity` ticks to fall back to where it
+// started, and repeating a little faster than that climbs gently, so the
+// feel stays the same when a modifier changes gravity.
+
+use macroquad::prelude::*;
+
+use crate::controls::{ControlScheme, RESERVED_KEYS};
+use crate::game::{Game, GameState};
+use crate::timestep::TICK_SECONDS;
+
+// Used when gravity is off or upside down, which no flap rate can match
+const FALLBACK_REPEAT_SECONDS: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum SwitchRepeat {
+    Off,
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl SwitchRepeat {
+    pub const ALL: [SwitchRepeat; 4] = [
+        SwitchRepeat::Off,
+        SwitchRepeat::Slow,
+        SwitchRepeat::Normal,
+        SwitchRepeat::Fast,
+    ];
+
+    /// Stable name used in the settings file.
+    pub fn id(self) -> &'static str {
+        match self {
+            SwitchRepeat::Off => "off",
+            SwitchRepeat::Slow => "slow",
+            SwitchRepeat::Normal => "normal",
+            SwitchRepeat::Fast => "fast",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<SwitchRepeat> {
+        SwitchRepeat::ALL
+            .into_iter()
+            .find(|repeat| repeat.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SwitchRepeat::Off => "Off",
+            SwitchRepeat::Slow => "Slow",
+            SwitchRepeat::Normal => "Normal",
+            SwitchRepeat::Fast => "Fast",
+        }
+    }
+
+    // Share of a flap's rise-and-fall between repeats; lower climbs faster
+    fn share(self) -> Option<f32> {
+        match self {
+            SwitchRepeat::Off => None,
+            SwitchRepeat::Slow => Some(0.9),
+            SwitchRepeat::Normal => Some(0.75),
+            SwitchRepeat::Fast => Some(0.6),
+        }
+    }
+
+    // Seconds between repeated flaps thrown up at `jump_velocity` under
+    // `gravity`
+    fn interval(self, gravity: f32, jump_velocity: f32) -> Option<f32> {
+        let share = self.share()?;
+        if gravity <= 0.0 {
+            return Some(FALLBACK_REPEAT_SECONDS);
+        }
+        Some(2.0 * -jump_velocity / gravity * TICK_SECONDS * share)
+    }
+}
+
+#[derive(Default)]
+pub struct SwitchAssist {
+    // Since the last flap while the switch has been held
+    held_for: f32,
+}
+
+impl SwitchAssist {
+    /// Whether the switch flaps this frame: a press, or a repeat while it's
+    /// held during a run. A switch still held when a run ends doesn't start
+    /// the next one.
+    pub fn update(&mut self, dt: f32, repeat: SwitchRepeat, game: &Game) -> bool {
+        if ControlScheme::AnyKey.flap().is_some() {
+            self.held_for = 0.0;
+            return true;
+        }
+        if !switch_held() || game.state != GameState::Playing {
+            return false;
+        }
+        let Some(interval) = repeat.interval(game.gravity, game.physics.jump_velocity()) else {
+            return false;
+        };
+        self.held_for += dt;
+        if self.held_for >= interval {
+            self.held_for -= interval;
+            return true;
+        }
+        false
+    }
+}
+
+fn switch_held() -> bool {
+    get_keys_down()
+        .iter()
+        .any(|key| !RESERVED_KEYS.contains(key))
+        || is_mouse_button_down(MouseButton::Left)
+        || !touches().is_empty()
+}
@@ -0,0 +1,205 @@
+// Launch options, from the command line or, in the web build, from the page
+// URL (`?seed=1234&mode=daily&mute=1` reads like `--seed 1234 --mode daily
+// --mute`)
+
+use crate::date::Date;
+
+// Options that take no value; in a URL they're on unless set to 0
+#[cfg(target_arch = "wasm32")]
+const FLAGS: [&str; 4] = ["kiosk", "mute", "practice", "tutorial"];
+
+#[derive(Default)]
+pub struct Options {
+    // Arcade cabinet mode, see `kiosk.rs`
+    pub kiosk: bool,
+    // Twitch channel whose chat votes on modifiers, see `twitch.rs`
+    pub twitch: Option<String>,
+    // Course seed shared by every run, for challenge links
+    pub seed: Option<u64>,
+    // Game mode to start in: "classic", a mod's name, "daily" for classic
+    // on today's course, or a challenge like "invisible_pipes"
+    pub mode: Option<String>,
+    pub mute: bool,
+    // Shows the first-launch tutorial even if it was already done
+    pub tutorial: bool,
+    // Online versus, see `versus.rs`: a port to host on, or a join code or
+    // address to join
+    pub host: Option<u16>,
+    pub join: Option<String>,
+    // A match to watch, see `spectator.rs`
+    pub watch: Option<String>,
+    // A level name or file to fly instead of the endless course
+    pub level: Option<String>,
+    // With a level, crashing goes back to the last checkpoint, see
+    // `flags.rs`
+    pub practice: bool,
+    // Prints frame timings for the session on the way out, see `perf.rs`
+    pub perf_report: bool,
+    // Runs the benchmark instead of the game, see `bench.rs`, optionally
+    // without a window
+    pub bench: bool,
+    pub headless: bool,
+    // Hours to soak-test for instead of playing, see `soak.rs`
+    pub soak: Option<f32>,
+    // A mod folder to check and package instead of playing, see
+    // `mod_pack.rs`
+    pub pack_mod: Option<String>,
+}
+
+impl Options {
+    /// Reads the options this process was started with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_env() -> Options {
+        Options::parse(std::env::args().skip(1))
+    }
+
+    /// Reads the options from the page's query string.
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_env() -> Options {
+        Options::from_query(&web::query_string())
+    }
+
+    /// Turns `a=1&b=2` into `--a 1 --b 2` and parses that.
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_query(query: &str) -> Options {
+        let mut args = Vec::new();
+        for pair in query.trim_start_matches('?').split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let (key, value) = (url_decode(key), url_decode(value));
+            if key.is_empty() {
+                continue;
+            }
+            if FLAGS.contains(&key.as_str()) {
+                if !matches!(value.as_str(), "0" | "false") {
+                    args.push(format!("--{key}"));
+                }
+            } else {
+                args.push(format!("--{key}"));
+                args.push(value);
+            }
+        }
+        Options::parse(args)
+    }
+
+    /// Parses `args` (without the program name). Unknown arguments are
+    /// reported and otherwise ignored.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Options {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--kiosk" => options.kiosk = true,
+                "--mute" => options.mute = true,
+                "--tutorial" => options.tutorial = true,
+                "--practice" => options.practice = true,
+                "--perf-report" => options.perf_report = true,
+                "--bench" => options.bench = true,
+                "--headless" => options.headless = true,
+                "--twitch" => match args.next() {
+                    Some(channel) => options.twitch = Some(channel),
+                    None => eprintln!("--twitch needs a channel name"),
+                },
+                "--seed" => match args.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => options.seed = Some(seed),
+                    _ => eprintln!("--seed needs a whole number"),
+                },
+                "--host" => match args.next().map(|port| port.parse()) {
+                    Some(Ok(port)) => options.host = Some(port),
+                    _ => eprintln!("--host needs a port number"),
+                },
+                "--join" => match args.next() {
+                    Some(address) => options.join = Some(address),
+                    None => {
+                        eprintln!("--join needs a join code or an address like 192.168.1.5:4000")
+                    }
+                },
+                "--watch" => match args.next() {
+                    Some(address) => options.watch = Some(address),
+                    None => eprintln!("--watch needs a join code or an address"),
+                },
+                "--level" => match args.next() {
+                    Some(level) => options.level = Some(level),
+                    None => eprintln!("--level needs a level name or file"),
+                },
+                "--soak" => match args.next().map(|hours| hours.parse()) {
+                    Some(Ok(hours)) => options.soak = Some(hours),
+                    _ => eprintln!("--soak needs a number of hours"),
+                },
+                "--pack-mod" => match args.next() {
+                    Some(dir) => options.pack_mod = Some(dir),
+                    None => eprintln!("--pack-mod needs a mod folder"),
+                },
+                "--mode" => match args.next() {
+                    Some(mode) => options.mode = Some(mode),
+                    None => eprintln!("--mode needs a mode name"),
+                },
+                _ => eprintln!("Ignoring unknown argument {arg}"),
+            }
+        }
+        options
+    }
+
+    /// The seed every run should use: the one given, or today's for the
+    /// daily mode.
+    pub fn course_seed(&self) -> Option<u64> {
+        self.seed
+            .or_else(|| self.daily().then(|| Date::today().seed()))
+    }
+
+    /// Whether the daily course was asked for.
+    pub fn daily(&self) -> bool {
+        self.mode.as_deref() == Some("daily")
+    }
+}
+
+// Decodes `%xx` escapes and `+` as space, keeping malformed escapes as-is
+#[cfg(target_arch = "wasm32")]
+fn url_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                out.push(byte);
+                i += 3;
+            }
+            (None, b'+') => {
+                out.push(b' ');
+                i += 1;
+            }
+            (None, byte) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    // Implemented in `web/query.js`
+    unsafe extern "C" {
+        fn flappy_query_len() -> usize;
+        fn flappy_query_read(out: *mut u8);
+    }
+
+    #[unsafe(no_mangle)]
+    pub extern "C" fn flappy_query_crate_version() -> u32 {
+        1
+    }
+
+    /// `window.location.search`, e.g. `?seed=1234`.
+    pub fn query_string() -> String {
+        unsafe {
+            let mut bytes = vec![0; flappy_query_len()];
+            flappy_query_read(bytes.as_mut_ptr());
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+    }
+}
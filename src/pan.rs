@@ -0,0 +1,122 @@
+// Stereo panning for sound effects. macroquad plays a sound at a volume and
+// nothing more, so each cue is loaded as a few copies mixed to points across
+// the stereo field, and the copy nearest to where the sound happens is the
+// one played. Only PCM WAV files (8 or 16 bit, mono or stereo) can be mixed;
+// anything else plays as it is, from the middle.
+
+const STEPS: usize = 5;
+// How far out the outermost copies sit, 1 being in one ear only
+const SPREAD: f32 = 0.8;
+
+/// Which copy to play for `pan`, from -1 (left) to 1 (right).
+pub fn nearest(pan: f32) -> usize {
+    let at = (pan.clamp(-1.0, 1.0) + 1.0) / 2.0;
+    (at * (STEPS - 1) as f32).round() as usize
+}
+
+/// `wav` mixed to each pan `nearest` picks from, left to right, as 16-bit
+/// stereo WAV files. None if it isn't a WAV file that can be mixed.
+pub fn mix(wav: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let pcm = decode(wav)?;
+    let copies = (0..STEPS).map(|step| {
+        let pan = (step as f32 / (STEPS - 1) as f32 * 2.0 - 1.0) * SPREAD;
+        encode(&pcm, gains(pan))
+    });
+    Some(copies.collect())
+}
+
+// Left and right gains for `pan`, by the equal-power law but with the
+// middle at full volume in both ears, as the unpanned sound was
+fn gains(pan: f32) -> [f32; 2] {
+    let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+    let boost = std::f32::consts::SQRT_2;
+    [
+        (angle.cos() * boost).min(1.0),
+        (angle.sin() * boost).min(1.0),
+    ]
+}
+
+struct Pcm {
+    rate: u32,
+    // Left and right, from -1 to 1
+    frames: Vec<[f32; 2]>,
+}
+
+fn decode(wav: &[u8]) -> Option<Pcm> {
+    if wav.get(0..4)? != b"RIFF" || wav.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let u16_at = |at: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(wav.get(at..at + 2)?.try_into().ok()?))
+    };
+    let u32_at = |at: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(wav.get(at..at + 4)?.try_into().ok()?))
+    };
+    let (mut format, mut data) = (None, None);
+    let mut chunk = 12;
+    while chunk + 8 <= wav.len() {
+        let size = u32_at(chunk + 4)? as usize;
+        let body = chunk + 8;
+        match &wav[chunk..chunk + 4] {
+            // Format tag, channels, sample rate, bits per sample
+            b"fmt " => {
+                format = Some((
+                    u16_at(body)?,
+                    u16_at(body + 2)?,
+                    u32_at(body + 4)?,
+                    u16_at(body + 14)?,
+                ))
+            }
+            b"data" => data = wav.get(body..body.saturating_add(size).min(wav.len())),
+            _ => {}
+        }
+        chunk = body.saturating_add(size + size % 2);
+    }
+    let (tag, channels, rate, bits) = format?;
+    const PCM: u16 = 1;
+    if tag != PCM || !(1..=2).contains(&channels) || rate == 0 {
+        return None;
+    }
+    let samples: Vec<f32> = match bits {
+        8 => data?
+            .iter()
+            .map(|&sample| (f32::from(sample) - 128.0) / 128.0)
+            .collect(),
+        16 => data?
+            .chunks_exact(2)
+            .map(|sample| f32::from(i16::from_le_bytes([sample[0], sample[1]])) / 32768.0)
+            .collect(),
+        _ => return None,
+    };
+    let frames = samples
+        .chunks_exact(usize::from(channels))
+        .map(|frame| [frame[0], frame[frame.len() - 1]])
+        .collect();
+    Some(Pcm { rate, frames })
+}
+
+fn encode(pcm: &Pcm, gains: [f32; 2]) -> Vec<u8> {
+    let data_size = pcm.frames.len() as u32 * 4;
+    let mut wav = Vec::with_capacity(44 + data_size as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, 2 channels
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&pcm.rate.to_le_bytes());
+    wav.extend_from_slice(&(pcm.rate * 4).to_le_bytes());
+    // 4 bytes a frame, 16 bits a sample
+    wav.extend_from_slice(&4u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for frame in &pcm.frames {
+        for (sample, gain) in frame.iter().zip(gains) {
+            let sample = (sample * gain * 32767.0).clamp(-32768.0, 32767.0) as i16;
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    wav
+}
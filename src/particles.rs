@@ -0,0 +1,178 @@
+// Short-lived particle effects (purely visual, never affect the simulation)
+//
+// Moving them is the one effect that gets heavy, with a boss fight's or a
+// party's worth of bursts on screen, so outside the web build it happens
+// on a thread of its own: `update` hands the particles over and the frame
+// carries on drawing everything else, and `draw` takes them back, waiting
+// for the step if it isn't done yet. Bursts thrown in between join the
+// rest then. The web has no threads, so there `update` moves them itself.
+
+use macroquad::prelude::*;
+
+use crate::quality::Quality;
+
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    life: f32,
+    max_life: f32,
+    size: f32,
+    color: Color,
+}
+
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    // Bursts throw fewer at lower tiers
+    pub quality: Quality,
+    // Thrown while the rest were away being moved
+    thrown: Vec<Particle>,
+    // How many are away, and the thread they're with, started on the first
+    // update
+    away: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    stepper: Option<stepper::Stepper>,
+}
+
+impl Particles {
+    /// Throws `count` particles outwards from `pos` in random directions,
+    /// or fewer at a lower quality tier.
+    pub fn burst(&mut self, pos: Vec2, count: usize, speed: f32, color: Color) {
+        for _ in 0..self.quality.particles(count) {
+            let angle = rand::gen_range(0.0, std::f32::consts::TAU);
+            let speed = rand::gen_range(speed * 0.4, speed);
+            let life = rand::gen_range(0.3, 0.7);
+            self.thrown.push(Particle {
+                pos,
+                vel: Vec2::from_angle(angle) * speed,
+                life,
+                max_life: life,
+                size: rand::gen_range(2.0, 4.0),
+                color,
+            });
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.particles.len() + self.thrown.len() + self.away
+    }
+
+    /// Moves every particle on by `dt`, on the particle thread when there
+    /// is one; `draw` picks them up again.
+    pub fn update(&mut self, dt: f32) {
+        self.take_back();
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.particles.is_empty() {
+            // Every batch sent is received before the next, so none is
+            // ever left waiting to come back a frame late
+            let particles = std::mem::take(&mut self.particles);
+            self.away = particles.len();
+            self.stepper
+                .get_or_insert_with(stepper::Stepper::spawn)
+                .send(particles, dt);
+        }
+        #[cfg(target_arch = "wasm32")]
+        step(&mut self.particles, dt);
+    }
+
+    pub fn draw(&mut self) {
+        self.take_back();
+        self.draw_held();
+    }
+
+    /// Draws them without waiting for any still being moved, for when
+    /// they're held still and already back from the last `draw`.
+    pub fn draw_held(&self) {
+        for p in &self.particles {
+            let mut color = p.color;
+            color.a *= p.life / p.max_life;
+            draw_rectangle(p.pos.x, p.pos.y, p.size, p.size, color);
+        }
+    }
+
+    // Waits for any particles still being moved, and adds the new ones
+    fn take_back(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.away > 0
+            && let Some(stepper) = &self.stepper
+        {
+            self.particles = stepper.receive();
+            self.away = 0;
+        }
+        self.particles.append(&mut self.thrown);
+    }
+}
+
+fn step(particles: &mut Vec<Particle>, dt: f32) {
+    for p in particles.iter_mut() {
+        p.pos += p.vel * dt;
+        p.vel.y += 300.0 * dt;
+        p.life -= dt;
+    }
+    particles.retain(|p| p.life > 0.0);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod stepper {
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+
+    use super::Particle;
+
+    pub struct Stepper {
+        requests: Sender<(Vec<Particle>, f32)>,
+        results: Receiver<Vec<Particle>>,
+    }
+
+    impl Stepper {
+        // The thread stops once the stepper is dropped
+        pub fn spawn() -> Stepper {
+            let (requests, requested) = mpsc::channel::<(Vec<Particle>, f32)>();
+            let (done, results) = mpsc::channel();
+            thread::spawn(move || {
+                for (mut particles, dt) in requested {
+                    super::step(&mut particles, dt);
+                    if done.send(particles).is_err() {
+                        return;
+                    }
+                }
+            });
+            Stepper { requests, results }
+        }
+
+        pub fn send(&self, particles: Vec<Particle>, dt: f32) {
+            let _ = self.requests.send((particles, dt));
+        }
+
+        // The particles sent last, moved on. They're lost if the thread
+        // has somehow gone, which only means the effects end early.
+        pub fn receive(&self) -> Vec<Particle> {
+            self.results.recv().unwrap_or_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_burst_comes_back_from_the_thread() {
+        let mut particles = Particles::default();
+        // A frame with nothing to move first, which mustn't leave anything
+        // to be picked up later
+        particles.update(0.01);
+        particles.take_back();
+        particles.burst(vec2(50.0, 50.0), 10, 100.0, WHITE);
+        for _ in 0..3 {
+            particles.update(0.01);
+            particles.take_back();
+            assert_eq!(particles.particles.len(), 10);
+        }
+        let moved = particles
+            .particles
+            .iter()
+            .all(|p| p.pos != vec2(50.0, 50.0));
+        assert!(moved);
+    }
+}
@@ -0,0 +1,384 @@
+// Party mode, opened from the title screen with P: two to four players share
+// one screen, each on their own key (or, with the `gamepad` feature, their
+// own controller's south button).
+//
+// Every player flies a separate `Game` on the same seed, so the pipes line
+// up exactly and only the leader's course is drawn under all the birds.
+// Crashing turns a bird into a ghost that can keep flapping along but no
+// longer scores; once one bird is left the round ends on a podium ranked by
+// who went out last.
+
+#[cfg(feature = "gamepad")]
+use gilrs::{Button, EventType, GamepadId, Gilrs};
+use macroquad::prelude::*;
+
+use crate::bird::BIRD_HEIGHT;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::theme::Theme;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+const MAX_PLAYERS: usize = 4;
+// Spread over the keyboard so four people can crowd round it
+const KEYS: [(KeyCode, &str); MAX_PLAYERS] = [
+    (KeyCode::A, "A"),
+    (KeyCode::L, "L"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Up, "Up"),
+];
+// Multiplied with the theme's tint, in joining order
+const COLORS: [Color; MAX_PLAYERS] = [
+    Color::new(1.0, 1.0, 1.0, 1.0),
+    Color::new(1.0, 0.5, 0.5, 1.0),
+    Color::new(0.5, 0.8, 1.0, 1.0),
+    Color::new(0.6, 1.0, 0.6, 1.0),
+];
+const GHOST_ALPHA: f32 = 0.35;
+// Starts once two players are in, and again whenever someone else joins
+const COUNTDOWN_SECONDS: f32 = 3.0;
+// Keeps a crash from skipping straight past the podium
+const PODIUM_SECONDS: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Control {
+    Key(KeyCode),
+    #[cfg(feature = "gamepad")]
+    Pad(GamepadId),
+}
+
+struct Player {
+    control: Control,
+    label: &'static str,
+    color: Color,
+    game: Game,
+    // Set on crashing: out in this place, with this score
+    out: Option<(usize, i64)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    Joining(Option<f32>),
+    Racing,
+    // Seconds since the round ended
+    Podium(f32),
+}
+
+pub struct Party {
+    players: Vec<Player>,
+    phase: Phase,
+    fixed_seed: Option<u64>,
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<Gilrs>,
+}
+
+impl Party {
+    /// Opens the joining screen. Rounds fly `fixed_seed` when set, or a new
+    /// course each.
+    pub fn new(fixed_seed: Option<u64>) -> Party {
+        Party {
+            players: Vec::new(),
+            phase: Phase::Joining(None),
+            fixed_seed,
+            #[cfg(feature = "gamepad")]
+            gilrs: Gilrs::new()
+                .inspect_err(|err| eprintln!("Gamepads unavailable: {err}"))
+                .ok(),
+        }
+    }
+
+    /// Handles everyone's buttons and the countdowns.
+    pub fn update(&mut self, dt: f32) {
+        for control in self.pressed() {
+            let index = self.players.iter().position(|p| p.control == control);
+            match (self.phase, index) {
+                (Phase::Joining(_), None) => self.add(control),
+                (Phase::Racing, Some(index)) => self.players[index].game.flap(),
+                (Phase::Podium(shown), Some(_)) if shown > PODIUM_SECONDS => {
+                    self.phase = Phase::Joining(Some(COUNTDOWN_SECONDS));
+                }
+                _ => {}
+            }
+        }
+
+        match &mut self.phase {
+            Phase::Joining(Some(left)) => {
+                *left -= dt;
+                if *left <= 0.0 {
+                    self.start_round();
+                }
+            }
+            Phase::Podium(shown) => *shown += dt,
+            _ => {}
+        }
+    }
+
+    // Controls pressed this frame, whether or not anyone is on them yet
+    fn pressed(&mut self) -> Vec<Control> {
+        #[allow(unused_mut)]
+        let mut pressed: Vec<Control> = KEYS
+            .iter()
+            .filter(|(key, _)| is_key_pressed(*key))
+            .map(|(key, _)| Control::Key(*key))
+            .collect();
+        #[cfg(feature = "gamepad")]
+        if let Some(gilrs) = &mut self.gilrs {
+            while let Some(event) = gilrs.next_event() {
+                if let EventType::ButtonPressed(Button::South, _) = event.event {
+                    pressed.push(Control::Pad(event.id));
+                }
+            }
+        }
+        pressed
+    }
+
+    fn add(&mut self, control: Control) {
+        if self.players.len() == MAX_PLAYERS {
+            return;
+        }
+        let label = match control {
+            Control::Key(key) => KEYS.iter().find(|(k, _)| *k == key).map_or("?", |k| k.1),
+            #[cfg(feature = "gamepad")]
+            Control::Pad(_) => "Pad",
+        };
+        self.players.push(Player {
+            control,
+            label,
+            color: COLORS[self.players.len()],
+            game: Game::with_seed(self.fixed_seed),
+            out: None,
+        });
+        if self.players.len() > 1 {
+            self.phase = Phase::Joining(Some(COUNTDOWN_SECONDS));
+        }
+    }
+
+    fn start_round(&mut self) {
+        let seed = self
+            .fixed_seed
+            .unwrap_or_else(|| u64::from(rand::rand()) << 32 | u64::from(rand::rand()));
+        for player in &mut self.players {
+            player.game = Game::with_seed(Some(seed));
+            player.out = None;
+            player.game.flap();
+        }
+        self.phase = Phase::Racing;
+    }
+
+    /// Runs one fixed-timestep update of every bird.
+    pub fn tick(&mut self) {
+        if self.phase != Phase::Racing {
+            return;
+        }
+        // Birds crashing on the same tick share the place they went out in
+        let flying = self.players.iter().filter(|p| p.out.is_none()).count();
+        for player in &mut self.players {
+            player.game.update();
+            if player.game.ghost {
+                // Ghosts can't crash, so keep them on screen instead
+                let bird = &mut player.game.bird;
+                bird.y = bird.y.clamp(0.0, HEIGHT - BIRD_HEIGHT);
+            } else if player.game.state == GameState::GameOver {
+                player.out = Some((flying, player.game.score));
+                player.game.ghost = true;
+                player.game.state = GameState::Playing;
+            }
+        }
+        let left = self.players.iter().filter(|p| p.out.is_none()).count();
+        if left > 1 {
+            return;
+        }
+        // The last bird wins; if the last ones crashed together, they all do
+        for player in &mut self.players {
+            match &mut player.out {
+                None => player.out = Some((1, player.game.score)),
+                Some((place, _)) if left == 0 && *place == flying => *place = 1,
+                _ => {}
+            }
+        }
+        self.phase = Phase::Podium(0.0);
+    }
+
+    /// Events from every player's run since the last call, for sound.
+    pub fn events(&mut self) -> Vec<GameEvent> {
+        self.players
+            .iter_mut()
+            .flat_map(|player| player.game.events.drain())
+            .collect()
+    }
+
+    pub fn draw(&self, theme: &Theme) {
+        match self.phase {
+            Phase::Joining(countdown) => self.draw_joining(countdown, theme),
+            Phase::Racing => self.draw_race(theme),
+            Phase::Podium(_) => {
+                self.draw_race(theme);
+                self.draw_podium(theme);
+            }
+        }
+    }
+
+    fn draw_joining(&self, countdown: Option<f32>, theme: &Theme) {
+        draw_centered_text("Party", 60.0, 32.0, theme.text_color);
+        for (i, player) in self.players.iter().enumerate() {
+            let y = 120.0 + i as f32 * 40.0;
+            draw_texture(
+                &theme.bird,
+                WIDTH / 2.0 - 60.0,
+                y - 18.0,
+                self.tint(player, theme),
+            );
+            draw_text(
+                &format!("P{} ({})", i + 1, player.label),
+                WIDTH / 2.0 - 16.0,
+                y,
+                20.0,
+                theme.text_color,
+            );
+        }
+        let keys: Vec<_> = KEYS.iter().map(|(_, label)| *label).collect();
+        let join = if cfg!(feature = "gamepad") {
+            format!("{} or a gamepad's A to join", keys.join(", "))
+        } else {
+            format!("{} to join", keys.join(", "))
+        };
+        if self.players.len() < MAX_PLAYERS {
+            draw_centered_text(&join, HEIGHT - 80.0, 16.0, theme.text_color);
+        }
+        match countdown {
+            Some(left) => {
+                let left = left.ceil() as u32;
+                draw_centered_text(
+                    &left.to_string(),
+                    HEIGHT / 2.0 + 60.0,
+                    48.0,
+                    theme.text_color,
+                );
+            }
+            None => draw_centered_text("Needs two players", HEIGHT - 56.0, 16.0, theme.text_color),
+        }
+        draw_centered_text("Esc to leave", HEIGHT - 20.0, 16.0, GRAY);
+    }
+
+    fn draw_race(&self, theme: &Theme) {
+        let leader = self
+            .players
+            .iter()
+            .find(|p| p.out.is_none())
+            .or(self.players.first());
+        if let Some(leader) = leader {
+            leader.game.draw_course(theme);
+        }
+        // Ghosts underneath, so the birds still in it stay readable
+        for player in self.players.iter().filter(|p| p.out.is_some()) {
+            player.game.bird.draw(&theme.bird, self.tint(player, theme));
+        }
+        for player in self.players.iter().filter(|p| p.out.is_none()) {
+            player.game.bird.draw(&theme.bird, self.tint(player, theme));
+        }
+
+        // One score per player across the top
+        let column = WIDTH / self.players.len().max(1) as f32;
+        for (i, player) in self.players.iter().enumerate() {
+            let text = format!("P{} {}", i + 1, player.final_score());
+            let size = measure_text(&text, None, 20, 1.0);
+            let x = column * i as f32 + (column - size.width) / 2.0;
+            let color = Color {
+                a: if player.out.is_some() { 0.6 } else { 1.0 },
+                ..player.color
+            };
+            draw_text(&text, x, 24.0, 20.0, color);
+        }
+    }
+
+    fn draw_podium(&self, theme: &Theme) {
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        draw_centered_text("Results", 60.0, 32.0, WHITE);
+
+        let mut ranking: Vec<(usize, &Player)> = self.players.iter().enumerate().collect();
+        ranking.sort_by_key(|(_, p)| p.out.map(|(place, score)| (place, -score)));
+
+        // Blocks for the top three, tallest in the middle
+        let (base, width) = (HEIGHT / 2.0 + 60.0, 70.0);
+        for (rank, (i, player)) in ranking.iter().enumerate().take(3) {
+            let (x, height) = match rank {
+                0 => (WIDTH / 2.0 - width / 2.0, 90.0),
+                1 => (WIDTH / 2.0 - width * 1.5 - 8.0, 60.0),
+                _ => (WIDTH / 2.0 + width / 2.0 + 8.0, 40.0),
+            };
+            draw_rectangle(
+                x,
+                base - height,
+                width,
+                height,
+                Color::new(0.9, 0.75, 0.3, 1.0),
+            );
+            let place = player.out.map_or(rank + 1, |(place, _)| place);
+            draw_text(
+                &place.to_string(),
+                x + width / 2.0 - 6.0,
+                base - 8.0,
+                28.0,
+                BLACK,
+            );
+            let bird_x = x + (width - theme.bird.width()) / 2.0;
+            draw_texture(
+                &theme.bird,
+                bird_x,
+                base - height - 28.0,
+                self.tint(player, theme),
+            );
+            let label = format!("P{} {}", i + 1, player.final_score());
+            let size = measure_text(&label, None, 16, 1.0);
+            draw_text(
+                &label,
+                x + (width - size.width) / 2.0,
+                base + 20.0,
+                16.0,
+                player.color,
+            );
+        }
+        if let Some((i, fourth)) = ranking.get(3) {
+            let label = format!("4th: P{} {}", i + 1, fourth.final_score());
+            draw_centered_text(&label, base + 50.0, 16.0, fourth.color);
+        }
+
+        if matches!(self.phase, Phase::Podium(shown) if shown > PODIUM_SECONDS) {
+            draw_centered_text(
+                "Flap for a rematch, Esc to leave",
+                HEIGHT - 20.0,
+                16.0,
+                GRAY,
+            );
+        }
+    }
+
+    // The player's color over the theme's, faded for ghosts
+    fn tint(&self, player: &Player, theme: &Theme) -> Color {
+        let tint = theme.tint;
+        let alpha = if player.out.is_some() && self.phase == Phase::Racing {
+            GHOST_ALPHA
+        } else {
+            1.0
+        };
+        Color::new(
+            tint.r * player.color.r,
+            tint.g * player.color.g,
+            tint.b * player.color.b,
+            tint.a * alpha,
+        )
+    }
+}
+
+impl Player {
+    // Frozen once out, since ghosts keep passing pipes
+    fn final_score(&self) -> i64 {
+        self.out.map_or(self.game.score, |(_, score)| score)
+    }
+}
@@ -0,0 +1,235 @@
+// Frame timing, for hunting down stutters: frame-time percentiles over the
+// current run, and spikes caught along with how long each part of their
+// frame took. F3 shows the overlay, and `--perf-report` prints a summary of
+// the whole session on the way out.
+//
+// A frame is a spike when it takes several times as long as the typical
+// recent frame, and long enough to be seen.
+
+use std::collections::VecDeque;
+
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+// Recent frames the typical frame time is taken from
+const RECENT_FRAMES: usize = 120;
+const SPIKE_FACTOR: f32 = 2.5;
+const SPIKE_MIN_MS: f32 = 25.0;
+// Spikes stay on the overlay this long, and the report lists the worst few
+const SPIKE_SHOWN_SECONDS: f64 = 5.0;
+const SPIKES_SHOWN: usize = 4;
+const WORST_SPIKES: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Section {
+    Update,
+    Collision,
+    Particles,
+    Draw,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Update,
+        Section::Collision,
+        Section::Particles,
+        Section::Draw,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Update => "update",
+            Section::Collision => "collision",
+            Section::Particles => "particles",
+            Section::Draw => "draw",
+        }
+    }
+
+    // For the overlay, which has little room
+    fn short_name(self) -> &'static str {
+        match self {
+            Section::Update => "upd",
+            Section::Collision => "col",
+            Section::Particles => "fx",
+            Section::Draw => "draw",
+        }
+    }
+}
+
+/// Seconds on a clock that works before the window opens and in tests,
+/// for timing sections.
+pub fn clock() -> f64 {
+    date::now()
+}
+
+#[derive(Clone, Copy)]
+struct Spike {
+    frame_ms: f32,
+    // Milliseconds in each of `Section::ALL`
+    sections: [f32; 4],
+    // Clock time it was caught at
+    at: f64,
+}
+
+#[derive(Default)]
+pub struct Perf {
+    pub overlay: bool,
+    // When the frame being timed started, and its sections so far
+    frame_started: Option<f64>,
+    sections: [f64; 4],
+    // The last whole frame's sections, in milliseconds, for the overlay
+    last_sections: [f32; 4],
+    // Frame times in milliseconds
+    recent: VecDeque<f32>,
+    run: Vec<f32>,
+    session: Vec<f32>,
+    spikes: VecDeque<Spike>,
+    worst: Vec<Spike>,
+    spike_count: u32,
+}
+
+impl Perf {
+    /// Adds `seconds` spent in `section` to the frame being timed.
+    pub fn time(&mut self, section: Section, seconds: f64) {
+        self.sections[section as usize] += seconds;
+    }
+
+    /// Ends the frame being timed and starts the next. Call at the top of
+    /// every frame; `playing` says whether a run is in progress.
+    pub fn next_frame(&mut self, playing: bool) {
+        let now = clock();
+        let Some(started) = self.frame_started.replace(now) else {
+            return;
+        };
+        let frame_ms = ((now - started) * 1000.0) as f32;
+        let sections = self.sections.map(|seconds| (seconds * 1000.0) as f32);
+        self.sections = [0.0; 4];
+        self.last_sections = sections;
+
+        let typical = percentile(self.recent.iter().copied().collect(), 0.5);
+        if self.recent.len() == RECENT_FRAMES
+            && frame_ms > SPIKE_MIN_MS
+            && frame_ms > typical * SPIKE_FACTOR
+        {
+            let spike = Spike {
+                frame_ms,
+                sections,
+                at: now,
+            };
+            self.spike_count += 1;
+            self.spikes.push_back(spike);
+            if self.spikes.len() > SPIKES_SHOWN {
+                self.spikes.pop_front();
+            }
+            self.worst.push(spike);
+            self.worst.sort_by(|a, b| b.frame_ms.total_cmp(&a.frame_ms));
+            self.worst.truncate(WORST_SPIKES);
+        }
+
+        self.recent.push_back(frame_ms);
+        if self.recent.len() > RECENT_FRAMES {
+            self.recent.pop_front();
+        }
+        self.session.push(frame_ms);
+        if playing {
+            self.run.push(frame_ms);
+        }
+    }
+
+    /// Starts the run percentiles over.
+    pub fn start_run(&mut self) {
+        self.run.clear();
+    }
+
+    /// Draws the overlay, with `memory` saying what assets are loaded.
+    pub fn draw(&self, memory: &str) {
+        if !self.overlay {
+            return;
+        }
+        let now = clock();
+        let spikes: Vec<&Spike> = self
+            .spikes
+            .iter()
+            .filter(|spike| now - spike.at <= SPIKE_SHOWN_SECONDS)
+            .collect();
+        let lines = 4 + spikes.len();
+        draw_rectangle(
+            0.0,
+            0.0,
+            200.0,
+            8.0 + lines as f32 * 12.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        let mut y = 14.0;
+        let mut line = |text: &str, color: Color| {
+            draw_text(text, 4.0, y, 13.0, color);
+            y += 12.0;
+        };
+        let [p50, p95, p99] = [0.5, 0.95, 0.99].map(|p| percentile(self.run.clone(), p));
+        line(
+            &format!("run p50 {p50:.1} p95 {p95:.1} p99 {p99:.1} ms"),
+            WHITE,
+        );
+        line(&format!("{} frames this run", self.run.len()), GRAY);
+        line(
+            &sections_label(&self.last_sections, Section::short_name),
+            GRAY,
+        );
+        line(memory, GRAY);
+        for spike in spikes {
+            let age = now - spike.at;
+            let alpha = 1.0 - (age / SPIKE_SHOWN_SECONDS) as f32 * 0.6;
+            line(
+                &format!(
+                    "{:.0}ms {}",
+                    spike.frame_ms,
+                    sections_label(&spike.sections, Section::short_name)
+                ),
+                Color::new(1.0, 0.35, 0.3, alpha),
+            );
+        }
+    }
+
+    /// A summary of the whole session, for `--perf-report`.
+    pub fn report(&self) -> String {
+        let mut report = format!("Frames: {}\n", self.session.len());
+        for (label, p) in [("p50", 0.5), ("p95", 0.95), ("p99", 0.99), ("max", 1.0)] {
+            let ms = percentile(self.session.clone(), p);
+            report += &format!("{label}: {ms:.2} ms\n");
+        }
+        report += &format!("Spikes: {}\n", self.spike_count);
+        for spike in &self.worst {
+            report += &format!(
+                "  {:.1} ms ({})\n",
+                spike.frame_ms,
+                sections_label(&spike.sections, Section::name)
+            );
+        }
+        report
+    }
+}
+
+/// Like "3.2 MB".
+pub fn megabytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// The `p` quantile of `samples` (0.5 for the median), or 0 without any.
+pub fn percentile(mut samples: Vec<f32>, p: f32) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.sort_by(f32::total_cmp);
+    let index = ((samples.len() - 1) as f32 * p).round() as usize;
+    samples[index]
+}
+
+// Like "update 1.2 collision 0.1 particles 0.3 draw 4.0"
+fn sections_label(sections: &[f32; 4], name: fn(Section) -> &'static str) -> String {
+    Section::ALL
+        .iter()
+        .zip(sections)
+        .map(|(&section, ms)| format!("{} {ms:.1}", name(section)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,250 @@
+// Photo mode, entered from the pause screen with F: the frozen run is shown
+// without the HUD through a camera that pans (arrows/WASD) and zooms (+/-
+// or the mouse wheel), C cycles filters, and Enter saves the shot at
+// PHOTO_SCALE times the screen's resolution
+
+use macroquad::prelude::*;
+
+use crate::game::draw_centered_text;
+use crate::screen::{Context, Screen, Transition};
+use crate::screenshot;
+use crate::viewport::Viewport;
+use crate::{HEIGHT, WIDTH};
+
+const PHOTO_SCALE: u32 = 4;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.0;
+// Zoom doubles every this many seconds while a key is held
+const ZOOM_SECONDS: f32 = 0.8;
+// Screen widths per second, whatever the zoom
+const PAN_SPEED: f32 = 0.6;
+const HINT_SECONDS: f32 = 3.0;
+
+// For drawing textures through a fragment shader, here and in `grading.rs`
+pub const VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+
+varying lowp vec2 uv;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}"#;
+
+// Mode 0 leaves the scene alone; the rest match `Filter`
+const FILTER_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform float Mode;
+
+void main() {
+    vec4 color = texture2D(Texture, uv);
+    float grey = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+    if (Mode > 2.5) {
+        float edge = distance(uv, vec2(0.5));
+        color.rgb *= smoothstep(0.75, 0.35, edge);
+    } else if (Mode > 1.5) {
+        color.rgb = grey * vec3(1.1, 0.9, 0.65);
+    } else if (Mode > 0.5) {
+        color.rgb = vec3(grey);
+    }
+    gl_FragColor = color;
+}"#;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Filter {
+    Plain,
+    Greyscale,
+    Sepia,
+    Vignette,
+}
+
+impl Filter {
+    const ALL: [Filter; 4] = [
+        Filter::Plain,
+        Filter::Greyscale,
+        Filter::Sepia,
+        Filter::Vignette,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Filter::Plain => "No filter",
+            Filter::Greyscale => "Greyscale",
+            Filter::Sepia => "Sepia",
+            Filter::Vignette => "Vignette",
+        }
+    }
+}
+
+pub struct PhotoMode {
+    center: Vec2,
+    zoom: f32,
+    filter: Filter,
+    // The scene through the camera, then with the filter on top
+    scene: RenderTarget,
+    shot: RenderTarget,
+    // Missing if the shader didn't compile, which leaves no filters
+    material: Option<Material>,
+    shown: f32,
+}
+
+impl PhotoMode {
+    pub fn new() -> PhotoMode {
+        let (width, height) = (WIDTH as u32 * PHOTO_SCALE, HEIGHT as u32 * PHOTO_SCALE);
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: VERTEX_SHADER,
+                fragment: FILTER_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![UniformDesc::new("Mode", UniformType::Float1)],
+                ..Default::default()
+            },
+        )
+        .inspect_err(|err| eprintln!("Photo filters are unavailable: {err}"))
+        .ok();
+        PhotoMode {
+            center: vec2(WIDTH / 2.0, HEIGHT / 2.0),
+            zoom: 1.0,
+            filter: Filter::Plain,
+            scene: render_target(width, height),
+            shot: render_target(width, height),
+            material,
+            shown: 0.0,
+        }
+    }
+
+    /// Renders `scene` (drawn in screen coordinates) through the camera and
+    /// filter, then shows it on the virtual screen.
+    fn show(&self, viewport: &Viewport, scene: impl FnOnce()) {
+        // Positive y zoom keeps render targets upright, as in `Viewport`
+        set_camera(&Camera2D {
+            target: self.center,
+            zoom: vec2(2.0 / WIDTH, 2.0 / HEIGHT) * self.zoom,
+            render_target: Some(self.scene.clone()),
+            ..Default::default()
+        });
+        scene();
+
+        set_camera(&Camera2D {
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0),
+            zoom: vec2(2.0 / WIDTH, 2.0 / HEIGHT),
+            render_target: Some(self.shot.clone()),
+            ..Default::default()
+        });
+        if let Some(material) = &self.material {
+            material.set_uniform("Mode", self.filter as u8 as f32);
+            gl_use_material(material);
+        }
+        draw_full(&self.scene.texture);
+        gl_use_default_material();
+
+        viewport.begin();
+        draw_full(&self.shot.texture);
+        if self.shown < HINT_SECONDS {
+            let hint = if self.material.is_some() {
+                format!("{} - C filter, Enter save", self.filter.name())
+            } else {
+                "Enter to save, F to go back".to_owned()
+            };
+            draw_centered_text(&hint, HEIGHT - 20.0, 16.0, WHITE);
+        }
+    }
+}
+
+impl Screen for PhotoMode {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        let dt = get_frame_time();
+        self.shown += dt;
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::F) {
+            return Transition::Pop;
+        }
+
+        let held = |keys: &[KeyCode]| keys.iter().any(|&key| is_key_down(key));
+        let mut pan = Vec2::ZERO;
+        if held(&[KeyCode::Left, KeyCode::A]) {
+            pan.x -= 1.0;
+        }
+        if held(&[KeyCode::Right, KeyCode::D]) {
+            pan.x += 1.0;
+        }
+        if held(&[KeyCode::Up, KeyCode::W]) {
+            pan.y -= 1.0;
+        }
+        if held(&[KeyCode::Down, KeyCode::S]) {
+            pan.y += 1.0;
+        }
+        self.center += pan * WIDTH * PAN_SPEED * dt / self.zoom;
+        self.center = self.center.clamp(Vec2::ZERO, vec2(WIDTH, HEIGHT));
+
+        let wheel = mouse_wheel().1;
+        let mut zoom_steps = if wheel > 0.0 {
+            0.25
+        } else if wheel < 0.0 {
+            -0.25
+        } else {
+            0.0
+        };
+        if held(&[KeyCode::Equal, KeyCode::KpAdd]) {
+            zoom_steps += dt / ZOOM_SECONDS;
+        }
+        if held(&[KeyCode::Minus, KeyCode::KpSubtract]) {
+            zoom_steps -= dt / ZOOM_SECONDS;
+        }
+        self.zoom = (self.zoom * zoom_steps.exp2()).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        if is_key_pressed(KeyCode::C) && self.material.is_some() {
+            let next = Filter::ALL
+                .iter()
+                .position(|&f| f == self.filter)
+                .unwrap_or(0)
+                + 1;
+            self.filter = Filter::ALL[next % Filter::ALL.len()];
+            self.shown = 0.0;
+        }
+        // The shot is saved at full resolution
+        if is_key_pressed(KeyCode::Enter) {
+            match screenshot::save(&self.shot.texture.get_texture_data()) {
+                Ok(path) => cx.toasts.push(format!("Saved {path}"), WHITE),
+                Err(err) => cx.toasts.push(format!("Photo failed: {err}"), RED),
+            }
+        }
+        Transition::Stay
+    }
+
+    /// Just the frozen world, for the camera.
+    fn draw(&self, cx: &Context) {
+        self.show(cx.viewport, || {
+            clear_background(cx.theme.sky_color);
+            cx.theme.draw_background(cx.game.scrolled());
+            cx.game.draw_world(cx.theme);
+            cx.particles.draw_held();
+        });
+    }
+
+    fn covers(&self) -> bool {
+        true
+    }
+}
+
+// Stretches `texture` over the whole virtual screen
+fn draw_full(texture: &Texture2D) {
+    draw_texture_ex(
+        texture,
+        0.0,
+        0.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(vec2(WIDTH, HEIGHT)),
+            ..Default::default()
+        },
+    );
+}
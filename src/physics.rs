@@ -0,0 +1,120 @@
+// Physics presets, picked in the settings menu and kept in the settings:
+// how hard gravity pulls, how fast a flap throws the bird up and how fast
+// it can fall. Classic is the original, floaty is gentler and slower, and
+// heavy is quicker and more punishing; a full hop climbs about as high in
+// each. Runs on the other two keep bests of their own (see `profile.rs`).
+// Challenges, matches and parties are always classic.
+//
+// Whatever the preset, `Limits` keeps the bird from falling faster than a
+// set speed and from flapping again straight after a flap, so mashing the
+// button can't climb faster than the gaps are laid out for. They're in
+// the settings file as `max_fall` and `flap_cooldown`, and the dev console
+// sets them too (see `console.rs`).
+
+use crate::bird::JUMP_VELOCITY;
+use crate::game::DEFAULT_GRAVITY;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Physics {
+    #[default]
+    Classic,
+    Floaty,
+    Heavy,
+}
+
+impl Physics {
+    pub const ALL: [Physics; 3] = [Physics::Classic, Physics::Floaty, Physics::Heavy];
+
+    /// Stable name used in the settings and profile files.
+    pub fn id(self) -> &'static str {
+        match self {
+            Physics::Classic => "classic",
+            Physics::Floaty => "floaty",
+            Physics::Heavy => "heavy",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Physics> {
+        Physics::ALL.into_iter().find(|physics| physics.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Physics::Classic => "Classic",
+            Physics::Floaty => "Floaty",
+            Physics::Heavy => "Heavy",
+        }
+    }
+
+    /// Speed gained falling each tick.
+    pub fn gravity(self) -> f32 {
+        match self {
+            Physics::Classic => DEFAULT_GRAVITY,
+            Physics::Floaty => 0.32,
+            Physics::Heavy => 0.7,
+        }
+    }
+
+    /// Upward speed right after a full flap.
+    pub fn jump_velocity(self) -> f32 {
+        match self {
+            Physics::Classic => JUMP_VELOCITY,
+            Physics::Floaty => -7.6,
+            Physics::Heavy => -11.8,
+        }
+    }
+
+    /// The fastest the bird falls, if the preset holds it under the limit
+    /// in `Limits`; classic leaves it to that.
+    pub fn max_fall(self) -> f32 {
+        match self {
+            Physics::Classic => f32::INFINITY,
+            Physics::Floaty => 6.0,
+            Physics::Heavy => 14.0,
+        }
+    }
+}
+
+/// Pixels a tick the bird falls at most, unless the preset holds it slower.
+pub const DEFAULT_MAX_FALL: f32 = 15.0;
+pub const MIN_MAX_FALL: f32 = 8.0;
+pub const MAX_MAX_FALL: f32 = 30.0;
+/// Ticks after a flap before the next one counts.
+pub const DEFAULT_FLAP_COOLDOWN: u32 = 4;
+pub const MAX_FLAP_COOLDOWN: u32 = 12;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Limits {
+    pub max_fall: f32,
+    pub flap_cooldown: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_fall: DEFAULT_MAX_FALL,
+            flap_cooldown: DEFAULT_FLAP_COOLDOWN,
+        }
+    }
+}
+
+impl Limits {
+    /// The fastest the bird falls flying with `physics`.
+    pub fn max_fall_with(self, physics: Physics) -> f32 {
+        physics.max_fall().min(self.max_fall)
+    }
+
+    /// Brought within the ranges the course is laid out for, as from a
+    /// settings file edited by hand.
+    pub fn clamped(self) -> Limits {
+        let max_fall = if self.max_fall.is_nan() {
+            DEFAULT_MAX_FALL
+        } else {
+            self.max_fall.clamp(MIN_MAX_FALL, MAX_MAX_FALL)
+        };
+        Limits {
+            max_fall,
+            flap_cooldown: self.flap_cooldown.min(MAX_FLAP_COOLDOWN),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::ops::RangeInclusive;
+
+use macroquad::prelude::*;
+
+use crate::attacks::Attack;
+use crate::obstacle::Obstacle;
+use crate::theme::Theme;
+use crate::viewport::FIELD_LEFT;
+use crate::{HEIGHT, WIDTH};
+
+// The classic pipe's size, which is also its sprite's
+pub const PIPE_WIDTH: f32 = 52.0;
+pub const PIPE_HEIGHT: f32 = 320.0;
+// Widths any pipe is kept to, however wide its sprite or level says
+pub const MIN_PIPE_WIDTH: f32 = 16.0;
+pub const MAX_PIPE_WIDTH: f32 = 96.0;
+// Pixels the world moves left each tick
+pub const SCROLL_SPEED: f32 = 5.0;
+// Horizontal distance between consecutive pipe pairs
+pub const PIPE_SPACING: f32 = 300.0;
+// Vertical opening between the two pipes of a pair
+pub const GAP_HEIGHT: f32 = 100.0;
+// Moving pairs turn around when their gap gets this close to either edge
+const DRIFT_MARGIN: f32 = 60.0;
+// How tall a sprite's cap is, the rows at its open end, for each pixel of
+// its width: 24 rows on the classic 52-wide pipe
+const CAP_ASPECT: f32 = 24.0 / 52.0;
+
+// What a pipe pair looks like. Each has its own sprite in the theme, and
+// its hitbox is the size of that sprite. Sprites are drawn as a cap, at the
+// end by the gap, and a body under it repeated as far as the pipe goes, so
+// a pipe of any length looks right whatever the sprite's height.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum PipeStyle {
+    #[default]
+    Pipe,
+    Stone,
+    Tree,
+}
+
+impl PipeStyle {
+    pub const ALL: [PipeStyle; 3] = [PipeStyle::Pipe, PipeStyle::Stone, PipeStyle::Tree];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            PipeStyle::Pipe => "pipe",
+            PipeStyle::Stone => "stone",
+            PipeStyle::Tree => "tree",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<PipeStyle> {
+        PipeStyle::ALL.into_iter().find(|style| style.id() == id)
+    }
+
+    /// The sprite's file, in a theme or the default sprites.
+    pub fn sprite_file(self) -> &'static str {
+        match self {
+            PipeStyle::Pipe => "pipe.png",
+            PipeStyle::Stone => "stone.png",
+            PipeStyle::Tree => "tree.png",
+        }
+    }
+
+    /// Where the style's sprite is in lists of them, in `ALL` order.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How big each style's pipes are, from the theme's sprites. Without a
+/// theme, as in tests, they're the default sprites' sizes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PipeSizes([Vec2; PipeStyle::ALL.len()]);
+
+impl Default for PipeSizes {
+    fn default() -> PipeSizes {
+        PipeSizes([
+            vec2(PIPE_WIDTH, PIPE_HEIGHT),
+            vec2(64.0, PIPE_HEIGHT),
+            vec2(44.0, PIPE_HEIGHT),
+        ])
+    }
+}
+
+impl PipeSizes {
+    /// Sizes from each style's sprite, in `PipeStyle::ALL` order. Widths
+    /// are kept between MIN_PIPE_WIDTH and MAX_PIPE_WIDTH, and short
+    /// sprites make pipes PIPE_HEIGHT long all the same so none leaves a hole
+    /// between it and the edge of the screen. The classic pipe stays the
+    /// classic size whatever its sprite, so runs and replays of the classic
+    /// course play the same in every theme.
+    pub fn from_sprites(sprites: [Vec2; PipeStyle::ALL.len()]) -> PipeSizes {
+        let mut sizes = sprites.map(|size| {
+            vec2(
+                size.x.clamp(MIN_PIPE_WIDTH, MAX_PIPE_WIDTH),
+                size.y.max(PIPE_HEIGHT),
+            )
+        });
+        sizes[PipeStyle::Pipe.index()] = vec2(PIPE_WIDTH, PIPE_HEIGHT);
+        PipeSizes(sizes)
+    }
+
+    pub fn of(&self, style: PipeStyle) -> Vec2 {
+        self.0[style.index()]
+    }
+}
+
+/// Where a gap's top edge can go, in pixels from the top of the screen, for
+/// pipes `pipe_height` tall to still reach the top of the screen and the
+/// ground, so there's no way round them. Every style's pipes are at least
+/// PIPE_HEIGHT tall, which makes `gap_top_range(PIPE_HEIGHT)` safe for all.
+pub fn gap_top_range(pipe_height: f32) -> RangeInclusive<f32> {
+    let lowest = (HEIGHT - GAP_HEIGHT - pipe_height).max(0.0);
+    let highest = (HEIGHT - GAP_HEIGHT).min(pipe_height);
+    lowest..=highest
+}
+
+// Pipe pair struct
+#[derive(Clone)]
+pub struct PairOfPipes {
+    pub x: f32,
+    pub top_y: f32,
+    pub bottom_y: f32,
+    pub style: PipeStyle,
+    // Of both pipes, and their hitboxes; the sprite is scaled to the width
+    // and its body repeated to the height
+    pub width: f32,
+    pub height: f32,
+    pub passed: bool,
+    // Closest the bird has come to this pair, for near-miss detection
+    pub closest: f32,
+    // Whether the bird's line through the gap was already judged for combos
+    pub judged: bool,
+    // Vertical speed of a moving pair, 0 for a still one
+    pub drift: f32,
+    // Garbage sent by a versus opponent, see `attacks.rs`
+    pub attack: Option<Attack>,
+    // Counts from 1 for the first pair of a run
+    pub number: u32,
+    // Ticks since the pair scrolled onto the screen
+    pub shown: u32,
+    // Faded out by the invisible pipes challenge
+    pub alpha: f32,
+}
+
+impl PairOfPipes {
+    /// Builds a pair whose gap starts at `top_bottom_y` (the bottom edge of the top pipe).
+    pub fn with_gap(x: f32, top_bottom_y: f32) -> Self {
+        PairOfPipes {
+            x,
+            top_y: top_bottom_y - PIPE_HEIGHT,
+            bottom_y: top_bottom_y + GAP_HEIGHT,
+            style: PipeStyle::Pipe,
+            width: PIPE_WIDTH,
+            height: PIPE_HEIGHT,
+            passed: false,
+            closest: f32::INFINITY,
+            judged: false,
+            drift: 0.0,
+            attack: None,
+            number: 0,
+            shown: 0,
+            alpha: 1.0,
+        }
+    }
+
+    /// Gives the pair `style`'s sprite at `size`, keeping the gap where it
+    /// is.
+    pub fn set_style(&mut self, style: PipeStyle, size: Vec2) {
+        let gap_top = self.gap_top();
+        self.style = style;
+        self.width = size.x;
+        self.height = size.y;
+        self.top_y = gap_top - size.y;
+    }
+
+    /// Moves the bottom pipe so the gap is `height` tall.
+    pub fn set_gap_height(&mut self, height: f32) {
+        self.bottom_y = self.gap_top() + height;
+    }
+
+    /// Grows the gap by `extra` pixels, half on each side.
+    pub fn widen(&mut self, extra: f32) {
+        self.top_y -= extra / 2.0;
+        self.bottom_y += extra / 2.0;
+    }
+
+    pub fn gap_top(&self) -> f32 {
+        self.top_y + self.height
+    }
+
+    pub fn gap_bottom(&self) -> f32 {
+        self.bottom_y
+    }
+
+    pub fn top_rect(&self) -> Rect {
+        Rect::new(self.x, self.top_y, self.width, self.height)
+    }
+
+    pub fn bottom_rect(&self) -> Rect {
+        Rect::new(self.x, self.bottom_y, self.width, self.height)
+    }
+
+    /// Where the pair's right edge is.
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+}
+
+impl Obstacle for PairOfPipes {
+    fn update(&mut self) {
+        self.x -= SCROLL_SPEED;
+        if self.drift != 0.0 {
+            self.top_y += self.drift;
+            self.bottom_y += self.drift;
+            if self.gap_top() < DRIFT_MARGIN || self.gap_bottom() > HEIGHT - DRIFT_MARGIN {
+                self.drift = -self.drift;
+            }
+        }
+    }
+
+    fn hitboxes(&self) -> Vec<Rect> {
+        vec![self.top_rect(), self.bottom_rect()]
+    }
+
+    fn on_screen(&self) -> bool {
+        self.x < WIDTH && self.right() > 0.0
+    }
+
+    fn is_gone(&self) -> bool {
+        self.right() < FIELD_LEFT
+    }
+
+    fn draw(&self, theme: &Theme) {
+        if self.alpha <= 0.0 {
+            return;
+        }
+        let tint = Color {
+            a: theme.tint.a * self.alpha,
+            ..theme.tint
+        };
+        let sprite = theme.pipe(self.style);
+        // The top pipe is the sprite flipped, going up from the gap
+        draw_pipe(sprite, self, self.gap_top(), true, tint);
+        draw_pipe(sprite, self, self.bottom_y, false, tint);
+    }
+}
+
+// Draws one of `pair`'s pipes from its open end at `open_y`, upwards or
+// downwards: the sprite's cap and then its body as many times as it takes,
+// the last one cut short
+fn draw_pipe(sprite: &Texture2D, pair: &PairOfPipes, open_y: f32, upwards: bool, tint: Color) {
+    let (sprite_width, sprite_height) = (sprite.width(), sprite.height());
+    let scale = pair.width / sprite_width;
+    let cap_rows = (sprite_width * CAP_ASPECT).min(sprite_height);
+    // A sprite that's all cap repeats the whole of itself
+    let body = if sprite_height > cap_rows {
+        (cap_rows, sprite_height - cap_rows)
+    } else {
+        (0.0, sprite_height)
+    };
+    let mut drawn = 0.0;
+    let mut segment = (0.0, cap_rows);
+    while drawn < pair.height {
+        let (first_row, rows) = segment;
+        let rows = rows.min((pair.height - drawn) / scale);
+        let height = rows * scale;
+        let y = if upwards {
+            open_y - drawn - height
+        } else {
+            open_y + drawn
+        };
+        draw_texture_ex(
+            sprite,
+            pair.x,
+            y,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(vec2(pair.width, height)),
+                source: Some(Rect::new(0.0, first_row, sprite_width, rows)),
+                flip_y: upwards,
+                ..Default::default()
+            },
+        );
+        drawn += height;
+        segment = body;
+    }
+}
@@ -0,0 +1,118 @@
+// Platform differences: where saves go and which parts of the screen are
+// covered by notches or system bars
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+/// Edges of the window that should be kept clear, in logical pixels.
+#[derive(Clone, Copy, Default)]
+pub struct Insets {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+/// Where the save file `file` lives: next to the game on desktop, in the
+/// app's private data directory on Android. The web build has no files and
+/// uses browser storage instead (see `storage.rs`).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn data_path(file: &str) -> PathBuf {
+    data_dir().join(file)
+}
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn data_dir() -> PathBuf {
+    PathBuf::from(".")
+}
+
+/// Opens `url` in the browser. Only desktop builds can; elsewhere this
+/// returns false and the link has to be shown instead.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn open_url(url: &str) -> bool {
+    use std::process::Command;
+
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url).spawn().is_ok()
+}
+
+#[cfg(any(target_os = "android", target_arch = "wasm32"))]
+pub fn open_url(url: &str) -> bool {
+    let _ = url;
+    false
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn safe_insets() -> Insets {
+    Insets::default()
+}
+
+#[cfg(target_os = "android")]
+fn data_dir() -> PathBuf {
+    use macroquad::miniquad::native::android::{ACTIVITY, attach_jni_env};
+    use macroquad::miniquad::{call_object_method, get_utf_str};
+
+    // Context.getFilesDir().getAbsolutePath()
+    unsafe {
+        let env = attach_jni_env();
+        let dir = call_object_method!(env, ACTIVITY, "getFilesDir", "()Ljava/io/File;");
+        if dir.is_null() {
+            return PathBuf::from(".");
+        }
+        let path = call_object_method!(env, dir, "getAbsolutePath", "()Ljava/lang/String;");
+        PathBuf::from(get_utf_str!(env, path))
+    }
+}
+
+#[cfg(target_os = "android")]
+pub fn safe_insets() -> Insets {
+    use macroquad::miniquad::native::android::{ACTIVITY, attach_jni_env};
+    use macroquad::miniquad::{call_int_method, call_object_method};
+
+    // getWindow().getDecorView().getRootWindowInsets().getDisplayCutout()
+    // (null before Android 9 or on phones without a cutout)
+    let px = unsafe {
+        let env = attach_jni_env();
+        let window = call_object_method!(env, ACTIVITY, "getWindow", "()Landroid/view/Window;");
+        let view = call_object_method!(env, window, "getDecorView", "()Landroid/view/View;");
+        let insets = call_object_method!(
+            env,
+            view,
+            "getRootWindowInsets",
+            "()Landroid/view/WindowInsets;"
+        );
+        if insets.is_null() {
+            return Insets::default();
+        }
+        let cutout = call_object_method!(
+            env,
+            insets,
+            "getDisplayCutout",
+            "()Landroid/view/DisplayCutout;"
+        );
+        if cutout.is_null() {
+            return Insets::default();
+        }
+        [
+            call_int_method!(env, cutout, "getSafeInsetTop", "()I"),
+            call_int_method!(env, cutout, "getSafeInsetBottom", "()I"),
+            call_int_method!(env, cutout, "getSafeInsetLeft", "()I"),
+            call_int_method!(env, cutout, "getSafeInsetRight", "()I"),
+        ]
+    };
+    let scale = macroquad::miniquad::window::dpi_scale();
+    Insets {
+        top: px[0] as f32 / scale,
+        bottom: px[1] as f32 / scale,
+        left: px[2] as f32 / scale,
+        right: px[3] as f32 / scale,
+    }
+}
@@ -0,0 +1,59 @@
+// "+1" floating up from the bird as it scores a pipe, "+2" and so on with
+// near-miss bonuses and combos (purely visual, like particles)
+
+use macroquad::prelude::*;
+
+use crate::tween::{ease_in_quad, ease_out_cubic};
+
+const SHOW_SECONDS: f32 = 0.8;
+// How far a popup rises over its life
+const RISE: f32 = 28.0;
+const FONT_SIZE: u16 = 18;
+
+struct Popup {
+    text: String,
+    // Where it starts, centered above the bird
+    pos: Vec2,
+    age: f32,
+}
+
+#[derive(Default)]
+pub struct ScorePopups {
+    popups: Vec<Popup>,
+}
+
+impl ScorePopups {
+    /// Shows `points` rising from `pos`.
+    pub fn push(&mut self, points: i64, pos: Vec2) {
+        self.popups.push(Popup {
+            text: format!("+{points}"),
+            pos,
+            age: 0.0,
+        });
+    }
+
+    pub fn count(&self) -> usize {
+        self.popups.len()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for popup in &mut self.popups {
+            popup.age += dt;
+        }
+        self.popups.retain(|popup| popup.age < SHOW_SECONDS);
+    }
+
+    pub fn draw(&self, color: Color) {
+        for popup in &self.popups {
+            let t = popup.age / SHOW_SECONDS;
+            let size = measure_text(&popup.text, None, FONT_SIZE, 1.0);
+            let x = popup.pos.x - size.width / 2.0;
+            let y = popup.pos.y - RISE * ease_out_cubic(t);
+            let color = Color {
+                a: color.a * (1.0 - ease_in_quad(t)),
+                ..color
+            };
+            draw_text(&popup.text, x, y, f32::from(FONT_SIZE), color);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Power-ups that float in pipe gaps and are collected by flying through them
+
+use macroquad::prelude::*;
+
+use crate::pipes::SCROLL_SPEED;
+
+pub const POWERUP_SIZE: f32 = 16.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PowerUpKind {
+    // Absorbs one hit
+    Shield,
+    // Pulls nearby coins in for a few seconds
+    Magnet,
+}
+
+impl PowerUpKind {
+    /// Stable name, as the event feed gives it (see `event_server.rs`).
+    pub fn id(self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "shield",
+            PowerUpKind::Magnet => "magnet",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PowerUpKind::Shield => SKYBLUE,
+            PowerUpKind::Magnet => RED,
+        }
+    }
+
+    fn letter(self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "S",
+            PowerUpKind::Magnet => "M",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub pos: Vec2,
+    pub taken: bool,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind, pos: Vec2) -> Self {
+        PowerUp {
+            kind,
+            pos,
+            taken: false,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.pos.x -= SCROLL_SPEED;
+    }
+
+    pub fn rect(&self) -> Rect {
+        let half = POWERUP_SIZE / 2.0;
+        Rect::new(
+            self.pos.x - half,
+            self.pos.y - half,
+            POWERUP_SIZE,
+            POWERUP_SIZE,
+        )
+    }
+
+    pub fn draw(&self) {
+        let color = self.kind.color();
+        draw_circle(self.pos.x, self.pos.y, POWERUP_SIZE / 2.0, color);
+        draw_circle_lines(self.pos.x, self.pos.y, POWERUP_SIZE / 2.0, 1.5, WHITE);
+        draw_text(
+            self.kind.letter(),
+            self.pos.x - 4.0,
+            self.pos.y + 5.0,
+            16.0,
+            WHITE,
+        );
+    }
+}
@@ -0,0 +1,119 @@
+// Discord Rich Presence: shows what the player is doing on their profile
+//
+// Needs the `discord` cargo feature and a Discord application id, baked in
+// at build time through the FLAPPY_DISCORD_APP_ID environment variable.
+// Without either (or without Discord running) this does nothing.
+//
+// The same status goes to Steam too, in `steam` builds started from Steam.
+
+#[cfg(feature = "discord")]
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient, activity::Activity};
+
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::shutdown::Shutdown;
+use crate::steam::Steam;
+
+// Discord drops updates sent faster than about one every few seconds
+const MIN_UPDATE_SECONDS: f32 = 4.0;
+
+pub struct Presence {
+    #[cfg(feature = "discord")]
+    client: Option<DiscordIpcClient>,
+    #[cfg(feature = "steam")]
+    steam: Option<steamworks::Client>,
+    // Status waiting to be sent
+    pending: Option<String>,
+    cooldown: f32,
+}
+
+impl Presence {
+    pub fn connect(steam: &Steam) -> Self {
+        #[cfg(not(feature = "steam"))]
+        let _ = steam;
+        Presence {
+            #[cfg(feature = "discord")]
+            client: connect_client(),
+            #[cfg(feature = "steam")]
+            steam: steam.client(),
+            pending: Some("In menu".to_owned()),
+            cooldown: 0.0,
+        }
+    }
+
+    /// Picks the status to show after `event`. `best_score` is the best
+    /// before this run.
+    pub fn handle(&mut self, event: GameEvent, game: &Game, best_score: i64) {
+        let status = match event {
+            GameEvent::RunStarted | GameEvent::PipePassed { .. } => {
+                format!("Flapping \u{2014} score {}", game.score)
+            }
+            GameEvent::Collided if game.score > best_score => {
+                format!("New best: {}!", game.score)
+            }
+            GameEvent::Collided => "In menu".to_owned(),
+            _ => return,
+        };
+        // Steam has no rate limit to wait for
+        #[cfg(feature = "steam")]
+        if let Some(client) = &self.steam {
+            crate::steam::set_status(client, &status);
+        }
+        self.pending = Some(status);
+    }
+
+    /// Sends the latest status once the rate limit allows.
+    pub fn update(&mut self, dt: f32) {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+        if self.cooldown > 0.0 {
+            return;
+        }
+        if let Some(status) = self.pending.take() {
+            self.send(&status);
+            self.cooldown = MIN_UPDATE_SECONDS;
+        }
+    }
+
+    #[cfg(feature = "discord")]
+    fn send(&mut self, status: &str) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        if let Err(err) = client.set_activity(Activity::new().state(status)) {
+            // Discord was closed; stop trying rather than erroring every update
+            eprintln!("Discord presence disconnected: {err}");
+            self.client = None;
+        }
+    }
+
+    #[cfg(not(feature = "discord"))]
+    fn send(&mut self, _status: &str) {}
+}
+
+impl Shutdown for Presence {
+    // Takes the status down rather than leaving it to go stale
+    fn shutdown(&mut self) {
+        #[cfg(feature = "discord")]
+        if let Some(client) = &mut self.client {
+            let _ = client.clear_activity();
+            let _ = client.close();
+        }
+        #[cfg(feature = "steam")]
+        if let Some(client) = &self.steam {
+            client.friends().clear_rich_presence();
+        }
+    }
+}
+
+#[cfg(feature = "discord")]
+fn connect_client() -> Option<DiscordIpcClient> {
+    let app_id = option_env!("FLAPPY_DISCORD_APP_ID")?;
+    let mut client = DiscordIpcClient::new(app_id);
+    match client.connect() {
+        Ok(()) => Some(client),
+        Err(err) => {
+            eprintln!("Could not connect to Discord: {err}");
+            None
+        }
+    }
+}
@@ -0,0 +1,484 @@
+// Lifetime stats, play streaks and unlocked achievements, saved as
+// `profile.cfg`
+
+use std::collections::HashMap;
+
+use crate::HEIGHT;
+use crate::achievements::Achievement;
+use crate::challenge::Challenge;
+use crate::controls::ControlScheme;
+use crate::cosmetics::{Item, Look};
+use crate::date;
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::history::{self, Run};
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::mod_manager::ModList;
+use crate::physics::{Limits, Physics};
+use crate::shutdown::Shutdown;
+use crate::storage;
+
+pub const PROFILE_FILE: &str = "profile.cfg";
+// Older crashes are dropped so the file stays small
+const MAX_DEATHS: usize = 500;
+// A bit over a year, enough for the calendar and the longest streaks
+const MAX_PLAYED_DAYS: usize = 400;
+
+// Where a run ended, for the heatmap on the stats screen
+#[derive(Clone, Copy)]
+pub struct Death {
+    // Pipe pairs passed before the crash, so 0 is the first
+    pub pipe: u32,
+    // From the middle of the gap being flown at to the bird's, down positive
+    pub offset: i32,
+    pub ground: bool,
+}
+
+impl Death {
+    fn of(game: &Game) -> Death {
+        let bird = game.bird.rect();
+        let ahead = game.pipes.iter().find(|pipe| pipe.right() >= bird.x);
+        let offset = ahead.map_or(0.0, |pipe| {
+            bird.center().y - (pipe.gap_top() + pipe.gap_bottom()) / 2.0
+        });
+        Death {
+            pipe: game.pipes_passed,
+            offset: offset.round() as i32,
+            ground: bird.bottom() >= HEIGHT,
+        }
+    }
+
+    // `pipe:offset`, with `:ground` on the end for ground crashes
+    fn parse(text: &str) -> Option<Death> {
+        let mut parts = text.trim().split(':');
+        Some(Death {
+            pipe: parts.next()?.parse().ok()?,
+            offset: parts.next()?.parse().ok()?,
+            ground: parts.next() == Some("ground"),
+        })
+    }
+
+    fn encode(self) -> String {
+        let ground = if self.ground { ":ground" } else { "" };
+        format!("{}:{}{ground}", self.pipe, self.offset)
+    }
+}
+
+#[derive(Default)]
+pub struct Profile {
+    pub runs: u32,
+    pub best_score: i64,
+    // Best score with assists on, kept apart from `best_score`
+    pub best_assisted: i64,
+    // Best score with any mutators on, likewise
+    pub best_mutated: i64,
+    // Each challenge's best, saved as `best_<id>`
+    pub challenge_bests: HashMap<Challenge, i64>,
+    // Best scores flying floaty and heavy, saved as `best_physics_<id>`
+    pub physics_bests: HashMap<Physics, i64>,
+    // The most stars each finished level has earned, by level id
+    pub level_stars: HashMap<String, u32>,
+    // Each level's best score, since their pipes can score anything
+    pub level_bests: HashMap<String, i64>,
+    // The Monday (days since 1970-01-01) the weekly best is for
+    pub weekly_week: i64,
+    // Quests finished on `quest_day` (days since 1970-01-01), by id
+    pub quest_day: i64,
+    pub quests_done: Vec<String>,
+    pub pipes_passed: u32,
+    pub flaps: u64,
+    pub milestones: u32,
+    pub coins: u32,
+    // Cosmetics bought from the wardrobe, and what the bird has on
+    pub owned: Vec<Item>,
+    pub look: Look,
+    // Finished or skipped the first-launch tutorial
+    pub tutorial_done: bool,
+    pub controls: ControlScheme,
+    pub achievements: Vec<Achievement>,
+    // Oldest first
+    pub deaths: Vec<Death>,
+    // Days since 1970-01-01 (UTC) that ended at least one run, oldest first
+    pub played_days: Vec<i64>,
+    // Every run, oldest first; kept in its own file
+    pub history: Vec<Run>,
+    // Mods turned off and their load order, see `mod_manager.rs`
+    pub mods: ModList,
+}
+
+impl Profile {
+    /// Loads the saved profile, or a fresh one before the first save. The
+    /// history is loaded on its own, with `history::load`.
+    pub fn load() -> Result<Self, LoadError> {
+        let text = storage::load_save(PROFILE_FILE)?.unwrap_or_default();
+        Ok(Profile::parse(&text))
+    }
+
+    /// Reads a saved profile, skipping anything that doesn't make sense.
+    pub fn parse(text: &str) -> Self {
+        let kv = KeyValues::parse(text);
+        let unlocked = kv.get("achievements").unwrap_or_default();
+        Profile {
+            runs: kv.get_parsed("runs").unwrap_or_default(),
+            best_score: kv.get_parsed("best_score").unwrap_or_default(),
+            best_assisted: kv.get_parsed("best_assisted").unwrap_or_default(),
+            best_mutated: kv.get_parsed("best_mutated").unwrap_or_default(),
+            challenge_bests: Challenge::ALL
+                .into_iter()
+                .filter_map(|c| Some((c, kv.get_parsed(&format!("best_{}", c.id()))?)))
+                .collect(),
+            physics_bests: Physics::ALL
+                .into_iter()
+                .filter_map(|p| Some((p, kv.get_parsed(&format!("best_physics_{}", p.id()))?)))
+                .collect(),
+            level_stars: parse_level_map(kv.get("level_stars")),
+            level_bests: parse_level_map(kv.get("level_bests")),
+            weekly_week: kv.get_parsed("weekly_week").unwrap_or_default(),
+            quest_day: kv.get_parsed("quest_day").unwrap_or_default(),
+            quests_done: kv
+                .get("quests_done")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            pipes_passed: kv.get_parsed("pipes_passed").unwrap_or_default(),
+            flaps: kv.get_parsed("flaps").unwrap_or_default(),
+            milestones: kv.get_parsed("milestones").unwrap_or_default(),
+            coins: kv.get_parsed("coins").unwrap_or_default(),
+            owned: items(kv.get("cosmetics")),
+            look: Look::wearing(&items(kv.get("wearing"))),
+            tutorial_done: kv.get_bool("tutorial_done").unwrap_or_default(),
+            controls: kv
+                .get("controls")
+                .and_then(ControlScheme::from_id)
+                .unwrap_or_default(),
+            achievements: Achievement::ALL
+                .into_iter()
+                .filter(|a| unlocked.split(',').any(|id| id.trim() == a.id()))
+                .collect(),
+            deaths: kv
+                .get("deaths")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(Death::parse)
+                .collect(),
+            played_days: kv
+                .get("played_days")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|day| day.trim().parse().ok())
+                .collect(),
+            history: Vec::new(),
+            mods: ModList::from_values(&kv),
+        }
+    }
+
+    pub fn save(&self) {
+        let mut kv = KeyValues::default();
+        kv.set("runs", self.runs);
+        kv.set("best_score", self.best_score);
+        kv.set("best_assisted", self.best_assisted);
+        kv.set("best_mutated", self.best_mutated);
+        for (challenge, best) in &self.challenge_bests {
+            kv.set(&format!("best_{}", challenge.id()), best);
+        }
+        for (physics, best) in &self.physics_bests {
+            kv.set(&format!("best_physics_{}", physics.id()), best);
+        }
+        kv.set("level_stars", level_map_value(&self.level_stars));
+        kv.set("level_bests", level_map_value(&self.level_bests));
+        kv.set("weekly_week", self.weekly_week);
+        kv.set("quest_day", self.quest_day);
+        kv.set("quests_done", self.quests_done.join(","));
+        kv.set("pipes_passed", self.pipes_passed);
+        kv.set("flaps", self.flaps);
+        kv.set("milestones", self.milestones);
+        kv.set("coins", self.coins);
+        let owned: Vec<&str> = self.owned.iter().map(|item| item.id()).collect();
+        kv.set("cosmetics", owned.join(","));
+        let wearing: Vec<&str> = self.look.items().map(Item::id).collect();
+        kv.set("wearing", wearing.join(","));
+        kv.set("tutorial_done", self.tutorial_done);
+        kv.set("controls", self.controls.id());
+        let unlocked: Vec<&str> = self.achievements.iter().map(|a| a.id()).collect();
+        kv.set("achievements", unlocked.join(","));
+        let deaths: Vec<String> = self.deaths.iter().map(|d| d.encode()).collect();
+        kv.set("deaths", deaths.join(","));
+        let played: Vec<String> = self.played_days.iter().map(i64::to_string).collect();
+        kv.set("played_days", played.join(","));
+        self.mods.write_values(&mut kv);
+        if let Err(err) = storage::backend().save_text(PROFILE_FILE, &kv.to_string()) {
+            eprintln!("Could not save profile: {err}");
+        }
+    }
+
+    /// The best score `game`'s run is up against, on its `Board`.
+    pub fn best_for(&self, game: &Game) -> i64 {
+        match Board::of(game) {
+            Board::Standard => self.best_score,
+            Board::Assisted => self.best_assisted,
+            Board::Mutated => self.best_mutated,
+            Board::Level(id) => self.level_bests.get(&id).copied().unwrap_or(0),
+            Board::Physics(physics) => self.physics_bests.get(&physics).copied().unwrap_or(0),
+            Board::Challenge(challenge) => {
+                self.challenge_bests.get(&challenge).copied().unwrap_or(0)
+            }
+        }
+    }
+
+    // Raises the best that `best_for` gives for `game` to its score
+    fn record_best(&mut self, game: &Game) {
+        let best = match Board::of(game) {
+            Board::Standard => &mut self.best_score,
+            Board::Assisted => &mut self.best_assisted,
+            Board::Mutated => &mut self.best_mutated,
+            Board::Level(id) => self.level_bests.entry(id).or_default(),
+            Board::Physics(physics) => self.physics_bests.entry(physics).or_default(),
+            Board::Challenge(challenge) => self.challenge_bests.entry(challenge).or_default(),
+        };
+        *best = (*best).max(game.score);
+    }
+
+    /// Drops the weekly best if it's for a week before `week`'s.
+    pub fn start_week(&mut self, week: i64) {
+        if self.weekly_week != week {
+            self.challenge_bests.remove(&Challenge::Weekly);
+            self.weekly_week = week;
+        }
+    }
+
+    /// Forgets the quests finished before `day`, so its own can be done.
+    pub fn start_quest_day(&mut self, day: i64) {
+        if self.quest_day != day {
+            self.quests_done.clear();
+            self.quest_day = day;
+        }
+    }
+
+    /// Records `achievement`, returning false if it was already unlocked.
+    pub fn unlock(&mut self, achievement: Achievement) -> bool {
+        if self.achievements.contains(&achievement) {
+            return false;
+        }
+        self.achievements.push(achievement);
+        true
+    }
+
+    /// Days in a row with a run, up to today. A streak carries on until a
+    /// whole day is missed, so one that ended yesterday still counts.
+    pub fn streak(&self, today: i64) -> u32 {
+        let mut day = match self.played_days.last() {
+            Some(&last) if last >= today - 1 => last,
+            _ => return 0,
+        };
+        let mut streak = 0;
+        for &played in self.played_days.iter().rev() {
+            if played != day {
+                break;
+            }
+            streak += 1;
+            day -= 1;
+        }
+        streak
+    }
+
+    pub fn best_streak(&self) -> u32 {
+        let (mut best, mut current) = (0, 0);
+        let mut previous = None;
+        for &day in &self.played_days {
+            current = if previous == Some(day - 1) {
+                current + 1
+            } else {
+                1
+            };
+            best = best.max(current);
+            previous = Some(day);
+        }
+        best
+    }
+
+    pub fn played_on(&self, day: i64) -> bool {
+        self.played_days.binary_search(&day).is_ok()
+    }
+
+    // Marks today as played, returning any streak achievement that unlocks
+    fn record_play(&mut self) -> Option<Achievement> {
+        let today = date::today_days();
+        // A clock set back doesn't get to rewrite the past
+        if self.played_days.last().is_none_or(|&last| last < today) {
+            self.played_days.push(today);
+            let extra = self.played_days.len().saturating_sub(MAX_PLAYED_DAYS);
+            self.played_days.drain(..extra);
+        }
+        Achievement::for_streak(self.streak(today)).filter(|&a| self.unlock(a))
+    }
+
+    /// Updates stats from a game event. Returns any achievement it unlocked.
+    pub fn handle(&mut self, event: GameEvent, game: &Game) -> Option<Achievement> {
+        match event {
+            GameEvent::PipePassed { .. } => self.pipes_passed += 1,
+            GameEvent::Flapped => self.flaps += 1,
+            GameEvent::CoinCollected => self.coins += 1,
+            GameEvent::ScoreRolledOver { .. } => {
+                return Some(Achievement::Odometer).filter(|&a| self.unlock(a));
+            }
+            GameEvent::MilestoneReached { pipes } => {
+                self.milestones += 1;
+                return Achievement::for_milestone(pipes).filter(|&a| self.unlock(a));
+            }
+            GameEvent::Collided => {
+                self.runs += 1;
+                self.record_best(game);
+                self.deaths.push(Death::of(game));
+                let extra = self.deaths.len().saturating_sub(MAX_DEATHS);
+                self.deaths.drain(..extra);
+                history::record(&mut self.history, Run::of(game));
+                let unlocked = self.record_play();
+                self.save();
+                return unlocked;
+            }
+            GameEvent::LevelCompleted => {
+                self.record_best(game);
+                if let (Some(level), Some(stars)) = (&game.level, game.stars()) {
+                    let best = self.level_stars.entry(level.id.clone()).or_default();
+                    *best = (*best).max(stars);
+                }
+                let unlocked = self.record_play();
+                self.save();
+                return unlocked;
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Shutdown for Profile {
+    fn shutdown(&mut self) {
+        self.save();
+    }
+}
+
+// Reads a list of item ids, skipping ones that aren't for sale
+fn items(list: Option<&str>) -> Vec<Item> {
+    list.unwrap_or_default()
+        .split(',')
+        .filter_map(|id| Item::from_id(id.trim()))
+        .collect()
+}
+
+/// Which runs a run competes with. Assisted runs (and ones flown with the
+/// fall and flap limits changed) only compete with each other, and so do
+/// runs with mutators, each level's, each challenge's and each physics
+/// preset's. Only a plain run on the endless course is on the standard
+/// board, the one `best_score` is for.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Board {
+    Standard,
+    Assisted,
+    Mutated,
+    Level(String),
+    Physics(Physics),
+    Challenge(Challenge),
+}
+
+impl Board {
+    pub fn of(game: &Game) -> Board {
+        match (&game.level, game.challenge) {
+            _ if assisted(game) => Board::Assisted,
+            // The weekly challenge brings mutators of its own; they're what
+            // its runs all fly with, not the player's pick
+            (None, Some(Challenge::Weekly)) => Board::Challenge(Challenge::Weekly),
+            _ if game.mutators.any() => Board::Mutated,
+            (Some(level), _) => Board::Level(level.id.clone()),
+            _ if game.physics != Physics::Classic => Board::Physics(game.physics),
+            (None, Some(challenge)) => Board::Challenge(challenge),
+            (None, None) => Board::Standard,
+        }
+    }
+}
+
+// Whether `game` is eased off, or tuned away from the limits everyone else
+// flies with
+fn assisted(game: &Game) -> bool {
+    game.assists.any() || game.practice || game.limits != Limits::default()
+}
+
+// Reads a list of `level_id:number` entries, like `level_stars`
+fn parse_level_map<T: std::str::FromStr>(list: Option<&str>) -> HashMap<String, T> {
+    list.unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let (id, value) = entry.trim().split_once(':')?;
+            Some((id.to_owned(), value.parse().ok()?))
+        })
+        .collect()
+}
+
+fn level_map_value<T: std::fmt::Display>(map: &HashMap<String, T>) -> String {
+    let mut entries: Vec<String> = map
+        .iter()
+        .map(|(id, value)| format!("{id}:{value}"))
+        .collect();
+    entries.sort();
+    entries.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::level::Level;
+    use crate::mutators::{Mutator, Mutators};
+
+    #[test]
+    fn only_plain_endless_runs_set_the_best_score() {
+        let mut profile = Profile::default();
+        let mut game = Game::from_seed(1);
+        game.score = 30;
+        game.set_mutators(Mutators::of(&[Mutator::TinyBird]));
+        profile.record_best(&game);
+        game.set_mutators(Mutators::default());
+        let level = Level::parse(b"name = Test\npipes = 200, 200\n", "test").unwrap();
+        game.set_level(Some(Rc::new(level)));
+        game.score = 50;
+        profile.record_best(&game);
+        assert_eq!(profile.best_score, 0);
+        assert_eq!(profile.best_mutated, 30);
+        assert_eq!(profile.level_bests.get("test"), Some(&50));
+        assert_eq!(profile.best_for(&game), 50);
+
+        game.set_level(None);
+        game.score = 10;
+        profile.record_best(&game);
+        assert_eq!(profile.best_score, 10);
+        assert_eq!(profile.best_for(&game), 10);
+
+        game.set_limits(Limits {
+            flap_cooldown: 0,
+            ..Limits::default()
+        });
+        game.score = 40;
+        profile.record_best(&game);
+        assert_eq!(profile.best_score, 10);
+        assert_eq!(profile.best_assisted, 40);
+
+        // The weekly challenge's own mutators keep its runs on its best
+        game.set_limits(Limits::default());
+        game.set_challenge(Some(Challenge::Weekly));
+        game.set_mutators(Mutators::of(&[Mutator::GiantBird, Mutator::IcyMomentum]));
+        game.score = 20;
+        profile.record_best(&game);
+        assert_eq!(profile.challenge_bests.get(&Challenge::Weekly), Some(&20));
+        assert_eq!(profile.best_mutated, 30);
+        assert_eq!(profile.best_for(&game), 20);
+        assert_eq!(profile.best_score, 10);
+
+        profile.start_week(profile.weekly_week + 7);
+        assert_eq!(profile.challenge_bests.get(&Challenge::Weekly), None);
+    }
+}
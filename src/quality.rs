@@ -0,0 +1,149 @@
+// Effect quality, for machines that can't keep up. Each tier says how much
+// of the purely visual work gets done: how many particles a burst throws,
+// whether the bird trail and score popups are drawn, and whether the color
+// grade's shader runs (see `grading.rs`). The backdrop is one texture
+// drawn once a frame, so there are no parallax layers to shed.
+//
+// The settings menu can lock a tier. Left on Auto, QualityScaler watches
+// the frame rate and steps a tier down whenever it stays low for a few
+// seconds running; it never steps back up by itself, since whatever slowed
+// the game down the first time would only do it again.
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Quality {
+    #[default]
+    High,
+    Medium,
+    Low,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 3] = [Quality::High, Quality::Medium, Quality::Low];
+
+    /// Stable name used in the settings file.
+    pub fn id(self) -> &'static str {
+        match self {
+            Quality::High => "high",
+            Quality::Medium => "medium",
+            Quality::Low => "low",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Quality> {
+        Quality::ALL.into_iter().find(|quality| quality.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Quality::High => "High",
+            Quality::Medium => "Medium",
+            Quality::Low => "Low",
+        }
+    }
+
+    // The next tier down, if there is one
+    fn lower(self) -> Option<Quality> {
+        match self {
+            Quality::High => Some(Quality::Medium),
+            Quality::Medium => Some(Quality::Low),
+            Quality::Low => None,
+        }
+    }
+
+    /// How many of a burst's `count` particles are thrown, at least one.
+    pub fn particles(self, count: usize) -> usize {
+        let share = match self {
+            Quality::High => return count,
+            Quality::Medium => 2,
+            Quality::Low => 4,
+        };
+        count.div_ceil(share)
+    }
+
+    pub fn trail(self) -> bool {
+        self == Quality::High
+    }
+
+    pub fn popups(self) -> bool {
+        self != Quality::Low
+    }
+
+    pub fn grading(self) -> bool {
+        self != Quality::Low
+    }
+}
+
+// Below this many frames a second a second counts as slow
+const MIN_FPS: f32 = 45.0;
+// Slow seconds in a row before a step down
+const SLOW_SECONDS: u32 = 3;
+// Frames this long are the window being dragged or the game being paused
+// by the system, not the game being slow
+const STALL_SECONDS: f32 = 0.25;
+
+#[derive(Default)]
+pub struct QualityScaler {
+    // The tier Auto is on
+    pub tier: Quality,
+    // The second being measured: time and frames so far
+    elapsed: f32,
+    frames: u32,
+    slow_seconds: u32,
+}
+
+impl QualityScaler {
+    /// Counts a frame that took `dt`, returning the new tier when it steps
+    /// down. Locked to a tier in the settings (`lock`), it only waits.
+    pub fn update(&mut self, dt: f32, lock: Option<Quality>) -> Option<Quality> {
+        if lock.is_some() || dt > STALL_SECONDS {
+            self.elapsed = 0.0;
+            self.frames = 0;
+            self.slow_seconds = 0;
+            return None;
+        }
+        self.elapsed += dt;
+        self.frames += 1;
+        if self.elapsed < 1.0 {
+            return None;
+        }
+        let fps = self.frames as f32 / self.elapsed;
+        self.elapsed = 0.0;
+        self.frames = 0;
+        if fps >= MIN_FPS {
+            self.slow_seconds = 0;
+            return None;
+        }
+        self.slow_seconds += 1;
+        if self.slow_seconds < SLOW_SECONDS {
+            return None;
+        }
+        self.slow_seconds = 0;
+        self.tier = self.tier.lower()?;
+        Some(self.tier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs `seconds` of frames at `fps`, returning every step down
+    fn run(scaler: &mut QualityScaler, fps: f32, seconds: f32) -> Vec<Quality> {
+        let frames = (fps * seconds) as usize;
+        (0..frames)
+            .filter_map(|_| scaler.update(1.0 / fps, None))
+            .collect()
+    }
+
+    #[test]
+    fn steps_down_only_when_slow_for_a_while() {
+        let mut scaler = QualityScaler::default();
+        assert!(run(&mut scaler, 60.0, 10.0).is_empty());
+        assert!(run(&mut scaler, 30.0, 2.5).is_empty());
+        assert!(run(&mut scaler, 60.0, 2.0).is_empty());
+        assert_eq!(run(&mut scaler, 30.0, 6.5), [Quality::Medium, Quality::Low]);
+        assert!(run(&mut scaler, 20.0, 10.0).is_empty());
+        assert_eq!(scaler.update(1.0, None), None);
+        assert_eq!(scaler.tier, Quality::Low);
+    }
+}
@@ -0,0 +1,271 @@
+// Quests: small goals handed out QUESTS_PER_DAY at a time, picked by the
+// date, that pay out coins, like "Collect 20 coins in one run". Each is a
+// run's worth: the count starts over with every run, and some quests also
+// start it over on whatever breaks them, like a double flap. They're
+// counted from the game's events, and the first one still to do is shown
+// as a chip in the corner during a run.
+//
+// What there is to do comes from `resources/quests.cfg`, or a `quests.cfg`
+// next to the game to try out new ones, with each key starting with the
+// quest's id:
+//
+//     steady.title = Pass 15 pipes without flapping twice in a row
+//     steady.counts = pipe
+//     steady.target = 15
+//     steady.broken_by = double_flap
+//     steady.reward = 25
+//
+// `counts` and the optional `broken_by` are one of pipe, coin, near_miss,
+// powerup, boss, flap or double_flap (a flap within DOUBLE_FLAP_TICKS of
+// the one before). Finished quests are kept in the profile until the day
+// is over, so each only pays once.
+
+use std::fs;
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+
+use crate::date::{self, Date};
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::profile::Profile;
+use crate::theme::Theme;
+use crate::{HEIGHT, WIDTH};
+
+const QUESTS_FILE: &str = "quests.cfg";
+const BUNDLED_QUESTS: &str = include_str!("../resources/quests.cfg");
+pub const QUESTS_PER_DAY: usize = 3;
+// Mixed into the date, so the day's quests don't follow its course
+const QUEST_SEED_SALT: u64 = 0x5155_4553;
+const DOUBLE_FLAP_TICKS: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trigger {
+    Pipe,
+    Coin,
+    NearMiss,
+    PowerUp,
+    Boss,
+    Flap,
+    DoubleFlap,
+}
+
+impl Trigger {
+    pub const ALL: [Trigger; 7] = [
+        Trigger::Pipe,
+        Trigger::Coin,
+        Trigger::NearMiss,
+        Trigger::PowerUp,
+        Trigger::Boss,
+        Trigger::Flap,
+        Trigger::DoubleFlap,
+    ];
+
+    /// Stable name used in quest files.
+    pub fn id(self) -> &'static str {
+        match self {
+            Trigger::Pipe => "pipe",
+            Trigger::Coin => "coin",
+            Trigger::NearMiss => "near_miss",
+            Trigger::PowerUp => "powerup",
+            Trigger::Boss => "boss",
+            Trigger::Flap => "flap",
+            Trigger::DoubleFlap => "double_flap",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Trigger> {
+        Trigger::ALL.into_iter().find(|trigger| trigger.id() == id)
+    }
+
+    // Whether `event` is one of these; `double` says whether a flap came
+    // hard on the last one
+    fn matches(self, event: GameEvent, double: bool) -> bool {
+        match (self, event) {
+            (Trigger::Pipe, GameEvent::PipePassed { .. })
+            | (Trigger::Coin, GameEvent::CoinCollected)
+            | (Trigger::NearMiss, GameEvent::NearMiss)
+            | (Trigger::PowerUp, GameEvent::PowerUpCollected { .. })
+            | (Trigger::Boss, GameEvent::BossBeaten { .. })
+            | (Trigger::Flap, GameEvent::Flapped) => true,
+            (Trigger::DoubleFlap, GameEvent::Flapped) => double,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    counts: Trigger,
+    target: u32,
+    broken_by: Option<Trigger>,
+    pub reward: u32,
+}
+
+/// Reads a quest file: every id that has keys, in the order they first
+/// appear.
+pub fn parse(text: &str) -> Result<Vec<Quest>, LoadError> {
+    let kv = KeyValues::parse(text);
+    let mut ids: Vec<&str> = Vec::new();
+    for id in kv.keys().into_iter().filter_map(|key| key.split_once('.')) {
+        if !ids.contains(&id.0) {
+            ids.push(id.0);
+        }
+    }
+    ids.into_iter()
+        .map(|id| {
+            let field = |name: &str| {
+                kv.get(&format!("{id}.{name}"))
+                    .ok_or_else(|| LoadError::invalid(format!("{id} has no {name}")))
+            };
+            let trigger = |name: &str, value: &str| {
+                Trigger::from_id(value).ok_or_else(|| {
+                    LoadError::invalid(format!("{id}'s {name} {value} isn't something to count"))
+                })
+            };
+            let number = |name: &str| {
+                field(name)?
+                    .parse::<u32>()
+                    .map_err(|_| LoadError::invalid(format!("{id}'s {name} isn't a whole number")))
+            };
+            let broken_by = match kv.get(&format!("{id}.broken_by")) {
+                Some(value) => Some(trigger("broken_by", value)?),
+                None => None,
+            };
+            Ok(Quest {
+                id: id.to_owned(),
+                title: field("title")?.to_owned(),
+                counts: trigger("counts", field("counts")?)?,
+                target: number("target")?.max(1),
+                broken_by,
+                reward: number("reward")?,
+            })
+        })
+        .collect()
+}
+
+/// The quests to hand out: the file next to the game if there is one,
+/// otherwise the bundled ones.
+pub fn load() -> Vec<Quest> {
+    let text = fs::read_to_string(QUESTS_FILE);
+    let text = text.as_deref().unwrap_or(BUNDLED_QUESTS);
+    parse(text).unwrap_or_else(|err| {
+        eprintln!("Could not load the quests: {err}");
+        parse(BUNDLED_QUESTS).unwrap_or_default()
+    })
+}
+
+/// Counts today's quests through each run.
+pub struct QuestTracker {
+    all: Vec<Quest>,
+    // Days since 1970-01-01 that `today` was picked for
+    day: i64,
+    // Today's, each with what this run has counted towards it
+    today: Vec<(Quest, u32)>,
+    // Flaps seen this run, to find each one's tick in `Game::flap_ticks`
+    flaps: usize,
+}
+
+impl QuestTracker {
+    pub fn new(all: Vec<Quest>) -> QuestTracker {
+        let mut tracker = QuestTracker {
+            all,
+            day: 0,
+            today: Vec::new(),
+            flaps: 0,
+        };
+        tracker.pick(date::today_days());
+        tracker
+    }
+
+    // Deals out `day`'s quests, the same for everyone with the same file
+    fn pick(&mut self, day: i64) {
+        let rng = RandGenerator::new();
+        rng.srand(Date::from_days(day).seed() ^ QUEST_SEED_SALT);
+        let mut left: Vec<&Quest> = self.all.iter().collect();
+        self.today.clear();
+        while self.today.len() < QUESTS_PER_DAY && !left.is_empty() {
+            let quest = left.remove(rng.gen_range(0, left.len()));
+            self.today.push((quest.clone(), 0));
+        }
+        self.day = day;
+    }
+
+    /// Counts `event` towards today's quests. When it finishes one, the
+    /// reward goes into `profile` and the quest is returned.
+    pub fn handle(
+        &mut self,
+        event: GameEvent,
+        game: &Game,
+        profile: &mut Profile,
+    ) -> Option<Quest> {
+        if event == GameEvent::RunStarted {
+            let today = date::today_days();
+            if today != self.day {
+                self.pick(today);
+            }
+            profile.start_quest_day(today);
+            self.flaps = 0;
+            for (_, count) in &mut self.today {
+                *count = 0;
+            }
+            return None;
+        }
+
+        let mut double = false;
+        if event == GameEvent::Flapped {
+            let ticks = &game.flap_ticks;
+            double = self.flaps > 0
+                && ticks
+                    .get(self.flaps)
+                    .zip(ticks.get(self.flaps - 1))
+                    .is_some_and(|(now, last)| now - last <= DOUBLE_FLAP_TICKS);
+            self.flaps += 1;
+        }
+        let mut finished = None;
+        for (quest, count) in &mut self.today {
+            if profile.quests_done.contains(&quest.id) {
+                continue;
+            }
+            if quest
+                .broken_by
+                .is_some_and(|trigger| trigger.matches(event, double))
+            {
+                *count = 0;
+            }
+            if quest.counts.matches(event, double) {
+                *count += 1;
+                if *count >= quest.target {
+                    profile.quests_done.push(quest.id.clone());
+                    profile.coins += quest.reward;
+                    finished = Some(quest.clone());
+                }
+            }
+        }
+        finished
+    }
+
+    /// The first of today's quests still to do in `profile`, as a chip in
+    /// the bottom right.
+    pub fn draw_chip(&self, profile: &Profile, theme: &Theme) {
+        let Some((quest, count)) = self
+            .today
+            .iter()
+            .find(|(quest, _)| !profile.quests_done.contains(&quest.id))
+        else {
+            return;
+        };
+        let label = format!("{}/{}  {}", count, quest.target, quest.title);
+        let size = measure_text(&label, None, 12, 1.0);
+        let (width, height) = (size.width + 12.0, 18.0);
+        let (x, y) = (WIDTH - width - 6.0, HEIGHT - height - 24.0);
+        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.45));
+        let progress = *count as f32 / quest.target as f32;
+        draw_rectangle(x, y + height - 2.0, width * progress, 2.0, GOLD);
+        draw_text(&label, x + 6.0, y + 13.0, 12.0, theme.text_color);
+    }
+}
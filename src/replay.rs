@@ -0,0 +1,350 @@
+// Golden replays: recorded runs kept in `replays/golden/`, flown again by
+// the tests to catch changes to the physics that weren't meant to happen.
+// Each file has the course's seed, the ticks the bird flapped on, and
+// what the run came to: how long it lasted, its score and a checksum of
+// the state after every tick.
+//
+//     sim_version = 1
+//     seed = 1
+//     flaps = 0 31 55 80
+//     ticks = 412
+//     score = 5
+//     checksum = 9a31f0c2d4e85b17
+//
+// They're recorded by the kiosk autopilot, so a change to it doesn't make
+// them fail, only a change to what the same flaps lead to. When that change
+// is on purpose, bump SIM_VERSION and record them again with
+// `cargo test record_golden_replays -- --ignored`. Builds with the
+// `fixed-point` feature fly differently (see `sim_math.rs`), so they have
+// goldens and versions of their own, in `replays/golden-fixed/`, recorded
+// with `--features fixed-point` added.
+//
+// The same format keeps the player's last run for the title screen (see
+// `menu_replay.rs`), played back a tick at a time with `Playback`, and
+// watched with the replay viewer (see `replay_viewer.rs`). A signed one is
+// what a leaderboard would be sent (see `submission.rs`).
+
+#[cfg(test)]
+use std::fs;
+#[cfg(test)]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::game::{Game, GameState};
+#[cfg(test)]
+use crate::kiosk;
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::obstacle::Obstacle;
+use crate::viewport::Layout;
+
+/// Goes up whenever the simulation changes how a recorded run plays out.
+pub const SIM_VERSION: u32 = if cfg!(feature = "fixed-point") {
+    FIXED_POINT_VERSIONS + 2
+} else {
+    2
+};
+// Where fixed-point builds' versions count from, so a replay from one kind
+// of build is never taken for the other's
+const FIXED_POINT_VERSIONS: u32 = 1000;
+
+// Seeds the goldens are recorded on, and the longest a recording runs
+#[cfg(test)]
+const GOLDEN_SEEDS: [(&str, u64); 3] = [("short", 0xbe4c), ("medium", 2024), ("long", 1)];
+#[cfg(test)]
+const MAX_TICKS: u32 = 60 * 60;
+
+// FNV-1a, which is simple and the same everywhere
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+pub struct Replay {
+    sim_version: u32,
+    seed: u64,
+    // Updates run before each flap
+    flaps: Vec<u32>,
+    outcome: Outcome,
+}
+
+#[derive(PartialEq, Debug)]
+struct Outcome {
+    ticks: u32,
+    score: i64,
+    checksum: u64,
+}
+
+impl Replay {
+    pub fn parse(bytes: &[u8]) -> Result<Replay, LoadError> {
+        let kv = KeyValues::from_bytes(bytes)?;
+        let mut flaps: Vec<u32> = field(&kv, "flaps")?
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| LoadError::invalid("flaps must be tick numbers"))?;
+        // Flaps are looked for in order, so any out of order would be lost
+        flaps.sort_unstable();
+        Ok(Replay {
+            sim_version: number(&kv, "sim_version")?,
+            seed: number(&kv, "seed")?,
+            flaps,
+            outcome: Outcome {
+                ticks: number(&kv, "ticks")?,
+                score: number(&kv, "score")?,
+                checksum: u64::from_str_radix(field(&kv, "checksum")?, 16)
+                    .map_err(|_| LoadError::invalid("checksum must be hexadecimal"))?,
+            },
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let flaps: Vec<String> = self.flaps.iter().map(u32::to_string).collect();
+        let mut kv = KeyValues::default();
+        kv.set("sim_version", self.sim_version);
+        kv.set("seed", self.seed);
+        kv.set("flaps", flaps.join(" "));
+        kv.set("ticks", self.outcome.ticks);
+        kv.set("score", self.outcome.score);
+        kv.set("checksum", format!("{:016x}", self.outcome.checksum));
+        kv.to_string()
+    }
+
+    /// The run `game` just finished, if flying its flaps again on its seed
+    /// plays out the same. Runs with anything else steering them (mode
+    /// scripts, mutators, assists, physics presets, soft flaps) don't, and
+    /// aren't kept.
+    pub fn of_run(game: &Game) -> Option<Replay> {
+        // Two flaps between the same updates fly as one
+        let mut ticks = game.flap_ticks.clone();
+        ticks.dedup();
+        let mut flaps = ticks.iter().peekable();
+        let outcome = fly(game.seed, game.ticks + 1, |again| {
+            flaps.next_if_eq(&&again.ticks).is_some()
+        });
+        let same = outcome.ticks == game.ticks && outcome.score == game.score;
+        same.then_some(Replay {
+            sim_version: SIM_VERSION,
+            seed: game.seed,
+            flaps: ticks,
+            outcome,
+        })
+    }
+
+    /// Whether this simulation plays the replay the way it was recorded.
+    pub fn is_current(&self) -> bool {
+        self.sim_version == SIM_VERSION
+    }
+
+    pub fn sim_version(&self) -> u32 {
+        self.sim_version
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn score(&self) -> i64 {
+        self.outcome.score
+    }
+
+    /// Of the state after every tick of the run.
+    pub fn checksum(&self) -> u64 {
+        self.outcome.checksum
+    }
+
+    /// Starts flying the replay again from the top.
+    pub fn play(&self) -> Playback {
+        Playback {
+            game: Game::from_seed(self.seed),
+            flaps: self.flaps.clone(),
+            next: 0,
+        }
+    }
+
+    /// Flies the kiosk autopilot on `seed` until it crashes or MAX_TICKS
+    /// go by.
+    #[cfg(test)]
+    fn record(seed: u64) -> Replay {
+        let mut flaps = Vec::new();
+        let outcome = fly(seed, MAX_TICKS, |game| {
+            let flap = game.state == GameState::Ready || kiosk::autopilot_flap(game);
+            if flap {
+                flaps.push(game.ticks);
+            }
+            flap
+        });
+        Replay {
+            sim_version: SIM_VERSION,
+            seed,
+            flaps,
+            outcome,
+        }
+    }
+
+    /// Flies the recorded flaps again.
+    #[cfg(test)]
+    fn replay(&self) -> Outcome {
+        let mut flaps = self.flaps.iter().peekable();
+        fly(self.seed, MAX_TICKS, |game| {
+            flaps.next_if_eq(&&game.ticks).is_some()
+        })
+    }
+}
+
+/// A replay being flown again, one update at a time.
+#[derive(Clone)]
+pub struct Playback {
+    game: Game,
+    flaps: Vec<u32>,
+    // The next flap in `flaps`
+    next: usize,
+}
+
+impl Playback {
+    /// Runs the next update. Returns false once the run is over.
+    pub fn step(&mut self) -> bool {
+        if self.game.state == GameState::GameOver {
+            return false;
+        }
+        if self.flaps.get(self.next) == Some(&self.game.ticks) {
+            self.next += 1;
+            self.game.flap();
+        }
+        if self.game.state == GameState::Ready {
+            return false;
+        }
+        self.game.update();
+        self.game.events.drain();
+        true
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Updates run so far.
+    pub fn ticks(&self) -> u32 {
+        self.game.ticks
+    }
+
+    /// The updates the replay flaps before.
+    pub fn flaps(&self) -> &[u32] {
+        &self.flaps
+    }
+
+    /// Draws the run for `layout`; it flies the same in any.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.game.layout = layout;
+    }
+}
+
+fn field<'a>(kv: &'a KeyValues, key: &str) -> Result<&'a str, LoadError> {
+    kv.get(key)
+        .ok_or_else(|| LoadError::invalid(format!("{key} is missing")))
+}
+
+fn number<T: FromStr>(kv: &KeyValues, key: &str) -> Result<T, LoadError> {
+    field(kv, key)?
+        .parse()
+        .map_err(|_| LoadError::invalid(format!("{key} must be a number")))
+}
+
+// Flies a plain run on `seed` for up to `max_ticks`, flapping whenever
+// `flap` says so before an update
+fn fly(seed: u64, max_ticks: u32, mut flap: impl FnMut(&Game) -> bool) -> Outcome {
+    let mut game = Game::from_seed(seed);
+    let mut checksum = FNV_OFFSET;
+    while game.ticks < max_ticks && game.state != GameState::GameOver {
+        if flap(&game) {
+            game.flap();
+        }
+        if game.state == GameState::Ready {
+            break;
+        }
+        game.update();
+        game.events.drain();
+        checksum = hash_state(checksum, &game);
+    }
+    Outcome {
+        ticks: game.ticks,
+        score: game.score,
+        checksum,
+    }
+}
+
+// Folds everything that moves or counts into `hash`
+fn hash_state(hash: u64, game: &Game) -> u64 {
+    let bird = game.bird.rect();
+    let mut values = vec![
+        bird.x.to_bits(),
+        bird.y.to_bits(),
+        game.bird.velocity.x.to_bits(),
+        game.bird.velocity.y.to_bits(),
+        game.score as u32,
+        game.pipes_passed,
+        game.coins_collected,
+        game.state as u32,
+    ];
+    for pipe in &game.pipes {
+        values.extend([pipe.x, pipe.top_y, pipe.bottom_y].map(f32::to_bits));
+    }
+    let hitboxes = game
+        .enemies
+        .iter()
+        .flat_map(Obstacle::hitboxes)
+        .chain(game.boss.iter().flat_map(Obstacle::hitboxes));
+    for hitbox in hitboxes {
+        values.extend([hitbox.x, hitbox.y].map(f32::to_bits));
+    }
+    values
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .fold(hash, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+#[cfg(test)]
+fn golden_dir() -> PathBuf {
+    let dir = if cfg!(feature = "fixed-point") {
+        "replays/golden-fixed"
+    } else {
+        "replays/golden"
+    };
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(dir)
+}
+
+#[test]
+fn golden_replays_play_out_the_same() {
+    let mut checked = 0;
+    let entries = fs::read_dir(golden_dir());
+    for entry in entries.expect("the golden replays are missing") {
+        let path = entry.unwrap().path();
+        let replay = Replay::parse(&fs::read(&path).unwrap())
+            .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+        assert_eq!(
+            replay.sim_version,
+            SIM_VERSION,
+            "{} is from another simulation version; record the goldens again",
+            path.display()
+        );
+        assert_eq!(
+            replay.replay(),
+            replay.outcome,
+            "{} played out differently",
+            path.display()
+        );
+        checked += 1;
+    }
+    assert_eq!(checked, GOLDEN_SEEDS.len());
+}
+
+#[test]
+#[ignore = "rewrites replays/golden"]
+fn record_golden_replays() {
+    fs::create_dir_all(golden_dir()).unwrap();
+    for (name, seed) in GOLDEN_SEEDS {
+        let replay = Replay::record(seed);
+        let path = golden_dir().join(format!("{name}.replay"));
+        fs::write(path, replay.to_text()).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+// Replay viewer, opened from the title screen with OPEN_KEY: the last run
+// (the one in the title screen's frame, see `menu_replay.rs`) full size,
+// with a playback bar along the bottom. Space pauses, Tab goes between
+// half, normal and double speed, Left and Right step a tick back or on
+// while paused, and clicking or dragging along the bar goes anywhere in
+// the run. The bar marks every flap.
+//
+// Going back can't run the simulation backwards, so the run is flown once
+// when the viewer opens and a copy of the game kept every CHECKPOINT_TICKS;
+// going anywhere starts from the last copy before it and flies the rest of
+// the way, at most CHECKPOINT_TICKS updates.
+
+use macroquad::prelude::*;
+
+use crate::game::draw_centered_text;
+use crate::replay::{Playback, Replay};
+use crate::screen::{Context, Screen, Transition};
+use crate::timestep::TICK_SECONDS;
+use crate::{HEIGHT, WIDTH};
+
+pub const OPEN_KEY: KeyCode = KeyCode::R;
+const CHECKPOINT_TICKS: u32 = 120;
+const SPEEDS: [f32; 3] = [0.5, 1.0, 2.0];
+const BAR_LEFT: f32 = 20.0;
+const BAR_WIDTH: f32 = WIDTH - 2.0 * BAR_LEFT;
+const BAR_Y: f32 = HEIGHT - 44.0;
+const BAR_HEIGHT: f32 = 8.0;
+// How far above and below the bar a click still lands on it
+const BAR_REACH: f32 = 10.0;
+
+pub struct ReplayViewer {
+    playback: Playback,
+    // The game every CHECKPOINT_TICKS updates, from the start
+    checkpoints: Vec<Playback>,
+    // Updates in the whole run
+    length: u32,
+    paused: bool,
+    speed: usize,
+    // Real time not yet run as updates
+    time: f32,
+    // Whether the mouse went down on the bar and is still held
+    scrubbing: bool,
+}
+
+impl ReplayViewer {
+    pub fn new(replay: &Replay) -> ReplayViewer {
+        let mut playback = replay.play();
+        let mut checkpoints = Vec::new();
+        loop {
+            if playback.ticks().is_multiple_of(CHECKPOINT_TICKS) {
+                checkpoints.push(playback.clone());
+            }
+            if !playback.step() {
+                break;
+            }
+        }
+        ReplayViewer {
+            length: playback.ticks(),
+            playback: checkpoints[0].clone(),
+            checkpoints,
+            paused: false,
+            speed: 1,
+            time: 0.0,
+            scrubbing: false,
+        }
+    }
+
+    // Flies from the last checkpoint before `ticks` up to it
+    fn seek(&mut self, ticks: u32) {
+        let ticks = ticks.min(self.length);
+        let checkpoint = (ticks / CHECKPOINT_TICKS) as usize;
+        self.playback = self.checkpoints[checkpoint.min(self.checkpoints.len() - 1)].clone();
+        while self.playback.ticks() < ticks && self.playback.step() {}
+        self.time = 0.0;
+    }
+
+    fn bar(&self) -> Rect {
+        Rect::new(BAR_LEFT, BAR_Y, BAR_WIDTH, BAR_HEIGHT)
+    }
+
+    // Where along the bar `ticks` is
+    fn bar_x(&self, ticks: u32) -> f32 {
+        BAR_LEFT + BAR_WIDTH * ticks as f32 / self.length.max(1) as f32
+    }
+}
+
+impl Screen for ReplayViewer {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(OPEN_KEY) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Space) {
+            if self.playback.ticks() >= self.length {
+                self.seek(0);
+                self.paused = false;
+            } else {
+                self.paused = !self.paused;
+            }
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            self.speed = (self.speed + 1) % SPEEDS.len();
+        }
+        if self.paused && is_key_pressed(KeyCode::Left) {
+            self.seek(self.playback.ticks().saturating_sub(1));
+        }
+        if self.paused && is_key_pressed(KeyCode::Right) {
+            self.seek(self.playback.ticks() + 1);
+        }
+
+        let mouse = cx.viewport.mouse_position();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let reach = Rect::new(
+                BAR_LEFT,
+                BAR_Y - BAR_REACH,
+                BAR_WIDTH,
+                BAR_HEIGHT + 2.0 * BAR_REACH,
+            );
+            self.scrubbing = reach.contains(mouse);
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.scrubbing = false;
+        }
+        if self.scrubbing {
+            let along = ((mouse.x - BAR_LEFT) / BAR_WIDTH).clamp(0.0, 1.0);
+            let ticks = (along * self.length as f32).round() as u32;
+            if ticks != self.playback.ticks() {
+                self.seek(ticks);
+            }
+        }
+        Transition::Stay
+    }
+
+    fn update(&mut self, cx: &mut Context, dt: f32) {
+        self.playback.set_layout(cx.game.layout);
+        if self.paused || self.scrubbing {
+            return;
+        }
+        self.time += dt * SPEEDS[self.speed];
+        while self.time >= TICK_SECONDS {
+            if !self.playback.step() {
+                self.time = 0.0;
+                return;
+            }
+            self.time -= TICK_SECONDS;
+        }
+    }
+
+    fn draw(&self, cx: &Context) {
+        let theme = cx.theme;
+        clear_background(theme.sky_color);
+        theme.draw_background(self.playback.game().scrolled());
+        self.playback.game().draw(theme);
+
+        let bar = self.bar();
+        draw_rectangle(
+            bar.x - 4.0,
+            bar.y - 20.0,
+            bar.w + 8.0,
+            HEIGHT - bar.y + 16.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        draw_rectangle(bar.x, bar.y, bar.w, bar.h, Color::new(1.0, 1.0, 1.0, 0.2));
+        let ticks = self.playback.ticks();
+        draw_rectangle(
+            bar.x,
+            bar.y,
+            self.bar_x(ticks) - bar.x,
+            bar.h,
+            theme.text_color,
+        );
+        for &flap in self.playback.flaps() {
+            let x = self.bar_x(flap);
+            draw_line(x, bar.y - 3.0, x, bar.y, 1.0, GRAY);
+        }
+        let head = self.bar_x(ticks);
+        draw_rectangle(head - 1.5, bar.y - 4.0, 3.0, bar.h + 8.0, WHITE);
+
+        let status = if ticks >= self.length {
+            "Ended".to_owned()
+        } else if self.paused {
+            "Paused".to_owned()
+        } else {
+            format!("{}x", SPEEDS[self.speed])
+        };
+        let line = format!("{} / {}   {status}", clock(ticks), clock(self.length));
+        draw_centered_text(&line, bar.y - 8.0, 16.0, WHITE);
+        let hint = "Space pause, Tab speed, Left/Right step, Esc close";
+        draw_centered_text(hint, HEIGHT - 14.0, 14.0, GRAY);
+    }
+
+    fn covers(&self) -> bool {
+        true
+    }
+}
+
+// Like 0:12.5
+fn clock(ticks: u32) -> String {
+    let seconds = ticks as f32 * TICK_SECONDS;
+    format!("{}:{:04.1}", (seconds / 60.0) as u32, seconds % 60.0)
+}
@@ -0,0 +1,222 @@
+// Screens: the game and everything that opens over it go on a stack, each
+// one a `Screen` that reads the input while it's on top and draws over
+// whatever is under it. A `Context` hands them the game's state for the
+// frame. Opening one only queues it, so the key that opened it isn't read
+// again by the new screen in the same frame.
+//
+// At the bottom, never taken off, is the title screen and the runs played
+// from it (see `gameplay.rs`); pausing and the results go over it like the
+// menus do. The other ways to play, like the tutorial, party mode and
+// online matches, still run in the main loop in its place, with only what
+// was opened over the game drawn on top.
+
+use crate::celebration::Celebration;
+use crate::challenge::Challenge;
+use crate::cloud::Cloud;
+use crate::console::Console;
+use crate::game::Game;
+use crate::latency::LatencyMeter;
+use crate::lobby::Online;
+use crate::menu_replay::MenuReplay;
+use crate::mutators::Mutators;
+use crate::options::Options;
+use crate::particles::Particles;
+use crate::profile::Profile;
+use crate::quests::QuestTracker;
+use crate::scripting::Scripting;
+use crate::settings::Settings;
+use crate::telemetry::Telemetry;
+use crate::theme::{Theme, ThemeInfo};
+use crate::timestep::Timestep;
+use crate::toast::Toasts;
+use crate::update::UpdateCheck;
+use crate::viewport::Viewport;
+use crate::weekly::Weekly;
+
+/// What screens get to look at and change.
+pub struct Context<'a> {
+    pub game: &'a mut Game,
+    pub settings: &'a mut Settings,
+    pub profile: &'a mut Profile,
+    pub mutators: &'a mut Mutators,
+    pub toasts: &'a mut Toasts,
+    pub scripting: &'a mut Scripting,
+    pub timestep: &'a mut Timestep,
+    pub latency: &'a mut LatencyMeter,
+    pub console: &'a mut Console,
+    pub challenge: &'a mut Option<Challenge>,
+    pub menu_replay: &'a mut Option<MenuReplay>,
+    pub theme: &'a Theme,
+    pub theme_id: &'a str,
+    pub themes: &'a [ThemeInfo],
+    pub cloud: &'a Cloud,
+    pub telemetry: &'a Telemetry,
+    pub viewport: &'a Viewport,
+    pub updates: &'a UpdateCheck,
+    pub options: &'a Options,
+    pub weekly: &'a Weekly,
+    pub quests: &'a QuestTracker,
+    pub particles: &'a Particles,
+    pub celebration: &'a Option<Celebration>,
+    // Simulation ticks run this frame, for the debug counter
+    pub ticks: u32,
+    // Set by a screen that changed the settings, so the main loop saves
+    // and applies them
+    pub settings_changed: bool,
+    // Set when mods were turned on or off, so the main loop reloads the
+    // themes they bring
+    pub mods_changed: bool,
+    pub request: Option<Request>,
+}
+
+/// What a screen wants of the main loop, for what only the loop can do.
+// Taken as soon as the screens are updated, like `Online`
+#[allow(clippy::large_enum_variant)]
+pub enum Request {
+    Quit,
+    // Local multiplayer, on the course from the options
+    Party,
+    Coop,
+    Online(Online),
+}
+
+/// What a screen wants done after reading the input.
+pub enum Transition {
+    Stay,
+    Push(Box<dyn Screen>),
+    Pop,
+}
+
+pub trait Screen {
+    /// When the screen goes on the stack.
+    fn on_enter(&mut self, _cx: &mut Context) {}
+
+    /// When it comes off.
+    fn on_exit(&mut self, _cx: &mut Context) {}
+
+    /// Reads the frame's input, which only the screen on top gets.
+    fn handle_input(&mut self, cx: &mut Context) -> Transition;
+
+    /// Runs every frame for every screen on the stack.
+    fn update(&mut self, _cx: &mut Context, _dt: f32) {}
+
+    fn draw(&self, cx: &Context);
+
+    /// Whether the screens under this one are hidden rather than drawn
+    /// first.
+    fn covers(&self) -> bool {
+        false
+    }
+
+    /// Whether typing goes to it, so the main loop's own letter keys keep
+    /// quiet.
+    fn reads_text(&self) -> bool {
+        false
+    }
+
+    /// Whether the game stays put while it's open, rather than going idle
+    /// for want of input.
+    fn keeps_awake(&self) -> bool {
+        false
+    }
+}
+
+pub struct ScreenStack {
+    // The bottom one is never taken off
+    screens: Vec<Box<dyn Screen>>,
+    // Opened this frame, going on top at the next update
+    opening: Option<Box<dyn Screen>>,
+}
+
+impl ScreenStack {
+    pub fn new(bottom: impl Screen + 'static) -> ScreenStack {
+        ScreenStack {
+            screens: vec![Box::new(bottom)],
+            opening: None,
+        }
+    }
+
+    /// Queues `screen` to go on top.
+    pub fn open(&mut self, screen: impl Screen + 'static) {
+        self.opening = Some(Box::new(screen));
+    }
+
+    /// Whether anything is open over the bottom screen.
+    pub fn is_open(&self) -> bool {
+        self.screens.len() > 1 || self.opening.is_some()
+    }
+
+    pub fn reads_text(&self) -> bool {
+        self.screens.iter().any(|screen| screen.reads_text())
+    }
+
+    pub fn keeps_awake(&self) -> bool {
+        self.screens.iter().any(|screen| screen.keeps_awake())
+    }
+
+    /// Puts on any screen just opened, hands the input to the one on top,
+    /// then updates them all.
+    pub fn update(&mut self, cx: &mut Context, dt: f32) {
+        if let Some(screen) = self.opening.take() {
+            self.push(screen, cx);
+        }
+        let transition = match self.screens.last_mut() {
+            Some(top) => top.handle_input(cx),
+            None => Transition::Stay,
+        };
+        match transition {
+            Transition::Stay => {}
+            Transition::Push(screen) => self.push(screen, cx),
+            Transition::Pop => self.pop(cx),
+        }
+        for screen in &mut self.screens {
+            screen.update(cx, dt);
+        }
+    }
+
+    /// Draws the bottom screen, unless one over it covers it. The main
+    /// loop draws the effects between this and `draw_over`.
+    pub fn draw_bottom(&self, cx: &Context) {
+        if self.covering() == 0 {
+            self.screens[0].draw(cx);
+        }
+    }
+
+    /// Draws what's open over the bottom screen, from the top one that
+    /// covers the rest.
+    pub fn draw_over(&self, cx: &Context) {
+        for screen in &self.screens[self.covering().max(1)..] {
+            screen.draw(cx);
+        }
+    }
+
+    /// Takes every screen but the bottom one off, as when the game goes
+    /// idle.
+    pub fn close_all(&mut self, cx: &mut Context) {
+        self.opening = None;
+        while self.screens.len() > 1 {
+            self.pop(cx);
+        }
+    }
+
+    // The top screen that hides the ones under it
+    fn covering(&self) -> usize {
+        self.screens
+            .iter()
+            .rposition(|screen| screen.covers())
+            .unwrap_or(0)
+    }
+
+    fn push(&mut self, mut screen: Box<dyn Screen>, cx: &mut Context) {
+        screen.on_enter(cx);
+        self.screens.push(screen);
+    }
+
+    fn pop(&mut self, cx: &mut Context) {
+        if self.screens.len() > 1
+            && let Some(mut screen) = self.screens.pop()
+        {
+            screen.on_exit(cx);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Screenshots of the current frame, saved under `screenshots/`
+
+use std::io;
+
+use image::ImageEncoder;
+use image::codecs::png::PngEncoder;
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+use crate::storage;
+
+pub const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// Saves `frame` (see `Viewport::capture`), returning the file name.
+pub fn save(frame: &Image) -> io::Result<String> {
+    let stamp = (date::now() * 1000.0) as u64;
+    let file = format!("shot-{stamp}.png");
+    storage::backend().save_blob(&format!("{SCREENSHOTS_DIR}/{file}"), &encode(frame)?)?;
+    Ok(file)
+}
+
+/// `image` as a PNG file.
+pub fn encode(image: &Image) -> io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(
+            &image.bytes,
+            u32::from(image.width),
+            u32::from(image.height),
+            image::ColorType::Rgba8,
+        )
+        .map_err(io::Error::other)?;
+    Ok(png)
+}
@@ -0,0 +1,314 @@
+// Custom game modes written as rhai scripts in `mods/`
+//
+// A mode is a `.rhai` file defining any of these hooks:
+//
+//     fn on_start() {}
+//     fn on_tick() {}
+//     fn on_pipe_passed(score) {}
+//     fn on_collision() {}
+//
+// Scripts only see the functions registered in `Scripting::new` (gravity,
+// score and pipe spawning), and the engine is capped on operations and
+// memory so a broken mod can't hang or crash the game.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use rhai::{AST, CallFnOptions, Dynamic, Engine, FLOAT, FuncArgs, INT, Scope};
+
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::mod_manager::{ModKind, ModList, mod_id};
+use crate::pipes::{PIPE_HEIGHT, gap_top_range};
+
+pub const MODS_DIR: &str = "./mods";
+
+const MAX_GRAVITY: f32 = 2.0;
+const MAX_SPAWNS_PER_HOOK: usize = 4;
+// Spawned gaps are kept this far below the top of the screen, and within
+// what the pipes can wall in (see `pipes::gap_top_range`)
+const MIN_GAP_Y: f32 = 50.0;
+
+// Values shared between the game and the script engine during a hook call
+#[derive(Default)]
+struct ScriptContext {
+    gravity: f32,
+    score: i64,
+    spawns: Vec<f32>,
+}
+
+impl ScriptContext {
+    fn set_gravity(&mut self, gravity: f32) {
+        if gravity.is_finite() {
+            self.gravity = gravity.clamp(-MAX_GRAVITY, MAX_GRAVITY);
+        }
+    }
+
+    fn spawn_pipe(&mut self, gap_y: f32) {
+        if gap_y.is_finite() && self.spawns.len() < MAX_SPAWNS_PER_HOOK {
+            let range = gap_top_range(PIPE_HEIGHT);
+            let lowest = MIN_GAP_Y.max(*range.start());
+            self.spawns.push(gap_y.clamp(lowest, *range.end()));
+        }
+    }
+}
+
+pub struct GameMode {
+    pub name: String,
+    ast: AST,
+    hooks: HashSet<String>,
+}
+
+// A mode that failed to compile, or whose hook failed
+pub struct ScriptError {
+    pub mode: String,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.mode, self.message)
+    }
+}
+
+pub struct Scripting {
+    engine: Engine,
+    context: Rc<RefCell<ScriptContext>>,
+    pub modes: Vec<GameMode>,
+    // `None` is the built-in classic mode
+    pub active: Option<usize>,
+    pub errors: Vec<ScriptError>,
+}
+
+impl Scripting {
+    pub fn new() -> Self {
+        let context = Rc::new(RefCell::new(ScriptContext::default()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(50_000);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(1024);
+        engine.set_max_array_size(1024);
+        engine.set_max_map_size(256);
+        engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+
+        let ctx = context.clone();
+        engine.register_fn("gravity", move || ctx.borrow().gravity as FLOAT);
+        let ctx = context.clone();
+        engine.register_fn("set_gravity", move |g: FLOAT| {
+            ctx.borrow_mut().set_gravity(g as f32)
+        });
+        let ctx = context.clone();
+        engine.register_fn("set_gravity", move |g: INT| {
+            ctx.borrow_mut().set_gravity(g as f32)
+        });
+
+        let ctx = context.clone();
+        engine.register_fn("score", move || ctx.borrow().score as INT);
+        let ctx = context.clone();
+        engine.register_fn("set_score", move |s: INT| ctx.borrow_mut().score = s.max(0));
+        let ctx = context.clone();
+        engine.register_fn("add_score", move |n: INT| {
+            let mut ctx = ctx.borrow_mut();
+            ctx.score = ctx.score.saturating_add(n).max(0);
+        });
+
+        let ctx = context.clone();
+        engine.register_fn("spawn_pipe", move |gap_y: FLOAT| {
+            ctx.borrow_mut().spawn_pipe(gap_y as f32)
+        });
+        let ctx = context.clone();
+        engine.register_fn("spawn_pipe", move |gap_y: INT| {
+            ctx.borrow_mut().spawn_pipe(gap_y as f32)
+        });
+
+        Scripting {
+            engine,
+            context,
+            modes: Vec::new(),
+            active: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Compiles every `.rhai` file in `dir` that `mods` has on, in its
+    /// load order. Scripts that fail to compile are reported in `errors`
+    /// and left out of the mode list.
+    pub fn load_mods(&mut self, dir: &str, mods: &ModList) {
+        let mut files = mode_files(dir);
+        mods.arrange(&mut files, |(name, _)| mod_id(ModKind::Mode, name));
+        for (name, path) in files {
+            if !mods.is_enabled(&mod_id(ModKind::Mode, &name)) {
+                continue;
+            }
+            // Read by hand; rhai has no file loading on the web
+            let compiled = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| self.engine.compile(source).map_err(|err| err.to_string()));
+            match compiled {
+                Ok(ast) => {
+                    let hooks = ast.iter_functions().map(|f| f.name.to_string()).collect();
+                    self.modes.push(GameMode { name, ast, hooks });
+                }
+                Err(err) => self.errors.push(ScriptError {
+                    mode: name,
+                    message: err,
+                }),
+            }
+        }
+    }
+
+    /// Compiles `source` without keeping it, for checking a script over.
+    pub fn check(&self, source: &str) -> Result<(), String> {
+        self.engine
+            .compile(source)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    /// The last thing to go wrong with the mode called `name`, if anything.
+    pub fn error_for(&self, name: &str) -> Option<&str> {
+        self.errors
+            .iter()
+            .rev()
+            .find(|error| error.mode == name)
+            .map(|error| error.message.as_str())
+    }
+
+    pub fn active_name(&self) -> &str {
+        self.active
+            .map_or("Classic", |i| self.modes[i].name.as_str())
+    }
+
+    /// Switches to the mode called `name` ("classic" for the built-in one),
+    /// ignoring case. Returns false if there is no such mode.
+    pub fn select(&mut self, name: &str) -> bool {
+        if name.eq_ignore_ascii_case("classic") {
+            self.active = None;
+            return true;
+        }
+        match self
+            .modes
+            .iter()
+            .position(|mode| mode.name.eq_ignore_ascii_case(name))
+        {
+            Some(index) => {
+                self.active = Some(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Steps through Classic followed by each loaded mode, wrapping around.
+    pub fn cycle_mode(&mut self, step: i32) {
+        let slots = self.modes.len() as i32 + 1;
+        let current = self.active.map_or(0, |i| i as i32 + 1);
+        let next = (current + step).rem_euclid(slots);
+        self.active = if next == 0 {
+            None
+        } else {
+            Some(next as usize - 1)
+        };
+    }
+
+    pub fn tick(&mut self, game: &mut Game) {
+        self.call(game, "on_tick", ());
+    }
+
+    pub fn handle(&mut self, game: &mut Game, event: GameEvent) {
+        match event {
+            GameEvent::RunStarted => self.call(game, "on_start", ()),
+            GameEvent::PipePassed { score, .. } => {
+                self.call(game, "on_pipe_passed", (score as INT,))
+            }
+            GameEvent::Collided => self.call(game, "on_collision", ()),
+            _ => {}
+        }
+    }
+
+    /// Runs `source` on `game` with the functions modes get, whatever mode
+    /// is on, giving back what it comes to. For the developer console.
+    pub fn eval(&mut self, game: &mut Game, source: &str) -> Result<String, String> {
+        self.share(game);
+        let result = self.engine.eval::<Dynamic>(source);
+        self.take_back(game);
+        result
+            .map(|value| value.to_string())
+            .map_err(|err| err.to_string())
+    }
+
+    fn call(&mut self, game: &mut Game, hook: &str, args: impl FuncArgs) {
+        let Some(index) = self.active else {
+            return;
+        };
+        let mode = &self.modes[index];
+        if !mode.hooks.contains(hook) {
+            return;
+        }
+
+        self.share(game);
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut Scope::new(),
+            &mode.ast,
+            hook,
+            args,
+        );
+        if let Err(err) = result {
+            // A misbehaving mod falls back to classic rather than erroring every frame
+            self.errors.push(ScriptError {
+                mode: mode.name.clone(),
+                message: format!("{hook}: {err}"),
+            });
+            self.active = None;
+        }
+        self.take_back(game);
+    }
+
+    // Hands the game's values to the script functions
+    fn share(&self, game: &Game) {
+        let mut ctx = self.context.borrow_mut();
+        ctx.gravity = game.gravity;
+        ctx.score = game.score;
+        ctx.spawns.clear();
+    }
+
+    // Puts back what the script functions changed
+    fn take_back(&self, game: &mut Game) {
+        let mut ctx = self.context.borrow_mut();
+        game.gravity = ctx.gravity;
+        game.score = ctx.score;
+        for gap_y in ctx.spawns.drain(..) {
+            game.spawn_pipe(gap_y);
+        }
+    }
+}
+
+/// Every `.rhai` file in `dir` with the mode name it goes by, sorted by
+/// name. Turned off ones included.
+pub fn mode_files(dir: &str) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(Path::new(dir)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            (name, path)
+        })
+        .collect();
+    files.sort();
+    files
+}
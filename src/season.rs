@@ -0,0 +1,65 @@
+// Seasonal theme packs that take over from the chosen theme around holidays
+
+use crate::date::Date;
+use crate::theme::{DEFAULT_THEME, ThemeInfo};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Season {
+    Halloween,
+    Winter,
+}
+
+impl Season {
+    pub fn on(date: Date) -> Option<Season> {
+        match (date.month, date.day) {
+            (10, 15..) | (11, 1) => Some(Season::Halloween),
+            (12, 15..) | (1, ..=6) => Some(Season::Winter),
+            _ => None,
+        }
+    }
+
+    // Folder name of the theme pack shipped for this season
+    fn theme_id(self) -> &'static str {
+        match self {
+            Season::Halloween => "halloween",
+            Season::Winter => "winter",
+        }
+    }
+}
+
+/// The theme to use right now: the seasonal pack if one is running and
+/// installed (and seasonal themes are enabled), otherwise `chosen`, or the
+/// default theme if `chosen` has been removed or turned off.
+pub fn theme_for_today(chosen: &str, seasonal: bool, themes: &[ThemeInfo]) -> String {
+    let season = Season::on(Date::today()).filter(|_| seasonal);
+    match season {
+        Some(season) if themes.iter().any(|t| t.id == season.theme_id()) => {
+            season.theme_id().to_owned()
+        }
+        _ if themes.iter().any(|t| t.id == chosen) => chosen.to_owned(),
+        _ => DEFAULT_THEME.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seasons_start_and_end_on_the_right_days() {
+        for (text, season) in [
+            ("2024-10-14", None),
+            ("2024-10-15", Some(Season::Halloween)),
+            ("2024-10-31", Some(Season::Halloween)),
+            ("2024-11-01", Some(Season::Halloween)),
+            ("2024-11-02", None),
+            ("2024-12-14", None),
+            ("2024-12-15", Some(Season::Winter)),
+            ("2024-12-31", Some(Season::Winter)),
+            ("2025-01-06", Some(Season::Winter)),
+            ("2025-01-07", None),
+        ] {
+            assert!(Season::on(Date::parse(text).unwrap()) == season, "{text}");
+        }
+    }
+}
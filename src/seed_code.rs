@@ -0,0 +1,158 @@
+// Course codes: a run's seed spelled in join code letters (see `net.rs`),
+// like `7K2M-9XQ4`, shown on the game over screen so friends can fly the
+// same course. K on the title screen opens a field to type one in; every
+// run after that flies its course, until an empty code goes back to the
+// usual ones. A code is only shown when it gives the same course: levels
+// bring their own pipes, and fresh courses shaped by gap settings other
+// than the defaults or by physics other than classic don't come out the
+// same from the seed alone.
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::game::{Game, draw_centered_text};
+use crate::gaps::GapParams;
+use crate::net;
+use crate::physics::Physics;
+use crate::screen::{Context, Screen, Transition};
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+
+pub const ENTRY_KEY: KeyCode = KeyCode::K;
+// Letters between dashes
+const GROUP: usize = 4;
+// A u64 needs 13 letters
+const MAX_CODE_LENGTH: usize = 13;
+
+/// Spells `seed` as a code, leaving out leading zeros.
+pub fn encode(seed: u64) -> String {
+    let digits = (64 - seed.leading_zeros() as usize).div_ceil(5).max(1);
+    let mut code = String::new();
+    for (written, i) in (0..digits).rev().enumerate() {
+        code.push(net::code_char(seed >> (i * 5)));
+        if i > 0 && (written + 1) % GROUP == 0 {
+            code.push('-');
+        }
+    }
+    code
+}
+
+/// Reads a code back, forgiving case, dashes and look-alike letters.
+pub fn parse(code: &str) -> Option<u64> {
+    let mut seed = 0_u64;
+    let mut digits = 0;
+    for c in code.chars().filter(|c| !matches!(c, '-' | ' ')) {
+        // The top letter of a 13-letter code only has four bits to give
+        seed = seed.checked_mul(32)? | net::code_digit(c)?;
+        digits += 1;
+    }
+    (digits > 0).then_some(seed)
+}
+
+/// The code for the course `game` flew, if the code alone gives it.
+pub fn for_run(game: &Game) -> Option<String> {
+    let same_course = game.fixed_seed.is_some() || game.gap_params == GapParams::default();
+    let classic = game.physics == Physics::Classic;
+    (game.level.is_none() && same_course && classic).then(|| encode(game.seed))
+}
+
+#[derive(Default)]
+pub struct SeedEntry {
+    code: String,
+    error: Option<String>,
+}
+
+impl Screen for SeedEntry {
+    fn on_enter(&mut self, _cx: &mut Context) {
+        // Or the K that opened the field would be typed into it
+        while get_char_pressed().is_some() {}
+    }
+
+    /// Handles typing. Enter on a good code flies its course from now on,
+    /// or on an empty one goes back to the courses the game started with.
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        // Read typed characters every frame so they don't pile up
+        while let Some(c) = get_char_pressed() {
+            if c.is_ascii_alphanumeric() && self.code.len() < MAX_CODE_LENGTH {
+                self.code.push(c.to_ascii_uppercase());
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.code.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        if !is_key_pressed(KeyCode::Enter) {
+            return Transition::Stay;
+        }
+        cx.game.fixed_seed = if self.code.is_empty() {
+            cx.weekly.course_seed(*cx.challenge, cx.options)
+        } else {
+            match parse(&self.code) {
+                Some(seed) => Some(seed),
+                None => {
+                    self.error = Some("That isn't a course code".to_owned());
+                    return Transition::Stay;
+                }
+            }
+        };
+        cx.game.reset();
+        Transition::Pop
+    }
+
+    fn draw(&self, _cx: &Context) {
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Play this course", 60.0, 32.0, WHITE);
+        draw_centered_text("Course code", 120.0, 20.0, WHITE);
+        // A blinking caret shows where typing goes
+        let caret = if get_time().fract() < 0.5 { "_" } else { " " };
+        draw_centered_text(&format!("{}{caret}", self.code), 160.0, 32.0, YELLOW);
+        draw_centered_text("Leave it empty for the usual courses", 200.0, 14.0, GRAY);
+        if let Some(error) = &self.error {
+            draw_centered_text(error, HEIGHT - 60.0, 14.0, RED);
+        }
+        draw_centered_text("Enter to play, Esc to close", HEIGHT - 20.0, 16.0, GRAY);
+    }
+
+    fn reads_text(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_read_back_as_their_seeds() {
+        for seed in [0, 1, 31, 32, 0xdead_beef, u64::MAX] {
+            let code = encode(seed);
+            assert_eq!(parse(&code), Some(seed), "{code}");
+            let letters = code.chars().filter(|&c| c != '-').count();
+            assert!(letters <= MAX_CODE_LENGTH, "{code}");
+        }
+        assert_eq!(encode(0), "0");
+        assert_eq!(encode(32 * 32 * 32 * 32), "1000-0");
+        // Typed in sloppily
+        let code = encode(0xdead_beef);
+        let typed = code.to_lowercase().replace('-', " ");
+        assert_eq!(parse(&typed), Some(0xdead_beef));
+        assert_eq!(parse("O1L"), parse("011"));
+    }
+
+    #[test]
+    fn bad_codes_read_as_nothing() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("--"), None);
+        // U isn't one of the letters
+        assert_eq!(parse("7K2U"), None);
+        // More than a u64 holds
+        assert_eq!(parse("ZZZZ-ZZZZ-ZZZZ-Z"), None);
+    }
+}
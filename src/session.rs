@@ -0,0 +1,93 @@
+// This sitting's stats, counted from the game's events apart from the
+// lifetime ones in the profile, and shown as a short recap when the player
+// quits after playing: runs, the best score, coins earned and time spent
+// in runs. It closes by itself after SUMMARY_SECONDS, or on any key or
+// click; closing the window again leaves straight away.
+
+use macroquad::prelude::*;
+
+use crate::events::GameEvent;
+use crate::game::{Game, draw_centered_text};
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH, Viewport};
+use crate::{HEIGHT, WIDTH};
+
+const SUMMARY_SECONDS: f32 = 6.0;
+
+#[derive(Default)]
+pub struct Session {
+    pub runs: u32,
+    pub best: i64,
+    // Picked up in runs and paid out by quests
+    pub coins: u32,
+    // Time in runs, not on the menus
+    pub seconds: f32,
+}
+
+impl Session {
+    pub fn handle(&mut self, event: GameEvent, game: &Game) {
+        match event {
+            GameEvent::CoinCollected => self.coins += 1,
+            GameEvent::Collided | GameEvent::LevelCompleted => {
+                self.runs += 1;
+                self.best = self.best.max(game.score);
+                self.seconds += game.ticks as f32 * TICK_SECONDS;
+            }
+            GameEvent::RunAbandoned => self.seconds += game.ticks as f32 * TICK_SECONDS,
+            _ => {}
+        }
+    }
+
+    /// Shows the recap until it times out or is skipped. Does nothing if
+    /// no run was finished.
+    pub async fn show_summary(&self, viewport: &Viewport, theme: &Theme) {
+        if self.runs == 0 {
+            return;
+        }
+        let mut age = 0.0;
+        while age < SUMMARY_SECONDS {
+            viewport.begin();
+            clear_background(theme.sky_color);
+            theme.draw_background(0.0);
+            self.draw(theme, age);
+            viewport.end(theme, theme.grade);
+            next_frame().await;
+            // Checked after a frame, so the key that quit doesn't skip it
+            if is_quit_requested()
+                || get_last_key_pressed().is_some()
+                || is_mouse_button_pressed(MouseButton::Left)
+            {
+                return;
+            }
+            age += get_frame_time();
+        }
+    }
+
+    fn draw(&self, theme: &Theme, age: f32) {
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        draw_centered_text("Thanks for playing!", 90.0, 26.0, theme.text_color);
+        let minutes = self.seconds as u32 / 60;
+        let lines = [
+            format!("Runs played: {}", self.runs),
+            format!("Best score: {}", self.best),
+            format!("Coins earned: {}", self.coins),
+            format!("Time played: {}:{:02}", minutes, self.seconds as u32 % 60),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let y = 140.0 + i as f32 * 26.0;
+            draw_centered_text(line, y, 20.0, theme.text_color);
+        }
+        // Runs down as the recap closes by itself
+        let left = (1.0 - age / SUMMARY_SECONDS).max(0.0);
+        let width = 120.0 * left;
+        draw_rectangle(WIDTH / 2.0 - width / 2.0, HEIGHT - 50.0, width, 2.0, GRAY);
+        draw_centered_text("Any key to skip", HEIGHT - 30.0, 14.0, GRAY);
+    }
+}
@@ -0,0 +1,245 @@
+// Player settings, saved as `settings.cfg` (see `storage.rs`)
+
+use crate::adaptive::Tuning;
+use crate::assists::Assists;
+use crate::cloud::CloudEndpoint;
+use crate::collision::{DEFAULT_GRACE_TICKS, MAX_GRACE_TICKS};
+use crate::controls::FlapEdge;
+use crate::gaps::GapParams;
+use crate::history::Run;
+use crate::idle::IdleTimeouts;
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::one_switch::SwitchRepeat;
+use crate::physics::{DEFAULT_FLAP_COOLDOWN, DEFAULT_MAX_FALL, Limits, Physics};
+use crate::quality::Quality;
+use crate::shutdown::Shutdown;
+use crate::storage;
+use crate::theme::DEFAULT_THEME;
+use crate::update::UpdateSource;
+use crate::viewport::WideView;
+use crate::window::FullscreenDisplay;
+
+const SETTINGS_FILE: &str = "settings.cfg";
+
+pub struct Settings {
+    pub theme: String,
+    // Let Halloween/winter packs override `theme` around their dates
+    pub seasonal: bool,
+    // Briefly slow the game down after a near miss
+    pub near_miss_slowmo: bool,
+    pub trail: bool,
+    // Accessibility: turns off decorative motion such as the bird trail
+    pub reduced_motion: bool,
+    // Accessibility: text captions for sound cues
+    pub captions: bool,
+    // Draws the game between ticks for smooth motion; off, every frame
+    // shows exactly the last tick (see `Game::blend`)
+    pub interpolation: bool,
+    // A corner counter of the run's ticks, see `debug_step.rs`
+    pub tick_counter: bool,
+    // Accessibility: play with a single switch, see `one_switch.rs`
+    pub one_switch: bool,
+    // How a held switch keeps flapping in one-switch play
+    pub switch_repeat: SwitchRepeat,
+    // Which end of a press flaps, set on the latency screen (see
+    // `latency.rs`)
+    pub flap_edge: FlapEdge,
+    // Gravity and flap strength for solo runs, see `physics.rs`
+    pub physics: Physics,
+    // How fast the bird falls at most and how soon it flaps again, saved
+    // as `max_fall` and `flap_cooldown`
+    pub limits: Limits,
+    // Assists, see `assists.rs`
+    pub slow_speed: bool,
+    pub wide_gaps: bool,
+    pub start_shield: bool,
+    pub adaptive: bool,
+    // Phone vibration and gamepad rumble
+    pub haptics: bool,
+    // A HUD strip previewing the next few gap heights
+    pub gap_preview: bool,
+    // Ticks a clipped pipe corner is forgiven for, see `collision::Grace`
+    pub corner_grace: u32,
+    // Pipes hit only where their sprite and the bird's actually overlap
+    pub pixel_collision: bool,
+    // Whether wide windows see more of the world or get side panels
+    pub wide_view: WideView,
+    pub fullscreen: bool,
+    // The display fullscreen uses, see `window.rs`
+    pub fullscreen_display: FullscreenDisplay,
+    // Effects locked to a tier, or None to step down on slow machines; see
+    // `quality.rs`
+    pub quality: Option<Quality>,
+    // How random gaps are drawn; only editable in the file, see `gaps.rs`
+    pub gaps: GapParams,
+    // How long screens wait for input; only set in the file, see `idle.rs`
+    pub idle: IdleTimeouts,
+    // Where saves are synced to, if anywhere; only set in the file, see
+    // `cloud.rs`
+    pub cloud: Option<CloudEndpoint>,
+    // Where to look for newer versions; only set in the file, see
+    // `update.rs`
+    pub updates: UpdateSource,
+    // For streaming: course and join codes stay off the screen, and toasts
+    // about bests, unlocks and coins aren't shown, though they're all still
+    // saved
+    pub streamer_mode: bool,
+    // Opted in to sending anonymous stats, see `telemetry.rs`
+    pub telemetry: bool,
+    // Overrides where they go; only set in the file
+    pub telemetry_url: Option<String>,
+    // Overrides where the weekly challenge comes from; only set in the file
+    pub weekly_url: Option<String>,
+    // Overrides the event feed's port; only set in the file, see
+    // `event_server.rs`
+    pub event_port: Option<u16>,
+}
+
+impl Settings {
+    /// Loads saved settings, using defaults for anything missing.
+    pub fn load() -> Result<Self, LoadError> {
+        let text = storage::load_save(SETTINGS_FILE)?.unwrap_or_default();
+        Ok(Settings::parse(&text))
+    }
+
+    /// Reads saved settings, using defaults for anything missing or that
+    /// doesn't make sense.
+    pub fn parse(text: &str) -> Self {
+        let kv = KeyValues::parse(text);
+        Settings {
+            theme: kv.get("theme").unwrap_or(DEFAULT_THEME).to_owned(),
+            seasonal: kv.get_bool("seasonal").unwrap_or(true),
+            near_miss_slowmo: kv.get_bool("near_miss_slowmo").unwrap_or(true),
+            trail: kv.get_bool("trail").unwrap_or(true),
+            reduced_motion: kv.get_bool("reduced_motion").unwrap_or(false),
+            captions: kv.get_bool("captions").unwrap_or(false),
+            interpolation: kv.get_bool("interpolation").unwrap_or(true),
+            tick_counter: kv.get_bool("tick_counter").unwrap_or(false),
+            one_switch: kv.get_bool("one_switch").unwrap_or(false),
+            switch_repeat: kv
+                .get("switch_repeat")
+                .and_then(SwitchRepeat::from_id)
+                .unwrap_or_default(),
+            flap_edge: kv
+                .get("flap_edge")
+                .and_then(FlapEdge::from_id)
+                .unwrap_or_default(),
+            physics: kv
+                .get("physics")
+                .and_then(Physics::from_id)
+                .unwrap_or_default(),
+            limits: Limits {
+                max_fall: kv.get_parsed("max_fall").unwrap_or(DEFAULT_MAX_FALL),
+                flap_cooldown: kv
+                    .get_parsed("flap_cooldown")
+                    .unwrap_or(DEFAULT_FLAP_COOLDOWN),
+            }
+            .clamped(),
+            slow_speed: kv.get_bool("assist_slow_speed").unwrap_or(false),
+            wide_gaps: kv.get_bool("assist_wide_gaps").unwrap_or(false),
+            start_shield: kv.get_bool("assist_start_shield").unwrap_or(false),
+            adaptive: kv.get_bool("assist_adaptive").unwrap_or(false),
+            haptics: kv.get_bool("haptics").unwrap_or(true),
+            gap_preview: kv.get_bool("gap_preview").unwrap_or(false),
+            corner_grace: kv
+                .get_parsed("corner_grace")
+                .unwrap_or(DEFAULT_GRACE_TICKS)
+                .min(MAX_GRACE_TICKS),
+            pixel_collision: kv.get_bool("pixel_collision").unwrap_or(false),
+            wide_view: kv
+                .get("wide_view")
+                .and_then(WideView::from_id)
+                .unwrap_or_default(),
+            fullscreen: kv.get_bool("fullscreen").unwrap_or(false),
+            fullscreen_display: kv
+                .get("fullscreen_display")
+                .and_then(FullscreenDisplay::from_id)
+                .unwrap_or_default(),
+            quality: kv.get("quality").and_then(Quality::from_id),
+            gaps: GapParams::from_values(&kv),
+            idle: IdleTimeouts::from_values(&kv),
+            cloud: CloudEndpoint::from_values(&kv),
+            updates: UpdateSource::from_values(&kv),
+            streamer_mode: kv.get_bool("streamer_mode").unwrap_or(false),
+            telemetry: kv.get_bool("telemetry").unwrap_or(false),
+            telemetry_url: kv
+                .get("telemetry_url")
+                .filter(|url| !url.is_empty())
+                .map(str::to_owned),
+            weekly_url: kv
+                .get("weekly_url")
+                .filter(|url| !url.is_empty())
+                .map(str::to_owned),
+            event_port: kv.get_parsed("event_port"),
+        }
+    }
+
+    pub fn save(&self) {
+        let mut kv = KeyValues::default();
+        kv.set("theme", &self.theme);
+        kv.set("seasonal", self.seasonal);
+        kv.set("near_miss_slowmo", self.near_miss_slowmo);
+        kv.set("trail", self.trail);
+        kv.set("reduced_motion", self.reduced_motion);
+        kv.set("captions", self.captions);
+        kv.set("interpolation", self.interpolation);
+        kv.set("tick_counter", self.tick_counter);
+        kv.set("one_switch", self.one_switch);
+        kv.set("switch_repeat", self.switch_repeat.id());
+        kv.set("flap_edge", self.flap_edge.id());
+        kv.set("physics", self.physics.id());
+        kv.set("max_fall", self.limits.max_fall);
+        kv.set("flap_cooldown", self.limits.flap_cooldown);
+        kv.set("assist_slow_speed", self.slow_speed);
+        kv.set("assist_wide_gaps", self.wide_gaps);
+        kv.set("assist_start_shield", self.start_shield);
+        kv.set("assist_adaptive", self.adaptive);
+        kv.set("haptics", self.haptics);
+        kv.set("gap_preview", self.gap_preview);
+        kv.set("corner_grace", self.corner_grace);
+        kv.set("pixel_collision", self.pixel_collision);
+        kv.set("wide_view", self.wide_view.id());
+        kv.set("fullscreen", self.fullscreen);
+        kv.set("fullscreen_display", self.fullscreen_display.id());
+        kv.set("quality", self.quality.map_or("auto", Quality::id));
+        self.gaps.write_values(&mut kv);
+        self.idle.write_values(&mut kv);
+        if let Some(cloud) = &self.cloud {
+            cloud.write_values(&mut kv);
+        }
+        self.updates.write_values(&mut kv);
+        kv.set("streamer_mode", self.streamer_mode);
+        kv.set("telemetry", self.telemetry);
+        if let Some(url) = &self.telemetry_url {
+            kv.set("telemetry_url", url);
+        }
+        if let Some(url) = &self.weekly_url {
+            kv.set("weekly_url", url);
+        }
+        if let Some(port) = self.event_port {
+            kv.set("event_port", port);
+        }
+        if let Err(err) = storage::backend().save_text(SETTINGS_FILE, &kv.to_string()) {
+            eprintln!("Could not save settings: {err}");
+        }
+    }
+
+    /// The assists picked, with adaptive difficulty tuned to `history`.
+    pub fn assists(&self, history: &[Run]) -> Assists {
+        Assists {
+            slow_speed: self.slow_speed,
+            wide_gaps: self.wide_gaps,
+            start_shield: self.start_shield,
+            one_switch: self.one_switch,
+            adaptive: self.adaptive.then(|| Tuning::for_history(history)),
+        }
+    }
+}
+
+impl Shutdown for Settings {
+    // Changes made with the settings menu still open
+    fn shutdown(&mut self) {
+        self.save();
+    }
+}
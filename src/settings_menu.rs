@@ -0,0 +1,340 @@
+// Settings screen, opened from the title screen with Tab
+
+use macroquad::prelude::*;
+
+use crate::collision::MAX_GRACE_TICKS;
+use crate::controls::ControlScheme;
+use crate::game::draw_centered_text;
+use crate::one_switch::SwitchRepeat;
+use crate::physics::Physics;
+use crate::profile::Profile;
+use crate::quality::Quality;
+use crate::screen::{Context, Screen, Transition};
+use crate::settings::Settings;
+use crate::telemetry::BATCH_RUNS;
+use crate::theme::ThemeInfo;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH, WideView};
+use crate::window::FullscreenDisplay;
+use crate::{HEIGHT, WIDTH};
+
+#[derive(Clone, Copy)]
+enum Row {
+    Theme,
+    Seasonal,
+    NearMissSlowmo,
+    Trail,
+    ReducedMotion,
+    Captions,
+    Interpolation,
+    TickCounter,
+    OneSwitch,
+    SwitchRepeat,
+    Physics,
+    SlowSpeed,
+    WideGaps,
+    StartShield,
+    Adaptive,
+    Controls,
+    Haptics,
+    GapPreview,
+    CornerGrace,
+    PixelCollision,
+    WideView,
+    Fullscreen,
+    FullscreenDisplay,
+    Quality,
+    StreamerMode,
+    Telemetry,
+}
+
+const ROWS: [Row; 26] = [
+    Row::Theme,
+    Row::Seasonal,
+    Row::NearMissSlowmo,
+    Row::Trail,
+    Row::ReducedMotion,
+    Row::Captions,
+    Row::Interpolation,
+    Row::TickCounter,
+    Row::OneSwitch,
+    Row::SwitchRepeat,
+    Row::Physics,
+    Row::SlowSpeed,
+    Row::WideGaps,
+    Row::StartShield,
+    Row::Adaptive,
+    Row::Controls,
+    Row::Haptics,
+    Row::GapPreview,
+    Row::CornerGrace,
+    Row::PixelCollision,
+    Row::WideView,
+    Row::Fullscreen,
+    Row::FullscreenDisplay,
+    Row::Quality,
+    Row::StreamerMode,
+    Row::Telemetry,
+];
+
+// Rows that fit on screen; the list scrolls to keep the selection in view
+const VISIBLE_ROWS: usize = 12;
+const ROW_SPACING: f32 = 24.0;
+const FIRST_ROW_Y: f32 = 100.0;
+
+const GRACE_LABELS: [&str; MAX_GRACE_TICKS as usize + 1] = ["Off", "1 tick", "2 ticks", "3 ticks"];
+
+#[derive(Default)]
+pub struct SettingsMenu {
+    selected: usize,
+}
+
+impl Screen for SettingsMenu {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Tab) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Enter) && matches!(ROWS[self.selected], Row::Telemetry) {
+            return Transition::Push(Box::new(TelemetryPreview));
+        }
+        if self.change(cx.settings, cx.profile, cx.themes) {
+            cx.settings_changed = true;
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        draw_background(cx.cloud.status_line());
+        self.draw_rows(cx.settings, cx.profile, cx.themes);
+    }
+}
+
+// The telemetry batch, line for line as it would be sent, opened with
+// Enter on its row
+struct TelemetryPreview;
+
+impl Screen for TelemetryPreview {
+    fn handle_input(&mut self, _cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn draw(&self, cx: &Context) {
+        draw_background(cx.cloud.status_line());
+        let heading = if cx.settings.telemetry {
+            format!("Sent every {BATCH_RUNS} runs, exactly as below:")
+        } else {
+            "Off: nothing is collected or sent".to_owned()
+        };
+        draw_centered_text(&heading, 100.0, 16.0, WHITE);
+        if cx.settings.telemetry {
+            for (i, line) in cx.telemetry.preview().lines().enumerate() {
+                draw_text(line, 40.0, 124.0 + i as f32 * 14.0, 14.0, LIGHTGRAY);
+            }
+        }
+        draw_centered_text("Enter to go back", HEIGHT - 20.0, 16.0, GRAY);
+    }
+
+    fn covers(&self) -> bool {
+        true
+    }
+}
+
+impl SettingsMenu {
+    // Handles the keys that change settings. Returns true when one was
+    // changed. The control scheme belongs to the profile rather than the
+    // settings.
+    fn change(
+        &mut self,
+        settings: &mut Settings,
+        profile: &mut Profile,
+        themes: &[ThemeInfo],
+    ) -> bool {
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + ROWS.len() - 1) % ROWS.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % ROWS.len();
+        }
+
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            return false;
+        };
+        match ROWS[self.selected] {
+            Row::Theme => {
+                let current = themes.iter().position(|t| t.id == settings.theme);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(themes.len() as i32);
+                settings.theme = themes[next as usize].id.clone();
+            }
+            Row::Seasonal => settings.seasonal = !settings.seasonal,
+            Row::NearMissSlowmo => settings.near_miss_slowmo = !settings.near_miss_slowmo,
+            Row::Trail => settings.trail = !settings.trail,
+            Row::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            Row::Captions => settings.captions = !settings.captions,
+            Row::Interpolation => settings.interpolation = !settings.interpolation,
+            Row::TickCounter => settings.tick_counter = !settings.tick_counter,
+            Row::OneSwitch => settings.one_switch = !settings.one_switch,
+            Row::SwitchRepeat => {
+                let all = SwitchRepeat::ALL;
+                let current = all.iter().position(|&r| r == settings.switch_repeat);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(all.len() as i32);
+                settings.switch_repeat = all[next as usize];
+            }
+            Row::Physics => {
+                let all = Physics::ALL;
+                let current = all.iter().position(|&p| p == settings.physics);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(all.len() as i32);
+                settings.physics = all[next as usize];
+            }
+            Row::SlowSpeed => settings.slow_speed = !settings.slow_speed,
+            Row::WideGaps => settings.wide_gaps = !settings.wide_gaps,
+            Row::StartShield => settings.start_shield = !settings.start_shield,
+            Row::Adaptive => settings.adaptive = !settings.adaptive,
+            Row::Controls => {
+                let all = ControlScheme::ALL;
+                let current = all.iter().position(|&c| c == profile.controls);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(all.len() as i32);
+                profile.controls = all[next as usize];
+            }
+            Row::Haptics => settings.haptics = !settings.haptics,
+            Row::GapPreview => settings.gap_preview = !settings.gap_preview,
+            Row::CornerGrace => {
+                let ticks = settings.corner_grace as i32 + step;
+                settings.corner_grace = ticks.rem_euclid(MAX_GRACE_TICKS as i32 + 1) as u32;
+            }
+            Row::PixelCollision => settings.pixel_collision = !settings.pixel_collision,
+            Row::WideView => {
+                let all = WideView::ALL;
+                let current = all.iter().position(|&v| v == settings.wide_view);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(all.len() as i32);
+                settings.wide_view = all[next as usize];
+            }
+            Row::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            Row::FullscreenDisplay => {
+                let all = FullscreenDisplay::ALL;
+                let current = all.iter().position(|&d| d == settings.fullscreen_display);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(all.len() as i32);
+                settings.fullscreen_display = all[next as usize];
+            }
+            Row::Quality => {
+                // Auto, then each tier locked in turn
+                let all = [
+                    None,
+                    Some(Quality::High),
+                    Some(Quality::Medium),
+                    Some(Quality::Low),
+                ];
+                let current = all.iter().position(|&q| q == settings.quality);
+                let next = (current.unwrap_or(0) as i32 + step).rem_euclid(all.len() as i32);
+                settings.quality = all[next as usize];
+            }
+            Row::StreamerMode => settings.streamer_mode = !settings.streamer_mode,
+            Row::Telemetry => settings.telemetry = !settings.telemetry,
+        }
+        true
+    }
+
+    fn draw_rows(&self, settings: &Settings, profile: &Profile, themes: &[ThemeInfo]) {
+        let first = self
+            .selected
+            .saturating_sub(VISIBLE_ROWS / 2)
+            .min(ROWS.len() - VISIBLE_ROWS);
+        for (i, row) in ROWS.iter().enumerate().skip(first).take(VISIBLE_ROWS) {
+            let (label, value) = match row {
+                Row::Theme => (
+                    "Theme",
+                    themes
+                        .iter()
+                        .find(|t| t.id == settings.theme)
+                        .map_or(settings.theme.as_str(), |t| t.name.as_str()),
+                ),
+                Row::Seasonal => ("Seasonal", on_off(settings.seasonal)),
+                Row::NearMissSlowmo => ("Near-miss slow-mo", on_off(settings.near_miss_slowmo)),
+                Row::Trail => ("Bird trail", on_off(settings.trail)),
+                Row::ReducedMotion => ("Reduced motion", on_off(settings.reduced_motion)),
+                Row::Captions => ("Sound captions", on_off(settings.captions)),
+                Row::Interpolation => ("Smooth motion", on_off(settings.interpolation)),
+                Row::TickCounter => ("Tick counter", on_off(settings.tick_counter)),
+                Row::OneSwitch => ("One-switch mode", on_off(settings.one_switch)),
+                Row::SwitchRepeat => ("Hold to repeat", settings.switch_repeat.name()),
+                Row::Physics => ("Physics", settings.physics.name()),
+                Row::SlowSpeed => ("75% speed", on_off(settings.slow_speed)),
+                Row::WideGaps => ("Wider gaps", on_off(settings.wide_gaps)),
+                Row::StartShield => ("Starting shield", on_off(settings.start_shield)),
+                Row::Adaptive => ("Adaptive difficulty", on_off(settings.adaptive)),
+                Row::Controls => ("Controls", profile.controls.name()),
+                Row::Haptics => ("Vibration", on_off(settings.haptics)),
+                Row::GapPreview => ("Gap preview", on_off(settings.gap_preview)),
+                Row::CornerGrace => ("Corner grace", GRACE_LABELS[settings.corner_grace as usize]),
+                Row::PixelCollision => ("Pixel collision", on_off(settings.pixel_collision)),
+                Row::WideView => ("Wide screens", settings.wide_view.name()),
+                Row::Fullscreen => (
+                    "Fullscreen",
+                    if settings.fullscreen {
+                        "Borderless"
+                    } else {
+                        "Off"
+                    },
+                ),
+                Row::FullscreenDisplay => ("Fullscreen on", settings.fullscreen_display.name()),
+                Row::Quality => ("Effects", settings.quality.map_or("Auto", Quality::name)),
+                Row::StreamerMode => ("Streamer mode", on_off(settings.streamer_mode)),
+                Row::Telemetry => ("Share anonymous stats", on_off(settings.telemetry)),
+            };
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            let y = FIRST_ROW_Y + (i - first) as f32 * ROW_SPACING;
+            draw_text(label, 20.0, y, 20.0, color);
+            let value = format!("< {value} >");
+            let size = measure_text(&value, None, 20, 1.0);
+            draw_text(&value, WIDTH - 20.0 - size.width, y, 20.0, color);
+        }
+
+        // Arrows when there are more rows above or below
+        let x = WIDTH - 10.0;
+        if first > 0 {
+            let y = FIRST_ROW_Y - 26.0;
+            draw_triangle(
+                vec2(x, y),
+                vec2(x - 6.0, y + 6.0),
+                vec2(x + 6.0, y + 6.0),
+                GRAY,
+            );
+        }
+        if first + VISIBLE_ROWS < ROWS.len() {
+            let y = FIRST_ROW_Y + (VISIBLE_ROWS as f32 - 1.0) * ROW_SPACING + 8.0;
+            draw_triangle(vec2(x, y + 6.0), vec2(x - 6.0, y), vec2(x + 6.0, y), GRAY);
+        }
+
+        let hint = match ROWS[self.selected] {
+            Row::Telemetry => "Enter: see what's sent  Tab/Esc to close",
+            _ => "Tab/Esc to close",
+        };
+        draw_centered_text(hint, HEIGHT - 20.0, 16.0, GRAY);
+    }
+}
+
+// The menu's backdrop and title, with `cloud_status` under it while saves
+// are synced
+fn draw_background(cloud_status: Option<String>) {
+    draw_rectangle(
+        FIELD_LEFT,
+        0.0,
+        FIELD_WIDTH,
+        HEIGHT,
+        Color::new(0.0, 0.0, 0.0, 0.7),
+    );
+    draw_centered_text("Settings", 60.0, 32.0, WHITE);
+    if let Some(status) = cloud_status {
+        draw_centered_text(&status, 78.0, 14.0, GRAY);
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "On" } else { "Off" }
+}
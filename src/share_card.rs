@@ -0,0 +1,137 @@
+// Share cards: a picture of a finished run to post somewhere, made from the
+// game over screen with H or the Share button. The card has the score and
+// medal, the theme's bird, the date, and the course code anyone can fly
+// again (see `seed_code.rs`). It's saved next to the screenshots, or
+// downloaded in the browser.
+
+use std::io;
+
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+use crate::date::Date;
+use crate::game::Game;
+use crate::medals::{MEDAL_SIZE, Medal};
+use crate::screenshot::{self, SCREENSHOTS_DIR};
+use crate::seed_code;
+use crate::storage;
+use crate::theme::Theme;
+use crate::viewport::Viewport;
+use crate::{HEIGHT, WIDTH};
+
+pub const SHARE_KEY: KeyCode = KeyCode::H;
+const CARD_WIDTH: f32 = 320.0;
+const CARD_HEIGHT: f32 = 160.0;
+// The bird is drawn this many times its size
+const BIRD_SCALE: f32 = 3.0;
+const BORDER: f32 = 4.0;
+
+/// The Share button, under the course code on the game over screen.
+pub fn button() -> Rect {
+    Rect::new(WIDTH / 2.0 - 36.0, HEIGHT / 2.0 + 108.0, 72.0, 22.0)
+}
+
+/// Whether the player asked for a card this frame. A click on the button
+/// shouldn't also start the next run.
+pub fn pressed(viewport: &Viewport) -> bool {
+    is_key_pressed(SHARE_KEY)
+        || (is_mouse_button_pressed(MouseButton::Left)
+            && button().contains(viewport.mouse_position()))
+}
+
+pub fn draw_button(viewport: &Viewport, theme: &Theme) {
+    let rect = button();
+    if rect.contains(viewport.mouse_position()) {
+        draw_rectangle(
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+            Color::new(1.0, 1.0, 1.0, 0.2),
+        );
+    }
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, theme.text_color);
+    let label = if cfg!(target_os = "android") {
+        "Share"
+    } else {
+        "Share (H)"
+    };
+    let size = measure_text(label, None, 14, 1.0);
+    draw_text(
+        label,
+        rect.center().x - size.width / 2.0,
+        rect.center().y + size.height / 2.0,
+        14.0,
+        theme.text_color,
+    );
+}
+
+/// Makes a card for the run `game` just finished, flown with `theme`
+/// (called `theme_name`), and saves it, returning the file name. Drawing
+/// goes to the card, so begin `viewport` again before drawing anything
+/// else.
+pub fn save(game: &Game, theme: &Theme, theme_name: &str) -> io::Result<String> {
+    let card = compose(game, theme, theme_name);
+    let stamp = (date::now() * 1000.0) as u64;
+    let file = format!("share-{stamp}.png");
+    let path = format!("{SCREENSHOTS_DIR}/{file}");
+    storage::backend().export_blob(&path, &screenshot::encode(&card)?)?;
+    Ok(file)
+}
+
+fn compose(game: &Game, theme: &Theme, theme_name: &str) -> Image {
+    let target = render_target(CARD_WIDTH as u32, CARD_HEIGHT as u32);
+    target.texture.set_filter(FilterMode::Nearest);
+    // Positive y zoom keeps render targets upright, as in `Viewport`
+    set_camera(&Camera2D {
+        target: vec2(CARD_WIDTH / 2.0, CARD_HEIGHT / 2.0),
+        zoom: vec2(2.0 / CARD_WIDTH, 2.0 / CARD_HEIGHT),
+        render_target: Some(target.clone()),
+        ..Default::default()
+    });
+    clear_background(theme.sky_color);
+
+    // The top of the background, repeated across the card
+    let background = &theme.background;
+    let mut x = 0.0;
+    while x < CARD_WIDTH {
+        draw_texture(background, x, 0.0, WHITE);
+        x += background.width().max(1.0);
+    }
+    draw_rectangle_lines(0.0, 0.0, CARD_WIDTH, CARD_HEIGHT, BORDER, theme.text_color);
+
+    let bird = theme.bird.size() * BIRD_SCALE;
+    let middle = CARD_HEIGHT / 2.0;
+    draw_texture_ex(
+        &theme.bird,
+        40.0 - bird.x / 2.0,
+        middle - bird.y / 2.0,
+        theme.tint,
+        DrawTextureParams {
+            dest_size: Some(bird),
+            ..Default::default()
+        },
+    );
+
+    let left = 88.0;
+    let color = theme.text_color;
+    draw_text(&format!("Score {}", game.score), left, 44.0, 32.0, color);
+    if let Some(medal) = Medal::for_score(game.score) {
+        medal.draw(theme, vec2(left + MEDAL_SIZE / 2.0, 66.0));
+        let name = format!("{} medal", medal.name());
+        draw_text(&name, left + MEDAL_SIZE + 6.0, 71.0, 16.0, color);
+    }
+    let Date { year, month, day } = Date::today();
+    let date = format!("{year:04}-{month:02}-{day:02}, {theme_name} theme");
+    draw_text(&date, left, 104.0, 14.0, color);
+    if let Some(code) = seed_code::for_run(game) {
+        draw_text(&format!("Course code {code}"), left, 122.0, 14.0, color);
+    }
+    let footer = format!("Flappy Bird, {} physics", game.physics.name());
+    draw_text(&footer, left, CARD_HEIGHT - 14.0, 12.0, color);
+
+    unsafe {
+        get_internal_gl().flush();
+    }
+    target.texture.get_texture_data()
+}
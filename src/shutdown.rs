@@ -0,0 +1,59 @@
+// Leaving cleanly. Closing the window, Escape on the menus, the kiosk
+// operator's exit and, on desktop, Ctrl-C in the terminal all end the main
+// loop rather than the process. A run still in progress is abandoned then
+// (see `Game::abandon_run`): the player walked away from it rather than
+// crashing, so it isn't a run in the stats, history, quests or the day's
+// streak, and only its time counts, as time played in the session recap.
+// After the recap everything that holds on to something gets its
+// `Shutdown` hook: saves not yet written are written (the stats and streak
+// from the runs that did finish among them), syncs in flight get a moment
+// to finish and sounds are stopped. A second Ctrl-C leaves straight away, in
+// case something hangs on the way out.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Longest a hook waits on the network, for a sync or a send in flight.
+#[cfg_attr(not(any(feature = "cloud", feature = "telemetry")), allow(dead_code))]
+pub const NETWORK_WAIT: Duration = Duration::from_secs(3);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+pub trait Shutdown {
+    /// Saves anything still pending and stops anything still running.
+    /// Called once, as the game exits; nothing is used after.
+    fn shutdown(&mut self);
+}
+
+/// Runs each of `hooks`, in order.
+pub fn run(hooks: &mut [&mut dyn Shutdown]) {
+    for hook in hooks {
+        hook.shutdown();
+    }
+}
+
+/// Catches Ctrl-C from now on, for `interrupted`.
+pub fn catch_interrupt() {
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as *const () as libc::sighandler_t,
+        );
+    }
+}
+
+/// Whether Ctrl-C was pressed, after `catch_interrupt`.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    // Only an atomic and the default handler going back, since a signal
+    // handler can't safely do much else
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
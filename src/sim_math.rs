@@ -0,0 +1,131 @@
+// The simulation's sines and cosines. Everything else a tick does is
+// adding, multiplying, dividing and comparing f32s, which IEEE 754 pins
+// down to the bit on every platform we build for, so a run plays out the
+// same anywhere. `sin` and `cos` aren't pinned down like that: each
+// platform's maths library rounds them its own way, and a wind gust or an
+// enemy's bob a bit off sends the run somewhere else.
+//
+// With the `fixed-point` cargo feature they're worked out here in integer
+// fixed point instead, which has only one answer, so runs, replays and
+// their checksums match bit for bit between x86_64, ARM and the web.
+// Without it they're the standard library's, as they've always been, so
+// the goldens in `replays/golden/` keep playing out the same; fixed-point
+// builds have their own in `replays/golden-fixed/` (see `replay.rs`).
+//
+// The tests pin the fixed-point answers down by hash, so running them on
+// another target checks that it gets exactly the same, e.g.
+// `cargo test --target aarch64-unknown-linux-gnu sim_math` on an ARM
+// machine.
+
+/// The simulation's sine of `radians`.
+pub fn sin(radians: f32) -> f32 {
+    if cfg!(feature = "fixed-point") {
+        fixed::sin(radians)
+    } else {
+        radians.sin()
+    }
+}
+
+/// The simulation's cosine of `radians`.
+pub fn cos(radians: f32) -> f32 {
+    if cfg!(feature = "fixed-point") {
+        fixed::cos(radians)
+    } else {
+        radians.cos()
+    }
+}
+
+#[cfg_attr(not(feature = "fixed-point"), allow(dead_code))]
+mod fixed {
+    // Angles go round in turns, as 32-bit fractions of a turn, so wrapping
+    // to one turn is just keeping the low bits. Within a quarter turn the
+    // sine is the Taylor series of sin(πz/2) up to z¹¹, which is closer
+    // than an f32 can tell, worked in 2.30 fixed point.
+    const ONE: i64 = 1 << 30;
+    const QUARTER_TURN: u32 = 1 << 30;
+    // 2⁶⁴ / 2π, for going from radians in 32.32 fixed point to turns
+    const TURNS_PER_RADIAN: i128 = 2_935_890_503_282_001_408;
+    // (π/2)ⁿ / n! for odd n, in 2.30
+    const TERMS: [i64; 6] = [
+        1_686_629_713,
+        693_598_668,
+        85_569_306,
+        5_026_995,
+        172_272,
+        3_864,
+    ];
+
+    pub fn sin(radians: f32) -> f32 {
+        sin_turns(turns(radians))
+    }
+
+    pub fn cos(radians: f32) -> f32 {
+        sin_turns(turns(radians).wrapping_add(QUARTER_TURN))
+    }
+
+    // How far round a turn `radians` is. An f32 is exact in 32.32 for
+    // every angle the game gets to, and far bigger ones saturate.
+    fn turns(radians: f32) -> u32 {
+        let fixed = (f64::from(radians) * 4_294_967_296.0) as i64;
+        ((i128::from(fixed) * TURNS_PER_RADIAN) >> 64) as u32
+    }
+
+    fn sin_turns(turns: u32) -> f32 {
+        let within = i64::from(turns % QUARTER_TURN);
+        // The first and third quarters rise the way the sine does from 0,
+        // the other two fall back from the top; the second half is below
+        let z = if (turns / QUARTER_TURN).is_multiple_of(2) {
+            within
+        } else {
+            ONE - within
+        };
+        let zz = (z * z) >> 30;
+        let series = TERMS
+            .iter()
+            .rev()
+            .fold(0, |rest, &term| term - ((rest * zz) >> 30));
+        let sine = (series * z) >> 30;
+        let sine = if turns >= 2 * QUARTER_TURN {
+            -sine
+        } else {
+            sine
+        };
+        sine as f32 / ONE as f32
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // Angles from minus to plus 64, plus what the simulation gets to
+        // after an hour of wind
+        fn angles() -> impl Iterator<Item = f32> {
+            (-4096..=4096)
+                .map(|i| i as f32 / 64.0)
+                .chain((0..512).map(|i| 3000.0 + i as f32 * 0.37))
+        }
+
+        #[test]
+        fn close_to_the_standard_library() {
+            for x in angles() {
+                let (s, c) = (f64::from(x).sin(), f64::from(x).cos());
+                assert!((f64::from(sin(x)) - s).abs() < 1e-6, "sin {x}");
+                assert!((f64::from(cos(x)) - c).abs() < 1e-6, "cos {x}");
+            }
+        }
+
+        #[test]
+        fn the_same_on_every_target() {
+            let hash = angles()
+                .flat_map(|x| [sin(x), cos(x)])
+                .flat_map(|y| y.to_bits().to_le_bytes())
+                .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                    (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+                });
+            assert_eq!(
+                hash, 0x5a0b_05f8_a727_6f46,
+                "sin or cos came out differently"
+            );
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// The simulation's random numbers. It's the generator macroquad's
+// `RandGenerator` is, number for number, so courses come out the same as
+// they always have, but its state is a plain number rather than an atomic,
+// so a game holding one can be cloned, as the replay viewer's checkpoints
+// are (see `replay_viewer.rs`).
+
+use std::cell::Cell;
+
+// PCG's constants, as in `quad-rand`
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+#[derive(Clone)]
+pub struct SimRng {
+    state: Cell<u64>,
+}
+
+impl SimRng {
+    /// A generator seeded like `RandGenerator::srand(seed)`.
+    pub fn new(seed: u64) -> SimRng {
+        let rng = SimRng {
+            state: Cell::new(0),
+        };
+        rng.next();
+        rng.state.set(rng.state.get().wrapping_add(seed));
+        rng.next();
+        rng
+    }
+
+    fn next(&self) -> u32 {
+        let old = self.state.get();
+        self.state
+            .set(old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT));
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// A number from `low` up to `high`, not including it.
+    pub fn gen_range<T: Ranged>(&self, low: T, high: T) -> T {
+        let r = f64::from(self.next()) / (f64::from(u32::MAX) + 1.0);
+        let (low, high) = (low.to_f64(), high.to_f64());
+        T::from_f64(low + (high - low) * r)
+    }
+}
+
+/// What `gen_range` can pick, worked through f64 as `quad-rand` does.
+pub trait Ranged: Copy {
+    fn to_f64(self) -> f64;
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! ranged {
+    ($($ty:ty),*) => {
+        $(
+            impl Ranged for $ty {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+            }
+        )*
+    };
+}
+
+ranged!(f32, i32, u32, usize);
+
+#[cfg(test)]
+mod tests {
+    use macroquad::rand::RandGenerator;
+
+    use super::*;
+
+    #[test]
+    fn the_same_numbers_as_macroquad() {
+        for seed in [0, 1, 2024, 0xbe4c, u64::MAX] {
+            let (ours, theirs) = (SimRng::new(seed), RandGenerator::new());
+            theirs.srand(seed);
+            for _ in 0..1000 {
+                assert_eq!(ours.gen_range(0, 2), theirs.gen_range(0, 2));
+                assert_eq!(ours.gen_range(-3.5, 80.0), theirs.gen_range(-3.5, 80.0));
+                assert_eq!(ours.gen_range(3, 900_usize), theirs.gen_range(3, 900_usize));
+            }
+        }
+    }
+}
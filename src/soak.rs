@@ -0,0 +1,336 @@
+// Soak test (`--soak <hours>`): the autopilot flies run after run without
+// a window for that long, cycling through the challenges on a new course
+// each run, with the effects the game would show for what happens. Every
+// so often it notes how much memory the process holds and how big each
+// pool got, and at the end writes it all to SOAK_REPORT_FILE, flagging
+// whatever kept growing. Panics are caught, noted with the run's seed, and
+// the soak carries on in a fresh game.
+//
+// Memory is read from /proc, so it's only measured on Linux.
+
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+
+use macroquad::prelude::*;
+
+use crate::challenge::Challenge;
+use crate::death_replay::StateBuffer;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState};
+use crate::kiosk;
+use crate::particles::Particles;
+use crate::perf;
+use crate::pipes::SCROLL_SPEED;
+use crate::popups::ScorePopups;
+use crate::timestep::TICK_SECONDS;
+use crate::toast::Toasts;
+use crate::trail::Trail;
+
+const SOAK_REPORT_FILE: &str = "soak_report.txt";
+const FIRST_SEED: u64 = 0x50a4;
+// The autopilot can fly forever, so runs are ended after ten minutes
+const MAX_RUN_TICKS: u32 = 60 * 60 * 10;
+// Ticks between looks at the clock
+const CHECK_TICKS: u32 = 600;
+// At least this many samples whatever the length, and at most a minute
+// apart
+const MIN_SAMPLES: f64 = 20.0;
+const MAX_SAMPLE_SECONDS: f64 = 60.0;
+// Something is growing when the second half of the samples peaks this much
+// higher than the first half did
+const GROWTH_FACTOR: f64 = 1.5;
+const GROWTH_SLACK: u64 = 16;
+const MEMORY_SLACK_KB: u64 = 8 * 1024;
+const POOLS: [&str; 11] = [
+    "pipes",
+    "coins",
+    "power-ups",
+    "enemies",
+    "terrain",
+    "flaps",
+    "particles",
+    "popups",
+    "toasts",
+    "trail",
+    "replay",
+];
+
+struct Sample {
+    // Seconds into the soak
+    at: f64,
+    memory_kb: Option<u64>,
+    // The largest each of POOLS got since the sample before
+    pools: [usize; POOLS.len()],
+}
+
+struct Panic {
+    run: u32,
+    seed: u64,
+    tick: u32,
+    message: String,
+}
+
+struct Soak {
+    game: Game,
+    particles: Particles,
+    popups: ScorePopups,
+    toasts: Toasts,
+    trail: Trail,
+    state_buffer: StateBuffer,
+    runs: u32,
+    ticks: u64,
+    panics: Vec<Panic>,
+    // Since the last sample
+    peaks: [usize; POOLS.len()],
+}
+
+impl Soak {
+    fn new() -> Soak {
+        Soak {
+            game: Game::new(),
+            particles: Particles::default(),
+            popups: ScorePopups::default(),
+            toasts: Toasts::default(),
+            trail: Trail::default(),
+            state_buffer: StateBuffer::default(),
+            runs: 0,
+            ticks: 0,
+            panics: Vec::new(),
+            peaks: [0; POOLS.len()],
+        }
+    }
+
+    // Starts the next run, on the next challenge and a course of its own
+    fn start_run(&mut self) {
+        let challenges = Challenge::ALL.len() as u32 + 1;
+        let challenge = Challenge::ALL.get((self.runs % challenges) as usize);
+        self.game.fixed_seed = Some(FIRST_SEED + u64::from(self.runs));
+        self.game.reset();
+        self.game.set_challenge(challenge.copied());
+        self.game.flap();
+        self.runs += 1;
+    }
+
+    fn tick(&mut self) {
+        if self.game.state != GameState::Playing {
+            self.start_run();
+        }
+        if kiosk::autopilot_flap(&self.game) {
+            self.game.flap();
+        }
+        self.game.update();
+        if self.game.ticks >= MAX_RUN_TICKS {
+            self.game.end_run();
+        }
+        for event in self.game.events.drain() {
+            self.handle(event);
+        }
+        self.state_buffer.record(&self.game);
+        self.trail.record(&self.game.bird, SCROLL_SPEED);
+        self.particles.update(TICK_SECONDS);
+        self.popups.update(TICK_SECONDS);
+        self.toasts.update(TICK_SECONDS);
+        let sizes = self.pool_sizes();
+        for (peak, size) in self.peaks.iter_mut().zip(sizes) {
+            *peak = (*peak).max(size);
+        }
+    }
+
+    // What the game shows for `event`, minus the drawing
+    fn handle(&mut self, event: GameEvent) {
+        let bird = self.game.bird.rect();
+        match event {
+            GameEvent::RunStarted => {
+                self.trail.clear();
+                self.state_buffer.clear();
+            }
+            GameEvent::PipePassed { points, .. } => {
+                self.popups
+                    .push(points, vec2(bird.center().x, bird.y - 4.0));
+            }
+            GameEvent::MilestoneReached { pipes } => {
+                self.toasts.push(format!("{pipes}! Keep going!"), GOLD);
+            }
+            GameEvent::BossStarted => self.toasts.push("Boss incoming!", RED),
+            GameEvent::BiomeReached { biome } => {
+                self.toasts
+                    .push(format!("Entering the {}", biome.name()), SKYBLUE);
+            }
+            GameEvent::CoinCollected => self.particles.burst(bird.center(), 8, 120.0, GOLD),
+            GameEvent::ShieldBroken { x, y } => {
+                self.particles.burst(vec2(x, y), 24, 160.0, SKYBLUE);
+            }
+            GameEvent::Collided => self.particles.burst(bird.center(), 30, 200.0, RED),
+            _ => {}
+        }
+    }
+
+    fn pool_sizes(&self) -> [usize; POOLS.len()] {
+        let game = &self.game;
+        let terrain = game
+            .terrain
+            .as_ref()
+            .map_or(0, |terrain| terrain.ceiling.len() + terrain.floor.len());
+        [
+            game.pipes.len(),
+            game.coins.len(),
+            game.powerups.len(),
+            game.enemies.len(),
+            terrain,
+            game.flap_ticks.len(),
+            self.particles.count(),
+            self.popups.count(),
+            self.toasts.count(),
+            self.trail.count(),
+            self.state_buffer.count(),
+        ]
+    }
+
+    // Notes the panic that ended the last tick and carries on in a fresh
+    // game, since the old one may be half updated
+    fn recover(&mut self, payload: Box<dyn std::any::Any + Send>) {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "(no message)".to_owned());
+        self.panics.push(Panic {
+            run: self.runs,
+            seed: self.game.seed,
+            tick: self.game.ticks,
+            message,
+        });
+        self.game = Game::new();
+        self.particles = Particles::default();
+        self.popups = ScorePopups::default();
+        self.toasts = Toasts::default();
+        self.trail.clear();
+        self.state_buffer.clear();
+    }
+}
+
+/// Soaks for `hours`, then prints and writes the report. Returns the exit
+/// code: 1 if anything panicked or kept growing.
+pub fn soak_command(hours: f32) -> i32 {
+    let seconds = f64::from(hours) * 3600.0;
+    let sample_every = (seconds / MIN_SAMPLES).min(MAX_SAMPLE_SECONDS);
+    println!("Soaking for {hours} hours, the report goes to {SOAK_REPORT_FILE}");
+    let mut soak = Soak::new();
+    let started = perf::clock();
+    let memory_at_start = memory_kb();
+    let mut samples = Vec::new();
+    let mut next_sample = sample_every;
+    loop {
+        for _ in 0..CHECK_TICKS {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| soak.tick())) {
+                soak.recover(payload);
+            }
+            soak.ticks += 1;
+        }
+        let at = perf::clock() - started;
+        if at >= next_sample || at >= seconds {
+            samples.push(Sample {
+                at,
+                memory_kb: memory_kb(),
+                pools: std::mem::take(&mut soak.peaks),
+            });
+            next_sample += sample_every;
+        }
+        if at >= seconds {
+            break;
+        }
+    }
+
+    let (report, passed) = report(&soak, memory_at_start, &samples);
+    print!("{report}");
+    if let Err(err) = fs::write(SOAK_REPORT_FILE, &report) {
+        eprintln!("Could not write {SOAK_REPORT_FILE}: {err}");
+    }
+    if passed { 0 } else { 1 }
+}
+
+// The report, and whether the soak passed
+fn report(soak: &Soak, memory_at_start: Option<u64>, samples: &[Sample]) -> (String, bool) {
+    let hours = samples.last().map_or(0.0, |sample| sample.at / 3600.0);
+    let played = soak.ticks as f64 * f64::from(TICK_SECONDS) / 3600.0;
+    let mut report = format!(
+        "Soaked: {hours:.2} h, {} ticks ({played:.1} h of play), {} runs\n",
+        soak.ticks, soak.runs
+    );
+    let mut passed = soak.panics.is_empty();
+
+    report += &format!("Panics: {}\n", soak.panics.len());
+    for panic in &soak.panics {
+        report += &format!(
+            "  run {} (seed {}) at tick {}: {}\n",
+            panic.run, panic.seed, panic.tick, panic.message
+        );
+    }
+
+    let memory: Vec<u64> = samples
+        .iter()
+        .filter_map(|sample| sample.memory_kb)
+        .collect();
+    match (memory_at_start, memory.last(), memory.iter().max()) {
+        (Some(start), Some(end), Some(peak)) => {
+            let growing = growing(&memory, MEMORY_SLACK_KB);
+            passed &= !growing;
+            report += &format!(
+                "Memory: {} at the start, {} at the end, {} at most{}\n",
+                megabytes(start),
+                megabytes(*end),
+                megabytes(*peak),
+                if growing { ", GROWING" } else { "" }
+            );
+        }
+        _ => report += "Memory: not measured on this system\n",
+    }
+
+    report += "Largest pools:\n";
+    for (i, name) in POOLS.iter().enumerate() {
+        let sizes: Vec<u64> = samples
+            .iter()
+            .map(|sample| sample.pools[i] as u64)
+            .collect();
+        let growing = growing(&sizes, GROWTH_SLACK);
+        passed &= !growing;
+        let largest = sizes.iter().max().copied().unwrap_or(0);
+        let flag = if growing { ", GROWING" } else { "" };
+        report += &format!("  {name}: {largest}{flag}\n");
+    }
+
+    report += &format!("Samples:\n  minutes memory {}\n", POOLS.join(" "));
+    for sample in samples {
+        let memory = sample.memory_kb.map_or("-".to_owned(), megabytes);
+        let pools: Vec<String> = sample.pools.iter().map(usize::to_string).collect();
+        report += &format!("  {:.1} {memory} {}\n", sample.at / 60.0, pools.join(" "));
+    }
+    report += if passed { "PASSED\n" } else { "FAILED\n" };
+    (report, passed)
+}
+
+// Whether the second half of `peaks` went well past the first half's
+// highest, which a pool that's cleaned up properly shouldn't do once the
+// game has been through everything
+fn growing(peaks: &[u64], slack: u64) -> bool {
+    if peaks.len() < 4 {
+        return false;
+    }
+    let (first, second) = peaks.split_at(peaks.len() / 2);
+    let (Some(&before), Some(&after)) = (first.iter().max(), second.iter().max()) else {
+        return false;
+    };
+    after > before + slack && after as f64 > before as f64 * GROWTH_FACTOR
+}
+
+fn megabytes(kb: u64) -> String {
+    perf::megabytes(kb as usize * 1024)
+}
+
+// How much of the process is in memory, from the VmRSS line of
+// /proc/self/status
+fn memory_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
@@ -0,0 +1,276 @@
+// Watching an online match, from the online menu or with
+// `--watch <code or address>`
+//
+// Spectators keep asking the host with WATCH, which also keeps them on its
+// list, and get VIEW snapshots of both birds back (see `versus.rs`).
+// Snapshots are played back DELAY_SECONDS late so there is nearly always a
+// newer one to ease towards, which hides network jitter. The pipes come from
+// the race's seed: the spectator scrolls its own copy of the course, with a
+// ghost bird following the host's so it unfolds the same way.
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
+
+use macroquad::prelude::*;
+
+use crate::bird::Bird;
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::net::{self, Link};
+use crate::theme::Theme;
+use crate::timestep::TICK_SECONDS;
+use crate::versus::{Packet, Racer, TIMEOUT_SECONDS};
+use crate::{HEIGHT, WIDTH};
+
+const DELAY_SECONDS: f32 = 0.25;
+// Further than this from where playback should be, jump instead of drifting
+const MAX_DRIFT_SECONDS: f32 = 0.5;
+const WATCH_INTERVAL_SECONDS: f32 = 0.5;
+const GUEST_TINT: Color = Color::new(0.6, 0.8, 1.0, 1.0);
+
+#[derive(Clone, Copy)]
+struct Snapshot {
+    time: f32,
+    host: Racer,
+    guest: Racer,
+    host_won: Option<bool>,
+}
+
+// The race on screen
+struct Race {
+    round: u32,
+    course: Game,
+    // Playback position, in the host's race time
+    clock: f32,
+    // Course updates run so far
+    ticks: u32,
+    // Oldest first; only the one before `clock` and those after are kept
+    snapshots: VecDeque<Snapshot>,
+}
+
+pub struct Spectator {
+    link: Link,
+    host: SocketAddr,
+    // Whether the host has answered yet
+    watching: bool,
+    race: Option<Race>,
+    // Seconds since the host was last heard from
+    silence: f32,
+    watch_timer: f32,
+}
+
+impl Spectator {
+    /// Starts watching the match behind a join code, or at `address`.
+    pub fn watch(address: &str) -> io::Result<Spectator> {
+        Ok(Spectator {
+            host: net::resolve(address)?,
+            link: Link::bind(0)?,
+            watching: false,
+            race: None,
+            silence: 0.0,
+            watch_timer: 0.0,
+        })
+    }
+
+    /// Exchanges packets and plays the race back. Returns a message worth
+    /// showing, if something happened.
+    pub fn update(&mut self, dt: f32) -> Option<String> {
+        let mut notice = None;
+        while let Some((text, from)) = self.link.receive() {
+            if from != self.host {
+                continue;
+            }
+            if let Some(packet) = Packet::decode(&text)
+                && let Some(text) = self.handle(packet)
+            {
+                notice = Some(text);
+            }
+        }
+
+        self.silence += dt;
+        if self.watching && self.silence > TIMEOUT_SECONDS {
+            self.watching = false;
+            self.race = None;
+            notice = Some("Lost the match".to_owned());
+        }
+
+        self.watch_timer -= dt;
+        if self.watch_timer <= 0.0 {
+            self.watch_timer = WATCH_INTERVAL_SECONDS;
+            self.link.send(Packet::Watch.encode(), self.host);
+        }
+
+        if let Some(race) = &mut self.race {
+            race.play(dt);
+        }
+        notice
+    }
+
+    fn handle(&mut self, packet: Packet) -> Option<String> {
+        self.silence = 0.0;
+        let notice = (!self.watching).then(|| "Watching".to_owned());
+        self.watching = true;
+        match packet {
+            Packet::Waiting => self.race = None,
+            Packet::View {
+                round,
+                seed,
+                time,
+                host,
+                guest,
+                host_won,
+            } => {
+                let current = self.race.as_ref().map(|race| race.round);
+                if current.is_some_and(|current| round < current) {
+                    return notice;
+                }
+                if current != Some(round) {
+                    self.race = Some(Race::new(round, seed, time));
+                }
+                if let Some(race) = &mut self.race {
+                    race.record(Snapshot {
+                        time,
+                        host,
+                        guest,
+                        host_won,
+                    });
+                }
+            }
+            Packet::Bye => {
+                self.watching = false;
+                self.race = None;
+                return Some("The host left".to_owned());
+            }
+            _ => {}
+        }
+        notice
+    }
+
+    pub fn draw(&self, theme: &Theme) {
+        let Some(race) = &self.race else {
+            let status = if self.watching {
+                "Waiting for the next race".to_owned()
+            } else {
+                format!("Connecting to {}", self.host)
+            };
+            draw_centered_text(&status, HEIGHT - 30.0, 14.0, theme.text_color);
+            return;
+        };
+
+        race.course.draw_course(theme);
+        let Some((host, guest)) = race.sample() else {
+            return;
+        };
+        let x = Bird::new().x;
+        if guest.alive {
+            draw_texture(&theme.bird, x, guest.y, GUEST_TINT);
+        }
+        if host.alive {
+            draw_texture(&theme.bird, x, host.y, theme.tint);
+        }
+
+        draw_text(
+            &format!("Host {}", host.score),
+            8.0,
+            24.0,
+            20.0,
+            theme.text_color,
+        );
+        let guest_score = format!("Guest {}", guest.score);
+        let size = measure_text(&guest_score, None, 20, 1.0);
+        draw_text(
+            &guest_score,
+            WIDTH - 8.0 - size.width,
+            24.0,
+            20.0,
+            GUEST_TINT,
+        );
+
+        if race.clock < 0.0 {
+            let left = (-race.clock).ceil() as u32;
+            draw_centered_text(&left.to_string(), HEIGHT / 2.0, 48.0, theme.text_color);
+        }
+        if let Some(host_won) = race.snapshots.front().and_then(|s| s.host_won) {
+            let result = if host_won { "Host wins" } else { "Guest wins" };
+            draw_centered_text(result, HEIGHT / 2.0 + 10.0, 28.0, theme.text_color);
+        }
+        draw_centered_text("Spectating", HEIGHT - 30.0, 14.0, theme.text_color);
+    }
+}
+
+impl Race {
+    fn new(round: u32, seed: u64, time: f32) -> Race {
+        let mut course = Game::with_seed(Some(seed));
+        course.ghost = true;
+        Race {
+            round,
+            course,
+            clock: time - DELAY_SECONDS,
+            ticks: 0,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, snapshot: Snapshot) {
+        // Anything older than the newest one arrived out of order
+        if self
+            .snapshots
+            .back()
+            .is_none_or(|last| snapshot.time > last.time)
+        {
+            self.snapshots.push_back(snapshot);
+        }
+    }
+
+    fn play(&mut self, dt: f32) {
+        let Some(newest) = self.snapshots.back() else {
+            return;
+        };
+        // Runs with the local clock, nudged back into line when the host's
+        // packets speed up or stall
+        let target = newest.time - DELAY_SECONDS;
+        self.clock += dt;
+        if (self.clock - target).abs() > MAX_DRIFT_SECONDS {
+            self.clock = target;
+        }
+        while self.snapshots.len() > 1 && self.snapshots[1].time <= self.clock {
+            self.snapshots.pop_front();
+        }
+
+        let Some((host, _)) = self.sample() else {
+            return;
+        };
+        if self.clock < 0.0 {
+            return;
+        }
+        if self.course.state == GameState::Ready {
+            self.course.flap();
+        }
+        // Catches up in one go when joining halfway through a race
+        let due = (self.clock / TICK_SECONDS) as u32;
+        while self.ticks < due {
+            self.course.bird.y = host.y;
+            self.course.bird.velocity = Vec2::ZERO;
+            self.course.update();
+            self.ticks += 1;
+        }
+        self.course.events.drain();
+    }
+
+    // Both birds at the playback clock, between the snapshots around it
+    fn sample(&self) -> Option<(Racer, Racer)> {
+        let before = *self.snapshots.front()?;
+        let Some(after) = self.snapshots.get(1) else {
+            return Some((before.host, before.guest));
+        };
+        let t = ((self.clock - before.time) / (after.time - before.time)).clamp(0.0, 1.0);
+        let blend = |a: Racer, b: Racer| Racer {
+            y: a.y + (b.y - a.y) * t,
+            ..a
+        };
+        Some((
+            blend(before.host, after.host),
+            blend(before.guest, after.guest),
+        ))
+    }
+}
@@ -0,0 +1,275 @@
+// Stats screen, opened from the title screen with S: lifetime totals, then
+// either a heatmap of where runs end, by pipe pair and height around its
+// gap, or a calendar of the days played (Tab switches)
+
+use macroquad::prelude::*;
+
+use crate::achievements::Achievement;
+use crate::challenge::Challenge;
+use crate::date::{self, Date};
+use crate::game::draw_centered_text;
+use crate::history;
+use crate::physics::Physics;
+use crate::pipes::GAP_HEIGHT;
+use crate::profile::Profile;
+use crate::screen::{Context, Screen, Transition};
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+// The last column also counts every later pipe pair
+const COLUMNS: usize = 8;
+// Crashes further from the gap's middle land in the outer rows
+const OFFSET_ROWS: usize = 10;
+const ROW_PIXELS: i32 = 12;
+const CELL_HEIGHT: f32 = 12.0;
+const GRID_LEFT: f32 = 48.0;
+const GRID_TOP: f32 = 172.0;
+const DAY_WIDTH: f32 = 32.0;
+const DAY_HEIGHT: f32 = 26.0;
+const CALENDAR_TOP: f32 = 192.0;
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+#[derive(Default, PartialEq)]
+enum Page {
+    #[default]
+    Crashes,
+    Calendar,
+}
+
+#[derive(Default)]
+pub struct StatsScreen {
+    page: Page,
+    // Months back from the current one, on the calendar
+    months_back: u32,
+}
+
+impl Screen for StatsScreen {
+    fn handle_input(&mut self, cx: &mut Context) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::S) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            self.page = match self.page {
+                Page::Crashes => Page::Calendar,
+                Page::Calendar => Page::Crashes,
+            };
+            self.months_back = 0;
+        }
+        if self.page == Page::Calendar {
+            if is_key_pressed(KeyCode::Left) {
+                self.months_back += 1;
+            }
+            if is_key_pressed(KeyCode::Right) {
+                self.months_back = self.months_back.saturating_sub(1);
+            }
+        }
+        if is_key_pressed(KeyCode::E) {
+            match history::export(&cx.profile.history) {
+                Ok(stem) => cx
+                    .toasts
+                    .push(format!("Exported {stem}.csv and .json"), WHITE),
+                Err(err) => cx.toasts.push(format!("Export failed: {err}"), RED),
+            }
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, cx: &Context) {
+        let profile = &*cx.profile;
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Stats", 60.0, 32.0, WHITE);
+
+        let today = date::today_days();
+        let mut totals = vec![
+            format!("Runs {}   Best {}", profile.runs, profile.best_score),
+            format!("Pipes {}   Coins {}", profile.pipes_passed, profile.coins),
+            format!(
+                "Achievements {}/{}   Streak {}",
+                profile.achievements.len(),
+                Achievement::ALL.len(),
+                profile.streak(today)
+            ),
+        ];
+        // Bests kept apart from the main one, once there are any
+        let mut other_bests = Vec::new();
+        if profile.best_assisted > 0 {
+            other_bests.push(format!("Assisted {}", profile.best_assisted));
+        }
+        if profile.best_mutated > 0 {
+            other_bests.push(format!("Mutators {}", profile.best_mutated));
+        }
+        for physics in Physics::ALL {
+            if let Some(best) = profile.physics_bests.get(&physics) {
+                other_bests.push(format!("{} {best}", physics.name()));
+            }
+        }
+        for challenge in Challenge::ALL {
+            if let Some(best) = profile.challenge_bests.get(&challenge) {
+                other_bests.push(format!("{} {best}", challenge.name()));
+            }
+        }
+        if !other_bests.is_empty() {
+            totals.push(other_bests.join("   "));
+        }
+        for (i, line) in totals.iter().enumerate() {
+            draw_centered_text(line, 92.0 + i as f32 * 18.0, 16.0, WHITE);
+        }
+
+        match self.page {
+            Page::Crashes => {
+                draw_centered_text("Where you crash", 160.0, 16.0, GRAY);
+                if profile.deaths.is_empty() {
+                    draw_centered_text("No crashes yet", GRID_TOP + 60.0, 16.0, GRAY);
+                } else {
+                    draw_heatmap(profile);
+                }
+                draw_centered_text(
+                    "Tab calendar, E export, Esc close",
+                    HEIGHT - 20.0,
+                    16.0,
+                    GRAY,
+                );
+            }
+            Page::Calendar => {
+                draw_calendar(profile, today, self.months_back);
+                draw_centered_text("Left/Right month, Tab crashes", HEIGHT - 20.0, 16.0, GRAY);
+            }
+        }
+    }
+}
+
+// Offset rows from the top of the gap down, then a row for the ground
+fn draw_heatmap(profile: &Profile) {
+    let mut counts = [[0_u32; COLUMNS]; OFFSET_ROWS + 1];
+    let (mut top, mut bottom, mut ground) = (0, 0, 0);
+    for death in &profile.deaths {
+        let column = (death.pipe as usize).min(COLUMNS - 1);
+        let row = if death.ground {
+            ground += 1;
+            OFFSET_ROWS
+        } else {
+            if death.offset < 0 {
+                top += 1;
+            } else {
+                bottom += 1;
+            }
+            let half = OFFSET_ROWS as i32 / 2;
+            (death.offset.div_euclid(ROW_PIXELS) + half).clamp(0, OFFSET_ROWS as i32 - 1) as usize
+        };
+        counts[row][column] += 1;
+    }
+    let most = counts.iter().flatten().copied().max().unwrap_or(1) as f32;
+
+    let cell_width = (WIDTH - GRID_LEFT - 12.0) / COLUMNS as f32;
+    for (row, cells) in counts.iter().enumerate() {
+        // A gap between the air and the ground row
+        let extra = if row == OFFSET_ROWS { 6.0 } else { 0.0 };
+        let y = GRID_TOP + row as f32 * CELL_HEIGHT + extra;
+        for (column, &count) in cells.iter().enumerate() {
+            let heat = count as f32 / most;
+            let color = if count == 0 {
+                Color::new(1.0, 1.0, 1.0, 0.06)
+            } else {
+                Color::new(1.0, 1.0 - heat, 0.1, 0.3 + 0.7 * heat)
+            };
+            let x = GRID_LEFT + column as f32 * cell_width;
+            draw_rectangle(x + 1.0, y + 1.0, cell_width - 2.0, CELL_HEIGHT - 2.0, color);
+        }
+    }
+
+    // Where the pipe lips sit in a normal-sized gap
+    let grid_width = cell_width * COLUMNS as f32;
+    let middle = GRID_TOP + OFFSET_ROWS as f32 / 2.0 * CELL_HEIGHT;
+    let lip = GAP_HEIGHT / 2.0 / ROW_PIXELS as f32 * CELL_HEIGHT;
+    for y in [middle - lip, middle + lip] {
+        draw_line(GRID_LEFT, y, GRID_LEFT + grid_width, y, 1.0, GREEN);
+    }
+
+    let ground_y = GRID_TOP + OFFSET_ROWS as f32 * CELL_HEIGHT + 6.0;
+    draw_text("Top", 8.0, GRID_TOP + 10.0, 14.0, GRAY);
+    draw_text("Gap", 8.0, middle + 4.0, 14.0, GRAY);
+    draw_text("Ground", 4.0, ground_y + 10.0, 14.0, GRAY);
+    for column in 0..COLUMNS {
+        let label = if column == COLUMNS - 1 {
+            format!("{}+", column + 1)
+        } else {
+            (column + 1).to_string()
+        };
+        let x = GRID_LEFT + column as f32 * cell_width + cell_width / 2.0 - 4.0;
+        draw_text(&label, x, ground_y + CELL_HEIGHT + 14.0, 14.0, GRAY);
+    }
+    draw_centered_text("Pipe", ground_y + CELL_HEIGHT + 30.0, 14.0, GRAY);
+
+    let total = profile.deaths.len() as f32;
+    let share = |n: i32| (n as f32 / total * 100.0).round();
+    let summary = format!(
+        "Top lip {}%  Bottom lip {}%  Ground {}%",
+        share(top),
+        share(bottom),
+        share(ground)
+    );
+    draw_centered_text(&summary, HEIGHT - 44.0, 14.0, WHITE);
+}
+
+// A month of days, Monday first, with the days played filled in
+fn draw_calendar(profile: &Profile, today: i64, months_back: u32) {
+    let mut first = Date::month_start(today);
+    for _ in 0..months_back {
+        first = Date::month_start(first - 1);
+    }
+    // 31 days on from the first is always in the next month
+    let length = Date::month_start(first + 31) - first;
+    let Date { year, month, .. } = Date::from_days(first);
+    let title = format!("{} {year}", MONTHS[month as usize - 1]);
+    draw_centered_text(&title, 160.0, 16.0, GRAY);
+
+    let left = (WIDTH - DAY_WIDTH * 7.0) / 2.0;
+    for (i, weekday) in ["M", "T", "W", "T", "F", "S", "S"].iter().enumerate() {
+        let x = left + i as f32 * DAY_WIDTH + DAY_WIDTH / 2.0 - 4.0;
+        draw_text(weekday, x, CALENDAR_TOP - 6.0, 14.0, GRAY);
+    }
+    let offset = i64::from(Date::weekday(first));
+    for day in first..first + length {
+        let cell = day - first + offset;
+        let x = left + (cell % 7) as f32 * DAY_WIDTH;
+        let y = CALENDAR_TOP + (cell / 7) as f32 * DAY_HEIGHT;
+        let (width, height) = (DAY_WIDTH - 4.0, DAY_HEIGHT - 4.0);
+        let fill = if profile.played_on(day) {
+            Color::new(0.2, 0.8, 0.3, 0.8)
+        } else {
+            Color::new(1.0, 1.0, 1.0, 0.06)
+        };
+        draw_rectangle(x + 2.0, y + 2.0, width, height, fill);
+        if day == today {
+            draw_rectangle_lines(x + 2.0, y + 2.0, width, height, 2.0, YELLOW);
+        }
+        let label = (day - first + 1).to_string();
+        draw_text(&label, x + 6.0, y + 18.0, 14.0, WHITE);
+    }
+
+    let summary = format!(
+        "Streak {} days   Best {} days",
+        profile.streak(today),
+        profile.best_streak()
+    );
+    draw_centered_text(&summary, HEIGHT - 44.0, 14.0, WHITE);
+}
@@ -0,0 +1,151 @@
+// Steam: achievements, Steam Cloud saves and rich presence for a Steam
+// release, fed from the same places as the game's own versions of them
+//
+// Needs the `steam` cargo feature, and the game started from Steam (or
+// with a `steam_appid.txt` next to it). Without either this does nothing.
+// The app's Steamworks setup needs:
+//
+// - an achievement for each of ours, with the API name being its id in
+//   the profile file (`keep_going`, `half_century`, ...)
+// - Steam Cloud turned on with room for the profile and run history
+//
+// Saves go up after every save of the profile and come down at launch
+// when Steam's copy is newer, so a second computer picks up where the
+// first left off. Steam sorts out conflicts itself before the game starts.
+
+#[cfg(feature = "steam")]
+use steamworks::Client;
+
+use crate::achievements::Achievement;
+use crate::shutdown::Shutdown;
+
+pub struct Steam {
+    #[cfg(feature = "steam")]
+    client: Option<Client>,
+}
+
+impl Steam {
+    pub fn connect() -> Steam {
+        Steam {
+            #[cfg(feature = "steam")]
+            client: Client::init()
+                .inspect_err(|err| eprintln!("Steam isn't available: {err}"))
+                .ok(),
+        }
+    }
+
+    /// The Steamworks client, for rich presence (see `presence.rs`).
+    #[cfg(feature = "steam")]
+    pub fn client(&self) -> Option<Client> {
+        self.client.clone()
+    }
+
+    /// Handles Steam's callbacks; call once a frame.
+    pub fn update(&self) {
+        #[cfg(feature = "steam")]
+        if let Some(client) = &self.client {
+            client.run_callbacks();
+        }
+    }
+
+    /// Marks `unlocked` as earned on Steam. Ones that already are stay as
+    /// they were, so this also catches up on any earned before.
+    pub fn unlock(&self, unlocked: &[Achievement]) {
+        #[cfg(feature = "steam")]
+        if let Some(client) = &self.client {
+            let stats = client.user_stats();
+            for achievement in unlocked {
+                if stats.achievement(achievement.id()).set().is_err() {
+                    eprintln!("Steam doesn't know achievement {}", achievement.id());
+                }
+            }
+            let _ = stats.store_stats();
+        }
+        #[cfg(not(feature = "steam"))]
+        let _ = unlocked;
+    }
+
+    /// Brings down saves that are newer on Steam Cloud. Call before
+    /// loading them.
+    pub fn pull_saves(&self) {
+        #[cfg(feature = "steam")]
+        if let Some(client) = &self.client {
+            cloud::pull(client);
+        }
+    }
+
+    /// Sends the saves up to Steam Cloud, after they've been saved.
+    pub fn push_saves(&self) {
+        #[cfg(feature = "steam")]
+        if let Some(client) = &self.client {
+            cloud::push(client);
+        }
+    }
+}
+
+impl Shutdown for Steam {
+    // The saves were just written once more
+    fn shutdown(&mut self) {
+        self.push_saves();
+    }
+}
+
+/// Shows `status` on the player's Steam profile.
+#[cfg(feature = "steam")]
+pub fn set_status(client: &Client, status: &str) {
+    client.friends().set_rich_presence("status", Some(status));
+}
+
+#[cfg(feature = "steam")]
+mod cloud {
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::time::UNIX_EPOCH;
+
+    use steamworks::Client;
+
+    use crate::history::HISTORY_FILE;
+    use crate::platform;
+    use crate::profile::PROFILE_FILE;
+
+    const FILES: [&str; 2] = [PROFILE_FILE, HISTORY_FILE];
+
+    pub fn pull(client: &Client) {
+        let storage = client.remote_storage();
+        for name in FILES {
+            let file = storage.file(name);
+            if !file.exists() {
+                continue;
+            }
+            let path = platform::data_path(name);
+            let local = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs() as i64);
+            if local.is_some_and(|local| local >= file.timestamp()) {
+                continue;
+            }
+            let mut bytes = Vec::new();
+            let result = file
+                .read()
+                .read_to_end(&mut bytes)
+                .and_then(|_| fs::write(&path, bytes));
+            if let Err(err) = result {
+                eprintln!("Could not bring {name} down from Steam Cloud: {err}");
+            }
+        }
+    }
+
+    pub fn push(client: &Client) {
+        let storage = client.remote_storage();
+        for name in FILES {
+            let Ok(bytes) = fs::read(platform::data_path(name)) else {
+                continue;
+            };
+            if let Err(err) = storage.file(name).write().write_all(&bytes) {
+                eprintln!("Could not send {name} to Steam Cloud: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Where saves are kept: files on desktop and Android, browser storage on
+// the web (localStorage for text, IndexedDB for binary files, see
+// `web/storage.js`)
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::io;
+
+use crate::load_error::LoadError;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::platform;
+
+pub trait StorageBackend {
+    /// Reads the text save `name`, e.g. `settings.cfg`.
+    fn load_text(&self, name: &str) -> Result<String, LoadError>;
+    fn save_text(&self, name: &str, text: &str) -> io::Result<()>;
+    /// Stores a binary file such as a screenshot. `name` may contain `/`.
+    fn save_blob(&self, name: &str, bytes: &[u8]) -> io::Result<()>;
+    /// Hands a file made for the player to them, such as a share card:
+    /// saved like `save_blob` on desktop, downloaded on the web.
+    fn export_blob(&self, name: &str, bytes: &[u8]) -> io::Result<()>;
+}
+
+/// Reads the save `name`, or `None` before it has first been written.
+pub fn load_save(name: &str) -> Result<Option<String>, LoadError> {
+    match backend().load_text(name) {
+        Ok(text) => Ok(Some(text)),
+        Err(LoadError::Missing) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The backend for this platform.
+pub fn backend() -> &'static dyn StorageBackend {
+    #[cfg(not(target_arch = "wasm32"))]
+    return &FileStorage;
+    #[cfg(target_arch = "wasm32")]
+    return &BrowserStorage;
+}
+
+// Plain files under `platform::data_path`
+#[cfg(not(target_arch = "wasm32"))]
+struct FileStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StorageBackend for FileStorage {
+    fn load_text(&self, name: &str) -> Result<String, LoadError> {
+        Ok(fs::read_to_string(platform::data_path(name))?)
+    }
+
+    fn save_text(&self, name: &str, text: &str) -> io::Result<()> {
+        self.save_blob(name, text.as_bytes())
+    }
+
+    fn save_blob(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        let path = platform::data_path(name);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, bytes)
+    }
+
+    fn export_blob(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.save_blob(name, bytes)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+struct BrowserStorage;
+
+// Implemented in `web/storage.js`
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    // Byte length of a localStorage entry, or -1 if there is none
+    fn flappy_storage_text_len(name: *const u8, name_len: usize) -> i32;
+    fn flappy_storage_text_read(name: *const u8, name_len: usize, out: *mut u8);
+    // 0 on success, non-zero when the browser refuses (usually quota)
+    fn flappy_storage_text_write(
+        name: *const u8,
+        name_len: usize,
+        text: *const u8,
+        text_len: usize,
+    ) -> i32;
+    fn flappy_storage_blob_write(
+        name: *const u8,
+        name_len: usize,
+        bytes: *const u8,
+        bytes_len: usize,
+    );
+    fn flappy_storage_download(
+        name: *const u8,
+        name_len: usize,
+        bytes: *const u8,
+        bytes_len: usize,
+    );
+}
+
+// Lets the JS plugin check it matches this build
+#[cfg(target_arch = "wasm32")]
+#[unsafe(no_mangle)]
+pub extern "C" fn flappy_storage_crate_version() -> u32 {
+    2
+}
+
+#[cfg(target_arch = "wasm32")]
+impl StorageBackend for BrowserStorage {
+    fn load_text(&self, name: &str) -> Result<String, LoadError> {
+        unsafe {
+            let len = flappy_storage_text_len(name.as_ptr(), name.len());
+            let len = usize::try_from(len).map_err(|_| LoadError::Missing)?;
+            let mut bytes = vec![0; len];
+            flappy_storage_text_read(name.as_ptr(), name.len(), bytes.as_mut_ptr());
+            String::from_utf8(bytes).map_err(|_| LoadError::NotText)
+        }
+    }
+
+    fn save_text(&self, name: &str, text: &str) -> io::Result<()> {
+        let status = unsafe {
+            flappy_storage_text_write(name.as_ptr(), name.len(), text.as_ptr(), text.len())
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::other("browser storage is full or disabled"))
+        }
+    }
+
+    fn save_blob(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        // IndexedDB writes finish in the background; failures are logged by JS
+        unsafe {
+            flappy_storage_blob_write(name.as_ptr(), name.len(), bytes.as_ptr(), bytes.len())
+        };
+        Ok(())
+    }
+
+    fn export_blob(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        // Downloads are named after the last part of the path
+        let file = name.rsplit('/').next().unwrap_or(name);
+        unsafe { flappy_storage_download(file.as_ptr(), file.len(), bytes.as_ptr(), bytes.len()) };
+        Ok(())
+    }
+}
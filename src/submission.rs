@@ -0,0 +1,159 @@
+// Score submissions: a finished run as it would be sent to an online
+// leaderboard, its replay (see `replay.rs`) with a signature added:
+//
+//     sim_version = 2
+//     seed = 918273
+//     flaps = 0 31 55 80
+//     ticks = 412
+//     score = 5
+//     checksum = 9a31f0c2d4e85b17
+//     signature = 5d0e…
+//
+// The signature is the HMAC-SHA256 (see `signing.rs`) of the simulation
+// version, the seed, the score and the checksum, under the key the game
+// was built with (FLAPPY_SCORE_KEY). The board's half is checking that,
+// and flying the flaps again to see they come to the checksum and score
+// claimed; `verify` is the first part of it.
+//
+// Only a run on the standard board (see `profile::Board`) is signed:
+// assisted runs, runs with mutators and everything else with a best of its
+// own are refused, so they can't be taken for plain ones. There's no board
+// in this tree to send submissions to yet, so only the tests make them.
+
+use crate::game::Game;
+use crate::kv::KeyValues;
+use crate::profile::Board;
+use crate::replay::Replay;
+use crate::signing;
+
+/// Why a run isn't signed for the standard board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Refusal {
+    // Assists on, or the fall and flap limits changed
+    Assisted,
+    Mutated,
+    // A level, a challenge or another physics preset
+    OtherBoard,
+    // Its replay wouldn't fly the same, see `Replay::of_run`
+    NotReplayable,
+    // Built without FLAPPY_SCORE_KEY
+    NoKey,
+}
+
+pub struct Submission {
+    replay: Replay,
+    signature: String,
+}
+
+impl Submission {
+    /// The run `game` just finished, signed with the key the game was built
+    /// with.
+    pub fn of_run(game: &Game) -> Result<Submission, Refusal> {
+        let key = option_env!("FLAPPY_SCORE_KEY").ok_or(Refusal::NoKey)?;
+        Submission::signed(game, key.as_bytes())
+    }
+
+    fn signed(game: &Game, key: &[u8]) -> Result<Submission, Refusal> {
+        match Board::of(game) {
+            Board::Standard => {}
+            Board::Assisted => return Err(Refusal::Assisted),
+            Board::Mutated => return Err(Refusal::Mutated),
+            _ => return Err(Refusal::OtherBoard),
+        }
+        let replay = Replay::of_run(game).ok_or(Refusal::NotReplayable)?;
+        let signature = signing::hex(&signing::hmac(key, signed_part(&replay).as_bytes()));
+        Ok(Submission { replay, signature })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut kv = KeyValues::parse(&self.replay.to_text());
+        kv.set("signature", &self.signature);
+        kv.to_string()
+    }
+}
+
+/// Whether `text` is a submission signed under `key`: what the board
+/// checks before flying it again.
+pub fn verify(text: &str, key: &[u8]) -> bool {
+    let Some(signature) = KeyValues::parse(text).get("signature").map(str::to_owned) else {
+        return false;
+    };
+    Replay::parse(text.as_bytes())
+        .is_ok_and(|replay| signing::verify(key, signed_part(&replay).as_bytes(), &signature))
+}
+
+// The flaps aren't in it: they're checked by flying them to the checksum
+fn signed_part(replay: &Replay) -> String {
+    format!(
+        "{} {} {} {:016x}",
+        replay.sim_version(),
+        replay.seed(),
+        replay.score(),
+        replay.checksum()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::Challenge;
+    use crate::game::GameState;
+    use crate::mutators::{Mutator, Mutators};
+    use crate::physics::Limits;
+
+    const KEY: &[u8] = b"test key";
+
+    // A run that flaps once and falls to the ground
+    fn crash(mut game: Game) -> Game {
+        game.flap();
+        while game.state != GameState::GameOver {
+            game.update();
+        }
+        game
+    }
+
+    #[test]
+    fn signs_plain_runs_and_catches_changes() {
+        let text = Submission::signed(&crash(Game::from_seed(3)), KEY)
+            .unwrap()
+            .to_text();
+        assert!(verify(&text, KEY));
+        assert!(!verify(&text, b"another key"));
+
+        let score = KeyValues::parse(&text).get("score").unwrap().to_owned();
+        let raised = text.replace(&format!("score = {score}"), "score = 999");
+        assert!(!verify(&raised, KEY));
+        let unsigned: Vec<&str> = text
+            .lines()
+            .filter(|l| !l.starts_with("signature"))
+            .collect();
+        assert!(!verify(&unsigned.join("\n"), KEY));
+    }
+
+    #[test]
+    fn refuses_runs_off_the_standard_board() {
+        let mut game = Game::from_seed(3);
+        game.set_limits(Limits {
+            flap_cooldown: 0,
+            ..Limits::default()
+        });
+        let refusal = Submission::signed(&crash(game), KEY).err();
+        assert_eq!(refusal, Some(Refusal::Assisted));
+
+        let mut game = Game::from_seed(3);
+        game.set_mutators(Mutators::of(&[Mutator::TinyBird]));
+        let refusal = Submission::signed(&crash(game), KEY).err();
+        assert_eq!(refusal, Some(Refusal::Mutated));
+
+        let mut game = Game::from_seed(3);
+        game.set_challenge(Some(Challenge::Weekly));
+        let refusal = Submission::signed(&crash(game), KEY).err();
+        assert_eq!(refusal, Some(Refusal::OtherBoard));
+    }
+
+    #[test]
+    fn only_builds_with_a_key_sign() {
+        let signed = Submission::of_run(&crash(Game::from_seed(3)));
+        assert_eq!(signed.is_ok(), option_env!("FLAPPY_SCORE_KEY").is_some());
+    }
+}
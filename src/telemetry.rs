@@ -0,0 +1,243 @@
+// Anonymous gameplay stats for balancing, sent only when the player turns
+// "Share anonymous stats" on in the settings. Runs are added up into a
+// batch of counts: where scores land, what ended runs, how often each
+// assist was on. There's no id, seed, date or name in it, so a batch can't
+// be told apart from anyone else's. Enter on the setting shows the batch
+// exactly as it would be sent.
+//
+// A batch is kept as `telemetry.cfg` until it covers BATCH_RUNS runs, then
+// posted as plain `key = value` text to the endpoint, baked in with
+// FLAPPY_TELEMETRY_URL or set in `settings.cfg` as `telemetry_url`. Sending
+// needs the `telemetry` cargo feature and isn't in web builds; without it
+// batches only ever go as far as the preview. Turning the setting off
+// throws the batch away.
+
+use crate::assists::Assists;
+use crate::history::{Cause, Run};
+use crate::kv::KeyValues;
+use crate::shutdown::Shutdown;
+use crate::storage;
+
+const TELEMETRY_FILE: &str = "telemetry.cfg";
+pub const BATCH_RUNS: u32 = 20;
+// Lowest score in each bucket of the score counts
+const SCORE_BUCKETS: [i64; 6] = [0, 5, 10, 20, 50, 100];
+const ASSISTS: [&str; 5] = [
+    "slow_speed",
+    "wide_gaps",
+    "start_shield",
+    "one_switch",
+    "adaptive",
+];
+
+#[derive(Clone, Default, PartialEq, Debug)]
+struct Batch {
+    runs: u32,
+    scores: [u32; SCORE_BUCKETS.len()],
+    causes: [u32; Cause::ALL.len()],
+    assists: [u32; ASSISTS.len()],
+}
+
+impl Batch {
+    fn parse(text: &str) -> Batch {
+        let kv = KeyValues::parse(text);
+        let count = |key: String| kv.get_parsed(&key).unwrap_or(0);
+        Batch {
+            runs: count("runs".to_owned()),
+            scores: std::array::from_fn(|i| count(score_key(i))),
+            causes: Cause::ALL.map(|cause| count(format!("ended_by_{}", cause.name()))),
+            assists: ASSISTS.map(|assist| count(format!("assist_{assist}"))),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut kv = KeyValues::default();
+        kv.set("version", env!("CARGO_PKG_VERSION"));
+        kv.set("runs", self.runs);
+        for (i, count) in self.scores.iter().enumerate() {
+            kv.set(&score_key(i), count);
+        }
+        for (cause, count) in Cause::ALL.iter().zip(self.causes) {
+            kv.set(&format!("ended_by_{}", cause.name()), count);
+        }
+        for (assist, count) in ASSISTS.iter().zip(self.assists) {
+            kv.set(&format!("assist_{assist}"), count);
+        }
+        kv.to_string()
+    }
+
+    fn add(&mut self, run: &Run, assists: [bool; ASSISTS.len()]) {
+        self.runs += 1;
+        let bucket = SCORE_BUCKETS
+            .iter()
+            .rposition(|&lowest| run.score >= lowest)
+            .unwrap_or(0);
+        self.scores[bucket] += 1;
+        if let Some(cause) = Cause::ALL.iter().position(|&cause| cause == run.cause) {
+            self.causes[cause] += 1;
+        }
+        for (count, on) in self.assists.iter_mut().zip(assists) {
+            *count += u32::from(on);
+        }
+    }
+
+    // Puts a batch that couldn't be sent back with the one since
+    #[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+    fn merge(&mut self, other: &Batch) {
+        self.runs += other.runs;
+        let pairs = [
+            (&mut self.scores[..], &other.scores[..]),
+            (&mut self.causes[..], &other.causes[..]),
+            (&mut self.assists[..], &other.assists[..]),
+        ];
+        for (counts, more) in pairs {
+            for (count, more) in counts.iter_mut().zip(more) {
+                *count += more;
+            }
+        }
+    }
+}
+
+// `scores_0-4`, ..., `scores_100+`
+fn score_key(bucket: usize) -> String {
+    let lowest = SCORE_BUCKETS[bucket];
+    match SCORE_BUCKETS.get(bucket + 1) {
+        Some(next) => format!("scores_{lowest}-{}", next - 1),
+        None => format!("scores_{lowest}+"),
+    }
+}
+
+pub struct Telemetry {
+    batch: Batch,
+    #[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+    url: Option<String>,
+    // The batch being sent, back again if it didn't make it
+    #[cfg(feature = "telemetry")]
+    sending: Option<std::sync::mpsc::Receiver<Result<(), Batch>>>,
+}
+
+impl Telemetry {
+    /// Picks up the batch so far. `url` is the endpoint from the settings,
+    /// if it overrides the one baked in.
+    pub fn load(url: Option<&str>) -> Telemetry {
+        let text = storage::load_save(TELEMETRY_FILE)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let baked = option_env!("FLAPPY_TELEMETRY_URL");
+        Telemetry {
+            batch: Batch::parse(&text),
+            url: url.or(baked).map(str::to_owned),
+            #[cfg(feature = "telemetry")]
+            sending: None,
+        }
+    }
+
+    /// Adds `run`, if the player has opted in, and sends the batch once
+    /// it's big enough.
+    pub fn record(&mut self, run: &Run, assists: Assists, enabled: bool) {
+        if !enabled {
+            return;
+        }
+        let on = [
+            assists.slow_speed,
+            assists.wide_gaps,
+            assists.start_shield,
+            assists.one_switch,
+            assists.adaptive.is_some(),
+        ];
+        self.batch.add(run, on);
+        if self.batch.runs >= BATCH_RUNS {
+            self.send();
+        }
+        self.save();
+    }
+
+    /// Throws away everything not yet sent, as when the setting is turned
+    /// off.
+    pub fn discard(&mut self) {
+        if self.batch != Batch::default() {
+            self.batch = Batch::default();
+            self.save();
+        }
+    }
+
+    /// Exactly what the next batch would send, so far.
+    pub fn preview(&self) -> String {
+        self.batch.to_text()
+    }
+
+    /// Catches up with a batch being sent; call once a frame.
+    pub fn poll(&mut self) {
+        #[cfg(feature = "telemetry")]
+        if let Some(sending) = &self.sending
+            && let Ok(result) = sending.try_recv()
+        {
+            self.sending = None;
+            if let Err(unsent) = result {
+                self.batch.merge(&unsent);
+                self.save();
+            }
+        }
+    }
+
+    fn send(&mut self) {
+        #[cfg(feature = "telemetry")]
+        if self.sending.is_none()
+            && let Some(url) = self.url.clone()
+        {
+            let batch = std::mem::take(&mut self.batch);
+            self.sending = Some(post::spawn(url, batch));
+        }
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::backend().save_text(TELEMETRY_FILE, &self.batch.to_text()) {
+            eprintln!("Could not save telemetry batch: {err}");
+        }
+    }
+}
+
+impl Shutdown for Telemetry {
+    // Gives a batch being sent a moment to make it, keeping it for next
+    // time if it doesn't
+    fn shutdown(&mut self) {
+        #[cfg(feature = "telemetry")]
+        if let Some(sending) = self.sending.take()
+            && let Ok(Err(unsent)) = sending.recv_timeout(crate::shutdown::NETWORK_WAIT)
+        {
+            self.batch.merge(&unsent);
+            self.save();
+        }
+    }
+}
+
+#[cfg(feature = "telemetry")]
+mod post {
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::Batch;
+
+    const TIMEOUT: Duration = Duration::from_secs(20);
+
+    pub fn spawn(url: String, batch: Batch) -> Receiver<Result<(), Batch>> {
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            let sent = agent
+                .post(&url)
+                .set("Content-Type", "text/plain; charset=utf-8")
+                .send_string(&batch.to_text());
+            let _ = sender.send(match sent {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    eprintln!("Could not send telemetry: {err}");
+                    Err(batch)
+                }
+            });
+        });
+        result
+    }
+}
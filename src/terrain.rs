@@ -0,0 +1,133 @@
+// Cave rock: an undulating ceiling and floor the bird has to keep clear of
+// as well as the pipes, laid as the journey goes through the cave (see
+// `biomes.rs`). Each is a line through points a segment apart, scrolling
+// with the world like the pipes, and wandering up and down a little at a
+// time. Rock never reaches within CLEARANCE of a gap while the bird can be
+// flying to or from it, so any course the pipes leave flyable still is;
+// and away from the pipes (while a boss is out, say) the middle of the
+// screen stays open.
+
+use crate::sim_rng::SimRng;
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::collision;
+use crate::pipes::{PIPE_SPACING, PIPE_WIDTH, PairOfPipes, SCROLL_SPEED};
+use crate::viewport::{FIELD_LEFT, FIELD_RIGHT};
+
+// Mixed into the course seed, so the rock has rolls of its own
+const TERRAIN_SEED_SALT: u64 = 0x524f_434b;
+const SEGMENT: f32 = 20.0;
+// Furthest the rock moves up or down from one point to the next
+const MAX_STEP: f32 = 9.0;
+// How far each reaches in from its edge of the screen
+const MIN_DEPTH: f32 = 16.0;
+const MAX_DEPTH: f32 = 120.0;
+// Room kept between the rock and the gaps
+const CLEARANCE: f32 = 36.0;
+// How far either side of a pair its gap is kept clear: to the next pair's
+// near edge, however wide the pairs. Pairs are spawned further out than
+// this past the rock, so the rock never goes down before its pair
+const GAP_REACH: f32 = PIPE_SPACING - PIPE_WIDTH;
+const ROCK_COLOR: Color = Color::new(0.22, 0.18, 0.2, 1.0);
+const EDGE_COLOR: Color = Color::new(0.4, 0.34, 0.33, 1.0);
+
+#[derive(Clone)]
+pub struct Terrain {
+    // Points left to right, at the same xs; the rock is above the ceiling
+    // and below the floor
+    pub ceiling: Vec<Vec2>,
+    pub floor: Vec<Vec2>,
+    rng: SimRng,
+    // Where the walk is, as depths from the top and bottom, before the
+    // cave blends it in
+    depths: (f32, f32),
+    // Where the next point goes (scrolls with the world)
+    next_x: f32,
+}
+
+impl Terrain {
+    pub fn new(seed: u64) -> Terrain {
+        let rng = SimRng::new(seed ^ TERRAIN_SEED_SALT);
+        Terrain {
+            ceiling: Vec::new(),
+            floor: Vec::new(),
+            rng,
+            depths: (MIN_DEPTH, MIN_DEPTH),
+            next_x: FIELD_LEFT - SEGMENT,
+        }
+    }
+
+    /// Moves the rock along with the world for one update, dropping what's
+    /// gone off the left.
+    pub fn scroll(&mut self) {
+        for point in self.ceiling.iter_mut().chain(&mut self.floor) {
+            point.x -= SCROLL_SPEED;
+        }
+        self.next_x -= SCROLL_SPEED;
+        // One point off screen keeps the first segment whole
+        let gone = self
+            .ceiling
+            .iter()
+            .take_while(|point| point.x < FIELD_LEFT - SEGMENT)
+            .count();
+        self.ceiling.drain(..gone);
+        self.floor.drain(..gone);
+    }
+
+    /// Lays rock up to the right edge of the widest layout, clear of
+    /// `pipes`' gaps. `cave` says how much of it there is at an x on
+    /// screen.
+    pub fn extend(&mut self, pipes: &[PairOfPipes], cave: impl Fn(f32) -> f32) {
+        while self.next_x <= FIELD_RIGHT + SEGMENT {
+            let x = self.next_x;
+            let (ceiling, floor) = self.depths;
+            let step = |depth: f32| {
+                let depth = depth + self.rng.gen_range(-MAX_STEP, MAX_STEP);
+                depth.clamp(MIN_DEPTH, MAX_DEPTH)
+            };
+            let (mut ceiling, mut floor) = (step(ceiling), step(floor));
+
+            // Gaps the bird can be on its way to or from here
+            for pipe in pipes {
+                if pipe.x - GAP_REACH < x && x < pipe.right() + GAP_REACH {
+                    ceiling = ceiling.min(pipe.gap_top() - CLEARANCE);
+                    floor = floor.min(HEIGHT - pipe.gap_bottom() - CLEARANCE);
+                }
+            }
+            let (ceiling, floor) = (ceiling.max(0.0), floor.max(0.0));
+            // The walk carries on from where the gaps pushed it, so the
+            // rock comes back a step at a time
+            self.depths = (ceiling.max(MIN_DEPTH), floor.max(MIN_DEPTH));
+
+            let amount = cave(x);
+            self.ceiling.push(vec2(x, ceiling * amount));
+            self.floor.push(vec2(x, HEIGHT - floor * amount));
+            self.next_x += SEGMENT;
+        }
+    }
+
+    /// The lowest the ceiling and highest the floor come between `left`
+    /// and `right` on screen.
+    pub fn opening(&self, left: f32, right: f32) -> (f32, f32) {
+        let ceiling = collision::line_span(&self.ceiling, left, right).map_or(0.0, |(_, y)| y);
+        let floor = collision::line_span(&self.floor, left, right).map_or(HEIGHT, |(y, _)| y);
+        (ceiling, floor)
+    }
+
+    pub fn draw(&self) {
+        let lines = [(&self.ceiling, 0.0), (&self.floor, HEIGHT)];
+        for (line, edge) in lines {
+            for pair in line.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                if a.y == edge && b.y == edge {
+                    continue;
+                }
+                let (a_edge, b_edge) = (vec2(a.x, edge), vec2(b.x, edge));
+                draw_triangle(a, b, b_edge, ROCK_COLOR);
+                draw_triangle(a, b_edge, a_edge, ROCK_COLOR);
+                draw_line(a.x, a.y, b.x, b.y, 2.0, EDGE_COLOR);
+            }
+        }
+    }
+}
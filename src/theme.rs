@@ -0,0 +1,386 @@
+// Theme packs: drop-in folders under `themes/` that reskin the game
+//
+// A theme folder may contain any of `bird.png`, `pipe.png` (and `stone.png`
+// and `tree.png` for the other pipe styles, see `pipes.rs`), `background.png`,
+// `cursor.png`, `cursor_pressed.png`, `panel.png` (art for the side panels
+// when the playfield is letterboxed), the `coin.png` and `medal.png` sprite
+// sheets with an `animations.cfg` to time them (see `animation.rs`), the
+// `hats.png` sheet with an `anchors.cfg` to fit it on the bird (see
+// `cosmetics.rs`), `checkpoint.png` and `finish.png` for levels (see
+// `flags.rs`), the sound cues (`flap.wav`, `point.wav`, `hit.wav`, `milestone.wav`), an
+// `ambience.wav` loop and a `theme.cfg` manifest:
+//
+//     name = Night
+//     text_color = #f0f0ff
+//     sky_color = #10102a
+//     background = skyline.png
+//     tint = #ffe0c0
+//
+// plus the `grade_` keys for its color grade (see `grading.rs`).
+//
+// Anything the theme leaves out falls back to the default resources.
+//
+// Only the theme in use is loaded, and switching lets the last one go, so
+// installing more themes costs nothing until one is picked. A theme whose
+// textures come to more than TEXTURE_BUDGET_BYTES still loads, with a
+// warning; F3 shows how much the one in use holds.
+//
+// The bird is stretched to its hitbox (BIRD_WIDTH by BIRD_HEIGHT) and the
+// classic pipe is scaled to PIPE_WIDTH, with its body repeated down to
+// PIPE_HEIGHT below the cap (see `pipes.rs`), so art of any size plays the
+// same; pixel-perfect collision only uses sprites that are exactly that
+// size. Stones and trees are as big as their sprites, within the limits in
+// `pipes.rs`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use crate::animation::{ANIMATIONS_FILE, Animations};
+use crate::assets;
+use crate::backdrop::Backdrop;
+use crate::bird::{BIRD_HEIGHT, BIRD_WIDTH};
+use crate::cosmetics::{ANCHORS_FILE, Anchors};
+use crate::grading::Grade;
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::mask::{Mask, SpriteMasks};
+use crate::mod_manager::{ModKind, ModList, mod_id};
+use crate::perf::megabytes;
+use crate::pipes::{PipeSizes, PipeStyle};
+
+pub const DEFAULT_THEME: &str = "default";
+
+pub const THEMES_DIR: &str = "./themes";
+#[cfg(not(target_os = "android"))]
+const DEFAULT_SPRITES_DIR: &str = "./resources/sprites";
+#[cfg(not(target_os = "android"))]
+const DEFAULT_SOUNDS_DIR: &str = "./resources/sounds";
+// The APK packs `resources/` as its asset root
+#[cfg(target_os = "android")]
+const DEFAULT_SPRITES_DIR: &str = "sprites";
+#[cfg(target_os = "android")]
+const DEFAULT_SOUNDS_DIR: &str = "sounds";
+pub const MANIFEST_FILE: &str = "theme.cfg";
+// Plenty for sprites this size, even at several times the resolution
+pub const TEXTURE_BUDGET_BYTES: usize = 32 * 1024 * 1024;
+// What a theme folder may have, for checking mod packages (see
+// `mod_pack.rs`); the background can also be any file the manifest names
+pub const MANIFEST_KEYS: [&str; 9] = [
+    "name",
+    "text_color",
+    "sky_color",
+    "background",
+    "tint",
+    "grade_tint",
+    "grade_saturation",
+    "grade_contrast",
+    "grade_brightness",
+];
+pub const COLOR_KEYS: [&str; 4] = ["text_color", "sky_color", "tint", "grade_tint"];
+pub const NUMBER_KEYS: [&str; 3] = ["grade_saturation", "grade_contrast", "grade_brightness"];
+pub const SPRITE_FILES: [&str; 13] = [
+    "bird.png",
+    "pipe.png",
+    "stone.png",
+    "tree.png",
+    "background.png",
+    "cursor.png",
+    "cursor_pressed.png",
+    "panel.png",
+    "coin.png",
+    "medal.png",
+    "hats.png",
+    "checkpoint.png",
+    "finish.png",
+];
+
+// A theme listed in the settings menu
+pub struct ThemeInfo {
+    pub id: String,
+    pub name: String,
+    // Why its manifest couldn't be read, for the mod manager
+    pub error: Option<String>,
+}
+
+pub struct Theme {
+    pub bird: Texture2D,
+    // One for each `PipeStyle`
+    pipes: [Texture2D; PipeStyle::ALL.len()],
+    pub background: Texture2D,
+    // The background made to loop, drawn behind the course
+    pub backdrop: Backdrop,
+    // Menu cursor, normal and while the mouse button is held
+    pub cursor: Texture2D,
+    pub cursor_pressed: Texture2D,
+    // Side panel art, drawn mirrored on the right; the default theme has none
+    pub panel: Option<Texture2D>,
+    // Sprite sheets, played as `animations` says
+    pub coin: Texture2D,
+    pub medal: Texture2D,
+    pub checkpoint: Texture2D,
+    pub finish: Texture2D,
+    pub animations: Animations,
+    // Hats for the bird, fitted on where `anchors` says
+    pub hats: Texture2D,
+    pub anchors: Anchors,
+    pub text_color: Color,
+    pub sky_color: Color,
+    // Multiplied into every world sprite
+    pub tint: Color,
+    // Over the whole screen, see `grading.rs`
+    pub grade: Grade,
+    // For the pixel-perfect collision option
+    pub masks: Rc<SpriteMasks>,
+    dir: Option<PathBuf>,
+}
+
+impl Theme {
+    /// Lists the default theme followed by the folders in `themes/` that
+    /// `mods` has on, in its load order.
+    pub fn available(mods: &ModList) -> Vec<ThemeInfo> {
+        let mut installed = Theme::installed();
+        installed.retain(|theme| mods.is_enabled(&mod_id(ModKind::Theme, &theme.id)));
+        mods.arrange(&mut installed, |theme| mod_id(ModKind::Theme, &theme.id));
+        let default = ThemeInfo {
+            id: DEFAULT_THEME.to_owned(),
+            name: "Default".to_owned(),
+            error: None,
+        };
+        std::iter::once(default).chain(installed).collect()
+    }
+
+    /// Every folder in `themes/`, sorted by folder name. Turned off ones
+    /// included.
+    pub fn installed() -> Vec<ThemeInfo> {
+        let mut themes = Vec::new();
+        let Ok(entries) = fs::read_dir(THEMES_DIR) else {
+            return themes;
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            let id = dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            let (manifest, error) = match KeyValues::load(dir.join(MANIFEST_FILE)) {
+                Ok(manifest) => (manifest, None),
+                Err(LoadError::Missing) => (KeyValues::default(), None),
+                Err(err) => (KeyValues::default(), Some(format!("{MANIFEST_FILE} {err}"))),
+            };
+            let name = manifest.get("name").unwrap_or(&id).to_owned();
+            themes.push(ThemeInfo { id, name, error });
+        }
+        themes
+    }
+
+    /// Loads the theme with folder name `id`. Unknown ids and missing files
+    /// fall back to the default assets.
+    pub async fn load(id: &str) -> Theme {
+        let dir = Path::new(THEMES_DIR).join(id);
+        let dir = (id != DEFAULT_THEME && dir.is_dir()).then_some(dir);
+        let manifest = dir
+            .as_ref()
+            .and_then(|dir| KeyValues::load(dir.join(MANIFEST_FILE)).ok())
+            .unwrap_or_default();
+
+        let background_file = manifest.get("background").unwrap_or("background.png");
+        let bird = load_sprite_image(dir.as_deref(), "bird.png", "bird.png").await;
+        let mut pipes = Vec::new();
+        for style in PipeStyle::ALL {
+            let file = style.sprite_file();
+            pipes.push(load_sprite_image(dir.as_deref(), file, file).await);
+        }
+        let pipes: [Image; PipeStyle::ALL.len()] = pipes.try_into().unwrap();
+        let background = load_sprite_image(dir.as_deref(), background_file, "background.png").await;
+        let theme = Theme {
+            bird: Texture2D::from_image(&bird),
+            pipes: pipes.each_ref().map(Texture2D::from_image),
+            background: Texture2D::from_image(&background),
+            backdrop: Backdrop::new(&background),
+            cursor: load_sprite(dir.as_deref(), "cursor.png").await,
+            cursor_pressed: load_sprite(dir.as_deref(), "cursor_pressed.png").await,
+            panel: load_theme_sprite(dir.as_deref(), "panel.png").await,
+            coin: load_sprite(dir.as_deref(), "coin.png").await,
+            medal: load_sprite(dir.as_deref(), "medal.png").await,
+            checkpoint: load_sprite(dir.as_deref(), "checkpoint.png").await,
+            finish: load_sprite(dir.as_deref(), "finish.png").await,
+            animations: load_animations(dir.as_deref()).await,
+            hats: load_sprite(dir.as_deref(), "hats.png").await,
+            anchors: load_anchors(
+                dir.as_deref(),
+                vec2(bird.width() as f32, bird.height() as f32),
+            )
+            .await,
+            text_color: manifest.get_color("text_color").unwrap_or(WHITE),
+            sky_color: manifest.get_color("sky_color").unwrap_or(BLACK),
+            tint: manifest.get_color("tint").unwrap_or(WHITE),
+            grade: Grade::from_manifest(&manifest),
+            masks: Rc::new(SpriteMasks {
+                bird: Mask::from_image(&bird),
+                pipes: pipes.each_ref().map(Mask::from_image),
+            }),
+            dir,
+        };
+        let bytes = theme.texture_bytes();
+        if bytes > TEXTURE_BUDGET_BYTES {
+            eprintln!(
+                "Theme {id} has {} of textures, over the {} budget",
+                megabytes(bytes),
+                megabytes(TEXTURE_BUDGET_BYTES)
+            );
+        }
+        theme
+    }
+
+    /// About how much texture memory the theme holds, at four bytes a
+    /// pixel.
+    pub fn texture_bytes(&self) -> usize {
+        let sheets = [
+            &self.bird,
+            &self.background,
+            self.backdrop.texture(),
+            &self.cursor,
+            &self.cursor_pressed,
+            &self.coin,
+            &self.medal,
+            &self.checkpoint,
+            &self.finish,
+            &self.hats,
+        ];
+        sheets
+            .into_iter()
+            .chain(&self.pipes)
+            .chain(&self.panel)
+            .map(|texture| texture.width() as usize * texture.height() as usize * 4)
+            .sum()
+    }
+
+    pub fn pipe(&self, style: PipeStyle) -> &Texture2D {
+        &self.pipes[style.index()]
+    }
+
+    /// How big each style's pipes are, going by the sprites.
+    pub fn pipe_sizes(&self) -> PipeSizes {
+        PipeSizes::from_sprites(self.pipes.each_ref().map(Texture2D::size))
+    }
+
+    /// Draws the background across the widest layout as it is `scroll`
+    /// pixels along the course (see `backdrop.rs`).
+    pub fn draw_background(&self, scroll: f32) {
+        self.backdrop.draw(scroll);
+    }
+
+    /// Where the theme keeps announcer line `line`, if it has one. There are
+    /// no default voice lines.
+    pub fn voice_path(&self, line: &str) -> Option<PathBuf> {
+        let path = self.dir.as_ref()?.join("voice").join(format!("{line}.wav"));
+        path.is_file().then_some(path)
+    }
+
+    /// Finds the sound file for `cue`, preferring the theme's own copy.
+    pub fn sound_path(&self, cue: &str) -> Option<PathBuf> {
+        let file = format!("{cue}.wav");
+        self.dir
+            .iter()
+            .map(|dir| dir.join(&file))
+            .find(|path| path.is_file())
+            .or_else(|| {
+                let path = Path::new(DEFAULT_SOUNDS_DIR).join(&file);
+                // APK assets aren't on the file system, so they can't be checked
+                (cfg!(target_os = "android") || path.is_file()).then_some(path)
+            })
+    }
+}
+
+async fn load_sprite(dir: Option<&Path>, file: &str) -> Texture2D {
+    load_sprite_as(dir, file, file).await
+}
+
+// Loads `file` from the theme folder, or `fallback` from the default sprites
+async fn load_sprite_as(dir: Option<&Path>, file: &str, fallback: &str) -> Texture2D {
+    Texture2D::from_image(&load_sprite_image(dir, file, fallback).await)
+}
+
+// Loads `file` from the theme folder, for sprites with no default
+async fn load_theme_sprite(dir: Option<&Path>, file: &str) -> Option<Texture2D> {
+    let path = dir?.join(file);
+    if !path.is_file() {
+        return None;
+    }
+    load_texture(&path.to_string_lossy())
+        .await
+        .inspect_err(|err| eprintln!("Could not load {}: {err}", path.display()))
+        .ok()
+}
+
+// The default timing, with whatever the theme's `animations.cfg` changes
+async fn load_animations(dir: Option<&Path>) -> Animations {
+    let default = load_string(&format!("{DEFAULT_SPRITES_DIR}/{ANIMATIONS_FILE}"))
+        .await
+        .inspect_err(|err| eprintln!("Could not load {ANIMATIONS_FILE}: {err}"))
+        .map(|text| Animations::from_values(&KeyValues::parse(&text), &Animations::default()))
+        .unwrap_or_default();
+    let Some(path) = dir.map(|dir| dir.join(ANIMATIONS_FILE)) else {
+        return default;
+    };
+    match KeyValues::load(&path) {
+        Ok(kv) => Animations::from_values(&kv, &default),
+        Err(LoadError::Missing) => default,
+        Err(err) => {
+            eprintln!("Could not load {}: {err}", path.display());
+            default
+        }
+    }
+}
+
+// The default bird's anchors, or the theme's for its own `bird_size` bird
+async fn load_anchors(dir: Option<&Path>, bird_size: Vec2) -> Anchors {
+    let default = load_string(&format!("{DEFAULT_SPRITES_DIR}/{ANCHORS_FILE}"))
+        .await
+        .inspect_err(|err| eprintln!("Could not load {ANCHORS_FILE}: {err}"))
+        .map(|text| {
+            let kv = KeyValues::parse(&text);
+            Anchors::from_values(&kv, vec2(BIRD_WIDTH, BIRD_HEIGHT), &Anchors::default())
+        })
+        .unwrap_or_default();
+    let Some(path) = dir.map(|dir| dir.join(ANCHORS_FILE)) else {
+        return default;
+    };
+    match KeyValues::load(&path) {
+        Ok(kv) => Anchors::from_values(&kv, bird_size, &default),
+        Err(LoadError::Missing) => default,
+        Err(err) => {
+            eprintln!("Could not load {}: {err}", path.display());
+            default
+        }
+    }
+}
+
+// The pixels behind `load_sprite_as`, for sprites that also need a mask
+async fn load_sprite_image(dir: Option<&Path>, file: &str, fallback: &str) -> Image {
+    if let Some(dir) = dir {
+        let path = dir.join(file);
+        if path.is_file() {
+            match load_image(&path.to_string_lossy()).await {
+                Ok(image) => return image,
+                Err(err) => eprintln!("Could not load {}: {err}", path.display()),
+            }
+        }
+    }
+    let path = format!("{DEFAULT_SPRITES_DIR}/{fallback}");
+    match load_image(&path).await {
+        Ok(image) => image,
+        Err(err) => {
+            eprintln!("Could not load {path}: {err}, using the built-in copy");
+            assets::sprite(fallback).unwrap_or_else(|| panic!("{fallback} isn't built in"))
+        }
+    }
+}
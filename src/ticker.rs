@@ -0,0 +1,112 @@
+// A ticker along the bottom of the title screen: lifetime stats from the
+// profile, like "You have flapped 12,304 times", one at a time, each fading
+// in, staying up a few seconds and fading out for the next. The lines are
+// made up fresh as they're drawn, so a run just finished shows up in them.
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::game::draw_centered_text;
+use crate::profile::Profile;
+use crate::tween::slide_in_out;
+
+const SHOW_SECONDS: f32 = 5.0;
+const FADE_SECONDS: f32 = 0.5;
+
+#[derive(Default)]
+pub struct Ticker {
+    // Which line is up, counting on past the last, and for how long
+    shown: usize,
+    age: f32,
+}
+
+impl Ticker {
+    pub fn update(&mut self, dt: f32) {
+        self.age += dt;
+        if self.age >= SHOW_SECONDS {
+            self.age -= SHOW_SECONDS;
+            self.shown += 1;
+        }
+    }
+
+    pub fn draw(&self, profile: &Profile, color: Color) {
+        let lines = lines(profile);
+        if lines.is_empty() {
+            return;
+        }
+        let line = &lines[self.shown % lines.len()];
+        let color = Color {
+            a: color.a * slide_in_out(self.age, SHOW_SECONDS, FADE_SECONDS),
+            ..color
+        };
+        draw_centered_text(line, HEIGHT - 22.0, 14.0, color);
+    }
+}
+
+// Whichever stats there's anything to say about yet
+fn lines(profile: &Profile) -> Vec<String> {
+    let mut lines = Vec::new();
+    if profile.flaps > 0 {
+        lines.push(format!("You have flapped {} times", grouped(profile.flaps)));
+    }
+    if profile.runs > 0 {
+        lines.push(format!("{} runs flown", grouped(profile.runs.into())));
+    }
+    if profile.pipes_passed > 0 {
+        let pipes = grouped(profile.pipes_passed.into());
+        lines.push(format!("{pipes} pipes passed in all"));
+    }
+    if let Some(death) = most_common_death(profile) {
+        lines.push(format!("Most common death: {death}"));
+    }
+    let streak = profile.best_streak();
+    if streak > 1 {
+        lines.push(format!("Longest streak: {streak} days"));
+    }
+    let seconds: f32 = profile.history.iter().map(|run| run.seconds).sum();
+    let minutes = (seconds / 60.0) as u64;
+    if minutes > 0 {
+        let time = match minutes {
+            0..60 => format!("{minutes}m"),
+            _ => format!("{}h {}m", minutes / 60, minutes % 60),
+        };
+        lines.push(format!("{time} in the air"));
+    }
+    lines
+}
+
+// Going by where the bird was, as the stats screen's crash map does
+fn most_common_death(profile: &Profile) -> Option<&'static str> {
+    let (mut top, mut bottom, mut ground) = (0, 0, 0);
+    for death in &profile.deaths {
+        if death.ground {
+            ground += 1;
+        } else if death.offset < 0 {
+            top += 1;
+        } else {
+            bottom += 1;
+        }
+    }
+    [
+        (top, "the top pipe"),
+        (bottom, "the bottom pipe"),
+        (ground, "the ground"),
+    ]
+    .into_iter()
+    .filter(|&(count, _)| count > 0)
+    .max_by_key(|&(count, _)| count)
+    .map(|(_, death)| death)
+}
+
+// 12304 as 12,304
+fn grouped(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
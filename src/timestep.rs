@@ -0,0 +1,77 @@
+// Fixed-timestep clock: the simulation always advances in 1/60 s ticks no
+// matter the frame rate, and time can be slowed down for effects or for
+// the whole game, or sped up for testing. Every speed only changes how
+// many ticks a frame runs, never what a tick does, so runs play the same
+
+pub const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+// Longest frame we try to catch up on, so a hitch doesn't fast-forward the game
+const MAX_FRAME_SECONDS: f32 = 0.25;
+pub const MIN_TEST_SPEED: f32 = 0.25;
+pub const MAX_TEST_SPEED: f32 = 8.0;
+
+pub struct Timestep {
+    accumulator: f32,
+    slow_scale: f32,
+    slow_remaining: f32,
+    // Game speed under the effects, see `set_speed`
+    speed: f32,
+    // On top of everything, see `set_test_speed`
+    test_speed: f32,
+}
+
+impl Timestep {
+    pub fn new() -> Self {
+        Timestep {
+            accumulator: 0.0,
+            slow_scale: 1.0,
+            slow_remaining: 0.0,
+            speed: 1.0,
+            test_speed: 1.0,
+        }
+    }
+
+    /// Runs the game at `scale` speed for `seconds` of real time.
+    pub fn slow_motion(&mut self, seconds: f32, scale: f32) {
+        self.slow_remaining = seconds;
+        self.slow_scale = scale;
+    }
+
+    /// Runs the game at `speed` until it's changed again, as for the slow
+    /// speed assist.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Runs the simulation `speed` times as fast, within MIN_TEST_SPEED and
+    /// MAX_TEST_SPEED, for soak-testing.
+    pub fn set_test_speed(&mut self, speed: f32) {
+        self.test_speed = speed.clamp(MIN_TEST_SPEED, MAX_TEST_SPEED);
+    }
+
+    pub fn test_speed(&self) -> f32 {
+        self.test_speed
+    }
+
+    /// How far the time `ticks` left over is towards the next tick, from 0
+    /// to 1, for drawing between ticks (see `Game::blend`).
+    pub fn blend(&self) -> f32 {
+        (self.accumulator / TICK_SECONDS).min(1.0)
+    }
+
+    /// Returns how many ticks to simulate for a frame that took `frame_seconds`.
+    pub fn ticks(&mut self, frame_seconds: f32) -> u32 {
+        let frame_seconds = frame_seconds.min(MAX_FRAME_SECONDS);
+        let scale = if self.slow_remaining > 0.0 {
+            self.slow_remaining -= frame_seconds;
+            self.slow_scale
+        } else {
+            1.0
+        };
+
+        self.accumulator += frame_seconds * scale * self.speed * self.test_speed;
+        let ticks = (self.accumulator / TICK_SECONDS) as u32;
+        self.accumulator -= ticks as f32 * TICK_SECONDS;
+        ticks
+    }
+}
@@ -0,0 +1,78 @@
+// Toast notifications: short messages that slide in at the top right
+//
+// Up to three toasts are shown at once; the rest wait their turn. Toasts have
+// their own update/draw pass so they keep running whatever the game is doing.
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::WIDTH;
+use crate::tween::slide_in_out;
+
+const MAX_VISIBLE: usize = 3;
+const SHOW_SECONDS: f32 = 3.0;
+const SLIDE_SECONDS: f32 = 0.25;
+const TOAST_WIDTH: f32 = 160.0;
+const TOAST_HEIGHT: f32 = 24.0;
+const MARGIN: f32 = 6.0;
+const FONT_SIZE: f32 = 16.0;
+
+struct Toast {
+    text: String,
+    color: Color,
+    age: f32,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    visible: Vec<Toast>,
+    waiting: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>, color: Color) {
+        self.waiting.push_back(Toast {
+            text: text.into(),
+            color,
+            age: 0.0,
+        });
+    }
+
+    /// Toasts showing or waiting.
+    pub fn count(&self) -> usize {
+        self.visible.len() + self.waiting.len()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for toast in &mut self.visible {
+            toast.age += dt;
+        }
+        self.visible.retain(|toast| toast.age < SHOW_SECONDS);
+        while self.visible.len() < MAX_VISIBLE {
+            let Some(toast) = self.waiting.pop_front() else {
+                break;
+            };
+            self.visible.push(toast);
+        }
+    }
+
+    pub fn draw(&self) {
+        for (i, toast) in self.visible.iter().enumerate() {
+            // 0 when off-screen, 1 when fully slid in
+            let slide = slide_in_out(toast.age, SHOW_SECONDS, SLIDE_SECONDS);
+            let x = WIDTH - (TOAST_WIDTH + MARGIN) * slide;
+            let y = MARGIN + i as f32 * (TOAST_HEIGHT + MARGIN);
+
+            draw_rectangle(
+                x,
+                y,
+                TOAST_WIDTH,
+                TOAST_HEIGHT,
+                Color::new(0.1, 0.1, 0.2, 0.85),
+            );
+            draw_rectangle(x, y, 3.0, TOAST_HEIGHT, toast.color);
+            draw_text(&toast.text, x + 9.0, y + 16.0, FONT_SIZE, WHITE);
+        }
+    }
+}
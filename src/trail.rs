@@ -0,0 +1,70 @@
+// Fading afterimages behind the bird, stronger the faster it moves, or a
+// line of colored dots when it wears a trail color (see `cosmetics.rs`)
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::bird::Bird;
+use crate::cosmetics::Look;
+use crate::theme::Theme;
+use crate::tween::ease_in_quad;
+
+const MAX_IMAGES: usize = 8;
+// Speed (pixels per tick) at which the trail is at full strength
+const FULL_SPEED: f32 = 12.0;
+const MAX_ALPHA: f32 = 0.45;
+const DOT_RADIUS: f32 = 3.5;
+
+struct Afterimage {
+    pos: Vec2,
+    // 0 to 1, from the bird's speed when the image was left behind
+    intensity: f32,
+}
+
+#[derive(Default)]
+pub struct Trail {
+    images: VecDeque<Afterimage>,
+}
+
+impl Trail {
+    pub fn clear(&mut self) {
+        self.images.clear();
+    }
+
+    pub fn count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Leaves an afterimage at the bird's current position.
+    pub fn record(&mut self, bird: &Bird, scroll: f32) {
+        // The world scrolls left, so older images drift left with it
+        for image in &mut self.images {
+            image.pos.x -= scroll;
+        }
+        self.images.push_front(Afterimage {
+            pos: vec2(bird.x, bird.y),
+            intensity: (bird.velocity.length() / FULL_SPEED).clamp(0.2, 1.0),
+        });
+        self.images.truncate(MAX_IMAGES);
+    }
+
+    pub fn draw(&self, theme: &Theme, look: Look) {
+        // Oldest first so newer images are drawn on top
+        for (i, image) in self.images.iter().enumerate().skip(1).rev() {
+            let fade = ease_in_quad(1.0 - i as f32 / MAX_IMAGES as f32);
+            let alpha = MAX_ALPHA * fade * image.intensity;
+            if let Some(trail) = look.trail {
+                let pos = image.pos + theme.anchors.trail;
+                let mut color = trail.color(i);
+                // Dots are smaller than the bird, so they need more to show
+                color.a = (alpha * 2.0).min(1.0);
+                draw_circle(pos.x, pos.y, DOT_RADIUS * (0.5 + fade), color);
+            } else {
+                let mut color = theme.tint;
+                color.a = alpha;
+                draw_texture(&theme.bird, image.pos.x, image.pos.y, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// Level triggers: things a level file has happen as the bird passes one of
+// its pipe pairs (see `level.rs`), listed as the pair's number and what
+// happens:
+//
+//     triggers = 4: wind 0.5, 8: music storm, 12: wind 0
+//
+// `wind` blows gusts like the wind modifier's, at that share of their
+// strength, until another `wind` changes it; 0 calms it. `music` switches
+// to the track of that name in the music folder, or `music next` to
+// whichever is up next. The game blows the wind itself and tells the main
+// loop about the rest with `GameEvent::TriggerFired`.
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Trigger {
+    Wind(f32),
+    Music(String),
+}
+
+// Stronger than this and no gap could be flown through
+const MAX_WIND: f32 = 3.0;
+
+impl Trigger {
+    /// Reads one trigger, like `wind 0.5`, saying what's wrong if it can't.
+    pub fn parse(text: &str) -> Result<Trigger, String> {
+        let (action, argument) = text.trim().split_once(' ').unwrap_or((text.trim(), ""));
+        let argument = argument.trim();
+        match action {
+            "wind" => match argument.parse::<f32>() {
+                Ok(strength) if (0.0..=MAX_WIND).contains(&strength) => Ok(Trigger::Wind(strength)),
+                _ => Err(format!("wind takes a strength from 0 to {MAX_WIND}")),
+            },
+            "music" if !argument.is_empty() => Ok(Trigger::Music(argument.to_owned())),
+            "music" => Err("music takes a track name, or next".to_owned()),
+            _ => Err(format!("{action} isn't a trigger (wind or music)")),
+        }
+    }
+}
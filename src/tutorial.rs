@@ -0,0 +1,162 @@
+// First-launch tutorial: flapping with gravity off, then one pipe pair with
+// a wide gap, then off to a normal run. Finishing or skipping it is saved
+// in the profile; `--tutorial` shows it again.
+
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use crate::HEIGHT;
+use crate::bird::BIRD_HEIGHT;
+use crate::controls::ControlScheme;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::level::Level;
+use crate::theme::Theme;
+
+const PRACTICE_FLAPS: u32 = 5;
+// With gravity off each flap's speed fades instead of turning into a fall
+const FLOAT_DRAG: f32 = 0.92;
+const WIDE_GAP_TOP: f32 = 140.0;
+const WIDE_GAP_HEIGHT: f32 = 170.0;
+const DONE_SECONDS: f32 = 2.0;
+
+enum Step {
+    Float { flaps: u32 },
+    Pipe,
+    Done(f32),
+}
+
+pub struct Tutorial {
+    step: Step,
+    game: Game,
+}
+
+impl Tutorial {
+    pub fn new() -> Tutorial {
+        Tutorial {
+            step: Step::Float { flaps: 0 },
+            game: Game::new(),
+        }
+    }
+
+    /// Flaps with the player's controls, as in a run.
+    pub fn input(&mut self, controls: ControlScheme) {
+        let Some(strength) = controls.flap() else {
+            return;
+        };
+        match &mut self.step {
+            Step::Float { flaps } => {
+                self.game.bird.jump_with(strength);
+                self.game.events.emit(GameEvent::Flapped);
+                *flaps += 1;
+            }
+            Step::Pipe if self.game.state == GameState::GameOver => self.game = pipe_course(),
+            Step::Pipe => self.game.flap_with(strength),
+            Step::Done(_) => {}
+        }
+    }
+
+    /// Runs one fixed-timestep update.
+    pub fn tick(&mut self) {
+        match self.step {
+            Step::Float { flaps } => {
+                let bird = &mut self.game.bird;
+                bird.update(0.0, f32::INFINITY);
+                bird.velocity *= FLOAT_DRAG;
+                bird.y = bird.y.clamp(0.0, HEIGHT - BIRD_HEIGHT);
+                // The last flap gets a moment to play out
+                if flaps >= PRACTICE_FLAPS && bird.velocity.length() < 0.5 {
+                    self.step = Step::Pipe;
+                    self.game = pipe_course();
+                }
+            }
+            Step::Pipe => {
+                self.game.update();
+                if self.game.pipes_passed > 0 {
+                    self.step = Step::Done(0.0);
+                }
+            }
+            Step::Done(_) => {}
+        }
+    }
+
+    /// Counts down the closing message. Returns true once the tutorial is
+    /// over.
+    pub fn update(&mut self, dt: f32) -> bool {
+        if let Step::Done(shown) = &mut self.step {
+            *shown += dt;
+            return *shown > DONE_SECONDS;
+        }
+        false
+    }
+
+    /// Events from the tutorial's run since the last call, for sound.
+    pub fn events(&mut self) -> Vec<GameEvent> {
+        self.game.events.drain()
+    }
+
+    pub fn draw(&self, theme: &Theme) {
+        let text = theme.text_color;
+        match self.step {
+            Step::Float { flaps } => {
+                self.game.bird.draw(&theme.bird, theme.tint);
+                draw_centered_text("Flap with Space or a tap", 80.0, 20.0, text);
+                let left = PRACTICE_FLAPS.saturating_sub(flaps);
+                let note = if left > 0 {
+                    format!("Gravity is off. Flap {left} more times")
+                } else {
+                    "Nice!".to_owned()
+                };
+                draw_centered_text(&note, 104.0, 14.0, text);
+            }
+            Step::Pipe => {
+                self.game.draw(theme);
+                let bird = &self.game.bird;
+                let prompt = match (self.game.state, self.game.pipes.first()) {
+                    (GameState::Ready, _) => "Now with gravity: flap through the gap",
+                    (GameState::GameOver, _) => "Ouch! Flap to try again",
+                    (_, Some(pipe)) if bird.y + BIRD_HEIGHT > pipe.gap_bottom() - 20.0 => {
+                        "Too low, flap!"
+                    }
+                    (_, Some(pipe)) if bird.y < pipe.gap_top() + 10.0 => {
+                        "Too high, let yourself fall"
+                    }
+                    _ => "Keep level with the gap",
+                };
+                draw_centered_text(prompt, 96.0, 16.0, text);
+            }
+            Step::Done(_) => {
+                self.game.draw(theme);
+                draw_centered_text(
+                    "That's all there is to it!",
+                    HEIGHT / 2.0 - 40.0,
+                    20.0,
+                    text,
+                );
+                draw_centered_text("Now for a real run", HEIGHT / 2.0 - 16.0, 16.0, text);
+            }
+        }
+        draw_centered_text("Tutorial - Esc to skip", HEIGHT - 20.0, 14.0, GRAY);
+    }
+}
+
+// A level of one pipe pair with a gap far wider than usual
+fn pipe_course() -> Game {
+    let level = Level {
+        id: "tutorial".to_owned(),
+        name: "Tutorial".to_owned(),
+        gaps: vec![WIDE_GAP_TOP],
+        checkpoints: Vec::new(),
+        styles: Vec::new(),
+        widths: Vec::new(),
+        scores: Vec::new(),
+        coins: Vec::new(),
+        triggers: Vec::new(),
+    };
+    let mut game = Game::with_level(None, Some(Rc::new(level)));
+    for pipe in &mut game.pipes {
+        pipe.set_gap_height(WIDE_GAP_HEIGHT);
+    }
+    game
+}
@@ -0,0 +1,17 @@
+// Easing curves for UI and effect animations; `t` runs from 0 to 1
+
+pub fn ease_out_cubic(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn ease_in_quad(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t
+}
+
+/// How far something that slides in over `slide` seconds, stays, and slides
+/// back out at the end of `total` seconds has come in at `age` (0 to 1).
+pub fn slide_in_out(age: f32, total: f32, slide: f32) -> f32 {
+    ease_out_cubic((age / slide).min((total - age) / slide))
+}
@@ -0,0 +1,172 @@
+// Twitch chat votes on the next obstacle modifier (`--twitch <channel>`)
+//
+// Chat is read anonymously over Twitch's IRC gateway on a background thread.
+// Viewers vote with `!1`/`!wind`, `!2`/`!moving` or `!3`/`!narrow`, one vote
+// each per round, and every VOTE_INTERVAL pipes the winner replaces the
+// current modifier.
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use macroquad::prelude::*;
+
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::modifiers::Modifier;
+
+const IRC_ADDRESS: &str = "irc.chat.twitch.tv:6667";
+const VOTE_INTERVAL: u32 = 10;
+
+// What the reader thread reports back
+enum ChatEvent {
+    Message { user: String, text: String },
+    Disconnected(String),
+}
+
+pub struct TwitchVotes {
+    channel: String,
+    chat: Receiver<ChatEvent>,
+    // Votes per entry of `Modifier::ALL` this round
+    tally: [u32; 3],
+    voters: HashSet<String>,
+    pub error: Option<String>,
+}
+
+impl TwitchVotes {
+    pub fn connect(channel: &str) -> Self {
+        let channel = channel.trim_start_matches('#').to_lowercase();
+        let (sender, chat) = mpsc::channel();
+        let thread_channel = channel.clone();
+        thread::spawn(move || {
+            if let Err(err) = read_chat(&thread_channel, &sender) {
+                let _ = sender.send(ChatEvent::Disconnected(err.to_string()));
+            }
+        });
+        TwitchVotes {
+            channel,
+            chat,
+            tally: [0; 3],
+            voters: HashSet::new(),
+            error: None,
+        }
+    }
+
+    /// Counts the votes that arrived since the last call.
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.chat.try_recv() {
+            match event {
+                ChatEvent::Message { user, text } => {
+                    if let Some(vote) = parse_vote(&text)
+                        && self.voters.insert(user)
+                    {
+                        self.tally[vote] += 1;
+                    }
+                }
+                ChatEvent::Disconnected(err) => self.error = Some(err),
+            }
+        }
+    }
+
+    /// Applies the winning modifier every VOTE_INTERVAL pipes and starts a
+    /// new round. Returns the winner, if anyone voted.
+    pub fn handle(&mut self, event: GameEvent, game: &mut Game) -> Option<Modifier> {
+        let GameEvent::PipePassed { .. } = event else {
+            return None;
+        };
+        if !game.pipes_passed.is_multiple_of(VOTE_INTERVAL) {
+            return None;
+        }
+        let winner = self.winner();
+        if winner.is_some() {
+            game.modifier = winner;
+        }
+        self.tally = [0; 3];
+        self.voters.clear();
+        winner
+    }
+
+    // Most votes wins; ties go to the earlier modifier
+    fn winner(&self) -> Option<Modifier> {
+        let (index, &votes) = self
+            .tally
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, votes)| votes)?;
+        (votes > 0).then_some(Modifier::ALL[index])
+    }
+
+    pub fn draw(&self, text_color: Color) {
+        let x = 4.0;
+        let mut y = 60.0;
+        draw_text(&format!("#{} votes", self.channel), x, y, 14.0, text_color);
+        if let Some(err) = &self.error {
+            draw_text(err, x, y + 14.0, 12.0, RED);
+            return;
+        }
+        let total = self.tally.iter().sum::<u32>().max(1);
+        for (i, modifier) in Modifier::ALL.iter().enumerate() {
+            y += 14.0;
+            let share = self.tally[i] as f32 / total as f32;
+            draw_rectangle(
+                x,
+                y - 9.0,
+                60.0 * share,
+                10.0,
+                Color::new(0.6, 0.4, 1.0, 0.6),
+            );
+            let label = format!("!{} {} {}", i + 1, modifier.name(), self.tally[i]);
+            draw_text(&label, x + 2.0, y, 12.0, text_color);
+        }
+    }
+}
+
+fn parse_vote(text: &str) -> Option<usize> {
+    match text.trim().to_lowercase().as_str() {
+        "!1" | "!wind" => Some(0),
+        "!2" | "!moving" => Some(1),
+        "!3" | "!narrow" => Some(2),
+        _ => None,
+    }
+}
+
+// Joins `channel` as an anonymous viewer and forwards chat until the
+// connection drops or the game exits
+fn read_chat(channel: &str, sender: &Sender<ChatEvent>) -> io::Result<()> {
+    let stream = TcpStream::connect(IRC_ADDRESS)?;
+    let mut writer = stream.try_clone()?;
+    let nick = format!("justinfan{}", rand::gen_range(10_000, 99_999));
+    write!(writer, "NICK {nick}\r\nJOIN #{channel}\r\n")?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if let Some(server) = line.strip_prefix("PING ") {
+            write!(writer, "PONG {server}\r\n")?;
+        } else if let Some((user, text)) = parse_privmsg(&line) {
+            let message = ChatEvent::Message {
+                user: user.to_owned(),
+                text: text.to_owned(),
+            };
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "chat disconnected",
+    ))
+}
+
+// Splits `:user!user@user.tmi.twitch.tv PRIVMSG #channel :text`
+fn parse_privmsg(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let (prefix, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix("PRIVMSG ")?;
+    let (_, text) = rest.split_once(" :")?;
+    let user = prefix.split('!').next()?;
+    Some((user, text))
+}
@@ -0,0 +1,203 @@
+// Update check: asks a version endpoint once at launch whether there's a
+// newer build than this one, and if so the title screen says so with a link
+// to where it can be had. Nothing is ever downloaded; itch.io players
+// mostly run whatever zip they first got, and this is only to tell them.
+//
+// Needs the `update-check` cargo feature, with the endpoint baked in
+// (FLAPPY_UPDATE_URL, and FLAPPY_UPDATE_PAGE for the link) or set in
+// `settings.cfg`, where it can also be turned off:
+//
+//     update_check = false
+//     update_url = https://itch.io/api/1/x/wharf/latest?target=sam/flappy&channel_name=linux
+//     update_page = https://sam.itch.io/flappy
+//
+// The endpoint can be itch.io's own for a butler channel, as above, which
+// answers with `{"latest":"1.2.0"}` once builds are pushed with
+// `butler push --userversion 1.2.0`. Anything else that answers with the
+// version on the first line works too, with the link on the second line
+// if it differs from `update_page`.
+
+use crate::kv::KeyValues;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct UpdateSource {
+    pub check: bool,
+    // Overrides for the ones baked in
+    pub url: Option<String>,
+    pub page: Option<String>,
+}
+
+impl UpdateSource {
+    pub fn from_values(kv: &KeyValues) -> UpdateSource {
+        let text = |key| {
+            kv.get(key)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+        };
+        UpdateSource {
+            check: kv.get_bool("update_check").unwrap_or(true),
+            url: text("update_url"),
+            page: text("update_page"),
+        }
+    }
+
+    pub fn write_values(&self, kv: &mut KeyValues) {
+        kv.set("update_check", self.check);
+        if let Some(url) = &self.url {
+            kv.set("update_url", url);
+        }
+        if let Some(page) = &self.page {
+            kv.set("update_page", page);
+        }
+    }
+
+    // Where to ask, if checking is on and there's anywhere to ask
+    #[cfg_attr(not(feature = "update-check"), allow(dead_code))]
+    fn url(&self) -> Option<String> {
+        let baked = option_env!("FLAPPY_UPDATE_URL").map(str::to_owned);
+        self.url.clone().or(baked).filter(|_| self.check)
+    }
+
+    #[cfg_attr(not(feature = "update-check"), allow(dead_code))]
+    fn page(&self) -> Option<String> {
+        let baked = option_env!("FLAPPY_UPDATE_PAGE").map(str::to_owned);
+        self.page.clone().or(baked)
+    }
+}
+
+/// A newer version than this one.
+#[cfg_attr(not(feature = "update-check"), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub struct NewVersion {
+    pub version: String,
+    pub page: Option<String>,
+}
+
+pub struct UpdateCheck {
+    #[cfg(feature = "update-check")]
+    answer: Option<std::sync::mpsc::Receiver<Option<NewVersion>>>,
+    found: Option<NewVersion>,
+}
+
+impl UpdateCheck {
+    /// Starts asking in the background, if `source` has somewhere to ask.
+    pub fn start(source: &UpdateSource) -> UpdateCheck {
+        #[cfg(feature = "update-check")]
+        {
+            let answer = source.url().map(|url| check::spawn(url, source.page()));
+            UpdateCheck {
+                answer,
+                found: None,
+            }
+        }
+        #[cfg(not(feature = "update-check"))]
+        {
+            let _ = source;
+            UpdateCheck { found: None }
+        }
+    }
+
+    /// Picks up the answer, once it's in.
+    pub fn poll(&mut self) {
+        #[cfg(feature = "update-check")]
+        if let Some(answer) = &self.answer
+            && let Ok(found) = answer.try_recv()
+        {
+            self.found = found;
+            self.answer = None;
+        }
+    }
+
+    /// The newer version, if the answer had one.
+    pub fn found(&self) -> Option<&NewVersion> {
+        self.found.as_ref()
+    }
+}
+
+#[cfg(feature = "update-check")]
+mod check {
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::NewVersion;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn spawn(url: String, page: Option<String>) -> Receiver<Option<NewVersion>> {
+        let (sender, answer) = mpsc::channel();
+        thread::spawn(move || {
+            let found = match fetch(&url) {
+                Ok(body) => newer_than_this(&body, page),
+                // Offline is normal; it just means no banner
+                Err(err) => {
+                    eprintln!("Could not check for updates: {err}");
+                    None
+                }
+            };
+            let _ = sender.send(found);
+        });
+        answer
+    }
+
+    fn fetch(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        let response = agent
+            .get(url)
+            .set(
+                "User-Agent",
+                concat!("flappy_bird_rust/", env!("CARGO_PKG_VERSION")),
+            )
+            .call()?;
+        Ok(response.into_string()?)
+    }
+
+    fn newer_than_this(body: &str, page: Option<String>) -> Option<NewVersion> {
+        let (version, link) = match latest_field(body) {
+            Some(version) => (version, None),
+            None => {
+                let mut lines = body.lines().map(str::trim);
+                let version = lines.next().filter(|line| !line.is_empty())?;
+                (version, lines.next().filter(|line| !line.is_empty()))
+            }
+        };
+        let version = version.trim_start_matches('v');
+        if !is_newer(version, env!("CARGO_PKG_VERSION")) {
+            return None;
+        }
+        Some(NewVersion {
+            version: version.to_owned(),
+            page: link.map(str::to_owned).or(page),
+        })
+    }
+
+    // The "latest" string out of itch.io's `{"latest":"1.2.0"}`, without
+    // pulling in a JSON parser for one field
+    fn latest_field(body: &str) -> Option<&str> {
+        let after = body.split_once("\"latest\"")?.1;
+        let value = after.trim_start().strip_prefix(':')?.trim_start();
+        let value = value.strip_prefix('"')?;
+        value.split_once('"').map(|(version, _)| version)
+    }
+
+    // Compares dotted numbers, so 1.10 is newer than 1.9. Anything after
+    // the numbers (as in 1.2.0-beta) is ignored
+    fn is_newer(version: &str, current: &str) -> bool {
+        numbers(version) > numbers(current)
+    }
+
+    fn numbers(version: &str) -> Vec<u32> {
+        let mut numbers: Vec<u32> = version
+            .split('.')
+            .map_while(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().ok()
+            })
+            .collect();
+        // 1.2 and 1.2.0 are the same version
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        numbers
+    }
+}
@@ -0,0 +1,914 @@
+// Online 1v1, started from the lobby screen or with `--host <port>` /
+// `--join <code or address>`
+//
+// Both players fly the same seeded course at the same time and see each
+// other as a translucent bird. The first to crash loses. Before each race
+// the players meet in a lobby where the host picks the course and game mode
+// and both ready up. Hosts are listed on the local network (see
+// `discovery.rs`), and others can watch their matches (see `spectator.rs`).
+// Peers talk over UDP (see `net.rs`) with one line of text per packet:
+//
+//     HELLO                         client -> host until welcomed
+//     LOBBY <round> <seed> <ready> <mode>
+//                                   host -> client, repeated in the lobby
+//     READY <ready>                 client -> host, repeated in the lobby
+//     START <round>                 host -> client, repeated while racing
+//     STATE <round> <y> <score> <alive> <attacks>
+//     BYE
+//
+// and for spectators:
+//
+//     WATCH                         spectator -> host, repeated
+//     WAITING                       host -> spectators between races
+//     VIEW <round> <seed> <time> <host y score alive> <guest y score alive>
+//          <winner>                 host -> spectators during a race; the
+//                                   winner is host, guest or - until decided
+//
+// `attacks` lists every attack sent this race as letters (see `attacks.rs`),
+// or - for none, so a lost state costs nothing and each is applied once.
+//
+// Packets may be lost or reordered; lobbies and states carry the round so
+// leftovers from another race are ignored, and a peer that goes quiet for
+// TIMEOUT_SECONDS counts as disconnected.
+
+use std::io;
+use std::net::{SocketAddr, SocketAddrV4};
+
+use macroquad::prelude::*;
+
+use crate::attacks::{Attack, PIPES_PER_ATTACK};
+use crate::bird::Bird;
+use crate::controls::ControlScheme;
+use crate::date::Date;
+use crate::discovery;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState, draw_centered_text};
+use crate::net::{self, Link};
+use crate::options::Options;
+use crate::scripting::Scripting;
+use crate::theme::Theme;
+use crate::viewport::{FIELD_LEFT, FIELD_WIDTH};
+use crate::{HEIGHT, WIDTH};
+
+const COUNTDOWN_SECONDS: f32 = 3.0;
+const SEND_INTERVAL_SECONDS: f32 = 0.05;
+const RESEND_INTERVAL_SECONDS: f32 = 0.5;
+pub const TIMEOUT_SECONDS: f32 = 3.0;
+const MAX_SPECTATORS: usize = 8;
+// How quickly the opponent's drawn position catches up with the last packet
+const SMOOTHING: f32 = 15.0;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Packet {
+    Hello,
+    Lobby {
+        round: u32,
+        seed: u64,
+        ready: bool,
+        mode: String,
+    },
+    Ready {
+        ready: bool,
+    },
+    Start {
+        round: u32,
+    },
+    State {
+        round: u32,
+        y: f32,
+        score: i64,
+        alive: bool,
+        attacks: Vec<Attack>,
+    },
+    Bye,
+    Watch,
+    Waiting,
+    View {
+        round: u32,
+        seed: u64,
+        // Seconds since the birds launched, negative during the countdown
+        time: f32,
+        host: Racer,
+        guest: Racer,
+        // Decided by the host, so spectators agree with the players
+        host_won: Option<bool>,
+    },
+}
+
+/// One player's bird, as shown to spectators.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Racer {
+    pub y: f32,
+    pub score: i64,
+    pub alive: bool,
+}
+
+impl Racer {
+    fn encode(self) -> String {
+        format!("{} {} {}", self.y, self.score, u8::from(self.alive))
+    }
+
+    fn decode<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Racer> {
+        Some(Racer {
+            y: words.next()?.parse().ok().filter(|y: &f32| y.is_finite())?,
+            score: words.next()?.parse().ok()?,
+            alive: words.next()? == "1",
+        })
+    }
+}
+
+impl Packet {
+    pub fn encode(&self) -> String {
+        match self {
+            Packet::Hello => "HELLO".to_owned(),
+            Packet::Lobby {
+                round,
+                seed,
+                ready,
+                mode,
+            } => format!("LOBBY {round} {seed} {} {mode}", u8::from(*ready)),
+            Packet::Ready { ready } => format!("READY {}", u8::from(*ready)),
+            Packet::Start { round } => format!("START {round}"),
+            Packet::State {
+                round,
+                y,
+                score,
+                alive,
+                attacks,
+            } => {
+                let mut letters: String = attacks.iter().map(|a| a.letter()).collect();
+                if letters.is_empty() {
+                    letters.push('-');
+                }
+                format!("STATE {round} {y} {score} {} {letters}", u8::from(*alive))
+            }
+            Packet::Bye => "BYE".to_owned(),
+            Packet::Watch => "WATCH".to_owned(),
+            Packet::Waiting => "WAITING".to_owned(),
+            Packet::View {
+                round,
+                seed,
+                time,
+                host,
+                guest,
+                host_won,
+            } => {
+                let winner = match host_won {
+                    Some(true) => "host",
+                    Some(false) => "guest",
+                    None => "-",
+                };
+                format!(
+                    "VIEW {round} {seed} {time} {} {} {winner}",
+                    host.encode(),
+                    guest.encode()
+                )
+            }
+        }
+    }
+
+    pub fn decode(text: &str) -> Option<Packet> {
+        let mut words = text.split_whitespace();
+        let packet = match words.next()? {
+            "HELLO" => Packet::Hello,
+            "LOBBY" => Packet::Lobby {
+                round: words.next()?.parse().ok()?,
+                seed: words.next()?.parse().ok()?,
+                ready: words.next()? == "1",
+                // Mode names come from file names and may contain spaces
+                mode: Some(words.collect::<Vec<_>>().join(" ")).filter(|m| !m.is_empty())?,
+            },
+            "READY" => Packet::Ready {
+                ready: words.next()? == "1",
+            },
+            "START" => Packet::Start {
+                round: words.next()?.parse().ok()?,
+            },
+            "STATE" => Packet::State {
+                round: words.next()?.parse().ok()?,
+                y: words.next()?.parse().ok().filter(|y: &f32| y.is_finite())?,
+                score: words.next()?.parse().ok()?,
+                alive: words.next()? == "1",
+                attacks: match words.next()? {
+                    "-" => Vec::new(),
+                    letters => letters
+                        .chars()
+                        .map(Attack::from_letter)
+                        .collect::<Option<_>>()?,
+                },
+            },
+            "BYE" => Packet::Bye,
+            "WATCH" => Packet::Watch,
+            "WAITING" => Packet::Waiting,
+            "VIEW" => Packet::View {
+                round: words.next()?.parse().ok()?,
+                seed: words.next()?.parse().ok()?,
+                time: words.next()?.parse().ok().filter(|t: &f32| t.is_finite())?,
+                host: Racer::decode(&mut words)?,
+                guest: Racer::decode(&mut words)?,
+                host_won: match words.next()? {
+                    "host" => Some(true),
+                    "guest" => Some(false),
+                    _ => None,
+                },
+            },
+            _ => return None,
+        };
+        Some(packet)
+    }
+}
+
+/// Which course the host picked.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Course {
+    Seed(u64),
+    // Today's daily course
+    Daily,
+}
+
+impl Course {
+    pub fn random() -> Course {
+        Course::Seed(u64::from(rand::rand()))
+    }
+
+    /// The course asked for on the command line, or a random one.
+    pub fn from_options(options: &Options) -> Course {
+        match options.seed {
+            Some(seed) => Course::Seed(seed),
+            None if options.daily() => Course::Daily,
+            None => Course::random(),
+        }
+    }
+
+    fn seed(self) -> u64 {
+        match self {
+            Course::Seed(seed) => seed,
+            Course::Daily => Date::today().seed(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Host,
+    Client { host: SocketAddr },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    Won,
+    Lost,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    // Host: waiting for someone to join. Client: waiting to be welcomed.
+    Connecting,
+    // Picking the course and readying up
+    Lobby,
+    // Seconds left before the birds launch
+    Countdown(f32),
+    Racing,
+    // The race is decided; the winner keeps flying until they crash too
+    Finished(Outcome),
+}
+
+// Someone watching this host's matches
+struct Watcher {
+    address: SocketAddr,
+    // Seconds since their last WATCH
+    silence: f32,
+}
+
+// The other player as last reported
+struct Opponent {
+    y: f32,
+    // Drawn position, eased towards `y` so 20 Hz updates look smooth
+    shown_y: f32,
+    score: i64,
+    alive: bool,
+    ready: bool,
+}
+
+pub struct Versus {
+    link: Link,
+    role: Role,
+    peer: Option<SocketAddr>,
+    // Host only; clients just learn the seed
+    course: Course,
+    seed: u64,
+    mode: String,
+    // Client: the host's mode isn't installed here, so we can't ready up
+    missing_mode: bool,
+    round: u32,
+    phase: Phase,
+    ready: bool,
+    opponent: Opponent,
+    // What the host shares with the other player, e.g. `1K8R4-0PZ2E`
+    join_code: Option<String>,
+    // Seconds since the peer was last heard from
+    silence: f32,
+    send_timer: f32,
+    resend_timer: f32,
+    announce_timer: f32,
+    // This race, for attacks
+    pipes_passed: u32,
+    attacks_sent: Vec<Attack>,
+    // How many of the opponent's attacks are already in our queue
+    attacks_received: usize,
+    // Host only
+    spectators: Vec<Watcher>,
+    view_timer: f32,
+    // Seconds since the birds launched, negative during the countdown
+    race_time: f32,
+}
+
+impl Versus {
+    /// Waits for an opponent on `port` (0 picks a free one), starting with
+    /// `course` and the game mode called `mode`.
+    pub fn host(port: u16, course: Course, mode: &str) -> io::Result<Versus> {
+        let link = Link::bind(port)?;
+        let join_code =
+            net::lan_ip().map(|ip| net::join_code(SocketAddrV4::new(ip, link.local_addr().port())));
+        let mut versus = Versus::new(link, Role::Host, join_code);
+        versus.course = course;
+        versus.seed = course.seed();
+        versus.mode = mode.to_owned();
+        Ok(versus)
+    }
+
+    /// Joins the game behind a join code, or at `address` (`host:port`).
+    pub fn join(address: &str) -> io::Result<Versus> {
+        let host = net::resolve(address)?;
+        Ok(Versus::new(Link::bind(0)?, Role::Client { host }, None))
+    }
+
+    fn new(link: Link, role: Role, join_code: Option<String>) -> Versus {
+        Versus {
+            link,
+            role,
+            peer: None,
+            course: Course::Seed(0),
+            seed: 0,
+            mode: String::new(),
+            missing_mode: false,
+            round: 0,
+            phase: Phase::Connecting,
+            ready: false,
+            opponent: Opponent {
+                y: HEIGHT / 2.0,
+                shown_y: HEIGHT / 2.0,
+                score: 0,
+                alive: false,
+                ready: false,
+            },
+            join_code,
+            silence: 0.0,
+            send_timer: 0.0,
+            resend_timer: 0.0,
+            announce_timer: 0.0,
+            pipes_passed: 0,
+            attacks_sent: Vec::new(),
+            attacks_received: 0,
+            spectators: Vec::new(),
+            view_timer: 0.0,
+            race_time: 0.0,
+        }
+    }
+
+    /// Handles the local player's input. Runs are started by the countdown
+    /// rather than the flap key, which flaps, readies up or asks for a
+    /// rematch depending on where the match is.
+    pub fn input(&mut self, game: &mut Game, scripting: &mut Scripting, controls: ControlScheme) {
+        let flap = controls.flap();
+        let pressed = flap.is_some() || is_key_pressed(KeyCode::Enter);
+        match self.phase {
+            Phase::Connecting | Phase::Countdown(_) => {}
+            Phase::Lobby => {
+                if pressed && !self.missing_mode {
+                    self.ready = !self.ready;
+                    self.resend_timer = 0.0;
+                }
+                if self.role == Role::Host {
+                    self.pick_course(scripting);
+                }
+            }
+            Phase::Racing | Phase::Finished(_) if pressed => {
+                if game.state == GameState::Playing {
+                    game.flap_with(flap.unwrap_or(1.0));
+                } else {
+                    self.rematch(game);
+                }
+            }
+            Phase::Racing | Phase::Finished(_) => {}
+        }
+    }
+
+    // Host: Left/Right picks the mode, Up/Down the course. Any change
+    // un-readies both players so nobody is rushed into something new.
+    fn pick_course(&mut self, scripting: &mut Scripting) {
+        let mode_step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+        if mode_step != 0 {
+            scripting.cycle_mode(mode_step);
+            self.mode = scripting.active_name().to_owned();
+        }
+        let course_changed = is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Down);
+        if course_changed {
+            self.course = match self.course {
+                Course::Seed(_) => Course::Daily,
+                Course::Daily => Course::random(),
+            };
+            self.seed = self.course.seed();
+        }
+        if mode_step != 0 || course_changed {
+            self.ready = false;
+            self.resend_timer = 0.0;
+        }
+    }
+
+    // Host only: back to the lobby once both birds are down
+    fn rematch(&mut self, game: &Game) {
+        if self.role == Role::Host
+            && self.peer.is_some()
+            && matches!(self.phase, Phase::Finished(_))
+            && game.state == GameState::GameOver
+            && !self.opponent.alive
+        {
+            self.enter_lobby();
+        }
+    }
+
+    /// Exchanges packets and moves the match along. Returns a message worth
+    /// showing the player, if something happened.
+    pub fn update(
+        &mut self,
+        game: &mut Game,
+        scripting: &mut Scripting,
+        dt: f32,
+    ) -> Option<String> {
+        let mut notice = self.receive(game, scripting);
+
+        self.silence += dt;
+        if self.peer.is_some() && self.silence > TIMEOUT_SECONDS {
+            self.disconnect(game);
+            notice = Some("Opponent disconnected".to_owned());
+        }
+
+        if self.phase == Phase::Lobby
+            && self.role == Role::Host
+            && self.ready
+            && self.opponent.ready
+        {
+            self.start_round(self.round + 1, game);
+        }
+
+        if let Phase::Countdown(left) = self.phase {
+            let left = left - dt;
+            if left <= 0.0 {
+                self.phase = Phase::Racing;
+                game.flap();
+            } else {
+                self.phase = Phase::Countdown(left);
+            }
+        }
+
+        if self.phase == Phase::Racing {
+            let lost = game.state == GameState::GameOver;
+            if lost || !self.opponent.alive {
+                let outcome = if lost { Outcome::Lost } else { Outcome::Won };
+                self.phase = Phase::Finished(outcome);
+                notice = Some(match outcome {
+                    Outcome::Won => "You win!".to_owned(),
+                    Outcome::Lost => "You lose".to_owned(),
+                });
+            }
+        }
+
+        self.send(game, dt);
+        if self.role == Role::Host {
+            self.announce(dt);
+            self.serve_spectators(game, dt);
+        }
+
+        let ease = 1.0 - (-SMOOTHING * dt).exp();
+        self.opponent.shown_y += (self.opponent.y - self.opponent.shown_y) * ease;
+        notice
+    }
+
+    fn receive(&mut self, game: &mut Game, scripting: &mut Scripting) -> Option<String> {
+        let mut notice = None;
+        while let Some((text, from)) = self.link.receive() {
+            let Some(packet) = Packet::decode(&text) else {
+                continue;
+            };
+            if let Some(text) = self.handle_packet(packet, from, game, scripting) {
+                notice = Some(text);
+            }
+        }
+        notice
+    }
+
+    /// Sends an attack every PIPES_PER_ATTACK pipes passed in a race.
+    /// Returns what was sent, if anything.
+    pub fn handle(&mut self, event: GameEvent) -> Option<String> {
+        if self.phase != Phase::Racing || !matches!(event, GameEvent::PipePassed { .. }) {
+            return None;
+        }
+        self.pipes_passed += 1;
+        if !self.pipes_passed.is_multiple_of(PIPES_PER_ATTACK) {
+            return None;
+        }
+        let attack = Attack::random();
+        self.attacks_sent.push(attack);
+        Some(format!("Sent: {}!", attack.name()))
+    }
+
+    fn handle_packet(
+        &mut self,
+        packet: Packet,
+        from: SocketAddr,
+        game: &mut Game,
+        scripting: &mut Scripting,
+    ) -> Option<String> {
+        if packet == Packet::Watch {
+            return match self.role {
+                Role::Host => self.add_spectator(from),
+                Role::Client { .. } => None,
+            };
+        }
+        // Only one opponent at a time; anyone else is ignored
+        match (self.role, self.peer) {
+            (Role::Host, None) if packet == Packet::Hello => {}
+            (Role::Client { host }, _) if from != host => return None,
+            (_, Some(peer)) if from != peer => return None,
+            (_, None) if !matches!(packet, Packet::Hello | Packet::Lobby { .. }) => return None,
+            _ => {}
+        }
+        self.silence = 0.0;
+
+        match packet {
+            Packet::Hello => {
+                if self.peer.is_none() {
+                    self.peer = Some(from);
+                    self.enter_lobby();
+                    return Some("Opponent joined".to_owned());
+                }
+            }
+            Packet::Lobby {
+                round,
+                seed,
+                ready,
+                mode,
+            } => {
+                let mut notice = None;
+                if self.peer.is_none() {
+                    self.peer = Some(from);
+                    notice = Some("Connected".to_owned());
+                }
+                // The lobby for the next race; anything older is a leftover
+                if round <= self.round {
+                    return notice;
+                }
+                if self.phase != Phase::Lobby {
+                    self.enter_lobby();
+                }
+                if seed != self.seed || mode != self.mode {
+                    self.ready = false;
+                }
+                if mode != self.mode {
+                    self.missing_mode = !scripting.select(&mode);
+                    self.mode = mode;
+                }
+                self.seed = seed;
+                self.opponent.ready = ready;
+                return notice;
+            }
+            Packet::Ready { ready } => {
+                if self.phase == Phase::Lobby {
+                    self.opponent.ready = ready;
+                }
+            }
+            Packet::Start { round } => {
+                if round > self.round {
+                    self.start_round(round, game);
+                }
+            }
+            Packet::State {
+                round,
+                y,
+                score,
+                alive,
+                attacks,
+            } => {
+                if round == self.round {
+                    self.opponent.y = y;
+                    self.opponent.score = score;
+                    self.opponent.alive = alive;
+                    if let Some(new) = attacks.get(self.attacks_received..)
+                        && !new.is_empty()
+                    {
+                        self.attacks_received = attacks.len();
+                        game.incoming.extend(new);
+                        let names: Vec<_> = new.iter().map(|a| a.name()).collect();
+                        return Some(format!("Incoming: {}!", names.join(", ")));
+                    }
+                }
+            }
+            Packet::Bye => {
+                self.disconnect(game);
+                return Some("Opponent left".to_owned());
+            }
+            // Only meant for spectators
+            Packet::Watch | Packet::Waiting | Packet::View { .. } => {}
+        }
+        None
+    }
+
+    fn add_spectator(&mut self, address: SocketAddr) -> Option<String> {
+        if let Some(watcher) = self.spectators.iter_mut().find(|w| w.address == address) {
+            watcher.silence = 0.0;
+            return None;
+        }
+        if self.spectators.len() == MAX_SPECTATORS {
+            return None;
+        }
+        self.spectators.push(Watcher {
+            address,
+            silence: 0.0,
+        });
+        Some("Someone is watching".to_owned())
+    }
+
+    fn enter_lobby(&mut self) {
+        self.phase = Phase::Lobby;
+        self.ready = false;
+        self.opponent.ready = false;
+        self.resend_timer = 0.0;
+    }
+
+    fn start_round(&mut self, round: u32, game: &mut Game) {
+        self.round = round;
+        self.phase = Phase::Countdown(COUNTDOWN_SECONDS);
+        self.race_time = -COUNTDOWN_SECONDS;
+        self.ready = false;
+        self.opponent.ready = false;
+        self.opponent.alive = true;
+        self.opponent.score = 0;
+        self.pipes_passed = 0;
+        self.attacks_sent.clear();
+        self.attacks_received = 0;
+        // Tell the client straight away so both countdowns line up
+        self.resend_timer = 0.0;
+        game.fixed_seed = Some(self.seed);
+        game.reset();
+    }
+
+    fn disconnect(&mut self, game: &mut Game) {
+        self.peer = None;
+        self.phase = Phase::Connecting;
+        self.opponent.alive = false;
+        game.reset();
+    }
+
+    fn send(&mut self, game: &Game, dt: f32) {
+        self.resend_timer -= dt;
+        let resend = self.resend_timer <= 0.0;
+        if resend {
+            self.resend_timer = RESEND_INTERVAL_SECONDS;
+        }
+
+        let Some(peer) = self.peer else {
+            // Knock until the host answers
+            if let Role::Client { host } = self.role
+                && resend
+            {
+                self.send_packet(&Packet::Hello, host);
+            }
+            return;
+        };
+
+        match (self.role, self.phase) {
+            (_, Phase::Connecting) => return,
+            (Role::Host, Phase::Lobby) if resend => {
+                let lobby = Packet::Lobby {
+                    round: self.round + 1,
+                    seed: self.seed,
+                    ready: self.ready,
+                    mode: self.mode.clone(),
+                };
+                self.send_packet(&lobby, peer);
+            }
+            (Role::Client { .. }, Phase::Lobby) if resend => {
+                self.send_packet(&Packet::Ready { ready: self.ready }, peer);
+            }
+            // Also during the race, in case every START so far was lost
+            (Role::Host, Phase::Countdown(_) | Phase::Racing) if resend => {
+                self.send_packet(&Packet::Start { round: self.round }, peer);
+            }
+            _ => {}
+        }
+        if self.phase == Phase::Lobby {
+            return;
+        }
+
+        self.send_timer -= dt;
+        if self.send_timer <= 0.0 {
+            self.send_timer = SEND_INTERVAL_SECONDS;
+            let state = Packet::State {
+                round: self.round,
+                y: game.bird.y,
+                score: game.score,
+                alive: game.state != GameState::GameOver,
+                attacks: self.attacks_sent.clone(),
+            };
+            self.send_packet(&state, peer);
+        }
+    }
+
+    // Lets nearby players find this game, to join it or to watch
+    fn announce(&mut self, dt: f32) {
+        self.announce_timer -= dt;
+        if self.announce_timer <= 0.0 {
+            self.announce_timer = discovery::ANNOUNCE_INTERVAL_SECONDS;
+            let announcement = discovery::announcement(self.peer.is_none(), &self.mode);
+            self.link.broadcast(announcement, discovery::DISCOVERY_PORT);
+        }
+    }
+
+    fn serve_spectators(&mut self, game: &Game, dt: f32) {
+        for watcher in &mut self.spectators {
+            watcher.silence += dt;
+        }
+        self.spectators
+            .retain(|watcher| watcher.silence < TIMEOUT_SECONDS);
+
+        let racing = matches!(
+            self.phase,
+            Phase::Countdown(_) | Phase::Racing | Phase::Finished(_)
+        );
+        if racing {
+            self.race_time += dt;
+        }
+        self.view_timer -= dt;
+        if self.view_timer > 0.0 {
+            return;
+        }
+        let packet = if racing {
+            self.view_timer = SEND_INTERVAL_SECONDS;
+            Packet::View {
+                round: self.round,
+                seed: self.seed,
+                time: self.race_time,
+                host: Racer {
+                    y: game.bird.y,
+                    score: game.score,
+                    alive: game.state != GameState::GameOver,
+                },
+                guest: Racer {
+                    y: self.opponent.y,
+                    score: self.opponent.score,
+                    alive: self.opponent.alive,
+                },
+                host_won: match self.phase {
+                    Phase::Finished(outcome) => Some(outcome == Outcome::Won),
+                    _ => None,
+                },
+            }
+        } else {
+            self.view_timer = RESEND_INTERVAL_SECONDS;
+            Packet::Waiting
+        };
+        for watcher in &self.spectators {
+            self.send_packet(&packet, watcher.address);
+        }
+    }
+
+    fn send_packet(&self, packet: &Packet, to: SocketAddr) {
+        self.link.send(packet.encode(), to);
+    }
+
+    /// Draws the opponent's bird behind the local one.
+    pub fn draw_opponent(&self, theme: &Theme) {
+        if self.peer.is_none() || !self.opponent.alive {
+            return;
+        }
+        let tint = Color::new(theme.tint.r, theme.tint.g, theme.tint.b, 0.4);
+        draw_texture(&theme.bird, Bird::new().x, self.opponent.shown_y, tint);
+    }
+
+    /// Draws the match's status, leaving the join code out in streamer
+    /// mode (see `settings.rs`).
+    pub fn draw_hud(&self, theme: &Theme, streamer_mode: bool) {
+        let status = match self.phase {
+            Phase::Connecting => match (self.role, &self.join_code) {
+                (Role::Host, Some(code)) => {
+                    draw_centered_text("Join code", HEIGHT / 2.0 - 20.0, 16.0, theme.text_color);
+                    let code = if streamer_mode { "Hidden" } else { code };
+                    draw_centered_text(code, HEIGHT / 2.0 + 10.0, 32.0, theme.text_color);
+                    "Waiting for opponent".to_owned()
+                }
+                (Role::Host, None) => {
+                    let port = self.link.local_addr().port();
+                    format!("Waiting for opponent on port {port}")
+                }
+                (Role::Client { host }, _) => format!("Connecting to {host}"),
+            },
+            Phase::Lobby => return self.draw_lobby(),
+            Phase::Countdown(left) => {
+                draw_centered_text(
+                    &(left.ceil() as u32).to_string(),
+                    HEIGHT / 2.0,
+                    48.0,
+                    theme.text_color,
+                );
+                return self.draw_rival_score(theme);
+            }
+            Phase::Racing => return self.draw_rival_score(theme),
+            Phase::Finished(outcome) => {
+                let result = match outcome {
+                    Outcome::Won => "You win!",
+                    Outcome::Lost => "You lose",
+                };
+                draw_centered_text(result, HEIGHT / 2.0 + 10.0, 28.0, theme.text_color);
+                self.draw_rival_score(theme);
+                match self.role {
+                    Role::Host => "Space for a rematch once both are down".to_owned(),
+                    Role::Client { .. } => "Waiting for the host's rematch".to_owned(),
+                }
+            }
+        };
+        draw_centered_text(&status, HEIGHT - 30.0, 14.0, theme.text_color);
+    }
+
+    // Laid out like the settings screen
+    fn draw_lobby(&self) {
+        draw_rectangle(
+            FIELD_LEFT,
+            0.0,
+            FIELD_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_centered_text("Lobby", 60.0, 32.0, WHITE);
+
+        let host = self.role == Role::Host;
+        let course = match (host, self.course) {
+            (true, Course::Daily) => "Daily".to_owned(),
+            _ => format!("Seed {}", self.seed),
+        };
+        let rows = [
+            ("Course", course),
+            ("Mode", self.mode.clone()),
+            ("You", ready_label(self.ready).to_owned()),
+            ("Rival", ready_label(self.opponent.ready).to_owned()),
+        ];
+        for (i, (label, value)) in rows.into_iter().enumerate() {
+            let y = 110.0 + i as f32 * 28.0;
+            draw_text(label, 20.0, y, 20.0, WHITE);
+            // The host can change the first two
+            let value = if host && i < 2 {
+                format!("< {value} >")
+            } else {
+                value
+            };
+            let size = measure_text(&value, None, 20, 1.0);
+            draw_text(&value, WIDTH - 20.0 - size.width, y, 20.0, WHITE);
+        }
+
+        if self.missing_mode {
+            let warning = format!("You don't have the {} mode", self.mode);
+            draw_centered_text(&warning, HEIGHT - 64.0, 16.0, RED);
+        }
+        let hint = if host {
+            "Arrows pick, Space to ready up"
+        } else {
+            "Space to ready up"
+        };
+        draw_centered_text(hint, HEIGHT - 40.0, 16.0, GRAY);
+        draw_centered_text("Esc to leave", HEIGHT - 20.0, 16.0, GRAY);
+    }
+
+    fn draw_rival_score(&self, theme: &Theme) {
+        let label = format!("Rival {}", self.opponent.score);
+        draw_centered_text(&label, 72.0, 16.0, theme.text_color);
+    }
+}
+
+fn ready_label(ready: bool) -> &'static str {
+    if ready { "Ready" } else { "Not ready" }
+}
+
+impl Drop for Versus {
+    fn drop(&mut self) {
+        let peers = self.peer.into_iter();
+        for address in peers.chain(self.spectators.iter().map(|w| w.address)) {
+            self.send_packet(&Packet::Bye, address);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+// Virtual resolution: the game always draws into a HEIGHT-tall target, which
+// is then scaled to fit the window with letterboxing. The target is WIDTH
+// wide in portrait windows, and wider in landscape and ultrawide ones.
+//
+// Wider layouts don't stretch the portrait one. The portrait screen keeps
+// its coordinates in the middle of the wider target, so menus and the HUD
+// stay put, and the extra room either side shows more of the pipe field.
+// With `WideView::Panels` picked in the settings the portrait layout is kept
+// instead, and the letterbox bars get the theme's side panel art.
+
+use macroquad::prelude::*;
+
+use crate::grading::{Grade, Grading};
+use crate::platform::{self, Insets};
+use crate::theme::Theme;
+use crate::{HEIGHT, WIDTH};
+
+pub const LANDSCAPE_WIDTH: f32 = 640.0;
+// About 21:9
+pub const ULTRAWIDE_WIDTH: f32 = 933.0;
+// Windows wider than these (width over height) get the wider layouts
+const LANDSCAPE_ASPECT: f32 = 1.1;
+const ULTRAWIDE_ASPECT: f32 = 1.95;
+// Side panels without art of their own show the background this dark
+const PANEL_DIM: Color = Color::new(0.4, 0.4, 0.45, 1.0);
+
+// The world x coordinates the widest layout shows. The simulation always
+// covers all of it, so a run plays the same in every layout and a wider
+// view never changes when anything spawns.
+pub const FIELD_LEFT: f32 = -(ULTRAWIDE_WIDTH - WIDTH) / 2.0;
+pub const FIELD_RIGHT: f32 = WIDTH - FIELD_LEFT;
+pub const FIELD_WIDTH: f32 = FIELD_RIGHT - FIELD_LEFT;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Layout {
+    #[default]
+    Portrait,
+    Landscape,
+    Ultrawide,
+}
+
+impl Layout {
+    /// The layout for a `width` by `height` window.
+    pub fn for_window(width: f32, height: f32) -> Layout {
+        if width > height * ULTRAWIDE_ASPECT {
+            Layout::Ultrawide
+        } else if width > height * LANDSCAPE_ASPECT {
+            Layout::Landscape
+        } else {
+            Layout::Portrait
+        }
+    }
+
+    /// The width of the virtual screen.
+    pub fn width(self) -> f32 {
+        match self {
+            Layout::Portrait => WIDTH,
+            Layout::Landscape => LANDSCAPE_WIDTH,
+            Layout::Ultrawide => ULTRAWIDE_WIDTH,
+        }
+    }
+
+    /// The world x coordinate at the left edge of the virtual screen.
+    pub fn left(self) -> f32 {
+        (WIDTH - self.width()) / 2.0
+    }
+
+    /// The world x coordinate at the right edge of the virtual screen.
+    pub fn right(self) -> f32 {
+        self.left() + self.width()
+    }
+}
+
+/// What wide windows show beside the portrait playfield.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum WideView {
+    // More of the world, see `Layout`
+    #[default]
+    Extend,
+    // Letterboxing with decorative side panels
+    Panels,
+}
+
+impl WideView {
+    pub const ALL: [WideView; 2] = [WideView::Extend, WideView::Panels];
+
+    /// Stable name used in the settings file.
+    pub fn id(self) -> &'static str {
+        match self {
+            WideView::Extend => "extend",
+            WideView::Panels => "panels",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<WideView> {
+        WideView::ALL.into_iter().find(|view| view.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WideView::Extend => "Extend world",
+            WideView::Panels => "Side panels",
+        }
+    }
+}
+
+pub struct Viewport {
+    layout: Layout,
+    wide_view: WideView,
+    target: RenderTarget,
+    camera: Camera2D,
+    // Kept clear of the game, e.g. for a phone's notch
+    insets: Insets,
+    grading: Grading,
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        let layout = Layout::for_window(screen_width(), screen_height());
+        let (target, camera) = Viewport::screen(layout);
+        Viewport {
+            layout,
+            wide_view: WideView::default(),
+            target,
+            camera,
+            insets: platform::safe_insets(),
+            grading: Grading::new(),
+        }
+    }
+
+    // The virtual screen for `layout`, and a camera drawing into it
+    fn screen(layout: Layout) -> (RenderTarget, Camera2D) {
+        let target = render_target(layout.width() as u32, HEIGHT as u32);
+        target.texture.set_filter(FilterMode::Nearest);
+        // Positive y zoom so the target texture comes out upright when drawn
+        let camera = Camera2D {
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0),
+            zoom: vec2(2.0 / layout.width(), 2.0 / HEIGHT),
+            render_target: Some(target.clone()),
+            ..Default::default()
+        };
+        (target, camera)
+    }
+
+    /// Switches layouts when the window (or phone) has been turned or
+    /// resized the other way, or `wide_view` has changed. Call once a frame,
+    /// before `begin`.
+    pub fn fit(&mut self, wide_view: WideView) {
+        self.wide_view = wide_view;
+        let layout = match wide_view {
+            WideView::Extend => Layout::for_window(screen_width(), screen_height()),
+            WideView::Panels => Layout::Portrait,
+        };
+        if layout != self.layout {
+            self.layout = layout;
+            (self.target, self.camera) = Viewport::screen(layout);
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Starts drawing in virtual coordinates.
+    pub fn begin(&self) {
+        set_camera(&self.camera);
+    }
+
+    /// Like `begin`, but magnified `zoom` times around `center`.
+    pub fn begin_zoomed(&self, center: Vec2, zoom: f32) {
+        set_camera(&Camera2D {
+            target: center,
+            zoom: self.camera.zoom * zoom,
+            render_target: Some(self.target.clone()),
+            ..Default::default()
+        });
+    }
+
+    /// Presents the virtual screen in the window, color graded with `grade`
+    /// (see `grading.rs`), with `theme`'s side panels when they're picked.
+    pub fn end(&self, theme: &Theme, grade: Grade) {
+        set_default_camera();
+        clear_background(BLACK);
+        let area = self.area();
+        if self.wide_view == WideView::Panels {
+            let (texture, tint) = match &theme.panel {
+                Some(panel) => (panel, WHITE),
+                None => (&theme.background, PANEL_DIM),
+            };
+            draw_panel(texture, tint, area.x, -1.0);
+            draw_panel(texture, tint, area.right(), 1.0);
+        }
+        self.grading.apply(grade, || {
+            draw_texture_ex(
+                &self.target.texture,
+                area.x,
+                area.y,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(area.size()),
+                    ..Default::default()
+                },
+            );
+        });
+    }
+
+    /// What has been drawn to the virtual screen so far this frame.
+    pub fn capture(&self) -> Image {
+        unsafe {
+            get_internal_gl().flush();
+        }
+        self.target.texture.get_texture_data()
+    }
+
+    /// The mouse position in virtual coordinates.
+    pub fn mouse_position(&self) -> Vec2 {
+        let area = self.area();
+        let (x, y) = mouse_position();
+        (vec2(x, y) - area.point()) * self.layout.width() / area.w + vec2(self.layout.left(), 0.0)
+    }
+
+    // Where the virtual screen sits in the window, inside the safe area.
+    // Whole-number scales are preferred so pixel art stays crisp.
+    fn area(&self) -> Rect {
+        let Insets {
+            top,
+            bottom,
+            left,
+            right,
+        } = self.insets;
+        let (space_w, space_h) = (
+            screen_width() - left - right,
+            screen_height() - top - bottom,
+        );
+        let width = self.layout.width();
+        let fit = (space_w / width).min(space_h / HEIGHT);
+        let scale = if fit >= 1.0 { fit.floor() } else { fit };
+        let (w, h) = (width * scale, HEIGHT * scale);
+        Rect::new(
+            (left + (space_w - w) / 2.0).round(),
+            (top + (space_h - h) / 2.0).round(),
+            w,
+            h,
+        )
+    }
+}
+
+// Fills the window from `edge` outwards (`direction` -1 for left, 1 for
+// right) with copies of `texture` as tall as the window, mirrored on the
+// right so the art faces the playfield from both sides
+fn draw_panel(texture: &Texture2D, tint: Color, edge: f32, direction: f32) {
+    let height = screen_height();
+    let width = texture.width() * height / texture.height();
+    let room = if direction < 0.0 {
+        edge
+    } else {
+        screen_width() - edge
+    };
+    let mut drawn = 0.0;
+    while drawn < room {
+        let x = if direction < 0.0 {
+            edge - drawn - width
+        } else {
+            edge + drawn
+        };
+        draw_texture_ex(
+            texture,
+            x,
+            0.0,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(vec2(width, height)),
+                flip_x: direction > 0.0,
+                ..Default::default()
+            },
+        );
+        drawn += width;
+    }
+}
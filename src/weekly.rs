@@ -0,0 +1,245 @@
+// The weekly challenge: a course and a set of mutators that change every
+// Monday (UTC), picked with C on the title screen after the built-in
+// challenges, or with `--mode weekly`. It has a best of its own in the
+// profile, which starts over with each week.
+//
+// A week's challenge comes from a manifest:
+//
+//     week = 2026-10-12
+//     name = Slippery giants
+//     seed = 918273
+//     mutators = giant, icy
+//     signature = 5d0e…
+//
+// `week` is the Monday it starts on, and the signature is the HMAC-SHA256
+// (see `signing.rs`) of every other line, under the key the game was built
+// with (FLAPPY_WEEKLY_KEY):
+//
+//     grep -v '^signature' weekly.cfg | openssl dgst -sha256 -hmac "$FLAPPY_WEEKLY_KEY"
+//
+// With the `weekly` cargo feature the manifest is fetched at launch from
+// FLAPPY_WEEKLY_URL (or `weekly_url` in `settings.cfg`), and kept as
+// `weekly.cfg` for playing offline; the signature stops the kept copy being
+// edited into an easier week. Weeks without a manifest, and builds without
+// a key, play the rotation bundled below instead.
+
+use crate::challenge::Challenge;
+use crate::date::{self, Date};
+use crate::kv::KeyValues;
+use crate::load_error::LoadError;
+use crate::mutators::{Mutator, Mutators};
+use crate::options::Options;
+use crate::signing;
+use crate::storage;
+
+pub const WEEKLY_FILE: &str = "weekly.cfg";
+// Mixed into the Monday's date for the bundled seeds, so the weekly
+// course isn't that day's daily one
+const SEED_SALT: u64 = 0x5745_454b;
+
+// Played in turn, one a week
+const ROTATION: [(&str, &[Mutator]); 6] = [
+    (
+        "Small and slippery",
+        &[Mutator::TinyBird, Mutator::IcyMomentum],
+    ),
+    ("Memory test", &[Mutator::InvisiblePipes]),
+    ("Heavyweight", &[Mutator::GiantBird]),
+    ("Backwards", &[Mutator::MirroredControls]),
+    ("Ice giant", &[Mutator::GiantBird, Mutator::IcyMomentum]),
+    (
+        "Ghost flight",
+        &[Mutator::TinyBird, Mutator::InvisiblePipes],
+    ),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Weekly {
+    // Days since 1970-01-01 of the Monday it starts on
+    pub week: i64,
+    pub name: String,
+    pub seed: u64,
+    pub mutators: Mutators,
+}
+
+impl Weekly {
+    /// This week's challenge: the kept manifest if it's for this week and
+    /// signed, or else the bundled one.
+    pub fn load() -> Weekly {
+        let week = this_week();
+        storage::load_save(WEEKLY_FILE)
+            .and_then(|text| Weekly::parse(&text.ok_or(LoadError::Missing)?))
+            .inspect_err(|err| {
+                if !matches!(err, LoadError::Missing) {
+                    eprintln!("Could not load {WEEKLY_FILE}: {err}");
+                }
+            })
+            .ok()
+            .filter(|weekly| weekly.week == week)
+            .unwrap_or_else(|| Weekly::bundled(week))
+    }
+
+    /// The rotation's challenge for the week starting on `week`.
+    pub fn bundled(week: i64) -> Weekly {
+        let (name, mutators) = ROTATION[(week / 7).rem_euclid(ROTATION.len() as i64) as usize];
+        Weekly {
+            week,
+            name: name.to_owned(),
+            seed: Date::from_days(week).seed() ^ SEED_SALT,
+            mutators: Mutators::of(mutators),
+        }
+    }
+
+    /// Reads a manifest, checking its signature.
+    pub fn parse(text: &str) -> Result<Weekly, LoadError> {
+        let key = option_env!("FLAPPY_WEEKLY_KEY")
+            .ok_or_else(|| LoadError::invalid("this build has no key to check it with"))?;
+        let kv = KeyValues::parse(text);
+        let signature = kv
+            .get("signature")
+            .ok_or_else(|| LoadError::invalid("it isn't signed"))?;
+        if !signing::verify(key.as_bytes(), signed_part(text).as_bytes(), signature) {
+            return Err(LoadError::invalid("its signature doesn't match"));
+        }
+
+        let field = |key: &str| {
+            kv.get(key)
+                .ok_or_else(|| LoadError::invalid(format!("{key} is missing")))
+        };
+        let week_text = field("week")?;
+        let week = Date::parse(week_text)
+            .map(Date::days)
+            .filter(|&days| Date::weekday(days) == 0)
+            .ok_or_else(|| LoadError::invalid(format!("week {week_text} isn't a Monday")))?;
+        let seed = field("seed")?
+            .parse()
+            .map_err(|_| LoadError::invalid("seed isn't a whole number"))?;
+        let mut mutators = Vec::new();
+        let ids = field("mutators")?.split(',').map(str::trim);
+        for id in ids.filter(|id| !id.is_empty()) {
+            let mutator = Mutator::from_id(id)
+                .ok_or_else(|| LoadError::invalid(format!("unknown mutator {id}")))?;
+            mutators.push(mutator);
+        }
+        Ok(Weekly {
+            week,
+            name: field("name")?.to_owned(),
+            seed,
+            mutators: Mutators::of(&mutators),
+        })
+    }
+
+    /// The seed runs fly with `challenge` picked: this week's for the
+    /// weekly challenge, or the one `options` asked for.
+    pub fn course_seed(&self, challenge: Option<Challenge>, options: &Options) -> Option<u64> {
+        match challenge {
+            Some(Challenge::Weekly) => Some(self.seed),
+            _ => options.course_seed(),
+        }
+    }
+
+    /// Days left until the next one, counting today.
+    pub fn days_left(&self) -> i64 {
+        self.week + 7 - date::today_days()
+    }
+}
+
+/// Days since 1970-01-01 of this week's Monday.
+pub fn this_week() -> i64 {
+    let today = date::today_days();
+    today - i64::from(Date::weekday(today))
+}
+
+// Every line but the signature, as `grep -v '^signature'` prints them
+fn signed_part(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.starts_with("signature"))
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+/// Asks for the week's manifest in the background, keeping it once it's in.
+pub struct WeeklyFetch {
+    #[cfg(feature = "weekly")]
+    answer: Option<std::sync::mpsc::Receiver<Option<String>>>,
+}
+
+impl WeeklyFetch {
+    /// Starts asking `url` (or the one baked in), if the build can check
+    /// what comes back.
+    pub fn start(url: Option<&str>) -> WeeklyFetch {
+        #[cfg(feature = "weekly")]
+        {
+            let baked = option_env!("FLAPPY_WEEKLY_URL");
+            let url = url
+                .or(baked)
+                .filter(|_| option_env!("FLAPPY_WEEKLY_KEY").is_some());
+            WeeklyFetch {
+                answer: url.map(|url| fetch::spawn(url.to_owned())),
+            }
+        }
+        #[cfg(not(feature = "weekly"))]
+        {
+            let _ = url;
+            WeeklyFetch {}
+        }
+    }
+
+    /// This week's challenge, once a manifest for it has come in and been
+    /// kept.
+    pub fn fetched(&mut self) -> Option<Weekly> {
+        #[cfg(feature = "weekly")]
+        if let Some(answer) = &self.answer
+            && let Ok(text) = answer.try_recv()
+        {
+            self.answer = None;
+            let text = text?;
+            let weekly = Weekly::parse(&text)
+                .inspect_err(|err| eprintln!("Could not use the weekly challenge: {err}"))
+                .ok()
+                .filter(|weekly| weekly.week == this_week())?;
+            if let Err(err) = storage::backend().save_text(WEEKLY_FILE, &text) {
+                eprintln!("Could not keep the weekly challenge: {err}");
+            }
+            return Some(weekly);
+        }
+        None
+    }
+}
+
+#[cfg(feature = "weekly")]
+mod fetch {
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn spawn(url: String) -> Receiver<Option<String>> {
+        let (sender, answer) = mpsc::channel();
+        thread::spawn(move || {
+            let text = match fetch(&url) {
+                Ok(text) => Some(text),
+                // Offline is normal; the kept or bundled one is played
+                Err(err) => {
+                    eprintln!("Could not fetch the weekly challenge: {err}");
+                    None
+                }
+            };
+            let _ = sender.send(text);
+        });
+        answer
+    }
+
+    fn fetch(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        let response = agent
+            .get(url)
+            .set(
+                "User-Agent",
+                concat!("flappy_bird_rust/", env!("CARGO_PKG_VERSION")),
+            )
+            .call()?;
+        Ok(response.into_string()?)
+    }
+}
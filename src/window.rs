@@ -0,0 +1,185 @@
+// Window placement, remembered between sessions in `window.cfg` (see
+// `storage.rs`). The size goes into the window config, and the position and
+// fullscreen are applied straight after the window opens, before the first
+// frame is drawn.
+//
+// miniquad can't list monitors, so fullscreen picks one by where the window
+// is: wherever it was left last time, or moved onto the primary display
+// first. Positions are only known on Windows and Linux.
+//
+// Fullscreen is always borderless: miniquad makes the window an undecorated
+// popup covering the display on Windows, and asks the window manager for
+// its fullscreen state on Linux, so alt-tabbing and screen capture behave
+// as with any other window. There's no exclusive mode to offer alongside
+// it, and the settings menu labels the option to say so.
+
+use macroquad::miniquad::window;
+use macroquad::prelude::*;
+
+use crate::kv::KeyValues;
+use crate::shutdown::Shutdown;
+use crate::storage;
+use crate::{HEIGHT, WIDTH};
+
+const WINDOW_FILE: &str = "window.cfg";
+// A moved or resized window is saved once it has stayed put this long, so
+// dragging it around doesn't write on every frame
+const SETTLE_SECONDS: f32 = 1.0;
+
+/// Which display fullscreen uses, picked in the settings menu.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum FullscreenDisplay {
+    // The one the window is on
+    #[default]
+    Current,
+    Primary,
+}
+
+impl FullscreenDisplay {
+    pub const ALL: [FullscreenDisplay; 2] =
+        [FullscreenDisplay::Current, FullscreenDisplay::Primary];
+
+    /// Stable name used in the settings file.
+    pub fn id(self) -> &'static str {
+        match self {
+            FullscreenDisplay::Current => "current",
+            FullscreenDisplay::Primary => "primary",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<FullscreenDisplay> {
+        FullscreenDisplay::ALL
+            .into_iter()
+            .find(|display| display.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FullscreenDisplay::Current => "Window's",
+            FullscreenDisplay::Primary => "Primary",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Placement {
+    width: i32,
+    height: i32,
+    position: Option<(u32, u32)>,
+}
+
+pub struct WindowState {
+    saved: Placement,
+    // The placement last seen, and for how long it's been that way
+    seen: Placement,
+    unchanged_for: f32,
+}
+
+impl WindowState {
+    /// Loads the saved placement, or the virtual screen's size at first.
+    pub fn load() -> WindowState {
+        // A damaged file only means opening at the default size
+        let kv = storage::backend()
+            .load_text(WINDOW_FILE)
+            .map(|text| KeyValues::parse(&text))
+            .unwrap_or_default();
+        let position = kv.get_parsed("x").zip(kv.get_parsed("y"));
+        let saved = Placement {
+            width: kv.get_parsed("width").unwrap_or(WIDTH as i32).max(1),
+            height: kv.get_parsed("height").unwrap_or(HEIGHT as i32).max(1),
+            position,
+        };
+        WindowState {
+            saved,
+            seen: saved,
+            unchanged_for: 0.0,
+        }
+    }
+
+    /// The window size to open with.
+    pub fn size(&self) -> (i32, i32) {
+        (self.saved.width, self.saved.height)
+    }
+
+    /// Moves the window back where it was, then goes fullscreen when the
+    /// settings say so. Call once, before the first frame.
+    pub fn restore(&self, fullscreen: bool, display: FullscreenDisplay) {
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        if let Some((x, y)) = self.saved.position {
+            window::set_window_position(x, y);
+        }
+        if fullscreen {
+            set_fullscreen(true, display);
+        }
+    }
+
+    /// Saves the window's size and position once they've settled after a
+    /// change. Fullscreen sizes aren't kept, so leaving fullscreen next
+    /// session goes back to the window as it was.
+    pub fn update(&mut self, dt: f32, fullscreen: bool) {
+        if fullscreen {
+            self.unchanged_for = 0.0;
+            return;
+        }
+        let current = Placement {
+            width: screen_width() as i32,
+            height: screen_height() as i32,
+            position: current_position(),
+        };
+        if current != self.seen {
+            self.seen = current;
+            self.unchanged_for = 0.0;
+            return;
+        }
+        self.unchanged_for += dt;
+        if self.unchanged_for >= SETTLE_SECONDS && current != self.saved {
+            self.saved = current;
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let mut kv = KeyValues::default();
+        kv.set("width", self.saved.width);
+        kv.set("height", self.saved.height);
+        if let Some((x, y)) = self.saved.position {
+            kv.set("x", x);
+            kv.set("y", y);
+        }
+        if let Err(err) = storage::backend().save_text(WINDOW_FILE, &kv.to_string()) {
+            eprintln!("Could not save window placement: {err}");
+        }
+    }
+}
+
+impl Shutdown for WindowState {
+    // A move or resize that hadn't settled yet
+    fn shutdown(&mut self) {
+        if self.seen != self.saved {
+            self.saved = self.seen;
+            self.save();
+        }
+    }
+}
+
+/// Switches fullscreen on (on `display`) or off.
+pub fn set_fullscreen(fullscreen: bool, display: FullscreenDisplay) {
+    // The primary display is the one at the desktop's origin
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    if fullscreen && display == FullscreenDisplay::Primary {
+        window::set_window_position(0, 0);
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    let _ = display;
+    window::set_fullscreen(fullscreen);
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn current_position() -> Option<(u32, u32)> {
+    Some(window::get_window_position())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn current_position() -> Option<(u32, u32)> {
+    None
+}
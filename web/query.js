@@ -0,0 +1,24 @@
+// Hands the page's query string to the game (see src/options.rs), so links
+// like index.html?seed=1234&mode=daily&mute=1 configure the run.
+//
+// Load after mq_js_bundle.js and before calling load().
+
+(function () {
+    function encoded() {
+        return new TextEncoder().encode(window.location.search);
+    }
+
+    miniquad_add_plugin({
+        name: "flappy_query",
+        version: 1,
+        register_plugin: function (importObject) {
+            importObject.env.flappy_query_len = function () {
+                return encoded().length;
+            };
+            importObject.env.flappy_query_read = function (out_ptr) {
+                const bytes = encoded();
+                new Uint8Array(wasm_memory.buffer, out_ptr, bytes.length).set(bytes);
+            };
+        },
+    });
+})();
@@ -0,0 +1,89 @@
+// Browser storage for the web build (see src/storage.rs). Text saves go to
+// localStorage, binary files such as screenshots to IndexedDB, and files
+// made for the player, such as share cards, are downloaded.
+//
+// Load after mq_js_bundle.js and before calling load().
+
+(function () {
+    const PREFIX = "flappy_bird_rust/";
+    const DB_NAME = "flappy_bird_rust";
+    const STORE = "files";
+
+    let db = null;
+    // Blobs written before the database finished opening
+    let queued = [];
+
+    function bytes(ptr, len) {
+        return new Uint8Array(wasm_memory.buffer, ptr, len);
+    }
+
+    function text(ptr, len) {
+        return new TextDecoder().decode(bytes(ptr, len));
+    }
+
+    function put(name, data) {
+        const tx = db.transaction(STORE, "readwrite");
+        tx.objectStore(STORE).put(data, name);
+        tx.onerror = () => console.error("Could not save " + name, tx.error);
+    }
+
+    function open_db() {
+        if (!window.indexedDB) {
+            console.warn("IndexedDB is not available; files won't be saved");
+            return;
+        }
+        const request = indexedDB.open(DB_NAME, 1);
+        request.onupgradeneeded = () => request.result.createObjectStore(STORE);
+        request.onsuccess = () => {
+            db = request.result;
+            queued.forEach(([name, data]) => put(name, data));
+            queued = [];
+        };
+        request.onerror = () => console.error("Could not open IndexedDB", request.error);
+    }
+
+    miniquad_add_plugin({
+        name: "flappy_storage",
+        version: 2,
+        register_plugin: function (importObject) {
+            importObject.env.flappy_storage_text_len = function (name_ptr, name_len) {
+                const value = localStorage.getItem(PREFIX + text(name_ptr, name_len));
+                return value === null ? -1 : new TextEncoder().encode(value).length;
+            };
+            importObject.env.flappy_storage_text_read = function (name_ptr, name_len, out_ptr) {
+                const value = localStorage.getItem(PREFIX + text(name_ptr, name_len)) || "";
+                const encoded = new TextEncoder().encode(value);
+                bytes(out_ptr, encoded.length).set(encoded);
+            };
+            importObject.env.flappy_storage_text_write = function (name_ptr, name_len, text_ptr, text_len) {
+                try {
+                    localStorage.setItem(PREFIX + text(name_ptr, name_len), text(text_ptr, text_len));
+                    return 0;
+                } catch (e) {
+                    console.error(e);
+                    return 1;
+                }
+            };
+            importObject.env.flappy_storage_blob_write = function (name_ptr, name_len, data_ptr, data_len) {
+                const name = text(name_ptr, name_len);
+                // Copy out of wasm memory, which may move before the write happens
+                const data = bytes(data_ptr, data_len).slice();
+                if (db) {
+                    put(name, data);
+                } else {
+                    queued.push([name, data]);
+                }
+            };
+            importObject.env.flappy_storage_download = function (name_ptr, name_len, data_ptr, data_len) {
+                const blob = new Blob([bytes(data_ptr, data_len).slice()]);
+                const link = document.createElement("a");
+                link.href = URL.createObjectURL(blob);
+                link.download = text(name_ptr, name_len);
+                link.click();
+                // Give the browser a moment to start the download first
+                setTimeout(() => URL.revokeObjectURL(link.href), 1000);
+            };
+        },
+        on_init: open_db,
+    });
+})();